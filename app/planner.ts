@@ -0,0 +1,382 @@
+/**
+ * GUSD transaction planner
+ *
+ * Assembles multi-instruction vault flows into ready-to-sign versioned transactions.
+ * Each plan is simulated once to size its compute-unit limit, gets an optional
+ * priority fee, and is compiled against the protocol address lookup table (ALT)
+ * when one is configured, so the static protocol accounts cost one byte each.
+ */
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  AddressLookupTableAccount,
+  AddressLookupTableProgram,
+  ComputeBudgetProgram,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  TransactionInstruction,
+  TransactionMessage,
+  VersionedTransaction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { Gusd } from "../target/types/gusd";
+
+/** Compute-unit limit of a single transaction */
+export const MAX_COMPUTE_UNITS = 1_400_000;
+
+/** Headroom added to the simulated compute units (10%) */
+export const DEFAULT_COMPUTE_UNIT_MARGIN = 1.1;
+
+export interface PlannerOptions {
+  /** Protocol ALT, created with `createProtocolLookupTable` */
+  lookupTable?: PublicKey;
+  /** Priority fee; omitted when unset */
+  computeUnitPriceMicroLamports?: number;
+  /** Multiplier on simulated compute units */
+  computeUnitMargin?: number;
+  /** Same prefix the program was built with (`GUSD_SEED_NAMESPACE`); empty by default */
+  seedNamespace?: Buffer;
+}
+
+/** Protocol-wide accounts shared by every vault flow */
+export interface ProtocolAccounts {
+  protocolState: PublicKey;
+  gusdMint: PublicKey;
+  deploymentConfig: PublicKey;
+  priceAccumulator: PublicKey;
+  priceFeed: PublicKey;
+  collateralConfig: PublicKey;
+  revenueStats: PublicKey;
+  treasury: PublicKey;
+}
+
+export function protocolAccounts(
+  programId: PublicKey,
+  seedNamespace: Buffer = Buffer.alloc(0)
+): ProtocolAccounts {
+  const pda = (...seeds: Buffer[]) =>
+    PublicKey.findProgramAddressSync(seeds, programId)[0];
+  return {
+    protocolState: pda(seedNamespace, Buffer.from("protocol")),
+    gusdMint: pda(seedNamespace, Buffer.from("gusd_mint")),
+    deploymentConfig: pda(Buffer.from("deployment")),
+    priceAccumulator: pda(Buffer.from("price_accumulator")),
+    // Native GOR has no mint; its price feed is keyed by the default pubkey
+    priceFeed: pda(Buffer.from("price_feed"), PublicKey.default.toBuffer()),
+    collateralConfig: pda(Buffer.from("collateral_config"), PublicKey.default.toBuffer()),
+    revenueStats: pda(Buffer.from("revenue_stats")),
+    treasury: pda(Buffer.from("treasury")),
+  };
+}
+
+/**
+ * A vault's PDAs. Vaults are keyed by their position NFT mint; the bootstrap vault by
+ * the default pubkey.
+ */
+export function vaultAccounts(
+  programId: PublicKey,
+  positionMint: PublicKey,
+  seedNamespace: Buffer = Buffer.alloc(0)
+): { vault: PublicKey; vaultCollateral: PublicKey } {
+  const pda = (seed: string) =>
+    PublicKey.findProgramAddressSync(
+      [seedNamespace, Buffer.from(seed), positionMint.toBuffer()],
+      programId
+    )[0];
+  return { vault: pda("vault"), vaultCollateral: pda("vault_collateral") };
+}
+
+/**
+ * Instructions creating and filling the protocol ALT with the protocol accounts and
+ * programs every vault flow touches. `authority` must sign; the table is usable one
+ * slot after the transaction lands.
+ */
+export function createProtocolLookupTable(
+  programId: PublicKey,
+  authority: PublicKey,
+  payer: PublicKey,
+  recentSlot: number,
+  seedNamespace?: Buffer
+): { address: PublicKey; instructions: TransactionInstruction[] } {
+  const [create, address] = AddressLookupTableProgram.createLookupTable({
+    authority,
+    payer,
+    recentSlot,
+  });
+  const extend = AddressLookupTableProgram.extendLookupTable({
+    lookupTable: address,
+    authority,
+    payer,
+    addresses: [
+      ...Object.values(protocolAccounts(programId, seedNamespace)),
+      programId,
+      SystemProgram.programId,
+      TOKEN_PROGRAM_ID,
+      ASSOCIATED_TOKEN_PROGRAM_ID,
+      SYSVAR_INSTRUCTIONS_PUBKEY,
+    ],
+  });
+  return { address, instructions: [create, extend] };
+}
+
+export class GusdPlanner {
+  readonly accounts: ProtocolAccounts;
+  private lookupTableAccount?: AddressLookupTableAccount;
+
+  constructor(
+    readonly program: Program<Gusd>,
+    readonly options: PlannerOptions = {}
+  ) {
+    this.accounts = protocolAccounts(program.programId, options.seedNamespace);
+  }
+
+  /**
+   * Create a vault, deposit `collateral` lamports, and mint `gusd` against it. The
+   * transaction comes back signed by the vault's fresh position NFT mint; the owner
+   * signs it last.
+   */
+  async createDepositMint(
+    owner: PublicKey,
+    collateral: BN,
+    gusd: BN
+  ): Promise<VersionedTransaction> {
+    const positionMint = Keypair.generate();
+    const vault = this.vaultAccounts(positionMint.publicKey);
+    const positionTokenAccount = getAssociatedTokenAddressSync(positionMint.publicKey, owner);
+    const instructions = [
+      await this.program.methods
+        .createVault()
+        .accountsPartial({
+          owner,
+          ...vault,
+          deploymentConfig: this.accounts.deploymentConfig,
+          collateralConfig: this.accounts.collateralConfig,
+          kycAttestation: null,
+          protocolState: this.accounts.protocolState,
+          positionMint: positionMint.publicKey,
+          ownerPositionAccount: positionTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction(),
+      await this.program.methods
+        .depositCollateral(collateral)
+        .accountsPartial({
+          owner,
+          delegate: null,
+          positionTokenAccount,
+          ...vault,
+          protocolState: this.accounts.protocolState,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          collateralConfig: this.accounts.collateralConfig,
+          collateralMint: null,
+          vaultTokenAccount: null,
+          ownerTokenAccount: null,
+          collateralTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction(),
+      await this.program.methods
+        .mintGusd(gusd)
+        .accountsPartial({
+          owner,
+          delegate: null,
+          positionTokenAccount,
+          vault: vault.vault,
+          protocolState: this.accounts.protocolState,
+          revenueStats: this.accounts.revenueStats,
+          gusdMint: this.accounts.gusdMint,
+          userGusdAccount: this.userGusdAccount(owner),
+          priceAccumulator: this.accounts.priceAccumulator,
+          deploymentConfig: this.accounts.deploymentConfig,
+          priceFeed: this.accounts.priceFeed,
+          collateralConfig: this.accounts.collateralConfig,
+          priceUpdate: null,
+          marginAccount: null,
+          feeExemption: null,
+          feeTierRegistry: null,
+          governanceStake: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction(),
+    ];
+    const transaction = await this.plan(owner, instructions);
+    transaction.sign([positionMint]);
+    return transaction;
+  }
+
+  /**
+   * Repay `repay` GUSD, withdraw `withdraw` lamports, and optionally close the vault
+   * keyed by `positionMint`, burning its position NFT.
+   * Closing needs the repayment and withdrawal to empty the vault; repay a little over
+   * the debt to cover interest accrued before the transaction lands (repayments are
+   * capped at the debt).
+   */
+  async repayWithdrawClose(
+    owner: PublicKey,
+    positionMint: PublicKey,
+    repay: BN,
+    withdraw: BN,
+    close = false
+  ): Promise<VersionedTransaction> {
+    const vault = this.vaultAccounts(positionMint);
+    const positionTokenAccount = getAssociatedTokenAddressSync(positionMint, owner);
+    const instructions: TransactionInstruction[] = [];
+    if (!repay.isZero()) {
+      instructions.push(
+        await this.program.methods
+          .repayGusd(repay)
+          .accountsPartial({
+            owner,
+            delegate: null,
+            positionTokenAccount,
+            vault: vault.vault,
+            protocolState: this.accounts.protocolState,
+            revenueStats: this.accounts.revenueStats,
+            priceFeed: this.accounts.priceFeed,
+            collateralConfig: this.accounts.collateralConfig,
+            gusdMint: this.accounts.gusdMint,
+            userGusdAccount: this.userGusdAccount(owner),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            feeTierRegistry: null,
+            governanceStake: null,
+          })
+          .instruction()
+      );
+    }
+    if (!withdraw.isZero()) {
+      instructions.push(
+        await this.program.methods
+          .withdrawCollateral(withdraw)
+          .accountsPartial({
+            owner,
+            delegate: null,
+            positionTokenAccount,
+            ...vault,
+            protocolState: this.accounts.protocolState,
+            revenueStats: this.accounts.revenueStats,
+            deploymentConfig: this.accounts.deploymentConfig,
+            priceFeed: this.accounts.priceFeed,
+            collateralConfig: this.accounts.collateralConfig,
+            priceUpdate: null,
+            marginAccount: null,
+            treasury: this.accounts.treasury,
+            feeExemption: null,
+            collateralMint: null,
+            vaultTokenAccount: null,
+            ownerTokenAccount: null,
+            treasuryCollateralAccount: null,
+            collateralTokenProgram: null,
+            systemProgram: SystemProgram.programId,
+            feeTierRegistry: null,
+            governanceStake: null,
+          })
+          .instruction()
+      );
+    }
+    if (close) {
+      instructions.push(
+        await this.program.methods
+          .closeVault()
+          .accountsPartial({
+            owner,
+            delegate: null,
+            positionTokenAccount,
+            ...vault,
+            systemProgram: SystemProgram.programId,
+            protocolState: this.accounts.protocolState,
+            positionMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .instruction()
+      );
+    }
+    if (instructions.length === 0) {
+      throw new Error("Nothing to plan: repay, withdraw, or close the vault");
+    }
+    return this.plan(owner, instructions);
+  }
+
+  /**
+   * Wrap `instructions` with compute-budget instructions and compile them into a
+   * versioned transaction paid by `payer`. The compute-unit limit is the simulated
+   * usage times the margin; a failing simulation throws with its logs.
+   */
+  async plan(
+    payer: PublicKey,
+    instructions: TransactionInstruction[]
+  ): Promise<VersionedTransaction> {
+    const connection = this.program.provider.connection;
+    const lookupTables = await this.lookupTables();
+    const price =
+      this.options.computeUnitPriceMicroLamports === undefined
+        ? []
+        : [
+            ComputeBudgetProgram.setComputeUnitPrice({
+              microLamports: this.options.computeUnitPriceMicroLamports,
+            }),
+          ];
+    const compile = (units: number, blockhash: string) =>
+      new VersionedTransaction(
+        new TransactionMessage({
+          payerKey: payer,
+          recentBlockhash: blockhash,
+          instructions: [
+            ComputeBudgetProgram.setComputeUnitLimit({ units }),
+            ...price,
+            ...instructions,
+          ],
+        }).compileToV0Message(lookupTables)
+      );
+
+    const { blockhash } = await connection.getLatestBlockhash();
+    const simulation = await connection.simulateTransaction(
+      compile(MAX_COMPUTE_UNITS, blockhash),
+      { sigVerify: false, replaceRecentBlockhash: true }
+    );
+    if (simulation.value.err) {
+      throw new Error(
+        `Simulation failed: ${JSON.stringify(simulation.value.err)}\n` +
+          (simulation.value.logs ?? []).join("\n")
+      );
+    }
+    const margin = this.options.computeUnitMargin ?? DEFAULT_COMPUTE_UNIT_MARGIN;
+    const units = Math.min(
+      Math.ceil((simulation.value.unitsConsumed ?? MAX_COMPUTE_UNITS) * margin),
+      MAX_COMPUTE_UNITS
+    );
+    return compile(units, blockhash);
+  }
+
+  private vaultAccounts(positionMint: PublicKey) {
+    return vaultAccounts(this.program.programId, positionMint, this.options.seedNamespace);
+  }
+
+  private userGusdAccount(owner: PublicKey): PublicKey {
+    return getAssociatedTokenAddressSync(this.accounts.gusdMint, owner);
+  }
+
+  private async lookupTables(): Promise<AddressLookupTableAccount[]> {
+    if (!this.options.lookupTable) {
+      return [];
+    }
+    if (!this.lookupTableAccount) {
+      const { value } = await this.program.provider.connection.getAddressLookupTable(
+        this.options.lookupTable
+      );
+      if (!value) {
+        throw new Error(`Lookup table ${this.options.lookupTable} not found`);
+      }
+      this.lookupTableAccount = value;
+    }
+    return [this.lookupTableAccount];
+  }
+}
@@ -0,0 +1,242 @@
+/**
+ * GUSD event replay
+ *
+ * Rebuilds vault state from the program's emitted events alone and diffs it against
+ * the live accounts, as an independent correctness check after incidents. Events
+ * carrying a running total (`total_collateral`, `remaining_debt`, ...) set the field
+ * outright, so a replay can start at any slot; delta-only events (liquidations,
+ * collateral fee payments) apply only once the field is known.
+ */
+import { BN, BorshCoder, EventParser, Program } from "@coral-xyz/anchor";
+import { Connection, PublicKey } from "@solana/web3.js";
+import { Gusd } from "../target/types/gusd";
+import { vaultAccounts } from "./planner";
+
+/** Signatures fetched per `getSignaturesForAddress` page */
+const SIGNATURE_PAGE_SIZE = 1_000;
+
+/** An event decoded from a confirmed program transaction */
+export interface ProgramEvent {
+  slot: number;
+  signature: string;
+  name: string;
+  data: any;
+}
+
+/** Vault state rebuilt from events; fields are undefined until an event fixes them */
+export interface ReplayedVault {
+  owner: PublicKey;
+  collateralMint?: PublicKey;
+  collateralAmount?: BN;
+  debtAmount?: BN;
+  closed: boolean;
+  /** Slot of the last event that touched the vault */
+  lastSlot: number;
+}
+
+export interface ReplayedState {
+  vaults: Map<string, ReplayedVault>;
+  /** `ProtocolState.bad_debt`, once a bad debt event has been seen */
+  badDebt?: BN;
+  /** Events of types replay doesn't model, by name */
+  skipped: Map<string, number>;
+}
+
+/** A field whose replayed value differs from the live account */
+export interface ReplayDiff {
+  account: PublicKey;
+  field: string;
+  replayed: string;
+  live: string;
+}
+
+/**
+ * Program events from confirmed transactions at or after `fromSlot`, oldest first.
+ * Failed transactions are skipped, since their events never took effect.
+ */
+export async function fetchProgramEvents(
+  connection: Connection,
+  programId: PublicKey,
+  coder: BorshCoder,
+  fromSlot: number
+): Promise<ProgramEvent[]> {
+  const signatures: { signature: string; slot: number }[] = [];
+  let before: string | undefined;
+  for (;;) {
+    const page = await connection.getSignaturesForAddress(
+      programId,
+      { before, limit: SIGNATURE_PAGE_SIZE },
+      "confirmed"
+    );
+    const inRange = page.filter((info) => info.slot >= fromSlot && info.err === null);
+    signatures.push(...inRange);
+    if (page.length < SIGNATURE_PAGE_SIZE || page[page.length - 1].slot < fromSlot) {
+      break;
+    }
+    before = page[page.length - 1].signature;
+  }
+
+  // Signatures come newest first
+  signatures.reverse();
+
+  const parser = new EventParser(programId, coder);
+  const events: ProgramEvent[] = [];
+  for (const { signature, slot } of signatures) {
+    const tx = await connection.getTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    if (!tx?.meta?.logMessages) {
+      continue;
+    }
+    for (const event of parser.parseLogs(tx.meta.logMessages)) {
+      events.push({ slot, signature, name: event.name, data: event.data });
+    }
+  }
+  return events;
+}
+
+/** Fields every vault event carries */
+interface VaultEventData {
+  owner?: PublicKey;
+  vaultOwner?: PublicKey;
+  positionMint: PublicKey;
+}
+
+/** Rebuild vault state and bad debt from `events`, applied in order */
+export function replayEvents(
+  events: ProgramEvent[],
+  programId: PublicKey,
+  seedNamespace: Buffer = Buffer.alloc(0)
+): ReplayedState {
+  const state: ReplayedState = { vaults: new Map(), skipped: new Map() };
+
+  const vaultAt = (key: PublicKey, owner: PublicKey | undefined, slot: number) => {
+    let vault = state.vaults.get(key.toString());
+    if (!vault) {
+      vault = { owner: owner ?? PublicKey.default, closed: false, lastSlot: slot };
+      state.vaults.set(key.toString(), vault);
+    }
+    vault.lastSlot = slot;
+    return vault;
+  };
+  // Vault events carry the owner and the position mint the vault's PDAs are keyed by
+  const ownedVault = (data: VaultEventData, slot: number) =>
+    vaultAt(
+      vaultAccounts(programId, data.positionMint, seedNamespace).vault,
+      data.owner ?? data.vaultOwner,
+      slot
+    );
+  const minus = (value: BN | undefined, delta: BN) =>
+    value === undefined ? undefined : value.sub(delta);
+
+  for (const { name, data, slot } of events) {
+    switch (name) {
+      case "VaultCreated": {
+        const vault = ownedVault(data, slot);
+        vault.collateralMint = data.collateralMint;
+        vault.collateralAmount = new BN(0);
+        vault.debtAmount = new BN(0);
+        vault.closed = false;
+        break;
+      }
+      case "CollateralDeposited":
+      case "CollateralDepositedFor":
+        ownedVault(data, slot).collateralAmount = data.totalCollateral;
+        break;
+      case "CollateralWithdrawn":
+        ownedVault(data, slot).collateralAmount = data.remainingCollateral;
+        break;
+      case "GusdMinted":
+      case "InterestAccrued":
+        ownedVault(data, slot).debtAmount = data.totalDebt;
+        break;
+      case "GusdRepaid":
+      case "GusdRepaidFor":
+        ownedVault(data, slot).debtAmount = data.remainingDebt;
+        break;
+      case "FeesPaidWithCollateral": {
+        const vault = ownedVault(data, slot);
+        vault.debtAmount = data.remainingDebt;
+        vault.collateralAmount = minus(vault.collateralAmount, data.collateral);
+        break;
+      }
+      case "VaultLiquidated": {
+        const vault = ownedVault(data, slot);
+        // A tip with no verified holder stays in the vault
+        const heldTip = data.tipHeld ? data.ownerTip : new BN(0);
+        vault.collateralAmount = minus(vault.collateralAmount, data.collateralSeized.sub(heldTip));
+        vault.debtAmount = minus(vault.debtAmount, data.debtRepaid);
+        break;
+      }
+      case "MarginLiquidated": {
+        const debtVault = vaultAt(data.debtVault, undefined, slot);
+        debtVault.debtAmount = minus(debtVault.debtAmount, data.debtRepaid);
+        const collateralVault = vaultAt(data.collateralVault, undefined, slot);
+        collateralVault.collateralAmount = minus(
+          collateralVault.collateralAmount,
+          data.collateralSeized
+        );
+        break;
+      }
+      case "VaultSettled": {
+        const vault = ownedVault(data, slot);
+        vault.collateralAmount = data.collateralHeld;
+        vault.debtAmount = new BN(0);
+        break;
+      }
+      case "CollateralBalanceSettled":
+        ownedVault(data, slot).debtAmount = data.remainingDebt;
+        break;
+      case "BadDebtRecorded":
+        ownedVault(data, slot).debtAmount = new BN(0);
+        state.badDebt = data.badDebt;
+        break;
+      case "BadDebtCovered":
+        state.badDebt = data.badDebt;
+        break;
+      case "VaultClosed":
+      case "AbandonedVaultSwept":
+        ownedVault(data, slot).closed = true;
+        break;
+      default:
+        state.skipped.set(name, (state.skipped.get(name) ?? 0) + 1);
+    }
+  }
+  return state;
+}
+
+/** Fields of `state` that disagree with the live vault and protocol accounts */
+export async function diffAgainstLive(
+  program: Program<Gusd>,
+  state: ReplayedState,
+  protocolState: PublicKey
+): Promise<ReplayDiff[]> {
+  const diffs: ReplayDiff[] = [];
+  const compare = (account: PublicKey, field: string, replayed: BN | undefined, live: BN) => {
+    if (replayed !== undefined && !replayed.eq(live)) {
+      diffs.push({ account, field, replayed: replayed.toString(), live: live.toString() });
+    }
+  };
+
+  for (const [key, replayed] of state.vaults) {
+    const address = new PublicKey(key);
+    const live = await program.account.vault.fetchNullable(address);
+    if (!live) {
+      if (!replayed.closed) {
+        diffs.push({ account: address, field: "exists", replayed: "true", live: "false" });
+      }
+      continue;
+    }
+    if (replayed.closed) {
+      diffs.push({ account: address, field: "exists", replayed: "false", live: "true" });
+      continue;
+    }
+    compare(address, "collateralAmount", replayed.collateralAmount, live.collateralAmount);
+    compare(address, "debtAmount", replayed.debtAmount, live.debtAmount);
+  }
+
+  const protocol = await program.account.protocolState.fetch(protocolState);
+  compare(protocolState, "badDebt", state.badDebt, protocol.badDebt);
+  return diffs;
+}
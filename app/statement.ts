@@ -0,0 +1,198 @@
+/**
+ * GUSD vault statements
+ *
+ * A vault statement is a timestamped JSON record of a vault's balances, embedding the
+ * `state_hash` returned by `get_vault_state_hash` and signed with the owner's key.
+ * Auditors check the signature, recompute the hash from the listed balances, and compare
+ * the balances against chain state at the listed slot.
+ */
+import { BN, Program } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { createHash, createPrivateKey, createPublicKey, sign, verify } from "crypto";
+import { Gusd } from "../target/types/gusd";
+import { protocolAccounts, vaultAccounts } from "./planner";
+
+/** Domain prefix of the on-chain vault state hash (`VAULT_STATE_DOMAIN`) */
+export const VAULT_STATE_DOMAIN = Buffer.from("GUSD_VAULT_STATE_V1");
+
+/** Version tag of the statement format below */
+export const VAULT_STATEMENT_VERSION = "GUSD_VAULT_STATEMENT_V1";
+
+/** DER prefixes wrapping raw ed25519 keys for node's crypto module */
+const ED25519_PKCS8_PREFIX = Buffer.from("302e020100300506032b657004220420", "hex");
+const ED25519_SPKI_PREFIX = Buffer.from("302a300506032b6570032100", "hex");
+
+/** Balances committed to by `get_vault_state_hash` */
+export interface VaultState {
+  vault: PublicKey;
+  owner: PublicKey;
+  collateralAmount: BN;
+  debtAmount: BN;
+  slot: BN;
+}
+
+/** Statement fields covered by the signature, in signing order */
+export interface VaultStatement {
+  version: string;
+  programId: string;
+  vault: string;
+  owner: string;
+  collateralAmount: string;
+  debtAmount: string;
+  slot: string;
+  /** Cluster time at `slot` (unix seconds) */
+  timestamp: string;
+  /** Hex `state_hash` */
+  stateHash: string;
+  /** When the statement was issued (ISO 8601) */
+  issuedAt: string;
+}
+
+export interface SignedVaultStatement {
+  statement: VaultStatement;
+  /** Key that signed the statement (base58) */
+  signer: string;
+  /** Hex ed25519 signature over `statementMessage(statement)` */
+  signature: string;
+}
+
+/**
+ * sha256(`VAULT_STATE_DOMAIN` || program id || vault || owner || collateral (u64 LE) ||
+ * debt (u64 LE) || slot (u64 LE)), exactly as `get_vault_state_hash` computes it
+ */
+export function vaultStateHash(programId: PublicKey, state: VaultState): Buffer {
+  return createHash("sha256")
+    .update(VAULT_STATE_DOMAIN)
+    .update(programId.toBuffer())
+    .update(state.vault.toBuffer())
+    .update(state.owner.toBuffer())
+    .update(state.collateralAmount.toArrayLike(Buffer, "le", 8))
+    .update(state.debtAmount.toArrayLike(Buffer, "le", 8))
+    .update(state.slot.toArrayLike(Buffer, "le", 8))
+    .digest();
+}
+
+/**
+ * Fetch the state hash of the vault keyed by `positionMint` and build a statement from it
+ * Fails if the returned hash doesn't match the returned balances.
+ */
+export async function buildVaultStatement(
+  program: Program<Gusd>,
+  positionMint: PublicKey,
+  seedNamespace: Buffer = Buffer.alloc(0)
+): Promise<VaultStatement> {
+  const accounts = protocolAccounts(program.programId, seedNamespace);
+  const { vault } = vaultAccounts(program.programId, positionMint, seedNamespace);
+  const { collateralMint } = await program.account.vault.fetch(vault);
+  const [priceFeed] = PublicKey.findProgramAddressSync(
+    [Buffer.from("price_feed"), collateralMint.toBuffer()],
+    program.programId
+  );
+  const [collateralConfig] = PublicKey.findProgramAddressSync(
+    [Buffer.from("collateral_config"), collateralMint.toBuffer()],
+    program.programId
+  );
+  const result = await program.methods
+    .getVaultStateHash()
+    .accounts({
+      vault,
+      protocolState: accounts.protocolState,
+      deploymentConfig: accounts.deploymentConfig,
+      priceFeed,
+      priceUpdate: null,
+      collateralConfig,
+    })
+    .view();
+
+  const stateHash = Buffer.from(result.stateHash);
+  if (!vaultStateHash(program.programId, result).equals(stateHash)) {
+    throw new Error("Vault state hash does not match the returned balances");
+  }
+
+  return {
+    version: VAULT_STATEMENT_VERSION,
+    programId: program.programId.toBase58(),
+    vault: result.vault.toBase58(),
+    owner: result.owner.toBase58(),
+    collateralAmount: result.collateralAmount.toString(),
+    debtAmount: result.debtAmount.toString(),
+    slot: result.slot.toString(),
+    timestamp: result.timestamp.toString(),
+    stateHash: stateHash.toString("hex"),
+    issuedAt: new Date().toISOString(),
+  };
+}
+
+/** Bytes a statement signature covers: its fields as JSON, in signing order */
+export function statementMessage(statement: VaultStatement): Buffer {
+  const {
+    version,
+    programId,
+    vault,
+    owner,
+    collateralAmount,
+    debtAmount,
+    slot,
+    timestamp,
+    stateHash,
+    issuedAt,
+  } = statement;
+  return Buffer.from(
+    JSON.stringify({
+      version,
+      programId,
+      vault,
+      owner,
+      collateralAmount,
+      debtAmount,
+      slot,
+      timestamp,
+      stateHash,
+      issuedAt,
+    })
+  );
+}
+
+/** Sign a statement with the vault owner's (or position NFT holder's) key */
+export function signVaultStatement(
+  statement: VaultStatement,
+  signer: Keypair
+): SignedVaultStatement {
+  const key = createPrivateKey({
+    key: Buffer.concat([ED25519_PKCS8_PREFIX, Buffer.from(signer.secretKey.slice(0, 32))]),
+    format: "der",
+    type: "pkcs8",
+  });
+  return {
+    statement,
+    signer: signer.publicKey.toBase58(),
+    signature: sign(null, statementMessage(statement), key).toString("hex"),
+  };
+}
+
+/**
+ * Check a signed statement: the signature, and that `stateHash` commits to the listed
+ * balances. Whether `signer` owned the vault at `slot` is checked against chain history.
+ */
+export function verifyVaultStatement(signed: SignedVaultStatement): boolean {
+  const { statement } = signed;
+  const key = createPublicKey({
+    key: Buffer.concat([ED25519_SPKI_PREFIX, new PublicKey(signed.signer).toBuffer()]),
+    format: "der",
+    type: "spki",
+  });
+  const signatureValid = verify(
+    null,
+    statementMessage(statement),
+    key,
+    Buffer.from(signed.signature, "hex")
+  );
+  const stateHash = vaultStateHash(new PublicKey(statement.programId), {
+    vault: new PublicKey(statement.vault),
+    owner: new PublicKey(statement.owner),
+    collateralAmount: new BN(statement.collateralAmount),
+    debtAmount: new BN(statement.debtAmount),
+    slot: new BN(statement.slot),
+  });
+  return signatureValid && stateHash.toString("hex") === statement.stateHash;
+}
@@ -0,0 +1,93 @@
+/**
+ * GUSD unit conversions
+ *
+ * Mirror of `programs/gusd/src/units.rs` so clients quote the exact amounts the
+ * program computes, including its rounding direction. Change both together.
+ */
+import { BN } from "@coral-xyz/anchor";
+
+/** GUSD mint decimals */
+export const GUSD_DECIMALS = 6;
+
+/** Basis-point denominator */
+export const BPS_DENOMINATOR = 10_000;
+
+/** Default liquidation threshold (120%) in bps */
+export const LIQUIDATION_THRESHOLD_BPS = 12_000;
+
+/** Health factor of exactly 1.0 */
+export const HEALTH_FACTOR_ONE = new BN(1_000_000);
+
+/** Largest value the program stores in a u64 */
+export const U64_MAX = new BN("18446744073709551615");
+
+function pow10(decimals: number): BN {
+  return new BN(10).pow(new BN(decimals));
+}
+
+/** Fail like the program's `MathOverflow` instead of truncating */
+function toU64(value: BN): BN {
+  if (value.gt(U64_MAX)) {
+    throw new Error("MathOverflow");
+  }
+  return value;
+}
+
+function divCeil(numerator: BN, denominator: BN): BN {
+  const { div, mod } = numerator.divmod(denominator);
+  return mod.isZero() ? div : div.addn(1);
+}
+
+/** USD value (6 decimals) of native collateral at `price` (rounds down) */
+export function lamportsToUsd(lamports: BN, price: BN, nativeDecimals: number): BN {
+  return toU64(lamports.mul(price).div(pow10(nativeDecimals)));
+}
+
+/** USD value of GUSD debt (1 GUSD = $1, same decimals) */
+export function gusdToUsd(gusd: BN): BN {
+  return gusd;
+}
+
+/** Lamports paid for `gusd` at `lamportsPerGusd` per whole GUSD (rounds up) */
+export function gusdCostInLamportsCeil(gusd: BN, lamportsPerGusd: BN): BN {
+  return toU64(divCeil(gusd.mul(lamportsPerGusd), pow10(GUSD_DECIMALS)));
+}
+
+/** Native amount worth `usd` at `price` (rounds down) */
+export function usdToLamports(usd: BN, price: BN, nativeDecimals: number): BN {
+  if (price.isZero()) {
+    throw new Error("MathOverflow");
+  }
+  return toU64(usd.mul(pow10(nativeDecimals)).div(price));
+}
+
+/** Native amount worth at least `usd` at `price` (rounds up) */
+export function usdToLamportsCeil(usd: BN, price: BN, nativeDecimals: number): BN {
+  if (price.isZero()) {
+    throw new Error("MathOverflow");
+  }
+  return toU64(divCeil(usd.mul(pow10(nativeDecimals)), price));
+}
+
+/** `usd` as a ratio of `debt` in bps, or `U64_MAX` when there is no debt */
+export function ratioBps(usd: BN, debt: BN): BN {
+  if (debt.isZero()) {
+    return U64_MAX;
+  }
+  return BN.min(usd.muln(BPS_DENOMINATOR).div(gusdToUsd(debt)), U64_MAX);
+}
+
+/**
+ * Normalize a collateral ratio to a health factor: `HEALTH_FACTOR_ONE` at
+ * `liquidationThresholdBps`, the position's collateral type threshold. Debt-free
+ * positions (`U64_MAX` ratio) stay `U64_MAX`.
+ */
+export function healthFactor(collateralRatioBps: BN, liquidationThresholdBps: number): BN {
+  if (collateralRatioBps.eq(U64_MAX) || liquidationThresholdBps === 0) {
+    return U64_MAX;
+  }
+  return BN.min(
+    collateralRatioBps.mul(HEALTH_FACTOR_ONE).divn(liquidationThresholdBps),
+    U64_MAX
+  );
+}
@@ -0,0 +1,95 @@
+//! Liquity-style base rate
+//!
+//! The base rate rises with GUSD volume and decays back toward zero with a 12-hour
+//! half-life. Fees charge it on top of their floor, so they climb during bursts of activity
+//! and fall back once the activity stops.
+
+use anchor_lang::prelude::*;
+
+use crate::{GusdError, ProtocolState, BPS_DENOMINATOR};
+
+/// 1.0 in base-rate precision (18 decimals)
+pub const BASE_RATE_ONE: u64 = 1_000_000_000_000_000_000;
+
+/// Decay per elapsed minute, (1/2)^(1/720): a 12-hour half-life
+const MINUTE_DECAY_FACTOR: u64 = 999_037_758_833_783_000;
+
+/// Longest decay computed (about 1000 years); the rate is zero long before
+const MAX_DECAY_MINUTES: u64 = 525_600_000;
+
+/// Multiply two 18-decimal values, rounding to nearest
+fn dec_mul(a: u128, b: u128) -> u128 {
+    (a * b + BASE_RATE_ONE as u128 / 2) / BASE_RATE_ONE as u128
+}
+
+/// `base` (18 decimals, ≤ 1.0) raised to `exponent`, by squaring
+fn dec_pow(base: u64, mut exponent: u64) -> u128 {
+    let mut base = base as u128;
+    let mut result = BASE_RATE_ONE as u128;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = dec_mul(result, base);
+        }
+        base = dec_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Whole minutes since the last fee operation
+fn minutes_elapsed(protocol: &ProtocolState, now: i64) -> u64 {
+    (now.saturating_sub(protocol.last_fee_op_ts).max(0) / 60) as u64
+}
+
+/// Base rate decayed to `now`, without updating state
+pub fn decayed_base_rate(protocol: &ProtocolState, now: i64) -> u64 {
+    let minutes = minutes_elapsed(protocol, now).min(MAX_DECAY_MINUTES);
+    if minutes == 0 {
+        return protocol.base_rate;
+    }
+    // Both factors are ≤ 1.0, so the product fits in u64
+    dec_mul(protocol.base_rate as u128, dec_pow(MINUTE_DECAY_FACTOR, minutes)) as u64
+}
+
+/// Decay the stored base rate to `now`
+/// `last_fee_op_ts` advances by whole minutes only, so frequent operations lose no decay.
+pub fn decay_base_rate(protocol: &mut ProtocolState, now: i64) {
+    let minutes = minutes_elapsed(protocol, now);
+    if minutes == 0 {
+        return;
+    }
+    protocol.base_rate = decayed_base_rate(protocol, now);
+    protocol.last_fee_op_ts = protocol.last_fee_op_ts
+        .saturating_add((minutes as i64).saturating_mul(60));
+}
+
+/// Raise the base rate by `weight_bps` of `volume` as a fraction of `supply`, capped at 100%
+pub fn bump_base_rate(
+    protocol: &mut ProtocolState,
+    volume: u64,
+    supply: u64,
+    weight_bps: u64,
+) -> Result<()> {
+    if supply == 0 || weight_bps == 0 {
+        return Ok(());
+    }
+    let increase = (volume as u128)
+        .checked_mul(BASE_RATE_ONE as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(supply as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_mul(weight_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    protocol.base_rate = (protocol.base_rate as u128)
+        .saturating_add(increase)
+        .min(BASE_RATE_ONE as u128) as u64;
+    Ok(())
+}
+
+/// Fee of `floor_bps` plus the base rate, capped at `max_bps`
+pub fn fee_with_base_rate_bps(base_rate: u64, floor_bps: u64, max_bps: u64) -> u64 {
+    let base_rate_bps = (base_rate as u128 * BPS_DENOMINATOR as u128
+        / BASE_RATE_ONE as u128) as u64;
+    floor_bps.saturating_add(base_rate_bps).min(max_bps)
+}
@@ -1,8 +1,41 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    hash::hashv,
+    instruction::{get_stack_height, AccountMeta, Instruction, TRANSACTION_LEVEL_STACK_HEIGHT},
+    program::{get_return_data, invoke, invoke_signed},
+};
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{self, Mint, MintTo, Burn, TokenAccount, TokenInterface},
+    token_interface::{
+        self, get_mint_extension_data,
+        spl_token_2022::{
+            self,
+            extension::{
+                non_transferable::NonTransferable, transfer_fee::TransferFeeConfig,
+                BaseStateWithExtensions, StateWithExtensions,
+            },
+            instruction::AuthorityType,
+        },
+        Burn, CloseAccount, FreezeAccount, Mint, MintTo, SetAuthority, ThawAccount,
+        TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+mod base_rate;
+mod oracle;
+pub mod units;
+
+use base_rate::{bump_base_rate, decay_base_rate, decayed_base_rate, fee_with_base_rate_bps};
+use oracle::{
+    apply_confidence, apply_price_spread, compute_twap, current_admin_price, decode_streams_report,
+    apply_fx_rate, get_price, get_price_with_fallback, get_spot_price, oracle_observed_at, read_oracle_price,
+    read_stake_pool_rate, observation_due, observation_spacing_secs, record_price_observation, update_ema_price, PriceBound,
+    PriceOperation,
 };
+use units::{health_factor, GorLamports, GusdUnits, UsdValue6};
 
 declare_id!("GUSD1111111111111111111111111111111111111111");
 
@@ -10,14 +43,36 @@ declare_id!("GUSD1111111111111111111111111111111111111111");
 // CONSTANTS
 // ============================================================================
 
+/// Prefix of the protocol, vault, vault_collateral, and gusd_mint PDA seeds
+/// Set at build time with the `GUSD_SEED_NAMESPACE` environment variable (e.g., `geur`) so
+/// independent instances built from this codebase never share addresses. Empty by default,
+/// which derives the same addresses as an unprefixed seed.
+pub const SEED_NAMESPACE: &[u8] = match option_env!("GUSD_SEED_NAMESPACE") {
+    Some(namespace) => namespace.as_bytes(),
+    None => b"",
+};
+const _: () = assert!(SEED_NAMESPACE.len() <= 32, "GUSD_SEED_NAMESPACE exceeds the PDA seed limit");
+
 /// Collateral ratio required to mint (150% = 15000 basis points)
 pub const MIN_COLLATERAL_RATIO_BPS: u64 = 15000;
 
 /// Collateral ratio below which liquidation is allowed (120% = 12000 basis points)
 pub const LIQUIDATION_THRESHOLD_BPS: u64 = 12000;
 
-/// Liquidation penalty (10% = 1000 basis points)
-pub const LIQUIDATION_PENALTY_BPS: u64 = 1000;
+/// Highest minimum collateral ratio a collateral type may require (500%)
+pub const MAX_COLLATERAL_RATIO_BPS: u64 = 50_000;
+
+/// 1.0 in stake pool exchange-rate precision (GOR lamports per 1e9 pool token base units)
+pub const EXCHANGE_RATE_ONE: u64 = 1_000_000_000;
+
+/// Epochs an LST feed's exchange rate stays usable after its last refresh
+pub const MAX_EXCHANGE_RATE_AGE_EPOCHS: u64 = 1;
+
+/// Default liquidator bonus, the whole default liquidation penalty (10% = 1000 basis points)
+pub const DEFAULT_LIQUIDATION_BONUS_BPS: u64 = 1000;
+
+/// Health factor of a position exactly at the liquidation threshold (1.0, 6 decimals)
+pub const HEALTH_FACTOR_ONE: u64 = 1_000_000;
 
 /// Basis points denominator
 pub const BPS_DENOMINATOR: u64 = 10000;
@@ -25,15 +80,323 @@ pub const BPS_DENOMINATOR: u64 = 10000;
 /// GUSD decimals (6, like USDC)
 pub const GUSD_DECIMALS: u8 = 6;
 
-/// GOR decimals (9, like SOL)
+/// GOR decimals (9, like SOL); default for `native_decimals` on Gorbagana
 pub const GOR_DECIMALS: u8 = 9;
 
-/// Maximum price change per update (20% = 2000 BPS) [MEDIUM-1]
-pub const MAX_PRICE_CHANGE_BPS: u64 = 2000;
+/// Upper bound for `native_decimals` accepted at initialize
+pub const MAX_NATIVE_DECIMALS: u8 = 18;
+
+/// Default minimum liquidator profit on repaid debt (5% = 500 BPS)
+pub const DEFAULT_MIN_LIQUIDATION_PROFIT_BPS: u64 = 500;
+
+/// Default speed at which the admin price moves toward a new target (BPS of the
+/// price per second; 1 = a 20% move takes about 33 minutes) [MEDIUM-1]
+pub const DEFAULT_PRICE_SLEW_BPS_PER_SEC: u64 = 1;
+
+/// Upper bound for the price slew rate (BPS per second)
+pub const MAX_PRICE_SLEW_BPS_PER_SEC: u64 = 10000;
 
 /// Minimum seconds between admin price updates (MVP safety)
 pub const MIN_PRICE_UPDATE_INTERVAL_SECS: i64 = 1;
 
+/// Anchor discriminator of the Pyth `PriceUpdateV2` account
+pub const PYTH_PRICE_UPDATE_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+/// Default maximum age of an oracle price before it is treated as stale
+pub const DEFAULT_MAX_PRICE_AGE_SECS: u64 = 60;
+
+/// Upper bound for the configurable price age limits (1 hour)
+pub const MAX_PRICE_AGE_LIMIT_SECS: u64 = 60 * 60;
+
+/// Maximum haircut applied to the cached price when the oracle is stale (50%)
+pub const MAX_STALE_PRICE_HAIRCUT_BPS: u64 = 5000;
+
+/// Anchor discriminator of the Switchboard `PullFeedAccountData` account
+pub const SWITCHBOARD_PULL_FEED_DISCRIMINATOR: [u8; 8] = [196, 27, 108, 196, 10, 215, 219, 40];
+
+/// Default maximum Pyth confidence interval relative to price (2%)
+pub const DEFAULT_MAX_CONFIDENCE_BPS: u64 = 200;
+
+/// Upper bound for the configurable `max_confidence_bps` (20%)
+pub const MAX_CONFIDENCE_LIMIT_BPS: u64 = 2000;
+
+/// Default oracle deviation from the last accepted price that trips the circuit breaker (20%)
+pub const DEFAULT_CIRCUIT_BREAKER_DEVIATION_BPS: u64 = 2000;
+
+/// Deviations are only compared against accepted prices at most this old (5 minutes)
+pub const CIRCUIT_BREAKER_WINDOW_SECS: i64 = 5 * 60;
+
+/// Time constant of the protocol price EMA (1 hour)
+pub const EMA_PERIOD_SECS: i64 = 60 * 60;
+
+/// Anchor discriminator of the Chainlink Data Streams verifier `verify` instruction
+pub const CHAINLINK_VERIFY_DISCRIMINATOR: [u8; 8] = [133, 161, 141, 48, 120, 198, 88, 150];
+
+/// Decimals of Chainlink Data Streams prices
+pub const CHAINLINK_STREAMS_PRECISION: u32 = 18;
+
+/// Approximate slot duration, used to convert Switchboard result slots into an age
+pub const SLOT_DURATION_MS: u64 = 400;
+
+/// Fixed-point precision of Switchboard feed values
+pub const SWITCHBOARD_PRECISION: u32 = 18;
+
+/// Price range covered by the CR ladder view on each side of spot (50% = 5000 BPS)
+pub const CR_LADDER_RANGE_BPS: u64 = 5000;
+
+/// Number of points returned by the CR ladder view (spot -50% .. spot +50%)
+pub const CR_LADDER_POINTS: u64 = 11;
+
+/// Inactivity after which an empty vault may be flagged as abandoned (2 years)
+pub const VAULT_INACTIVITY_PERIOD_SECS: i64 = 2 * 365 * 24 * 60 * 60;
+
+/// Window in which an owner can reclaim a flagged vault before it is swept (90 days)
+pub const VAULT_RECLAIM_WINDOW_SECS: i64 = 90 * 24 * 60 * 60;
+
+/// Collateral value (USD, 6 decimals) at or below which an abandoned vault counts as dust ($1)
+pub const ABANDONED_VAULT_DUST_USD: u64 = 1_000_000;
+
+/// Minimum lead time for a governance notice affecting vaults (7 days)
+pub const GOVERNANCE_NOTICE_MIN_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Number of observations kept by the price accumulator ring buffer
+pub const PRICE_OBSERVATION_COUNT: usize = 32;
+
+/// Maximum TWAP window (24 hours)
+pub const MAX_TWAP_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Maximum number of entries in the audit registry
+pub const MAX_AUDIT_RECORDS: usize = 16;
+
+/// Entries per parameter changelog page
+pub const PARAMETER_LOG_PAGE_SIZE: usize = 32;
+
+/// Changelog value of an optional parameter that falls back to the protocol-wide one
+pub const PARAMETER_UNSET: u64 = u64::MAX;
+
+/// Length of one repayment streak period (30 days)
+pub const REPAYMENT_PERIOD_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Stability-fee rebate tiers as (minimum streak in periods, rebate bps)
+pub const REPAYMENT_REBATE_TIERS: [(u16, u64); 3] = [(3, 25), (6, 50), (12, 100)];
+
+/// Debt index value of 1.0 (18 decimals)
+pub const DEBT_INDEX_ONE: u128 = 1_000_000_000_000_000_000;
+
+/// Upper bound for the annual stability fee (20% = 2000 BPS)
+pub const MAX_STABILITY_FEE_BPS: u64 = 2000;
+
+/// Upper bound for the rate model's slope above the kink (100% = 10000 BPS)
+pub const MAX_RATE_SLOPE2_BPS: u64 = 10000;
+
+/// Default utilization at which the rate model's steep slope starts (80%)
+pub const DEFAULT_RATE_KINK_BPS: u64 = 8000;
+
+/// Upper bound for the mint (origination) fee (5% = 500 BPS)
+pub const MAX_MINT_FEE_BPS: u64 = 500;
+
+/// Upper bound for the collateral withdrawal fee (1% = 100 BPS)
+pub const MAX_WITHDRAW_FEE_BPS: u64 = 100;
+
+/// Upper bound for the liquidation penalty, liquidator bonus plus protocol fee (20%)
+pub const MAX_LIQUIDATION_PENALTY_BPS: u64 = 2000;
+
+/// Upper bound for the annual rate lock premium (10% of debt per year locked)
+pub const MAX_RATE_LOCK_PREMIUM_BPS: u64 = 1000;
+
+/// Longest stability fee rate lock (days)
+pub const MAX_RATE_LOCK_DAYS: u16 = 365;
+
+/// Seconds per year used to spread the annual stability fee per second
+pub const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+/// Max share of the treasury balance swappable into USDC per epoch (10%)
+pub const TREASURY_SWAP_EPOCH_CAP_BPS: u64 = 1000;
+
+/// Maximum accounts in a whitelisted treasury swap route
+pub const MAX_SWAP_ROUTE_ACCOUNTS: usize = 24;
+
+/// Max slippage of a treasury swap versus the protocol price (2%)
+pub const MAX_TREASURY_SWAP_SLIPPAGE_BPS: u64 = 200;
+
+/// Compute budget program, whose instructions set a transaction's priority fee
+pub mod compute_budget {
+    anchor_lang::declare_id!("ComputeBudget111111111111111111111111111111");
+}
+
+/// Base fee of a single-signature transaction (lamports)
+pub const BASE_SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+/// Compute units a transaction is charged per instruction when it sets no limit
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNITS: u64 = 200_000;
+
+/// Compute unit limit of a transaction
+pub const MAX_TRANSACTION_COMPUTE_UNITS: u64 = 1_400_000;
+
+/// Largest transaction fee credited to a keeper for one crank (lamports), so an outsized
+/// priority fee can't inflate the rebate
+pub const MAX_KEEPER_CRANK_FEE_LAMPORTS: u64 = 100_000;
+
+/// Max value gap between the GUSD and GOR legs of a liquidity deployment, at the
+/// protocol price (2%)
+pub const MAX_POL_IMBALANCE_BPS: u64 = 200;
+
+/// Maximum number of registered price feeders
+pub const MAX_PRICE_FEEDERS: usize = 8;
+
+/// Domain prefix of feeder-signed price messages
+pub const SIGNED_PRICE_DOMAIN: &[u8] = b"GUSD_PRICE_V2";
+
+/// Domain prefix of feeder-signed price batches
+pub const SIGNED_PRICE_BATCH_DOMAIN: &[u8] = b"GUSD_PRICE_BATCH_V2";
+
+/// Maximum observations in one signed price batch
+pub const MAX_PRICE_BATCH_LEN: usize = 16;
+
+/// Maximum price feeds refreshed by one `update_prices_batch`
+pub const MAX_PRICE_FEED_BATCH_LEN: usize = 8;
+
+/// Domain prefix of vault state hashes
+pub const VAULT_STATE_DOMAIN: &[u8] = b"GUSD_VAULT_STATE_V1";
+
+/// Length of one liquidation insurance period (30 days)
+pub const INSURANCE_PERIOD_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Maximum insurance premium per period, in bps of vault debt (5%)
+pub const MAX_INSURANCE_PREMIUM_BPS: u64 = 500;
+
+/// Maximum liquidation penalty discount for insured vaults (50%)
+pub const MAX_INSURANCE_DISCOUNT_BPS: u64 = 5000;
+
+/// Maximum length of a token symbol in the display config
+pub const MAX_SYMBOL_LEN: usize = 10;
+
+/// Maximum length of an explorer URL template in the display config
+pub const MAX_URL_LEN: usize = 128;
+
+/// Key of the native collateral's price feed (native GOR has no mint)
+pub const NATIVE_COLLATERAL_MINT: Pubkey = Pubkey::new_from_array([0u8; 32]);
+
+/// Position key of the bootstrap vault, which has no position NFT; seeds its PDAs in place
+/// of a position mint
+pub const BOOTSTRAP_VAULT_POSITION: Pubkey = Pubkey::new_from_array([0u8; 32]);
+
+/// Interval between automatic debt ceiling increases during the launch ramp (1 week)
+pub const LAUNCH_RAMP_INTERVAL_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Maximum debt ceiling increase per launch ramp step (100%)
+pub const MAX_LAUNCH_RAMP_STEP_BPS: u64 = 10000;
+
+/// Maximum vaults linked to one margin account
+pub const MAX_MARGIN_VAULTS: usize = 8;
+
+/// Maximum extra collateral balances backing one vault's debt
+pub const MAX_COLLATERAL_BALANCES: usize = 4;
+
+/// Default extra collateral ratio margin accounts must hold above the minimum (BPS)
+pub const DEFAULT_MARGIN_BUFFER_BPS: u64 = 2500;
+
+/// Upper bound for the margin buffer (BPS)
+pub const MAX_MARGIN_BUFFER_BPS: u64 = 10000;
+
+/// Default spread between mint-side and liquidation-side prices (BPS, 0.5%)
+pub const DEFAULT_PRICE_SPREAD_BPS: u64 = 50;
+
+/// Upper bound for the price spread (BPS)
+pub const MAX_PRICE_SPREAD_BPS: u64 = 500;
+
+/// Upper bound for the liquidation target collateral ratio (BPS)
+pub const MAX_LIQUIDATION_TARGET_CR_BPS: u64 = 20000;
+
+/// Hard upper bound for the GUSD max supply parameter (10 billion GUSD)
+pub const MAX_GUSD_SUPPLY_LIMIT: u64 = 10_000_000_000 * 1_000_000;
+
+/// Default minimum outstanding debt of a vault with debt (50 GUSD)
+pub const DEFAULT_MIN_VAULT_DEBT: u64 = 50 * 1_000_000;
+
+/// Upper bound for the minimum vault debt (10,000 GUSD)
+pub const MAX_MIN_VAULT_DEBT: u64 = 10_000 * 1_000_000;
+
+/// GUSD peg target (peg currency, 6 decimals)
+pub const GUSD_PEG_PRICE: u64 = 1_000_000;
+
+/// ISO 4217 code of the currency a fresh deployment's debt token is pegged to
+pub const DEFAULT_PEG_CURRENCY: [u8; 3] = *b"USD";
+
+/// Default GUSD market price deviation treated as a de-peg (BPS, 2%)
+pub const DEFAULT_DEPEG_THRESHOLD_BPS: u64 = 200;
+
+/// Upper bound for the de-peg threshold (BPS)
+pub const MAX_DEPEG_THRESHOLD_BPS: u64 = 5000;
+
+/// Minimum time an oracle must run in shadow mode before its switch can be scheduled
+pub const MIN_ORACLE_SHADOW_SECS: u64 = 24 * 60 * 60;
+
+/// Delay between scheduling an oracle switch and activating it
+pub const ORACLE_SWITCH_TIMELOCK_SECS: i64 = 48 * 60 * 60;
+
+/// Fallback oracle sources per price feed, tried in order after the primary
+pub const MAX_FALLBACK_ORACLES: usize = 2;
+
+/// Health records kept per vault attestation account
+pub const HEALTH_ATTESTATION_COUNT: usize = 16;
+
+/// Maximum vaults checked by one `check_and_flag_page` call
+pub const MAX_SCAN_PAGE_VAULTS: usize = 16;
+
+/// Upper bound for the priority liquidation window after a vault is flagged (~60s)
+pub const MAX_PRIORITY_WINDOW_SLOTS: u64 = 150;
+
+/// Bidding time of the first priority liquidation rights auction
+pub const PRIORITY_AUCTION_BIDDING_SECS: i64 = 60 * 60;
+
+/// Length of each priority liquidation rights period (bidding for the next runs during it)
+pub const PRIORITY_RIGHTS_SECS: i64 = 24 * 60 * 60;
+
+/// Minimum raise over the leading priority rights bid (5%)
+pub const MIN_PRIORITY_BID_INCREMENT_BPS: u64 = 500;
+
+/// Shortest configurable pause lifetime (1 hour)
+pub const MIN_MAX_PAUSE_SECS: u64 = 60 * 60;
+
+/// Longest configurable pause lifetime (30 days)
+pub const MAX_MAX_PAUSE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Default wait between proposing and executing a treasury spend (48 hours)
+pub const DEFAULT_TREASURY_SPEND_DELAY_SECS: u64 = 48 * 60 * 60;
+
+/// Shortest configurable treasury spend delay (24 hours)
+pub const MIN_TREASURY_SPEND_DELAY_SECS: u64 = 24 * 60 * 60;
+
+/// Longest configurable treasury spend delay (30 days)
+pub const MAX_TREASURY_SPEND_DELAY_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Staker fee tiers held by the `FeeTierRegistry`
+pub const MAX_FEE_TIERS: usize = 4;
+
+/// Largest fee discount a staker tier can grant (50%)
+pub const MAX_STAKER_DISCOUNT_BPS: u64 = 5000;
+
+/// Time a governance stake must be held before it earns a fee discount (7 days), so a
+/// stake borrowed for a single transaction earns nothing
+pub const STAKE_WARMUP_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Time after an emergency shutdown during which GUSD holders queue redemptions
+pub const REDEMPTION_REQUEST_WINDOW_SECS: i64 = 24 * 60 * 60;
+
+/// Time after the settlement price is marked for keepers to settle every vault (7 days);
+/// redemption claims open when it ends even if debt is left, e.g. uncovered bad debt
+pub const SETTLEMENT_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Health attestation flag: vault is below the liquidation threshold
+pub const HEALTH_FLAG_LIQUIDATABLE: u8 = 1 << 0;
+
+/// Health attestation flag: vault is below the minimum collateral ratio
+pub const HEALTH_FLAG_BELOW_MIN_CR: u8 = 1 << 1;
+
+/// Health attestation flag: the price was under a guardian dispute
+pub const HEALTH_FLAG_PRICE_DISPUTED: u8 = 1 << 2;
+
 // ============================================================================
 // PROGRAM
 // ============================================================================
@@ -51,21 +414,185 @@ pub mod gusd_stablecoin {
     ///   - 4776 = $0.004776 (current sGOR price)
     ///   - 1_000_000 = $1.00
     ///   - 10_000 = $0.01
-    pub fn initialize(ctx: Context<Initialize>, initial_gor_price_usd: u64) -> Result<()> {
+    /// * `native_decimals` - Decimals of the chain's native token (9 on Gorbagana)
+    /// * `pyth_receiver_program` - Owner of Pyth `PriceUpdateV2` accounts on this cluster
+    /// * `switchboard_program` - Owner of Switchboard `PullFeedAccountData` accounts on this cluster
+    /// * `chainlink_verifier_program` - Chainlink Data Streams verifier on this cluster
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        initial_gor_price_usd: u64,
+        native_decimals: u8,
+        pyth_receiver_program: Pubkey,
+        switchboard_program: Pubkey,
+        chainlink_verifier_program: Pubkey,
+    ) -> Result<()> {
         // [LOW-1] Validate initial price
         require!(initial_gor_price_usd > 0, GusdError::InvalidPrice);
+        require!(
+            native_decimals <= MAX_NATIVE_DECIMALS,
+            GusdError::InvalidNativeDecimals
+        );
 
+        let now = Clock::get()?.unix_timestamp;
         let protocol = &mut ctx.accounts.protocol_state;
         
         protocol.admin = ctx.accounts.admin.key();
         protocol.gusd_mint = ctx.accounts.gusd_mint.key();
-        protocol.gor_price_usd = initial_gor_price_usd; // Price in USD with 6 decimals
         protocol.total_collateral = 0;
         protocol.total_debt = 0;
         protocol.bump = ctx.bumps.protocol_state;
         protocol.mint_bump = ctx.bumps.gusd_mint;
-        protocol.is_paused = false; // [MEDIUM-2] Initialize pause state
-        protocol.last_price_update_ts = Clock::get()?.unix_timestamp;
+        protocol.lifecycle = Lifecycle::Active; // Guarded once a launch ramp is set
+        protocol.native_decimals = native_decimals;
+        protocol.compliance_authority = ctx.accounts.admin.key();
+        protocol.epoch_liquidation_cap = 0;
+        protocol.epoch_redemption_cap = 0;
+        protocol.outflow_epoch = Clock::get()?.epoch;
+        protocol.epoch_liquidated_collateral = 0;
+        protocol.epoch_redeemed_collateral = 0;
+        protocol.risk_manager = ctx.accounts.admin.key();
+        protocol.accrual_paused_at = 0;
+        protocol.accrual_skipped_secs = 0;
+        protocol.stability_fee_bps = 0; // No interest until configured
+        protocol.rate_target_debt = 0; // Flat stability fee until a rate model is configured
+        protocol.rate_base_bps = 0;
+        protocol.rate_slope1_bps = 0;
+        protocol.rate_slope2_bps = 0;
+        protocol.rate_kink_bps = DEFAULT_RATE_KINK_BPS;
+        protocol.debt_index = DEBT_INDEX_ONE;
+        protocol.debt_index_updated_at = now;
+        protocol.accrued_fees = 0;
+        protocol.surplus_buffer = 0;
+        protocol.surplus_buffer_cap = 0; // All fee revenue goes to the treasury
+        protocol.parameter_log_entries = 0;
+        protocol.savings_fee_share_bps = 0; // No savings yield until configured
+        protocol.savings_accrued = 0;
+        protocol.paused_at = 0;
+        protocol.max_pause_secs = 0; // Pauses last until unpaused
+        protocol.bad_debt = 0;
+        protocol.rate_lock_premium_bps = 0; // Rate locks unavailable until configured
+        protocol.base_rate = 0;
+        protocol.last_fee_op_ts = now;
+        protocol.base_rate_mint_weight_bps = 0; // Mints don't move the base rate until configured
+        protocol.mint_fee_upfront = false; // Fees are added to vault debt
+        protocol.stability_fee_upfront = false;
+        protocol.liquidation_tip_max_bps = 0; // Liquidation tips disabled until configured
+        protocol.keeper_rebate_epoch_cap = 0; // Keeper rebates disabled until configured
+        protocol.keeper_rebate_epoch = 0;
+        protocol.keeper_rebated_in_epoch = 0;
+        protocol.insurance_fee_share_bps = 0; // Insurance fund gets no fee revenue until configured
+        protocol.insurance_accrued = 0;
+        protocol.mint_fee_bps = 0; // No origination fee until configured
+        protocol.shutdown_at = 0;
+        protocol.withdraw_fee_bps = 0; // No withdrawal fee until configured
+        protocol.withdraw_fees_collected = 0;
+        protocol.liquidation_bonus_bps = DEFAULT_LIQUIDATION_BONUS_BPS;
+        protocol.liquidation_fee_bps = 0; // Liquidators keep the whole penalty
+        protocol.fee_destination = ctx.accounts.admin.key();
+        protocol.treasury_spend_delay_secs = DEFAULT_TREASURY_SPEND_DELAY_SECS;
+        protocol.treasury_spend_count = 0;
+        protocol.priority_window_slots = 0; // No priority liquidation window
+        protocol.priority_liquidator = Pubkey::default();
+        protocol.priority_rights_until = 0;
+        protocol.min_liquidation_profit_bps = DEFAULT_MIN_LIQUIDATION_PROFIT_BPS;
+        protocol.treasurer = ctx.accounts.admin.key();
+        protocol.pending_notice = None;
+        protocol.twap_window_secs = 0; // Spot price until configured
+        protocol.twap_for_liquidations = false;
+        protocol.max_price_age_secs = DEFAULT_MAX_PRICE_AGE_SECS;
+        protocol.mint_max_price_age_secs = 0; // Use max_price_age_secs
+        protocol.withdraw_max_price_age_secs = 0;
+        protocol.liquidation_max_price_age_secs = 0;
+        protocol.margin_buffer_bps = DEFAULT_MARGIN_BUFFER_BPS;
+        protocol.price_spread_bps = DEFAULT_PRICE_SPREAD_BPS;
+        protocol.depeg_threshold_bps = DEFAULT_DEPEG_THRESHOLD_BPS;
+        protocol.liquidation_target_cr_bps = 0; // Repay as much as collateral allows
+        protocol.price_slew_bps_per_sec = DEFAULT_PRICE_SLEW_BPS_PER_SEC;
+        protocol.peg_currency = DEFAULT_PEG_CURRENCY;
+        protocol.fx_price_feed = Pubkey::default(); // Prices quoted in the peg currency
+        protocol.fx_rate = 0;
+        protocol.fx_rate_updated_at = 0;
+        protocol.stale_price_haircut_bps = 0; // Reject stale prices by default
+        protocol.max_confidence_bps = DEFAULT_MAX_CONFIDENCE_BPS;
+        protocol.ema_price = initial_gor_price_usd;
+        protocol.ema_updated_at = now;
+        protocol.price_mode = PriceMode::Spot;
+        protocol.circuit_breaker_deviation_bps = DEFAULT_CIRCUIT_BREAKER_DEVIATION_BPS;
+        protocol.insurance_premium_bps = 0; // Insurance unavailable until configured
+        protocol.insurance_discount_bps = 0;
+        protocol.treasury_swap_epoch = 0;
+        protocol.treasury_swap_epoch_limit = 0;
+        protocol.treasury_swapped_in_epoch = 0;
+        protocol.guardian = ctx.accounts.admin.key();
+        protocol.debt_ceiling = 0; // Unlimited until a launch ramp is configured
+        protocol.max_supply = 0; // Unlimited until the risk manager caps it
+        protocol.min_vault_debt = DEFAULT_MIN_VAULT_DEBT;
+        protocol.max_vault_debt = 0; // No per-vault cap until the risk manager sets one
+        protocol.launch_ramp_step_bps = 0;
+        protocol.launch_ramp_target = 0;
+        protocol.launch_ramp_last_step_ts = 0;
+        protocol.price_disputed_at = 0;
+
+        init_price_feed_state(
+            &mut ctx.accounts.price_feed,
+            NATIVE_COLLATERAL_MINT,
+            native_decimals,
+            initial_gor_price_usd,
+            now,
+            now,
+            ctx.bumps.price_feed,
+        );
+        // GOR is the first collateral type, at the protocol's floor ratios
+        init_collateral_config(
+            &mut ctx.accounts.collateral_config,
+            &ctx.accounts.price_feed,
+            MIN_COLLATERAL_RATIO_BPS,
+            LIQUIDATION_THRESHOLD_BPS,
+            now,
+            ctx.bumps.collateral_config,
+        );
+
+        let accumulator = &mut ctx.accounts.price_accumulator;
+        accumulator.last_price = initial_gor_price_usd;
+        accumulator.last_update_ts = now;
+        accumulator.observations[0] = PriceObservation {
+            timestamp: now,
+            cumulative_price: 0,
+            price: initial_gor_price_usd,
+        };
+        accumulator.bump = ctx.bumps.price_accumulator;
+
+        let deployment = &mut ctx.accounts.deployment_config;
+        deployment.pyth_receiver_program = pyth_receiver_program;
+        deployment.switchboard_program = switchboard_program;
+        deployment.chainlink_verifier_program = chainlink_verifier_program;
+        deployment.swap_program = Pubkey::default(); // Treasury swaps disabled until set
+        deployment.usdc_mint = Pubkey::default();
+        deployment.swap_route_hash = [0; 32];
+        deployment.kyc_attestation_mint = Pubkey::default(); // Vaults open to anyone
+        deployment.stake_pool_program = Pubkey::default(); // No LST collateral until set
+        deployment.bump = ctx.bumps.deployment_config;
+
+        let stats = &mut ctx.accounts.revenue_stats;
+        stats.stability_fees = 0;
+        stats.mint_fees = 0;
+        stats.rate_lock_premiums = 0;
+        stats.insurance_premiums = 0;
+        stats.liquidation_fees = 0;
+        stats.withdraw_fees = 0;
+        stats.bump = ctx.bumps.revenue_stats;
+
+        let supply = &mut ctx.accounts.supply_stats;
+        supply.minted_vault_debt = 0;
+        supply.minted_treasury_fees = 0;
+        supply.minted_savings_yield = 0;
+        supply.minted_insurance_fees = 0;
+        supply.burned_repayments = 0;
+        supply.burned_liquidations = 0;
+        supply.burned_redemptions = 0;
+        supply.burned_treasury = 0;
+        supply.burned_bad_debt = 0;
+        supply.bump = ctx.bumps.supply_stats;
 
         msg!("GUSD Protocol initialized!");
         msg!("Initial GOR price: ${}", initial_gor_price_usd as f64 / 1_000_000.0);
@@ -73,52 +600,107 @@ pub mod gusd_stablecoin {
         Ok(())
     }
 
-    /// Update the GOR/USD price (admin only for MVP)
+    /// Update a feed's admin price (admin only for MVP)
     /// In production, this would use an oracle like Pyth
-    /// [MEDIUM-1] Now includes price change limits
+    /// [MEDIUM-1] The new price is a target the effective price ramps toward. The feed is
+    /// the `price_feed` passed in; only the native feed drives the TWAP and EMA.
     pub fn update_price(ctx: Context<UpdatePrice>, new_gor_price_usd: u64) -> Result<()> {
-        require!(new_gor_price_usd > 0, GusdError::InvalidPrice);
+        let now = Clock::get()?.unix_timestamp;
+        let old_price = apply_price_update(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.price_accumulator,
+            new_gor_price_usd,
+            now,
+        )?;
 
-        let protocol = &mut ctx.accounts.protocol_state;
-        let old_price = protocol.gor_price_usd;
+        msg!("GOR price updated: {} -> {}", old_price, new_gor_price_usd);
 
-        // Enforce a minimum update interval (helps mitigate admin compromise / fat-finger risk)
-        let now = Clock::get()?.unix_timestamp;
-        let elapsed = now.saturating_sub(protocol.last_price_update_ts);
+        // [MEDIUM-3] Emit event
+        emit!(PriceUpdated {
+            collateral_mint: ctx.accounts.price_feed.collateral_mint,
+            old_price,
+            new_price: new_gor_price_usd,
+        });
+
+        Ok(())
+    }
+
+    /// Register the m-of-n feeder keys allowed to sign prices (admin only)
+    pub fn set_price_feeders(
+        ctx: Context<SetPriceFeeders>,
+        feeders: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require!(
+            !feeders.is_empty() && feeders.len() <= MAX_PRICE_FEEDERS,
+            GusdError::InvalidParameter
+        );
         require!(
-            elapsed >= MIN_PRICE_UPDATE_INTERVAL_SECS,
-            GusdError::PriceUpdateTooFrequent
+            threshold > 0 && threshold as usize <= feeders.len(),
+            GusdError::InvalidParameter
         );
+        for (i, feeder) in feeders.iter().enumerate() {
+            require!(!feeders[..i].contains(feeder), GusdError::InvalidParameter);
+        }
 
-        // [MEDIUM-1] Calculate absolute price change
-        let price_change = if new_gor_price_usd > old_price {
-            new_gor_price_usd.saturating_sub(old_price)
-        } else {
-            old_price.saturating_sub(new_gor_price_usd)
-        };
+        let registry = &mut ctx.accounts.feeder_registry;
+        registry.feeders = feeders;
+        registry.threshold = threshold;
+        registry.bump = ctx.bumps.feeder_registry;
 
-        // [MEDIUM-1] Check change is within 20% limit (ceiling division so small prices still move)
-        let max_change_u128 = (old_price as u128)
-            .checked_mul(MAX_PRICE_CHANGE_BPS as u128)
-            .ok_or(GusdError::MathOverflow)?
-            .checked_add((BPS_DENOMINATOR - 1) as u128)
-            .ok_or(GusdError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR as u128)
-            .ok_or(GusdError::MathOverflow)?
-            .max(1);
+        msg!("Price feeders set: {}-of-{}", threshold, registry.feeders.len());
+
+        emit!(PriceFeedersUpdated {
+            feeders: registry.feeders.clone(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Update a feed's admin price from a message signed by m-of-n registered feeders
+    /// The transaction must carry ed25519 program instructions verifying each signature
+    /// over `SIGNED_PRICE_DOMAIN || program id || collateral mint || price (u64 LE) ||
+    /// timestamp (i64 LE)`, so a signature for one feed can't be replayed on another.
+    /// Applies the same interval limit and price ramp as `update_price`.
+    pub fn update_price_signed(
+        ctx: Context<UpdatePriceSigned>,
+        new_gor_price_usd: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let registry = &ctx.accounts.feeder_registry;
+        require!(timestamp > registry.last_signed_ts, GusdError::InvalidPriceSignature);
+        require!(
+            timestamp <= now
+                && now.saturating_sub(timestamp) <= ctx.accounts.protocol_state.max_price_age_secs as i64,
+            GusdError::StaleOraclePrice
+        );
 
-        require!(max_change_u128 <= u64::MAX as u128, GusdError::MathOverflow);
-        let max_change = max_change_u128 as u64;
+        let mut message = Vec::with_capacity(SIGNED_PRICE_DOMAIN.len() + 80);
+        message.extend_from_slice(SIGNED_PRICE_DOMAIN);
+        message.extend_from_slice(crate::ID.as_ref());
+        message.extend_from_slice(ctx.accounts.price_feed.collateral_mint.as_ref());
+        message.extend_from_slice(&new_gor_price_usd.to_le_bytes());
+        message.extend_from_slice(&timestamp.to_le_bytes());
 
-        require!(price_change <= max_change, GusdError::PriceChangeExceedsLimit);
+        let signers = count_feeder_signatures(&ctx.accounts.instructions, &registry.feeders, &message)?;
+        require!(signers >= registry.threshold as usize, GusdError::InvalidPriceSignature);
 
-        protocol.gor_price_usd = new_gor_price_usd;
-        protocol.last_price_update_ts = now;
+        let old_price = apply_price_update(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.price_accumulator,
+            new_gor_price_usd,
+            now,
+        )?;
+        ctx.accounts.feeder_registry.last_signed_ts = timestamp;
 
-        msg!("GOR price updated: {} -> {}", old_price, new_gor_price_usd);
+        msg!("GOR price updated by {} feeders: {} -> {}", signers, old_price, new_gor_price_usd);
 
-        // [MEDIUM-3] Emit event
         emit!(PriceUpdated {
+            collateral_mint: ctx.accounts.price_feed.collateral_mint,
             old_price,
             new_price: new_gor_price_usd,
         });
@@ -126,1016 +708,18003 @@ pub mod gusd_stablecoin {
         Ok(())
     }
 
-    /// [MEDIUM-2] Pause protocol (admin only)
-    pub fn pause_protocol(ctx: Context<UpdatePrice>) -> Result<()> {
-        ctx.accounts.protocol_state.is_paused = true;
-        msg!("Protocol paused");
-        Ok(())
-    }
+    /// Apply a batch of feeder-observed prices collected off-chain since the last update
+    /// Signed like `update_price_signed`, over `SIGNED_PRICE_BATCH_DOMAIN || program id ||
+    /// collateral mint || (price (u64 LE) || timestamp (i64 LE))` for each observation. Observations must have
+    /// strictly increasing timestamps after the last accumulator checkpoint, and each is
+    /// applied at its own timestamp as a new ramp target, so the TWAP stays accurate
+    /// between infrequent submissions.
+    pub fn update_price_batch_signed(
+        ctx: Context<UpdatePriceSigned>,
+        observations: Vec<PricePoint>,
+    ) -> Result<()> {
+        require!(
+            !observations.is_empty() && observations.len() <= MAX_PRICE_BATCH_LEN,
+            GusdError::InvalidParameter
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let registry = &ctx.accounts.feeder_registry;
+        let first_ts = observations[0].timestamp;
+        let last_ts = observations[observations.len() - 1].timestamp;
+        require!(
+            first_ts > registry.last_signed_ts
+                && first_ts > ctx.accounts.price_accumulator.last_update_ts,
+            GusdError::InvalidPriceSignature
+        );
+        for pair in observations.windows(2) {
+            require!(pair[1].timestamp > pair[0].timestamp, GusdError::InvalidParameter);
+        }
+        require!(
+            last_ts <= now
+                && now.saturating_sub(last_ts) <= ctx.accounts.protocol_state.max_price_age_secs as i64,
+            GusdError::StaleOraclePrice
+        );
+
+        let mut message = Vec::with_capacity(SIGNED_PRICE_BATCH_DOMAIN.len() + 64 + observations.len() * 16);
+        message.extend_from_slice(SIGNED_PRICE_BATCH_DOMAIN);
+        message.extend_from_slice(crate::ID.as_ref());
+        message.extend_from_slice(ctx.accounts.price_feed.collateral_mint.as_ref());
+        for observation in &observations {
+            message.extend_from_slice(&observation.price.to_le_bytes());
+            message.extend_from_slice(&observation.timestamp.to_le_bytes());
+        }
+
+        let signers = count_feeder_signatures(&ctx.accounts.instructions, &registry.feeders, &message)?;
+        require!(signers >= registry.threshold as usize, GusdError::InvalidPriceSignature);
+
+        let mut old_price = 0;
+        for (i, observation) in observations.iter().enumerate() {
+            let previous = apply_price_update(
+                &mut ctx.accounts.protocol_state,
+                &mut ctx.accounts.price_feed,
+                &mut ctx.accounts.price_accumulator,
+                observation.price,
+                observation.timestamp,
+            )?;
+            if i == 0 {
+                old_price = previous;
+            }
+        }
+        ctx.accounts.feeder_registry.last_signed_ts = last_ts;
+
+        let new_price = observations[observations.len() - 1].price;
+        msg!(
+            "GOR price batch of {} applied by {} feeders: {} -> {}",
+            observations.len(),
+            signers,
+            old_price,
+            new_price
+        );
+
+        emit!(PriceUpdated {
+            collateral_mint: ctx.accounts.price_feed.collateral_mint,
+            old_price,
+            new_price,
+        });
 
-    /// [MEDIUM-2] Unpause protocol (admin only)
-    pub fn unpause_protocol(ctx: Context<UpdatePrice>) -> Result<()> {
-        ctx.accounts.protocol_state.is_paused = false;
-        msg!("Protocol unpaused");
         Ok(())
     }
 
-    /// [LOW-2] Transfer admin role to a new address
-    pub fn transfer_admin(ctx: Context<TransferAdmin>, new_admin: Pubkey) -> Result<()> {
-        require!(new_admin != Pubkey::default(), GusdError::InvalidAmount);
-        
-        let protocol = &mut ctx.accounts.protocol_state;
-        let old_admin = protocol.admin;
-        protocol.admin = new_admin;
-        
-        msg!("Admin transferred from {} to {}", old_admin, new_admin);
-        
+    /// Register a price feed for a collateral mint (admin only)
+    /// The feed starts on the admin price; configure its oracle with `set_oracle`.
+    pub fn init_price_feed(
+        ctx: Context<InitPriceFeed>,
+        collateral_mint: Pubkey,
+        decimals: u8,
+        initial_price_usd: u64,
+    ) -> Result<()> {
+        require!(initial_price_usd > 0, GusdError::InvalidPrice);
+        require!(decimals <= MAX_NATIVE_DECIMALS, GusdError::InvalidNativeDecimals);
+
+        let now = Clock::get()?.unix_timestamp;
+        init_price_feed_state(
+            &mut ctx.accounts.price_feed,
+            collateral_mint,
+            decimals,
+            initial_price_usd,
+            now,
+            accrual_clock(&ctx.accounts.protocol_state, now),
+            ctx.bumps.price_feed,
+        );
+
+        msg!("Price feed registered for {}", collateral_mint);
+
+        emit!(PriceFeedRegistered {
+            collateral_mint,
+            decimals,
+            initial_price: initial_price_usd,
+        });
+
         Ok(())
     }
 
-    /// Create a new vault for a user
-    /// [CRITICAL-4] Now initializes vault_collateral PDA
-    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        
-        vault.owner = ctx.accounts.owner.key();
-        vault.collateral_amount = 0;
-        vault.debt_amount = 0;
-        vault.bump = ctx.bumps.vault;
-        vault.collateral_bump = ctx.bumps.vault_collateral; // [CRITICAL-4] Store collateral bump
+    /// Register a collateral type backed by an existing price feed (admin only)
+    /// Its ratios may be stricter than the protocol's floors, never looser.
+    pub fn add_collateral_type(
+        ctx: Context<AddCollateralType>,
+        collateral_mint: Pubkey,
+        min_collateral_ratio_bps: u64,
+        liquidation_threshold_bps: u64,
+    ) -> Result<()> {
+        validate_collateral_ratios(min_collateral_ratio_bps, liquidation_threshold_bps)?;
 
-        msg!("Vault created for user: {}", ctx.accounts.owner.key());
+        let config = &mut ctx.accounts.collateral_config;
+        init_collateral_config(
+            config,
+            &ctx.accounts.price_feed,
+            min_collateral_ratio_bps,
+            liquidation_threshold_bps,
+            Clock::get()?.unix_timestamp,
+            ctx.bumps.collateral_config,
+        );
 
-        // [MEDIUM-3] Emit event
-        emit!(VaultCreated {
-            owner: ctx.accounts.owner.key(),
-            timestamp: Clock::get()?.unix_timestamp,
+        msg!("Collateral type added: {}", collateral_mint);
+
+        emit!(CollateralTypeAdded {
+            collateral_mint,
+            decimals: config.decimals,
+            price_feed: config.price_feed,
+            min_collateral_ratio_bps,
+            liquidation_threshold_bps,
         });
-        
+
         Ok(())
     }
 
-    /// Deposit GOR collateral into a vault
-    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
-        require!(amount > 0, GusdError::InvalidAmount);
+    /// Update a collateral type's ratios and debt ceiling, or stop new vaults and mints
+    /// against it (risk manager only)
+    /// Disabling a type leaves its vaults free to repay, withdraw, and be liquidated. A
+    /// ceiling below the type's current debt only blocks further mints. Each changed value
+    /// is appended to the parameter changelog.
+    pub fn set_collateral_type(
+        ctx: Context<SetCollateralType>,
+        min_collateral_ratio_bps: u64,
+        liquidation_threshold_bps: u64,
+        debt_ceiling: u64,
+        enabled: bool,
+    ) -> Result<()> {
+        validate_collateral_ratios(min_collateral_ratio_bps, liquidation_threshold_bps)?;
 
-        // Transfer GOR from user to vault's collateral account
-        let cpi_accounts = anchor_lang::system_program::Transfer {
-            from: ctx.accounts.owner.to_account_info(),
-            to: ctx.accounts.vault_collateral.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.system_program.to_account_info();
-        anchor_lang::system_program::transfer(
-            CpiContext::new(cpi_program, cpi_accounts),
-            amount,
+        let config = &mut ctx.accounts.collateral_config;
+        let changes = changed_parameters(
+            config.collateral_mint,
+            ctx.accounts.risk_manager.key(),
+            &[
+                (
+                    RiskParameter::CollateralMinRatioBps,
+                    std::mem::replace(&mut config.min_collateral_ratio_bps, min_collateral_ratio_bps),
+                    min_collateral_ratio_bps,
+                ),
+                (
+                    RiskParameter::CollateralLiquidationThresholdBps,
+                    std::mem::replace(&mut config.liquidation_threshold_bps, liquidation_threshold_bps),
+                    liquidation_threshold_bps,
+                ),
+                (
+                    RiskParameter::CollateralDebtCeiling,
+                    std::mem::replace(&mut config.debt_ceiling, debt_ceiling),
+                    debt_ceiling,
+                ),
+                (
+                    RiskParameter::CollateralEnabled,
+                    std::mem::replace(&mut config.enabled, enabled) as u64,
+                    enabled as u64,
+                ),
+            ],
+        )?;
+        log_parameter_changes(
+            &mut ctx.accounts.protocol_state,
+            (&mut ctx.accounts.parameter_log, ctx.bumps.parameter_log),
+            (&mut ctx.accounts.next_parameter_log, ctx.bumps.next_parameter_log),
+            &changes,
         )?;
 
-        // Update vault state
-        let vault = &mut ctx.accounts.vault;
-        vault.collateral_amount = vault.collateral_amount.checked_add(amount)
-            .ok_or(GusdError::MathOverflow)?;
-
-        // Update protocol totals
-        let protocol = &mut ctx.accounts.protocol_state;
-        protocol.total_collateral = protocol.total_collateral.checked_add(amount)
-            .ok_or(GusdError::MathOverflow)?;
+        let config = &ctx.accounts.collateral_config;
 
-        msg!("Deposited {} GOR. Total collateral: {}", amount, vault.collateral_amount);
+        msg!(
+            "Collateral type {} updated: MCR {} bps, liquidation threshold {} bps, debt ceiling {}, enabled {}",
+            config.collateral_mint,
+            min_collateral_ratio_bps,
+            liquidation_threshold_bps,
+            debt_ceiling,
+            enabled
+        );
 
-        // [MEDIUM-3] Emit event
-        emit!(CollateralDeposited {
-            owner: ctx.accounts.owner.key(),
-            amount,
-            total_collateral: vault.collateral_amount,
+        emit!(CollateralTypeUpdated {
+            collateral_mint: config.collateral_mint,
+            min_collateral_ratio_bps,
+            liquidation_threshold_bps,
+            debt_ceiling,
+            enabled,
+            risk_manager: ctx.accounts.risk_manager.key(),
         });
-        
+
         Ok(())
     }
 
-    /// Mint GUSD against deposited collateral
-    pub fn mint_gusd(ctx: Context<MintGusd>, amount: u64) -> Result<()> {
-        // [MEDIUM-2] Check pause state
-        require!(!ctx.accounts.protocol_state.is_paused, GusdError::ProtocolPaused);
-        require!(amount > 0, GusdError::InvalidAmount);
+    /// Whitelist the stake pool program whose pools back liquid-staked GOR (admin only)
+    /// The default pubkey stops new feeds from binding to a pool; bound feeds keep theirs.
+    pub fn set_stake_pool_program(
+        ctx: Context<SetStakePoolProgram>,
+        stake_pool_program: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.deployment_config.stake_pool_program = stake_pool_program;
 
-        let vault = &mut ctx.accounts.vault;
-        let protocol = &ctx.accounts.protocol_state;
+        msg!("Stake pool program set to {}", stake_pool_program);
 
-        // Calculate new debt
-        let new_debt = vault.debt_amount.checked_add(amount)
-            .ok_or(GusdError::MathOverflow)?;
+        emit!(StakePoolProgramUpdated { stake_pool_program });
 
-        // Check collateral ratio after minting
-        let collateral_value_usd = calculate_usd_value(
-            vault.collateral_amount,
-            protocol.gor_price_usd,
-            GOR_DECIMALS,
+        Ok(())
+    }
+
+    /// Value a feed's collateral as liquid-staked GOR (admin only)
+    /// The feed must price its mint off GOR's oracle, and `stake_pool` must be a pool of the
+    /// whitelisted program whose pool mint is the feed's collateral. From then on
+    /// valuations multiply the GOR price by the pool's exchange rate, which
+    /// `refresh_exchange_rate` keeps current.
+    pub fn set_liquid_staking_pool(ctx: Context<SetLiquidStakingPool>) -> Result<()> {
+        let native_feed = &ctx.accounts.native_price_feed;
+        let feed = &ctx.accounts.price_feed;
+        require!(feed.decimals == native_feed.decimals, GusdError::InvalidStakePool);
+        require!(
+            feed.oracle_kind == native_feed.oracle_kind
+                && feed.oracle_account == native_feed.oracle_account
+                && feed.oracle_feed_id == native_feed.oracle_feed_id,
+            GusdError::InvalidOracleAccount
+        );
+
+        let epoch = Clock::get()?.epoch;
+        let exchange_rate = read_stake_pool_rate(
+            &ctx.accounts.stake_pool,
+            &feed.collateral_mint,
+            epoch,
         )?;
 
-        let required_collateral = new_debt
-            .checked_mul(MIN_COLLATERAL_RATIO_BPS)
-            .ok_or(GusdError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR)
-            .ok_or(GusdError::MathOverflow)?;
+        let feed = &mut ctx.accounts.price_feed;
+        feed.stake_pool = ctx.accounts.stake_pool.key();
+        feed.exchange_rate = exchange_rate;
+        feed.exchange_rate_epoch = epoch;
 
-        require!(
-            collateral_value_usd >= required_collateral,
-            GusdError::InsufficientCollateral
+        msg!(
+            "{} valued through stake pool {} at {} lamports per token",
+            feed.collateral_mint,
+            feed.stake_pool,
+            exchange_rate
         );
 
-        // Mint GUSD to user
-        let seeds = &[
-            b"protocol".as_ref(),
-            &[protocol.bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
+        emit!(LiquidStakingPoolSet {
+            collateral_mint: feed.collateral_mint,
+            stake_pool: feed.stake_pool,
+            exchange_rate,
+        });
 
-        let cpi_accounts = MintTo {
-            mint: ctx.accounts.gusd_mint.to_account_info(),
-            to: ctx.accounts.user_gusd_account.to_account_info(),
-            authority: ctx.accounts.protocol_state.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        
-        token_interface::mint_to(
-            CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds),
-            amount,
-        )?;
+        Ok(())
+    }
 
-        // Update vault debt
-        vault.debt_amount = new_debt;
+    /// Refresh an LST feed's exchange rate from its stake pool (permissionless crank)
+    /// The pool must already be updated for the current epoch, so the rate includes the
+    /// epoch's staking rewards; vault collateral appreciates with it.
+    pub fn refresh_exchange_rate(ctx: Context<RefreshExchangeRate>) -> Result<()> {
+        let epoch = Clock::get()?.epoch;
+        let exchange_rate = read_stake_pool_rate(
+            &ctx.accounts.stake_pool,
+            &ctx.accounts.price_feed.collateral_mint,
+            epoch,
+        )?;
 
-        // [HIGH-3] Fixed: Don't clone protocol_state
-        let protocol = &mut ctx.accounts.protocol_state;
-        protocol.total_debt = protocol.total_debt
-            .checked_add(amount)
-            .ok_or(GusdError::MathOverflow)?;
+        let feed = &mut ctx.accounts.price_feed;
+        let old_rate = feed.exchange_rate;
+        feed.exchange_rate = exchange_rate;
+        feed.exchange_rate_epoch = epoch;
 
-        // Calculate collateral ratio for event
-        let collateral_ratio_bps = collateral_value_usd
-            .checked_mul(BPS_DENOMINATOR)
-            .ok_or(GusdError::MathOverflow)?
-            .checked_div(new_debt)
-            .ok_or(GusdError::MathOverflow)?;
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            ctx.accounts.keeper.as_ref().map(|keeper| keeper.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
 
-        msg!("Minted {} GUSD. Total debt: {}", amount, vault.debt_amount);
-        msg!("Collateral ratio: {}%", collateral_ratio_bps as f64 / 100.0);
+        let feed = &ctx.accounts.price_feed;
+        msg!(
+            "Exchange rate for {} refreshed in epoch {}: {} -> {}",
+            feed.collateral_mint,
+            epoch,
+            old_rate,
+            exchange_rate
+        );
 
-        // [MEDIUM-3] Emit event
-        emit!(GusdMinted {
-            owner: ctx.accounts.owner.key(),
-            amount,
-            total_debt: vault.debt_amount,
-            collateral_ratio_bps,
+        emit!(ExchangeRateRefreshed {
+            collateral_mint: feed.collateral_mint,
+            old_rate,
+            new_rate: exchange_rate,
+            epoch,
         });
-        
+
         Ok(())
     }
 
-    /// Repay GUSD debt (burns GUSD)
-    pub fn repay_gusd(ctx: Context<RepayGusd>, amount: u64) -> Result<()> {
-        require!(amount > 0, GusdError::InvalidAmount);
-        
-        let vault = &mut ctx.accounts.vault;
-        
-        // Can't repay more than owed
-        let repay_amount = amount.min(vault.debt_amount);
+    /// Configure a feed's oracle backend, price account, and feed id (admin only)
+    /// `feed_id` is the Pyth feed id or the Switchboard feed hash.
+    /// Switches valuation to the oracle; the admin price remains available as a fallback.
+    /// The changelog records the oracle kind; `OracleConfigured` carries the account and id.
+    pub fn set_oracle(
+        ctx: Context<SetOracle>,
+        kind: OracleKind,
+        price_account: Pubkey,
+        feed_id: [u8; 32],
+    ) -> Result<()> {
+        require!(price_account != Pubkey::default(), GusdError::InvalidOracleAccount);
 
-        // Burn GUSD from user
-        let cpi_accounts = Burn {
-            mint: ctx.accounts.gusd_mint.to_account_info(),
-            from: ctx.accounts.user_gusd_account.to_account_info(),
-            authority: ctx.accounts.owner.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        
-        token_interface::burn(
-            CpiContext::new(cpi_program, cpi_accounts),
-            repay_amount,
+        let feed = &mut ctx.accounts.price_feed;
+        log_parameter_change(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.parameter_log,
+            ctx.bumps.parameter_log,
+            ParameterChange {
+                parameter: RiskParameter::OracleKind,
+                old_value: feed.oracle_kind as u64,
+                new_value: kind as u64,
+                actor: ctx.accounts.admin.key(),
+                slot: Clock::get()?.slot,
+                target: feed.collateral_mint,
+            },
         )?;
+        feed.oracle_kind = kind;
+        feed.oracle_account = price_account;
+        feed.oracle_feed_id = feed_id;
+        feed.use_admin_price = false;
+        clear_oracle_migration(feed);
 
-        // Update vault debt
-        vault.debt_amount = vault.debt_amount.checked_sub(repay_amount)
-            .ok_or(GusdError::MathOverflow)?;
+        msg!("Oracle for {} set to {:?} price account {}", feed.collateral_mint, kind, price_account);
 
-        // Update protocol totals
-        let protocol = &mut ctx.accounts.protocol_state;
-        protocol.total_debt = protocol.total_debt.checked_sub(repay_amount)
-            .ok_or(GusdError::MathOverflow)?;
+        emit!(OracleConfigured {
+            collateral_mint: feed.collateral_mint,
+            kind,
+            price_account,
+            feed_id,
+        });
 
-        msg!("Repaid {} GUSD. Remaining debt: {}", repay_amount, vault.debt_amount);
+        Ok(())
+    }
 
-        // [MEDIUM-3] Emit event
-        emit!(GusdRepaid {
-            owner: ctx.accounts.owner.key(),
-            amount: repay_amount,
-            remaining_debt: vault.debt_amount,
+    /// Set a feed's ordered fallback oracle sources, replacing any existing list (admin only)
+    /// When the primary oracle is stale or invalid, valuation tries each fallback in order
+    /// if its price account is passed. An empty list disables failover.
+    pub fn set_fallback_oracles(
+        ctx: Context<ConfigurePriceFeed>,
+        fallbacks: Vec<OracleSource>,
+    ) -> Result<()> {
+        require!(fallbacks.len() <= MAX_FALLBACK_ORACLES, GusdError::TooManyFallbackOracles);
+
+        let feed = &mut ctx.accounts.price_feed;
+        for (index, source) in fallbacks.iter().enumerate() {
+            // Fallbacks are read live, so they need a price account the caller can pass
+            require!(
+                source.kind != OracleKind::ChainlinkStreams,
+                GusdError::InvalidFallbackOracle
+            );
+            require!(
+                source.account != Pubkey::default()
+                    && source.account != feed.oracle_account
+                    && fallbacks[..index].iter().all(|prior| prior.account != source.account),
+                GusdError::InvalidFallbackOracle
+            );
+        }
+
+        feed.fallback_oracles = [OracleSource::UNSET; MAX_FALLBACK_ORACLES];
+        feed.fallback_oracles[..fallbacks.len()].copy_from_slice(&fallbacks);
+
+        msg!("Fallback oracles for {} set ({} sources)", feed.collateral_mint, fallbacks.len());
+
+        emit!(FallbackOraclesSet {
+            collateral_mint: feed.collateral_mint,
+            fallbacks,
         });
-        
+
         Ok(())
     }
 
-    /// Withdraw collateral (if ratio remains healthy)
-    /// [CRITICAL-1] Fixed: Uses PDA-signed system transfer
-    pub fn withdraw_collateral(ctx: Context<WithdrawCollateral>, amount: u64) -> Result<()> {
-        // [MEDIUM-2] Check pause state
-        require!(!ctx.accounts.protocol_state.is_paused, GusdError::ProtocolPaused);
-        require!(amount > 0, GusdError::InvalidAmount);
+    /// Verify a Chainlink Data Streams report and cache its price (permissionless)
+    /// The report is verified by CPI into the Chainlink verifier; the submitter signs that
+    /// CPI and pays any verification cost. Used when `oracle_kind` is `ChainlinkStreams`.
+    pub fn update_price_chainlink(
+        ctx: Context<UpdatePriceChainlink>,
+        signed_report: Vec<u8>,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.price_feed.oracle_kind == OracleKind::ChainlinkStreams,
+            GusdError::OracleNotConfigured
+        );
 
-        let vault = &mut ctx.accounts.vault;
-        let protocol = &ctx.accounts.protocol_state;
+        let mut data = CHAINLINK_VERIFY_DISCRIMINATOR.to_vec();
+        signed_report.serialize(&mut data)?;
+        let ix = Instruction {
+            program_id: ctx.accounts.verifier_program.key(),
+            accounts: vec![
+                AccountMeta::new_readonly(ctx.accounts.verifier_account.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.access_controller.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.submitter.key(), true),
+                AccountMeta::new_readonly(ctx.accounts.config_account.key(), false),
+            ],
+            data,
+        };
+        invoke(
+            &ix,
+            &[
+                ctx.accounts.verifier_account.to_account_info(),
+                ctx.accounts.access_controller.to_account_info(),
+                ctx.accounts.submitter.to_account_info(),
+                ctx.accounts.config_account.to_account_info(),
+            ],
+        )?;
 
+        let (returned_by, report) = get_return_data().ok_or(GusdError::InvalidOracleAccount)?;
+        require_keys_eq!(
+            returned_by,
+            ctx.accounts.verifier_program.key(),
+            GusdError::InvalidOracleAccount
+        );
+        let report = decode_streams_report(&report)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let feed = &mut ctx.accounts.price_feed;
+        require!(report.feed_id == feed.oracle_feed_id, GusdError::InvalidOracleAccount);
+        require!(now <= report.expires_at, GusdError::StaleOraclePrice);
         require!(
-            amount <= vault.collateral_amount,
-            GusdError::InsufficientCollateral
+            report.observations_timestamp > feed.cached_observed_at,
+            GusdError::StaleOraclePrice
         );
 
-        // Calculate remaining collateral after withdrawal
-        let remaining_collateral = vault.collateral_amount.checked_sub(amount)
+        let conf_bps = (report.ask.saturating_sub(report.bid) as u128 / 2)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(report.price as u128)
+            .min(u64::MAX as u128) as u64;
+        let price = (report.price as u128)
+            .checked_div(10u128.pow(CHAINLINK_STREAMS_PRECISION - GUSD_DECIMALS as u32))
             .ok_or(GusdError::MathOverflow)?;
+        require!(price > 0, GusdError::InvalidPrice);
+        require!(price <= u64::MAX as u128, GusdError::MathOverflow);
 
-        // If there's debt, check that ratio stays healthy
-        if vault.debt_amount > 0 {
-            let remaining_value_usd = calculate_usd_value(
-                remaining_collateral,
-                protocol.gor_price_usd,
-                GOR_DECIMALS,
-            )?;
+        feed.cached_price = price as u64;
+        feed.cached_conf_bps = conf_bps;
+        feed.cached_observed_at = report.observations_timestamp;
 
-            let required_collateral = vault.debt_amount
-                .checked_mul(MIN_COLLATERAL_RATIO_BPS)
-                .ok_or(GusdError::MathOverflow)?
-                .checked_div(BPS_DENOMINATOR)
-                .ok_or(GusdError::MathOverflow)?;
+        msg!("Chainlink report verified: price {}", feed.cached_price);
 
-            require!(
-                remaining_value_usd >= required_collateral,
-                GusdError::WouldUndercollateralize
-            );
-        }
+        emit!(ChainlinkReportVerified {
+            price: feed.cached_price,
+            conf_bps,
+            observations_timestamp: report.observations_timestamp,
+            native_fee: report.native_fee,
+            link_fee: report.link_fee,
+        });
 
-        // [CRITICAL-1] Fixed: Use PDA-signed transfer instead of direct lamport manipulation
-        let owner_key = ctx.accounts.owner.key();
-        let seeds = &[
-            b"vault_collateral".as_ref(),
-            owner_key.as_ref(),
-            &[ctx.bumps.vault_collateral],
-        ];
-        let signer_seeds = &[&seeds[..]];
+        Ok(())
+    }
 
-        anchor_lang::system_program::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.vault_collateral.to_account_info(),
-                    to: ctx.accounts.owner.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount,
+    /// Read a feed's Pyth or Switchboard account and cache the normalized price (permissionless)
+    /// Later instructions can omit `price_update` and value against the cached price, which
+    /// is still subject to the usual age and confidence limits.
+    pub fn crank_price(ctx: Context<CrankPrice>) -> Result<()> {
+        let (old_price, new_price) = cache_oracle_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.deployment_config,
+            &mut ctx.accounts.price_feed,
+            ctx.accounts.price_update.as_ref(),
         )?;
 
-        // Update vault state
-        vault.collateral_amount = remaining_collateral;
-
-        // Update protocol totals
-        let protocol = &mut ctx.accounts.protocol_state;
-        protocol.total_collateral = protocol.total_collateral.checked_sub(amount)
-            .ok_or(GusdError::MathOverflow)?;
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            ctx.accounts.keeper.as_ref().map(|keeper| keeper.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
 
-        msg!("Withdrew {} GOR. Remaining collateral: {}", amount, vault.collateral_amount);
+        let feed = &ctx.accounts.price_feed;
+        msg!("Oracle price cached for {}: {} -> {}", feed.collateral_mint, old_price, new_price);
 
-        // [MEDIUM-3] Emit event
-        emit!(CollateralWithdrawn {
-            owner: ctx.accounts.owner.key(),
-            amount,
-            remaining_collateral: vault.collateral_amount,
+        emit!(PriceUpdated {
+            collateral_mint: feed.collateral_mint,
+            old_price,
+            new_price,
         });
-        
+
         Ok(())
     }
 
-    /// Close an empty vault (debt == 0 and tracked collateral == 0)
-    /// Transfers any remaining lamports in the collateral PDA (e.g., rent) back to the owner.
-    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
-        require!(ctx.accounts.vault.debt_amount == 0, GusdError::VaultNotEmpty);
-        require!(ctx.accounts.vault.collateral_amount == 0, GusdError::VaultNotEmpty);
+    /// Cache oracle prices for several collateral types in one transaction (permissionless)
+    /// `remaining_accounts` holds `[price_feed (writable), price_update]` per feed. Each pair
+    /// is validated and cached exactly as `crank_price` would, so crank cost grows with the
+    /// number of feeds rather than the number of transactions.
+    pub fn update_prices_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UpdatePricesBatch<'info>>,
+    ) -> Result<()> {
+        let pairs = ctx.remaining_accounts;
+        require!(
+            !pairs.is_empty() && pairs.len().is_multiple_of(2) && pairs.len() / 2 <= MAX_PRICE_FEED_BATCH_LEN,
+            GusdError::InvalidPriceFeedBatch
+        );
 
-        // Drain any remaining lamports (rent, etc.) from the collateral PDA back to the owner.
-        let vault_owner_key = ctx.accounts.owner.key();
-        let vault_collateral_bump = ctx.accounts.vault.collateral_bump;
+        for pair in pairs.chunks_exact(2) {
+            require!(pair[0].is_writable, GusdError::InvalidPriceFeedBatch);
+            let mut feed = Account::<PriceFeed>::try_from(&pair[0])?;
+            let (old_price, new_price) = cache_oracle_price(
+                &ctx.accounts.protocol_state,
+                &ctx.accounts.deployment_config,
+                &mut feed,
+                &pair[1],
+            )?;
+            feed.exit(&crate::ID)?;
 
-        let balance = **ctx.accounts.vault_collateral.lamports.borrow();
-        if balance > 0 {
-            let seeds = &[
-                b"vault_collateral".as_ref(),
-                vault_owner_key.as_ref(),
-                &[vault_collateral_bump],
-            ];
-            let signer_seeds = &[&seeds[..]];
+            msg!("Oracle price cached for {}: {} -> {}", feed.collateral_mint, old_price, new_price);
 
-            anchor_lang::system_program::transfer(
-                CpiContext::new_with_signer(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.vault_collateral.to_account_info(),
-                        to: ctx.accounts.owner.to_account_info(),
-                    },
-                    signer_seeds,
-                ),
-                balance,
-            )?;
+            emit!(PriceUpdated {
+                collateral_mint: feed.collateral_mint,
+                old_price,
+                new_price,
+            });
         }
 
-        msg!("Vault closed: {}", vault_owner_key);
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            ctx.accounts.keeper.as_ref().map(|keeper| keeper.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
+
         Ok(())
     }
 
-    /// Liquidate an undercollateralized vault
-    /// [CRITICAL-2] Fixed: Uses PDA-signed system transfer
-    /// [CRITICAL-3] Fixed: Correct liquidation math
-    pub fn liquidate(ctx: Context<Liquidate>) -> Result<()> {
-        // [MEDIUM-2] Check pause state
-        require!(!ctx.accounts.protocol_state.is_paused, GusdError::ProtocolPaused);
+    /// Start running an oracle in shadow mode on an admin-priced feed (admin only)
+    /// The oracle is configured but the admin price stays authoritative. `record_shadow_price`
+    /// compares the two; after `shadow_period_secs` without a divergence above `alert_bps`,
+    /// `schedule_oracle_switch` starts the timelock to `activate_oracle`.
+    pub fn begin_oracle_shadow(
+        ctx: Context<ConfigurePriceFeed>,
+        kind: OracleKind,
+        price_account: Pubkey,
+        feed_id: [u8; 32],
+        shadow_period_secs: u64,
+        alert_bps: u64,
+    ) -> Result<()> {
+        require!(price_account != Pubkey::default(), GusdError::InvalidOracleAccount);
+        require!(shadow_period_secs >= MIN_ORACLE_SHADOW_SECS, GusdError::InvalidParameter);
+        require!(alert_bps > 0 && alert_bps <= BPS_DENOMINATOR, GusdError::InvalidParameter);
 
-        // Snapshot values we need before taking mutable borrows
-        let vault_owner_key = ctx.accounts.vault_owner.key();
-        let price = ctx.accounts.protocol_state.gor_price_usd;
+        let feed = &mut ctx.accounts.price_feed;
+        require!(feed.use_admin_price, GusdError::OracleAlreadyActive);
 
-        let vault_collateral_amount = ctx.accounts.vault.collateral_amount;
-        let vault_debt_amount = ctx.accounts.vault.debt_amount;
-        let vault_collateral_bump = ctx.accounts.vault.collateral_bump;
+        feed.oracle_kind = kind;
+        feed.oracle_account = price_account;
+        feed.oracle_feed_id = feed_id;
+        // Prices cached from a previous oracle must not be compared against this one
+        feed.cached_price = 0;
+        feed.cached_conf_bps = 0;
+        feed.cached_observed_at = 0;
+        clear_oracle_migration(feed);
+        feed.shadow_started_at = Clock::get()?.unix_timestamp;
+        feed.shadow_period_secs = shadow_period_secs;
+        feed.shadow_alert_bps = alert_bps;
 
-        require!(vault_debt_amount > 0, GusdError::NoDebtToLiquidate);
+        msg!(
+            "Oracle shadow run for {} started with {:?} price account {}",
+            feed.collateral_mint,
+            kind,
+            price_account
+        );
 
-        // Check if vault is undercollateralized
-        let collateral_value_usd = calculate_usd_value(
-            vault_collateral_amount,
-            price,
-            GOR_DECIMALS,
-        )?;
+        emit!(OracleShadowStarted {
+            collateral_mint: feed.collateral_mint,
+            kind,
+            price_account,
+            feed_id,
+            shadow_period_secs,
+        });
 
-        let collateral_ratio_bps = (collateral_value_usd as u128)
-            .checked_mul(BPS_DENOMINATOR as u128)
-            .ok_or(GusdError::MathOverflow)?
-            .checked_div(vault_debt_amount as u128)
-            .ok_or(GusdError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Compare the shadow oracle's price against the admin price (permissionless crank)
+    /// Emits `OracleShadowSampled` for every comparison and `OracleShadowDiverged` when the
+    /// prices differ by more than the feed's alert threshold.
+    pub fn record_shadow_price(ctx: Context<CrankPrice>) -> Result<()> {
+        let feed = &ctx.accounts.price_feed;
+        require!(feed.shadow_started_at != 0, GusdError::NoOracleShadow);
 
+        let reading = read_oracle_price(
+            feed,
+            &ctx.accounts.deployment_config,
+            Some(ctx.accounts.price_update.as_ref()),
+        )?;
+        let protocol = &ctx.accounts.protocol_state;
         require!(
-            collateral_ratio_bps < LIQUIDATION_THRESHOLD_BPS as u128,
-            GusdError::VaultNotLiquidatable
+            reading.age_secs <= protocol.max_price_age_secs,
+            GusdError::StaleOraclePrice
         );
+        let oracle_price = apply_confidence(protocol, &reading, PriceBound::Mid)?;
 
-        // Determine the maximum profitable repay amount given available collateral.
-        // We only allow liquidations where: collateral_seized >= repay_amount * (1 + penalty)
-        let bonus_denominator = (BPS_DENOMINATOR + LIQUIDATION_PENALTY_BPS) as u128;
-
-        let max_repay_u128 = (collateral_value_usd as u128)
+        let feed = &mut ctx.accounts.price_feed;
+        let admin_price = current_admin_price(feed, Clock::get()?.unix_timestamp);
+        let divergence_bps = (oracle_price.abs_diff(admin_price) as u128)
             .checked_mul(BPS_DENOMINATOR as u128)
             .ok_or(GusdError::MathOverflow)?
-            .checked_div(bonus_denominator)
-            .ok_or(GusdError::MathOverflow)?;
+            .checked_div(admin_price as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .min(u64::MAX as u128) as u64;
 
-        let repay_u128 = (vault_debt_amount as u128).min(max_repay_u128);
-        require!(repay_u128 > 0, GusdError::LiquidationNotProfitable);
-        require!(repay_u128 <= u64::MAX as u128, GusdError::MathOverflow);
-        let repay_amount = repay_u128 as u64;
+        feed.shadow_samples = feed.shadow_samples.saturating_add(1);
+        feed.shadow_max_divergence_bps = feed.shadow_max_divergence_bps.max(divergence_bps);
 
-        // Burn GUSD from liquidator
-        let cpi_accounts = Burn {
-            mint: ctx.accounts.gusd_mint.to_account_info(),
-            from: ctx.accounts.liquidator_gusd_account.to_account_info(),
-            authority: ctx.accounts.liquidator.to_account_info(),
-        };
-        let cpi_program = ctx.accounts.token_program.to_account_info();
+        msg!(
+            "Shadow price for {}: oracle {} vs admin {} ({} bps)",
+            feed.collateral_mint,
+            oracle_price,
+            admin_price,
+            divergence_bps
+        );
 
-        token_interface::burn(
-            CpiContext::new(cpi_program, cpi_accounts),
-            repay_amount,
-        )?;
+        emit!(OracleShadowSampled {
+            collateral_mint: feed.collateral_mint,
+            oracle_price,
+            admin_price,
+            divergence_bps,
+        });
 
-        // Calculate USD value with liquidation bonus
-        let repay_with_bonus_u128 = (repay_amount as u128)
-            .checked_mul(bonus_denominator)
-            .ok_or(GusdError::MathOverflow)?
-            .checked_div(BPS_DENOMINATOR as u128)
-            .ok_or(GusdError::MathOverflow)?;
+        if divergence_bps > feed.shadow_alert_bps {
+            emit!(OracleShadowDiverged {
+                collateral_mint: feed.collateral_mint,
+                oracle_price,
+                admin_price,
+                divergence_bps,
+                alert_bps: feed.shadow_alert_bps,
+            });
+        }
 
-        // Convert USD (6 decimals) to GOR lamports (9 decimals)
-        let collateral_to_liquidator_u128 = repay_with_bonus_u128
-            .checked_mul(10u128.pow(GOR_DECIMALS as u32))
-            .ok_or(GusdError::MathOverflow)?
-            .checked_div(price as u128)
-            .ok_or(GusdError::MathOverflow)?;
+        Ok(())
+    }
 
-        require!(collateral_to_liquidator_u128 <= u64::MAX as u128, GusdError::MathOverflow);
-        let collateral_to_liquidator = collateral_to_liquidator_u128 as u64;
+    /// Schedule the shadow oracle to become authoritative (admin only)
+    /// Requires a completed shadow period with at least one sample and no divergence above
+    /// the alert threshold. The switch can be activated after `ORACLE_SWITCH_TIMELOCK_SECS`.
+    pub fn schedule_oracle_switch(ctx: Context<ConfigurePriceFeed>) -> Result<()> {
+        let feed = &mut ctx.accounts.price_feed;
+        let now = Clock::get()?.unix_timestamp;
 
-        // Final sanity check: do not seize more than tracked collateral
+        require!(feed.shadow_started_at != 0, GusdError::NoOracleShadow);
+        require!(feed.oracle_activation_at == 0, GusdError::OracleSwitchAlreadyScheduled);
+        require!(
+            now.saturating_sub(feed.shadow_started_at) >= feed.shadow_period_secs as i64,
+            GusdError::OracleShadowIncomplete
+        );
+        require!(feed.shadow_samples > 0, GusdError::OracleShadowIncomplete);
         require!(
-            collateral_to_liquidator <= vault_collateral_amount,
-            GusdError::MathOverflow
+            feed.shadow_max_divergence_bps <= feed.shadow_alert_bps,
+            GusdError::OracleShadowDiverged
         );
 
-        // Transfer collateral to liquidator (PDA signed)
-        let seeds = &[
-            b"vault_collateral".as_ref(),
-            vault_owner_key.as_ref(),
-            &[vault_collateral_bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
+        feed.oracle_activation_at = now
+            .checked_add(ORACLE_SWITCH_TIMELOCK_SECS)
+            .ok_or(GusdError::MathOverflow)?;
 
-        anchor_lang::system_program::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.vault_collateral.to_account_info(),
-                    to: ctx.accounts.liquidator.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            collateral_to_liquidator,
-        )?;
+        msg!("Oracle switch for {} scheduled at {}", feed.collateral_mint, feed.oracle_activation_at);
 
-        // Update protocol totals
-        let protocol = &mut ctx.accounts.protocol_state;
-        protocol.total_collateral = protocol.total_collateral
-            .checked_sub(collateral_to_liquidator)
-            .ok_or(GusdError::MathOverflow)?;
-        protocol.total_debt = protocol.total_debt
-            .checked_sub(repay_amount)
-            .ok_or(GusdError::MathOverflow)?;
-
-        // Update vault
-        let vault = &mut ctx.accounts.vault;
-        vault.collateral_amount = vault.collateral_amount
-            .checked_sub(collateral_to_liquidator)
-            .ok_or(GusdError::MathOverflow)?;
-        vault.debt_amount = vault.debt_amount
-            .checked_sub(repay_amount)
-            .ok_or(GusdError::MathOverflow)?;
-
-        msg!(
-            "Liquidation: repaid {} GUSD, seized {} GOR. Remaining debt: {}, remaining collateral: {}",
-            repay_amount,
-            collateral_to_liquidator,
-            vault.debt_amount,
-            vault.collateral_amount
-        );
-
-        // [MEDIUM-3] Emit event
-        emit!(VaultLiquidated {
-            vault_owner: vault_owner_key,
-            liquidator: ctx.accounts.liquidator.key(),
-            debt_repaid: repay_amount,
-            collateral_seized: collateral_to_liquidator,
+        emit!(OracleSwitchScheduled {
+            collateral_mint: feed.collateral_mint,
+            activation_at: feed.oracle_activation_at,
+            shadow_samples: feed.shadow_samples,
+            max_divergence_bps: feed.shadow_max_divergence_bps,
         });
 
         Ok(())
     }
 
-    /// Get vault health metrics (view function)
-    pub fn get_vault_health(ctx: Context<GetVaultHealth>) -> Result<VaultHealth> {
-        let vault = &ctx.accounts.vault;
-        let protocol = &ctx.accounts.protocol_state;
-
-        let collateral_value_usd = calculate_usd_value(
-            vault.collateral_amount,
-            protocol.gor_price_usd,
-            GOR_DECIMALS,
-        )?;
+    /// Make the shadow oracle authoritative once its timelock has passed (admin only)
+    pub fn activate_oracle(ctx: Context<ConfigurePriceFeed>) -> Result<()> {
+        let feed = &mut ctx.accounts.price_feed;
+        let now = Clock::get()?.unix_timestamp;
 
-        let collateral_ratio = if vault.debt_amount > 0 {
-            collateral_value_usd
-                .checked_mul(BPS_DENOMINATOR)
-                .ok_or(GusdError::MathOverflow)?
-                .checked_div(vault.debt_amount)
-                .ok_or(GusdError::MathOverflow)?
-        } else {
-            u64::MAX // No debt = infinite ratio
-        };
+        require!(feed.oracle_activation_at != 0, GusdError::NoOracleShadow);
+        require!(now >= feed.oracle_activation_at, GusdError::OracleSwitchTimelocked);
 
-        let is_liquidatable = vault.debt_amount > 0 && 
-            collateral_ratio < LIQUIDATION_THRESHOLD_BPS;
+        feed.use_admin_price = false;
+        clear_oracle_migration(feed);
 
-        let health = VaultHealth {
-            collateral_amount: vault.collateral_amount,
-            collateral_value_usd,
-            debt_amount: vault.debt_amount,
-            collateral_ratio_bps: collateral_ratio,
-            is_liquidatable,
-        };
+        msg!("Oracle for {} is now authoritative", feed.collateral_mint);
 
-        msg!("Vault Health:");
-        msg!("  Collateral: {} GOR (${:.2})", 
-            vault.collateral_amount as f64 / 1e9,
-            collateral_value_usd as f64 / 1e6
-        );
-        msg!("  Debt: {} GUSD", vault.debt_amount as f64 / 1e6);
-        msg!("  Ratio: {}%", collateral_ratio as f64 / 100.0);
-        msg!("  Liquidatable: {}", is_liquidatable);
+        emit!(OracleConfigured {
+            collateral_mint: feed.collateral_mint,
+            kind: feed.oracle_kind,
+            price_account: feed.oracle_account,
+            feed_id: feed.oracle_feed_id,
+        });
 
-        Ok(health)
+        Ok(())
     }
-}
 
-// ============================================================================
-// HELPER FUNCTIONS
-// ============================================================================
+    /// Abandon a shadow run or scheduled oracle switch (admin only)
+    /// The admin price stays authoritative.
+    pub fn cancel_oracle_migration(ctx: Context<ConfigurePriceFeed>) -> Result<()> {
+        let feed = &mut ctx.accounts.price_feed;
+        require!(feed.shadow_started_at != 0, GusdError::NoOracleShadow);
 
-/// Calculate USD value of GOR amount
-/// [HIGH-1] Fixed: Now checks for u128 -> u64 overflow
-fn calculate_usd_value(gor_amount: u64, gor_price_usd: u64, gor_decimals: u8) -> Result<u64> {
-    // gor_amount is in lamports (10^-9)
-    // gor_price_usd has 6 decimals
-    // Result should have 6 decimals (GUSD decimals)
-    
-    let value = (gor_amount as u128)
-        .checked_mul(gor_price_usd as u128)
-        .ok_or(GusdError::MathOverflow)?
-        .checked_div(10u128.pow(gor_decimals as u32))
-        .ok_or(GusdError::MathOverflow)?;
-    
-    // [HIGH-1] Add overflow check
-    require!(value <= u64::MAX as u128, GusdError::MathOverflow);
-    
-    Ok(value as u64)
-}
+        clear_oracle_migration(feed);
 
-// ============================================================================
-// ACCOUNTS
-// ============================================================================
+        msg!("Oracle migration for {} cancelled", feed.collateral_mint);
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(mut)]
-    pub admin: Signer<'info>,
+        emit!(OracleMigrationCancelled {
+            collateral_mint: feed.collateral_mint,
+        });
 
-    #[account(
-        init,
-        payer = admin,
-        space = 8 + ProtocolState::INIT_SPACE,
-        seeds = [b"protocol"],
-        bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+        Ok(())
+    }
 
-    #[account(
-        init,
-        payer = admin,
-        mint::decimals = GUSD_DECIMALS,
-        mint::authority = protocol_state,
-        mint::freeze_authority = protocol_state,
-        seeds = [b"gusd_mint"],
-        bump
-    )]
-    pub gusd_mint: InterfaceAccount<'info, Mint>,
+    /// Toggle a feed's admin-price fallback (admin only)
+    /// While enabled, valuation uses the feed's admin-set `admin_price` instead of the oracle.
+    pub fn set_admin_price_fallback(ctx: Context<ConfigurePriceFeed>, enabled: bool) -> Result<()> {
+        let feed = &mut ctx.accounts.price_feed;
+        if !enabled {
+            require!(
+                feed.oracle_account != Pubkey::default(),
+                GusdError::OracleNotConfigured
+            );
+        }
+        feed.use_admin_price = enabled;
 
-    pub token_program: Interface<'info, TokenInterface>,
-    pub system_program: Program<'info, System>,
-}
+        msg!("Admin price fallback for {}: {}", feed.collateral_mint, enabled);
 
-#[derive(Accounts)]
-pub struct UpdatePrice<'info> {
-    #[account(
-        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
-    )]
-    pub admin: Signer<'info>,
+        emit!(AdminPriceFallbackSet {
+            collateral_mint: feed.collateral_mint,
+            enabled,
+        });
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
-}
+        Ok(())
+    }
 
-/// [LOW-2] Admin transfer accounts struct
-#[derive(Accounts)]
-pub struct TransferAdmin<'info> {
-    #[account(
-        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
-    )]
-    pub admin: Signer<'info>,
+    /// Record the current oracle price into the TWAP accumulator (permissionless crank)
+    /// Keeps the TWAP and EMA current while valuation comes from an oracle instead of
+    /// `update_price`, and caches the fresh price as the feed's `last_good_price` for the
+    /// stale-oracle fallback. Only native samples feed the TWAP and EMA; other feeds are
+    /// sampled at most once per TWAP slot spacing. A sample deviating more than
+    /// `circuit_breaker_deviation_bps` from a recent accepted price is rejected and pauses
+    /// the protocol until the admin unpauses.
+    pub fn record_price_sample(ctx: Context<RecordPriceSample>) -> Result<()> {
+        let price = get_spot_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        let native = ctx.accounts.price_feed.collateral_mint == NATIVE_COLLATERAL_MINT;
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
-}
+        // Only pay for a sample when it would fill a new slot, so cranking can't push
+        // every observation older than the TWAP window out of the ring
+        let spacing = observation_spacing_secs(ctx.accounts.protocol_state.twap_window_secs);
+        let due = if native {
+            now > ctx.accounts.price_accumulator.last_update_ts
+                && observation_due(&ctx.accounts.price_accumulator, spacing, now)
+        } else {
+            now.saturating_sub(ctx.accounts.price_feed.last_good_price_ts) >= spacing
+        };
+        require!(due, GusdError::PriceUpdateTooFrequent);
 
-/// [CRITICAL-4] Fixed: Now initializes vault_collateral PDA
-#[derive(Accounts)]
-pub struct CreateVault<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        let feed = &mut ctx.accounts.price_feed;
+        let protocol = &mut ctx.accounts.protocol_state;
+        let last_price = feed.last_good_price;
+        let threshold_bps = protocol.circuit_breaker_deviation_bps;
+        if threshold_bps > 0
+            && last_price > 0
+            && now.saturating_sub(feed.last_good_price_ts) <= CIRCUIT_BREAKER_WINDOW_SECS
+        {
+            let deviation_bps = (price.abs_diff(last_price) as u128)
+                .checked_mul(BPS_DENOMINATOR as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(last_price as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .min(u64::MAX as u128) as u64;
 
-    #[account(
-        init,
-        payer = owner,
-        space = 8 + Vault::INIT_SPACE,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump
-    )]
-    pub vault: Account<'info, Vault>,
+            if deviation_bps > threshold_bps {
+                // Return Ok so the pause persists; the sample itself is not accepted
+                enter_pause(protocol, now)?;
 
-    /// [CRITICAL-4] Initialize the vault_collateral PDA
-    #[account(
-        init,
-        payer = owner,
-        space = 0,
-        seeds = [b"vault_collateral", owner.key().as_ref()],
-        bump
-    )]
-    /// CHECK: PDA that holds GOR collateral as lamports
-    pub vault_collateral: AccountInfo<'info>,
+                msg!("Circuit breaker tripped: {} -> {} ({} bps)", last_price, price, deviation_bps);
 
-    pub system_program: Program<'info, System>,
-}
+                emit!(CircuitBreakerTripped {
+                    last_price,
+                    new_price: price,
+                    deviation_bps,
+                    timestamp: now,
+                });
 
-#[derive(Accounts)]
-pub struct DepositCollateral<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+                return Ok(());
+            }
+        }
 
-    #[account(
-        mut,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == owner.key() @ GusdError::Unauthorized
-    )]
-    pub vault: Account<'info, Vault>,
+        feed.last_good_price = price;
+        feed.last_good_price_ts = now;
+        if native {
+            record_price_observation(&mut ctx.accounts.price_accumulator, price, now, spacing)?;
+            update_ema_price(protocol, price, now)?;
 
-    #[account(
-        mut,
-        seeds = [b"vault_collateral", owner.key().as_ref()],
-        bump
-    )]
-    /// CHECK: This is a PDA that holds lamports (GOR)
-    pub vault_collateral: AccountInfo<'info>,
+            // An oracle observation published after a guardian veto resolves it; the admin
+            // price never does, since it may be what was vetoed
+            if protocol.price_disputed_at != 0 {
+                let observed_at = oracle_observed_at(
+                    feed,
+                    &ctx.accounts.deployment_config,
+                    ctx.accounts.price_update.as_deref(),
+                );
+                if observed_at.is_some_and(|observed_at| observed_at > protocol.price_disputed_at) {
+                    clear_price_dispute_state(protocol, now);
+                }
+            }
+        }
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            ctx.accounts.keeper.as_ref().map(|keeper| keeper.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
 
-    pub system_program: Program<'info, System>,
-}
+        msg!("Price sample recorded for {}: {}", ctx.accounts.price_feed.collateral_mint, price);
 
-#[derive(Accounts)]
-pub struct MintGusd<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == owner.key() @ GusdError::Unauthorized
-    )]
-    pub vault: Account<'info, Vault>,
+    /// Create the GUSD peg tracker (admin only)
+    /// The market price is read from the price feed registered for the GUSD mint.
+    pub fn init_peg_state(ctx: Context<InitPegState>) -> Result<()> {
+        let peg_state = &mut ctx.accounts.peg_state;
+        peg_state.price = GUSD_PEG_PRICE;
+        peg_state.updated_at = 0;
+        peg_state.depegged_since = 0;
+        peg_state.bump = ctx.bumps.peg_state;
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+        msg!("Peg state initialized");
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        seeds = [b"gusd_mint"],
-        bump = protocol_state.mint_bump
-    )]
-    pub gusd_mint: InterfaceAccount<'info, Mint>,
+    /// Record the GUSD market price from its price feed (permissionless crank)
+    /// Flags a de-peg while the price is more than `depeg_threshold_bps` from one unit of the
+    /// peg currency. A USD-quoted market price is converted like collateral prices.
+    pub fn update_peg_price(ctx: Context<UpdatePegPrice>) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let spot = get_spot_price(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let price = apply_fx_rate(protocol, spot)?;
+        let now = Clock::get()?.unix_timestamp;
+        let deviation_bps = peg_deviation_bps(price)?;
+        let depegged = deviation_bps > protocol.depeg_threshold_bps;
 
-    #[account(
-        init_if_needed,
-        payer = owner,
-        associated_token::mint = gusd_mint,
-        associated_token::authority = owner,
-        associated_token::token_program = token_program
-    )]
-    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+        let peg_state = &mut ctx.accounts.peg_state;
+        let was_depegged = peg_state.depegged_since != 0;
+        peg_state.price = price;
+        peg_state.updated_at = now;
 
-    pub token_program: Interface<'info, TokenInterface>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
+        if depegged != was_depegged {
+            peg_state.depegged_since = if depegged { now } else { 0 };
+            msg!("GUSD peg status changed: depegged = {}", depegged);
+            emit!(PegStatusChanged {
+                depegged,
+                price,
+                timestamp: now,
+            });
+        }
 
-#[derive(Accounts)]
-pub struct RepayGusd<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        msg!("GUSD market price: ${} ({} bps from peg)", price as f64 / 1e6, deviation_bps);
 
-    #[account(
-        mut,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == owner.key() @ GusdError::Unauthorized
-    )]
-    pub vault: Account<'info, Vault>,
+        emit!(PegPriceUpdated {
+            price,
+            deviation_bps,
+            depegged_since: peg_state.depegged_since,
+        });
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+        Ok(())
+    }
 
-    #[account(
-        mut,
-        seeds = [b"gusd_mint"],
-        bump = protocol_state.mint_bump
-    )]
-    pub gusd_mint: InterfaceAccount<'info, Mint>,
+    /// Cache the USD price of the peg currency from `fx_price_feed` (permissionless crank)
+    /// Valuations convert USD-quoted prices with this rate and fail as stale once it is
+    /// older than `max_price_age_secs`.
+    pub fn update_fx_rate(ctx: Context<UpdateFxRate>) -> Result<()> {
+        let rate = get_spot_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.fx_price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
 
-    #[account(
-        mut,
-        associated_token::mint = gusd_mint,
-        associated_token::authority = owner,
-        associated_token::token_program = token_program
-    )]
-    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.fx_rate = rate;
+        protocol.fx_rate_updated_at = now;
 
-    pub token_program: Interface<'info, TokenInterface>,
-}
+        msg!("FX rate: 1 {} = ${}", String::from_utf8_lossy(&protocol.peg_currency), rate as f64 / 1e6);
 
-#[derive(Accounts)]
-pub struct WithdrawCollateral<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        emit!(FxRateUpdated {
+            peg_currency: protocol.peg_currency,
+            rate,
+            timestamp: now,
+        });
 
-    #[account(
-        mut,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == owner.key() @ GusdError::Unauthorized
-    )]
-    pub vault: Account<'info, Vault>,
+        Ok(())
+    }
 
-    #[account(
+    /// [MEDIUM-2] Pause protocol (admin only)
+    /// Re-pausing while paused keeps the original start, so the pause still lapses
+    /// `max_pause_secs` after it began. After a pause lapses, the admin must wait another
+    /// `max_pause_secs` before pausing again.
+    pub fn pause_protocol(ctx: Context<TransferAdmin>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        // A lapsed pause leaves `paused_at` set until the admin unpauses
+        if !pause_in_effect(protocol, now) && protocol.paused_at != 0 {
+            let cooldown_ends = protocol.paused_at
+                .saturating_add(2 * protocol.max_pause_secs as i64);
+            require!(now >= cooldown_ends, GusdError::PauseCooldownActive);
+        }
+        enter_pause(protocol, now)?;
+        msg!("Protocol paused");
+        Ok(())
+    }
+
+    /// [MEDIUM-2] Unpause protocol (admin only)
+    /// Resumes guarded launch while a launch ramp runs, active otherwise; `sync_lifecycle`
+    /// then enters recovery mode if the system is undercollateralized.
+    pub fn unpause_protocol(ctx: Context<TransferAdmin>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        require!(protocol.lifecycle != Lifecycle::Settlement, GusdError::ProtocolShutDown);
+        if protocol.lifecycle == Lifecycle::Paused {
+            let next = resume_lifecycle(protocol);
+            transition_lifecycle(protocol, next, Clock::get()?.unix_timestamp)?;
+        }
+        protocol.paused_at = 0;
+        msg!("Protocol unpaused");
+        Ok(())
+    }
+
+    /// Bring the lifecycle state up to date (permissionless)
+    /// Ends a lapsed pause, then enters or leaves recovery mode as GOR's aggregate collateral
+    /// ratio crosses its configured minimum at the current price. Paused and settled
+    /// protocols are otherwise left alone.
+    pub fn sync_lifecycle(ctx: Context<SyncLifecycle>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        let in_effect = lifecycle_in_effect(protocol, now);
+        transition_lifecycle(protocol, in_effect, now)?;
+
+        if matches!(
+            protocol.lifecycle,
+            Lifecycle::GuardedLaunch | Lifecycle::Active | Lifecycle::RecoveryMode
+        ) {
+            require!(protocol.price_disputed_at == 0, GusdError::PriceDisputed);
+            let price = get_price_with_fallback(
+                protocol,
+                &ctx.accounts.price_feed,
+                &ctx.accounts.deployment_config,
+                ctx.accounts.price_update.as_deref(),
+                PriceBound::Lower,
+                PriceOperation::Other,
+            )?;
+            let next = if is_recovery_mode(protocol, &ctx.accounts.collateral_config, price)? {
+                Lifecycle::RecoveryMode
+            } else {
+                resume_lifecycle(protocol)
+            };
+            transition_lifecycle(protocol, next, now)?;
+        }
+
+        msg!("Protocol lifecycle: {:?}", protocol.lifecycle);
+        Ok(())
+    }
+
+    /// Permanently shut the protocol down for global settlement (admin only)
+    /// Pauses minting, withdrawals, and liquidations for good and opens the redemption
+    /// window. The admin funds the rent of the settlement pool that will hold seized
+    /// collateral.
+    pub fn trigger_shutdown(ctx: Context<TriggerShutdown>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let rent = Rent::get()?.minimum_balance(0);
+        let top_up = rent.saturating_sub(ctx.accounts.settlement_pool.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: ctx.accounts.settlement_pool.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.shutdown_at = now;
+        transition_lifecycle(protocol, Lifecycle::Settlement, now)?;
+
+        let settlement = &mut ctx.accounts.settlement;
+        settlement.shutdown_at = now;
+        settlement.price = 0;
+        settlement.marked_at = 0;
+        settlement.requested = 0;
+        settlement.collateral = 0;
+        settlement.claimed = 0;
+        settlement.pool_bump = ctx.bumps.settlement_pool;
+        settlement.bump = ctx.bumps.settlement;
+        settlement.marked_debt = 0;
+        settlement.settled_debt = 0;
+        settlement.debt = 0;
+
+        msg!("Protocol shut down; redemption requests open until {}", now + REDEMPTION_REQUEST_WINDOW_SECS);
+
+        emit!(ShutdownTriggered {
+            admin: ctx.accounts.admin.key(),
+            total_debt: protocol.total_debt,
+            total_collateral: protocol.total_collateral,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Queue GUSD for redemption after a shutdown, burning it now
+    /// Every request made before the settlement price is marked is paid from the same
+    /// pool on the same terms, so claim order never matters.
+    pub fn request_redemption(ctx: Context<RequestRedemption>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+        require!(ctx.accounts.settlement.price == 0, GusdError::SettlementAlreadyMarked);
+
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    from: ctx.accounts.user_gusd_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        record_supply(&mut ctx.accounts.supply_stats.burned_redemptions, amount)?;
+
+        let request = &mut ctx.accounts.redemption_request;
+        request.owner = ctx.accounts.owner.key();
+        request.amount = request.amount.checked_add(amount).ok_or(GusdError::MathOverflow)?;
+        request.bump = ctx.bumps.redemption_request;
+
+        let settlement = &mut ctx.accounts.settlement;
+        settlement.requested = settlement.requested
+            .checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Redemption requested: {} GUSD (total queued {})", amount, settlement.requested);
+
+        emit!(RedemptionRequested {
+            owner: request.owner,
+            amount,
+            total_requested: request.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Fix the collateral price that vaults settle and redemptions pay out at (admin only)
+    /// Allowed once the redemption window has passed; closes the queue to new requests.
+    pub fn mark_settlement(ctx: Context<MarkSettlement>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let settlement = &ctx.accounts.settlement;
+        require!(settlement.price == 0, GusdError::SettlementAlreadyMarked);
+        require!(
+            now >= settlement.shutdown_at.saturating_add(REDEMPTION_REQUEST_WINDOW_SECS),
+            GusdError::RedemptionWindowOpen
+        );
+
+        let price = get_price_with_fallback(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+
+        let protocol = &ctx.accounts.protocol_state;
+        let settlement = &mut ctx.accounts.settlement;
+        settlement.price = price;
+        settlement.marked_at = now;
+        settlement.marked_debt = protocol.total_debt.saturating_sub(protocol.bad_debt);
+
+        msg!("Settlement price marked at {}; {} GUSD queued", price, settlement.requested);
+
+        emit!(SettlementMarked {
+            price,
+            requested: settlement.requested,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a vault at the marked price (permissionless)
+    /// Collateral covering the vault's debt (or all of it, if less) moves to the settlement
+    /// pool of its collateral type, the rest goes back to the owner, and the vault is left
+    /// empty. Token vaults settle into their mint's pool at the price `mark_token_settlement`
+    /// fixed, so that must run first. Without a valid `position_token_account` for the
+    /// current holder the rest stays in the vault, and the holder settles again to claim it.
+    /// A vault with extra collateral balances settles each with `settle_collateral_balance`
+    /// first and passes all of them, in order, in `remaining_accounts`.
+    pub fn settle_vault<'info>(ctx: Context<'_, '_, 'info, 'info, SettleVault<'info>>) -> Result<()> {
+        require!(ctx.accounts.settlement.price > 0, GusdError::SettlementNotMarked);
+
+        let vault = &ctx.accounts.vault;
+        let collateral = vault.collateral_amount;
+        let debt = vault.debt_amount;
+        let principal = debt.saturating_sub(vault.unpaid_interest);
+        let owner_verified = is_vault_owner(
+            vault,
+            &ctx.accounts.vault_owner,
+            ctx.accounts.position_token_account.as_ref(),
+        );
+        require!(debt > 0 || (collateral > 0 && owner_verified), GusdError::InvalidAmount);
+        // Extra collateral balances settle their part of the debt first
+        if debt > 0 {
+            require_balances_settled(vault, ctx.remaining_accounts)?;
+        }
+
+        let vault_owner_key = vault.owner;
+        let position_mint = vault.position_mint;
+        let native = vault.collateral_mint == NATIVE_COLLATERAL_MINT;
+        let (to_pool, returned, transfer_fee) = if native {
+            // Round up in favour of the redemption pool
+            let debt_collateral = GusdUnits(debt)
+                .to_usd()
+                .to_lamports_ceil(
+                    UsdValue6(ctx.accounts.settlement.price),
+                    ctx.accounts.protocol_state.native_decimals,
+                )?
+                .0;
+            let to_pool = debt_collateral.min(collateral);
+            let returned = if owner_verified { collateral - to_pool } else { 0 };
+
+            let seeds = &[
+                SEED_NAMESPACE,
+                b"vault_collateral".as_ref(),
+                position_mint.as_ref(),
+                &[vault.collateral_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+            for (to, amount) in [
+                (ctx.accounts.settlement_pool.to_account_info(), to_pool),
+                (ctx.accounts.vault_owner.to_account_info(), returned),
+            ] {
+                if amount == 0 {
+                    continue;
+                }
+                anchor_lang::system_program::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.vault_collateral.to_account_info(),
+                            to,
+                        },
+                        signer_seeds,
+                    ),
+                    amount,
+                )?;
+            }
+            (to_pool, returned, 0)
+        } else {
+            let pool = ctx.accounts.settlement_token_pool
+                .as_ref()
+                .ok_or(GusdError::SettlementNotMarked)?;
+            let custody = token_custody(
+                ctx.accounts.collateral_mint.as_ref(),
+                ctx.accounts.vault_token_account.as_ref(),
+                ctx.accounts.collateral_token_program.as_ref(),
+            )?;
+            let pool_token_account = ctx.accounts.settlement_pool_token_account
+                .as_ref()
+                .ok_or(GusdError::MissingCollateralAccounts)?;
+
+            // Round up in favour of the redemption pool
+            let debt_collateral = GusdUnits(debt)
+                .to_usd()
+                .to_lamports_ceil(UsdValue6(pool.price), pool.decimals)?
+                .0;
+            let to_pool = debt_collateral.min(collateral);
+            let returned = if owner_verified { collateral - to_pool } else { 0 };
+
+            let transfer_fee = transfer_vault_tokens(
+                custody,
+                &ctx.accounts.vault_collateral,
+                position_mint,
+                vault.collateral_bump,
+                pool_token_account.to_account_info(),
+                to_pool,
+            )?;
+            if returned > 0 {
+                let owner_token_account = ctx.accounts.owner_token_account
+                    .as_ref()
+                    .ok_or(GusdError::MissingCollateralAccounts)?;
+                transfer_vault_tokens(
+                    custody,
+                    &ctx.accounts.vault_collateral,
+                    position_mint,
+                    vault.collateral_bump,
+                    owner_token_account.to_account_info(),
+                    returned,
+                )?;
+            }
+            (to_pool, returned, transfer_fee)
+        };
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_debt = protocol.total_debt
+            .checked_sub(debt)
+            .ok_or(GusdError::MathOverflow)?;
+        let config = &mut ctx.accounts.collateral_config;
+        release_collateral_debt(config, principal);
+
+        // Whatever isn't pooled or returned stays in the vault for its holder
+        let held = collateral - to_pool - returned;
+        let removed = to_pool + returned;
+        let settlement = &mut ctx.accounts.settlement;
+        let credited_debt = credit_settled_debt(settlement, debt);
+        if native {
+            protocol.total_collateral = protocol.total_collateral
+                .checked_sub(removed)
+                .ok_or(GusdError::MathOverflow)?;
+            settlement.collateral = settlement.collateral
+                .checked_add(to_pool)
+                .ok_or(GusdError::MathOverflow)?;
+            settlement.debt = settlement.debt
+                .checked_add(credited_debt)
+                .ok_or(GusdError::MathOverflow)?;
+        } else {
+            config.total_collateral = config.total_collateral
+                .checked_sub(removed)
+                .ok_or(GusdError::MathOverflow)?;
+            // Presence checked above
+            let pool = ctx.accounts.settlement_token_pool.as_mut().ok_or(GusdError::SettlementNotMarked)?;
+            pool.collateral = pool.collateral
+                .checked_add(to_pool - transfer_fee)
+                .ok_or(GusdError::MathOverflow)?;
+            pool.debt = pool.debt
+                .checked_add(credited_debt)
+                .ok_or(GusdError::MathOverflow)?;
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        let collateral_mint = vault.collateral_mint;
+        vault.collateral_amount = held;
+        vault.debt_amount = 0;
+        vault.unpaid_interest = 0;
+        require_collateral_reserve(&ctx.accounts.vault_collateral, vault)?;
+
+        msg!(
+            "Vault {} settled: {} debt, {} {} collateral to pool, {} returned, {} held",
+            vault_owner_key,
+            debt,
+            to_pool,
+            collateral_mint,
+            returned,
+            held
+        );
+
+        emit!(VaultSettled {
+            owner: vault_owner_key,
+            debt,
+            collateral_to_pool: to_pool,
+            collateral_returned: returned,
+            collateral_mint,
+            transfer_fee,
+            collateral_held: held,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Settle one of a vault's extra collateral balances into its mint's settlement pool
+    /// (permissionless)
+    /// The balance backs the part of the vault's debt its value at the pool's price supports
+    /// at its type's minimum collateral ratio. That debt is settled with the tokens worth it,
+    /// and the rest of the balance stays withdrawable once the vault has settled. The mint's
+    /// pool must be open (`mark_token_settlement`), and each balance settles once.
+    pub fn settle_collateral_balance(ctx: Context<SettleCollateralBalance>) -> Result<()> {
+        require!(ctx.accounts.settlement.price > 0, GusdError::SettlementNotMarked);
+        require!(!ctx.accounts.collateral_balance.settled, GusdError::CollateralBalanceSettled);
+
+        let vault = &ctx.accounts.vault;
+        let debt = vault.debt_amount;
+        let amount = ctx.accounts.collateral_balance.amount;
+        require!(debt > 0 && amount > 0, GusdError::InvalidAmount);
+
+        let pool = &ctx.accounts.settlement_token_pool;
+        let value_usd = calculate_usd_value(amount, pool.price, pool.decimals)?;
+        let backed = (value_usd as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(ctx.accounts.balance_collateral_config.min_collateral_ratio_bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .min(debt as u128) as u64;
+        // Round up in favour of the redemption pool
+        let to_pool = GusdUnits(backed)
+            .to_usd()
+            .to_lamports_ceil(UsdValue6(pool.price), pool.decimals)?
+            .0
+            .min(amount);
+
+        let vault_owner_key = vault.owner;
+        let transfer_fee = transfer_vault_tokens(
+            (
+                &ctx.accounts.collateral_mint,
+                &ctx.accounts.balance_token_account,
+                &ctx.accounts.collateral_token_program,
+            ),
+            &ctx.accounts.vault_collateral,
+            vault.position_mint,
+            vault.collateral_bump,
+            ctx.accounts.settlement_pool_token_account.to_account_info(),
+            to_pool,
+        )?;
+
+        let balance = &mut ctx.accounts.collateral_balance;
+        let collateral_mint = balance.collateral_mint;
+        balance.amount = amount - to_pool;
+        balance.settled = true;
+        let remaining_balance = balance.amount;
+        let balance_config = &mut ctx.accounts.balance_collateral_config;
+        balance_config.total_collateral = balance_config.total_collateral
+            .checked_sub(to_pool)
+            .ok_or(GusdError::MathOverflow)?;
+
+        // The debt is booked against the vault's primary type
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_debt = protocol.total_debt
+            .checked_sub(backed)
+            .ok_or(GusdError::MathOverflow)?;
+        let principal = settle_unpaid_interest(&mut ctx.accounts.vault, backed);
+        release_collateral_debt(&mut ctx.accounts.collateral_config, principal);
+        let vault = &mut ctx.accounts.vault;
+        vault.debt_amount = debt - backed;
+        let remaining_debt = vault.debt_amount;
+
+        let credited_debt = credit_settled_debt(&mut ctx.accounts.settlement, backed);
+        let pool = &mut ctx.accounts.settlement_token_pool;
+        pool.collateral = pool.collateral
+            .checked_add(to_pool - transfer_fee)
+            .ok_or(GusdError::MathOverflow)?;
+        pool.debt = pool.debt
+            .checked_add(credited_debt)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!(
+            "Vault {} settled {} debt with {} {} from its collateral balance; {} left, {} debt remaining",
+            vault_owner_key,
+            backed,
+            to_pool,
+            collateral_mint,
+            remaining_balance,
+            remaining_debt
+        );
+
+        emit!(CollateralBalanceSettled {
+            owner: vault_owner_key,
+            collateral_mint,
+            debt: backed,
+            collateral_to_pool: to_pool,
+            transfer_fee,
+            remaining_balance,
+            remaining_debt,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Claim collateral for a queued redemption
+    /// Claims open once every vault has settled, or `SETTLEMENT_WINDOW_SECS` after the price
+    /// is marked, whichever comes first. Each request is owed its pro-rata share of the
+    /// pool, capped at the face value of the burned GUSD at the settlement price; vaults
+    /// settled after a claim grow the pool, and the request can claim the difference.
+    /// The request account closes once its face value is paid.
+    pub fn claim_redemption(ctx: Context<ClaimRedemption>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let settlement = &ctx.accounts.settlement;
+        require!(settlement.price > 0, GusdError::SettlementNotMarked);
+        require!(
+            redemption_claims_open(settlement, ctx.accounts.protocol_state.total_debt, now),
+            GusdError::SettlementIncomplete
+        );
+
+        let request = &ctx.accounts.redemption_request;
+        let amount = request.amount;
+        let native_decimals = ctx.accounts.protocol_state.native_decimals;
+        let (entitled, face_value) = redemption_entitlement(settlement, amount, native_decimals)?;
+        let payout = entitled.saturating_sub(request.claimed);
+        require!(payout > 0, GusdError::InvalidAmount);
+
+        // Enforce the per-epoch redemption cap; the pool pays out GOR
+        {
+            let protocol = &mut ctx.accounts.protocol_state;
+            roll_outflow_epoch(protocol, Clock::get()?.epoch);
+            let cap = protocol.epoch_redemption_cap;
+            consume_epoch_cap(&mut protocol.epoch_redeemed_collateral, cap, payout, false)?;
+        }
+
+        let settlement = &ctx.accounts.settlement;
+        let seeds = &[b"settlement_pool".as_ref(), &[settlement.pool_bump]];
+        let signer_seeds = &[&seeds[..]];
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.settlement_pool.to_account_info(),
+                    to: ctx.accounts.owner.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
+        )?;
+
+        let settlement = &mut ctx.accounts.settlement;
+        settlement.claimed = settlement.claimed
+            .checked_add(payout)
+            .ok_or(GusdError::MathOverflow)?;
+
+        let request = &mut ctx.accounts.redemption_request;
+        request.claimed = entitled;
+        if entitled == face_value {
+            request.close(ctx.accounts.owner.to_account_info())?;
+        }
+
+        msg!("Redemption claimed: {} GUSD -> {} collateral ({} of {} so far)", amount, payout, entitled, face_value);
+
+        emit!(RedemptionClaimed {
+            owner: ctx.accounts.owner.key(),
+            amount,
+            collateral: payout,
+            total_claimed: entitled,
+        });
+
+        Ok(())
+    }
+
+    /// Fix the settlement price of a token collateral type and open its settlement pool
+    /// (admin only)
+    /// Allowed once the GOR price is marked. The pool (`["settlement_pool", mint]`) holds
+    /// its tokens in `["settlement_pool_tokens", mint]`; `settle_vault` moves the mint's
+    /// vaults into it at this price.
+    pub fn mark_token_settlement(ctx: Context<MarkTokenSettlement>) -> Result<()> {
+        require!(ctx.accounts.settlement.price > 0, GusdError::SettlementNotMarked);
+
+        let price = get_price_with_fallback(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+
+        let pool = &mut ctx.accounts.settlement_token_pool;
+        pool.collateral_mint = ctx.accounts.collateral_mint.key();
+        pool.decimals = ctx.accounts.collateral_mint.decimals;
+        pool.price = price;
+        pool.marked_at = now;
+        pool.debt = 0;
+        pool.collateral = 0;
+        pool.claimed = 0;
+        pool.bump = ctx.bumps.settlement_token_pool;
+
+        msg!("Settlement price of {} marked at {}", pool.collateral_mint, price);
+
+        emit!(TokenSettlementMarked {
+            collateral_mint: pool.collateral_mint,
+            price,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a queued redemption's share of a token collateral type's settlement pool
+    /// Opens with `claim_redemption`, on the same terms: the request's pro-rata share of the
+    /// pool, capped at the face value of the pool's part of its GUSD. Claims are tracked per
+    /// request and mint in a `RedemptionTokenClaim`; token payouts don't count against the
+    /// GOR redemption cap.
+    pub fn claim_token_redemption(ctx: Context<ClaimTokenRedemption>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let settlement = &ctx.accounts.settlement;
+        require!(settlement.price > 0, GusdError::SettlementNotMarked);
+        require!(
+            redemption_claims_open(settlement, ctx.accounts.protocol_state.total_debt, now),
+            GusdError::SettlementIncomplete
+        );
+
+        let amount = ctx.accounts.redemption_request.amount;
+        let pool = &ctx.accounts.settlement_token_pool;
+        let (entitled, _) = token_redemption_entitlement(settlement, pool, amount)?;
+        let payout = entitled.saturating_sub(ctx.accounts.token_claim.claimed);
+        require!(payout > 0, GusdError::InvalidAmount);
+
+        let collateral_mint = pool.collateral_mint;
+        let seeds = &[b"settlement_pool".as_ref(), collateral_mint.as_ref(), &[pool.bump]];
+        let signer_seeds = &[&seeds[..]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.collateral_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.settlement_pool_token_account.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.settlement_token_pool.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            payout,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+
+        let pool = &mut ctx.accounts.settlement_token_pool;
+        pool.claimed = pool.claimed
+            .checked_add(payout)
+            .ok_or(GusdError::MathOverflow)?;
+
+        let claim = &mut ctx.accounts.token_claim;
+        claim.owner = ctx.accounts.owner.key();
+        claim.collateral_mint = collateral_mint;
+        claim.claimed = entitled;
+        claim.bump = ctx.bumps.token_claim;
+
+        msg!("Token redemption claimed: {} GUSD -> {} {} ({} so far)", amount, payout, collateral_mint, entitled);
+
+        emit!(TokenRedemptionClaimed {
+            owner: claim.owner,
+            collateral_mint,
+            amount,
+            collateral: payout,
+            total_claimed: entitled,
+        });
+
+        Ok(())
+    }
+
+    /// Flag the current price as disputed (guardian only)
+    /// Freezes mints, withdrawals against debt, and liquidations until `record_price_sample`
+    /// accepts an oracle observation published after the veto, or the admin clears the
+    /// dispute. Admin price updates never clear it.
+    pub fn veto_price(ctx: Context<GuardianOnly>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        require!(protocol.price_disputed_at == 0, GusdError::PriceDisputed);
+        protocol.price_disputed_at = now;
+
+        msg!("Price vetoed by guardian {}", ctx.accounts.guardian.key());
+
+        emit!(PriceVetoed {
+            guardian: ctx.accounts.guardian.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Set a feed's price immediately, with both admin and guardian signing (dual control)
+    /// For post-incident recovery when the market has legitimately moved beyond the circuit
+    /// breaker band: skips the update interval and ramp, and re-anchors the circuit breaker
+    /// on the new price so oracle samples near it are accepted again. For the native feed it
+    /// also re-anchors the EMA and TWAP and clears any guardian dispute; a tripped pause
+    /// still needs `unpause`.
+    pub fn emergency_set_price(ctx: Context<EmergencySetPrice>, new_gor_price_usd: u64) -> Result<()> {
+        require!(new_gor_price_usd > 0, GusdError::InvalidPrice);
+        let now = Clock::get()?.unix_timestamp;
+
+        let feed = &mut ctx.accounts.price_feed;
+        let old_price = current_admin_price(feed, now);
+        feed.admin_price = new_gor_price_usd;
+        feed.admin_ramp_price = new_gor_price_usd;
+        feed.admin_ramp_rate = 0;
+        feed.admin_price_ts = now;
+        feed.last_good_price = new_gor_price_usd;
+        feed.last_good_price_ts = now;
+        let collateral_mint = feed.collateral_mint;
+
+        if collateral_mint == NATIVE_COLLATERAL_MINT {
+            // The dual-signed reset always starts a fresh slot
+            record_price_observation(&mut ctx.accounts.price_accumulator, new_gor_price_usd, now, 0)?;
+
+            let protocol = &mut ctx.accounts.protocol_state;
+            protocol.ema_price = new_gor_price_usd;
+            protocol.ema_updated_at = now;
+            if protocol.price_disputed_at != 0 {
+                clear_price_dispute_state(protocol, now);
+            }
+        }
+
+        msg!("Emergency price set for {}: {} -> {}", collateral_mint, old_price, new_gor_price_usd);
+
+        emit!(EmergencyPriceSet {
+            collateral_mint,
+            admin: ctx.accounts.admin.key(),
+            guardian: ctx.accounts.guardian.key(),
+            old_price,
+            new_price: new_gor_price_usd,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Clear a guardian price dispute (admin only)
+    pub fn clear_price_dispute(ctx: Context<TransferAdmin>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        require!(protocol.price_disputed_at != 0, GusdError::NoPriceDispute);
+        clear_price_dispute_state(protocol, Clock::get()?.unix_timestamp);
+
+        msg!("Price dispute cleared by admin");
+
+        Ok(())
+    }
+
+    /// Pause interest accrual independently of the protocol pause (risk manager only)
+    /// Used when users could not repay (e.g., a protocol-caused outage) so they aren't charged.
+    pub fn pause_accrual(ctx: Context<RiskManagerOnly>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        require!(protocol.accrual_paused_at == 0, GusdError::AccrualAlreadyPaused);
+
+        let now = Clock::get()?.unix_timestamp;
+        accrue_debt_index(protocol, now)?;
+        protocol.accrual_paused_at = now;
+
+        msg!("Interest accrual paused");
+
+        emit!(AccrualPaused {
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Resume interest accrual, recording the skipped interval (risk manager only)
+    pub fn resume_accrual(ctx: Context<RiskManagerOnly>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let paused_at = protocol.accrual_paused_at;
+        require!(paused_at != 0, GusdError::AccrualNotPaused);
+
+        let now = Clock::get()?.unix_timestamp;
+        // Skips the paused interval: the index only moves its timestamp forward
+        accrue_debt_index(protocol, now)?;
+        let skipped_secs = now.saturating_sub(paused_at) as u64;
+        protocol.accrual_skipped_secs = protocol.accrual_skipped_secs
+            .checked_add(skipped_secs)
+            .ok_or(GusdError::MathOverflow)?;
+        protocol.accrual_paused_at = 0;
+
+        msg!("Interest accrual resumed after {} seconds", skipped_secs);
+
+        emit!(AccrualResumed {
+            paused_at,
+            resumed_at: now,
+            skipped_secs,
+        });
+
+        Ok(())
+    }
+
+    /// Choose how collateral is valued: spot, EMA, or min(spot, EMA) (risk manager only)
+    /// Logged to the parameter changelog like `set_risk_parameter`.
+    pub fn set_price_mode(ctx: Context<SetRiskParameter>, mode: PriceMode) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let old_mode = std::mem::replace(&mut protocol.price_mode, mode);
+        log_parameter_change(
+            protocol,
+            &mut ctx.accounts.parameter_log,
+            ctx.bumps.parameter_log,
+            ParameterChange {
+                parameter: RiskParameter::PriceMode,
+                old_value: old_mode as u64,
+                new_value: mode as u64,
+                actor: ctx.accounts.risk_manager.key(),
+                slot: Clock::get()?.slot,
+                target: Pubkey::default(),
+            },
+        )?;
+
+        msg!("Price mode updated: {:?} -> {:?}", old_mode, mode);
+
+        emit!(PriceModeUpdated {
+            old_mode,
+            new_mode: mode,
+        });
+
+        Ok(())
+    }
+
+    /// Update a tunable risk parameter (risk manager only)
+    /// Each parameter is validated against its own bounds. Every change is appended to the
+    /// parameter changelog; the risk manager pays for each new page.
+    pub fn set_risk_parameter(
+        ctx: Context<SetRiskParameter>,
+        parameter: RiskParameter,
+        value: u64,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let old_value = apply_risk_parameter(protocol, parameter, value)?;
+        log_parameter_change(
+            protocol,
+            &mut ctx.accounts.parameter_log,
+            ctx.bumps.parameter_log,
+            ParameterChange {
+                parameter,
+                old_value,
+                new_value: value,
+                actor: ctx.accounts.risk_manager.key(),
+                slot: Clock::get()?.slot,
+                target: Pubkey::default(),
+            },
+        )
+    }
+
+    /// Apply a named risk parameter preset in one instruction (risk manager only)
+    /// Each of the template's parameters goes through the same bounds as
+    /// `set_risk_parameter`, in order, and is logged to the changelog; if any is rejected
+    /// the whole template reverts. The next changelog page is passed in case the template
+    /// crosses a page boundary.
+    pub fn apply_risk_template(
+        ctx: Context<ApplyRiskTemplate>,
+        template: RiskTemplate,
+    ) -> Result<()> {
+        let actor = ctx.accounts.risk_manager.key();
+        let slot = Clock::get()?.slot;
+
+        let mut changes = Vec::with_capacity(template.parameters().len());
+        for &(parameter, value) in template.parameters() {
+            let old_value = apply_risk_parameter(&mut ctx.accounts.protocol_state, parameter, value)?;
+            changes.push(ParameterChange {
+                parameter,
+                old_value,
+                new_value: value,
+                actor,
+                slot,
+                target: Pubkey::default(),
+            });
+        }
+        log_parameter_changes(
+            &mut ctx.accounts.protocol_state,
+            (&mut ctx.accounts.parameter_log, ctx.bumps.parameter_log),
+            (&mut ctx.accounts.next_parameter_log, ctx.bumps.next_parameter_log),
+            &changes,
+        )?;
+
+        msg!("Risk template {:?} applied", template);
+
+        emit!(RiskTemplateApplied {
+            template,
+            parameters: template.parameters().len() as u8,
+        });
+
+        Ok(())
+    }
+
+    /// Set the fees charged on vaults backed by one collateral (risk manager only)
+    /// Riskier collateral can carry a stability fee premium over the protocol rate and its
+    /// own mint fee and liquidation penalty. The premium accrued so far is charged at the
+    /// old rate. Each changed fee is appended to the parameter changelog, with
+    /// `PARAMETER_UNSET` standing for a fee that falls back to the protocol-wide one.
+    pub fn set_collateral_fees(ctx: Context<SetCollateralFees>, fees: CollateralFees) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        require!(
+            fees.stability_fee_premium_bps <= MAX_STABILITY_FEE_BPS
+                && fees.mint_fee_bps.is_none_or(|bps| bps <= MAX_MINT_FEE_BPS),
+            GusdError::InvalidParameter
+        );
+        match (fees.liquidation_bonus_bps, fees.liquidation_fee_bps) {
+            (Some(bonus_bps), Some(fee_bps)) => require!(
+                bonus_bps > 0
+                    && bonus_bps >= protocol.min_liquidation_profit_bps
+                    && bonus_bps.saturating_add(fee_bps) <= MAX_LIQUIDATION_PENALTY_BPS,
+                GusdError::InvalidParameter
+            ),
+            (None, None) => {}
+            _ => return err!(GusdError::InvalidParameter),
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let feed = &mut ctx.accounts.price_feed;
+        accrue_premium_index(protocol, feed, now)?;
+        let old_fees = std::mem::replace(&mut feed.fees, fees);
+
+        let unset_or = |bps: Option<u64>| bps.unwrap_or(PARAMETER_UNSET);
+        let changes = changed_parameters(
+            feed.collateral_mint,
+            ctx.accounts.risk_manager.key(),
+            &[
+                (
+                    RiskParameter::CollateralStabilityFeePremiumBps,
+                    old_fees.stability_fee_premium_bps,
+                    fees.stability_fee_premium_bps,
+                ),
+                (
+                    RiskParameter::CollateralMintFeeBps,
+                    unset_or(old_fees.mint_fee_bps),
+                    unset_or(fees.mint_fee_bps),
+                ),
+                (
+                    RiskParameter::CollateralLiquidationBonusBps,
+                    unset_or(old_fees.liquidation_bonus_bps),
+                    unset_or(fees.liquidation_bonus_bps),
+                ),
+                (
+                    RiskParameter::CollateralLiquidationFeeBps,
+                    unset_or(old_fees.liquidation_fee_bps),
+                    unset_or(fees.liquidation_fee_bps),
+                ),
+            ],
+        )?;
+        log_parameter_changes(
+            &mut ctx.accounts.protocol_state,
+            (&mut ctx.accounts.parameter_log, ctx.bumps.parameter_log),
+            (&mut ctx.accounts.next_parameter_log, ctx.bumps.next_parameter_log),
+            &changes,
+        )?;
+
+        let feed = &ctx.accounts.price_feed;
+
+        msg!("Collateral fees updated for {}: {:?}", feed.collateral_mint, fees);
+
+        emit!(CollateralFeesUpdated {
+            collateral_mint: feed.collateral_mint,
+            fees,
+        });
+
+        Ok(())
+    }
+
+    /// [LOW-2] Transfer admin role to a new address
+    pub fn transfer_admin(ctx: Context<TransferAdmin>, new_admin: Pubkey) -> Result<()> {
+        require!(new_admin != Pubkey::default(), GusdError::InvalidAmount);
+        
+        let protocol = &mut ctx.accounts.protocol_state;
+        let old_admin = protocol.admin;
+        protocol.admin = new_admin;
+        
+        msg!("Admin transferred from {} to {}", old_admin, new_admin);
+        
+        Ok(())
+    }
+
+    /// Set the fiat currency the debt token is pegged to (admin only)
+    /// Every price and value the engine handles ("USD" in field names) is denominated in
+    /// this currency, so a gEUR deployment prices collateral in EUR and values 1 gEUR of
+    /// debt at 1 EUR. With `fx_price_feed` set (a feed quoting the currency in USD), feeds
+    /// quote USD and valuations divide by the FX rate cached by `update_fx_rate`; with the
+    /// default key, feeds must quote the peg currency directly. Only allowed while no debt
+    /// is outstanding.
+    pub fn set_peg_currency(
+        ctx: Context<TransferAdmin>,
+        currency: [u8; 3],
+        fx_price_feed: Pubkey,
+    ) -> Result<()> {
+        require!(
+            currency.iter().all(u8::is_ascii_uppercase),
+            GusdError::InvalidPegCurrency
+        );
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        require!(protocol.total_debt == 0, GusdError::PegCurrencyLocked);
+        let old_currency = std::mem::replace(&mut protocol.peg_currency, currency);
+        protocol.fx_price_feed = fx_price_feed;
+        protocol.fx_rate = 0;
+        protocol.fx_rate_updated_at = 0;
+
+        msg!(
+            "Peg currency changed from {} to {} (FX feed {})",
+            String::from_utf8_lossy(&old_currency),
+            String::from_utf8_lossy(&currency),
+            fx_price_feed
+        );
+
+        emit!(PegCurrencySet {
+            old_currency,
+            new_currency: currency,
+            fx_price_feed,
+        });
+
+        Ok(())
+    }
+
+    /// Set per-epoch caps on collateral leaving via liquidation and redemption (admin only)
+    /// A cap of 0 disables the limit. The liquidation cap is lifted while the liquidated
+    /// collateral type is below its own minimum ratio; the redemption cap limits settlement
+    /// claims. Each changed cap is appended to the parameter changelog.
+    pub fn set_epoch_caps(
+        ctx: Context<SetAdminParameters>,
+        epoch_liquidation_cap: u64,
+        epoch_redemption_cap: u64,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let changes = changed_parameters(
+            Pubkey::default(),
+            ctx.accounts.admin.key(),
+            &[
+                (
+                    RiskParameter::EpochLiquidationCap,
+                    std::mem::replace(&mut protocol.epoch_liquidation_cap, epoch_liquidation_cap),
+                    epoch_liquidation_cap,
+                ),
+                (
+                    RiskParameter::EpochRedemptionCap,
+                    std::mem::replace(&mut protocol.epoch_redemption_cap, epoch_redemption_cap),
+                    epoch_redemption_cap,
+                ),
+            ],
+        )?;
+        log_parameter_changes(
+            protocol,
+            (&mut ctx.accounts.parameter_log, ctx.bumps.parameter_log),
+            (&mut ctx.accounts.next_parameter_log, ctx.bumps.next_parameter_log),
+            &changes,
+        )?;
+
+        msg!(
+            "Epoch caps updated: liquidation {}, redemption {}",
+            epoch_liquidation_cap,
+            epoch_redemption_cap
+        );
+
+        emit!(EpochCapsUpdated {
+            epoch_liquidation_cap,
+            epoch_redemption_cap,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the global debt ceiling and its launch ramp (admin only)
+    /// While `step_bps` > 0 the ceiling grows by `step_bps` every week until it reaches
+    /// `target_ceiling` or the guardian halts the ramp. A ceiling of 0 is unlimited. Each
+    /// changed value is appended to the parameter changelog.
+    pub fn set_launch_ramp(
+        ctx: Context<SetAdminParameters>,
+        debt_ceiling: u64,
+        step_bps: u64,
+        target_ceiling: u64,
+    ) -> Result<()> {
+        require!(step_bps <= MAX_LAUNCH_RAMP_STEP_BPS, GusdError::InvalidParameter);
+        if step_bps > 0 {
+            require!(
+                debt_ceiling > 0 && target_ceiling > debt_ceiling,
+                GusdError::InvalidParameter
+            );
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        let target_ceiling = if step_bps > 0 { target_ceiling } else { 0 };
+        let changes = changed_parameters(
+            Pubkey::default(),
+            ctx.accounts.admin.key(),
+            &[
+                (
+                    RiskParameter::DebtCeiling,
+                    std::mem::replace(&mut protocol.debt_ceiling, debt_ceiling),
+                    debt_ceiling,
+                ),
+                (
+                    RiskParameter::LaunchRampStepBps,
+                    std::mem::replace(&mut protocol.launch_ramp_step_bps, step_bps),
+                    step_bps,
+                ),
+                (
+                    RiskParameter::LaunchRampTarget,
+                    std::mem::replace(&mut protocol.launch_ramp_target, target_ceiling),
+                    target_ceiling,
+                ),
+            ],
+        )?;
+        log_parameter_changes(
+            protocol,
+            (&mut ctx.accounts.parameter_log, ctx.bumps.parameter_log),
+            (&mut ctx.accounts.next_parameter_log, ctx.bumps.next_parameter_log),
+            &changes,
+        )?;
+        protocol.launch_ramp_last_step_ts = now;
+        sync_launch_lifecycle(protocol, now)?;
+
+        msg!(
+            "Launch ramp set: ceiling {}, step {} bps, target {}",
+            debt_ceiling,
+            step_bps,
+            protocol.launch_ramp_target
+        );
+
+        emit!(LaunchRampUpdated {
+            debt_ceiling,
+            step_bps,
+            target_ceiling: protocol.launch_ramp_target,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Stop the launch ramp, freezing the debt ceiling at its current value (guardian only)
+    pub fn halt_launch_ramp(ctx: Context<GuardianOnly>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        require!(protocol.launch_ramp_step_bps > 0, GusdError::LaunchRampInactive);
+
+        // Apply steps already earned before freezing
+        advance_launch_ramp(protocol, now)?;
+        protocol.launch_ramp_step_bps = 0;
+        protocol.launch_ramp_target = 0;
+        sync_launch_lifecycle(protocol, now)?;
+
+        msg!("Launch ramp halted at ceiling {}", protocol.debt_ceiling);
+
+        emit!(LaunchRampHalted {
+            guardian: ctx.accounts.guardian.key(),
+            debt_ceiling: protocol.debt_ceiling,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Exempt an address from mint and withdrawal fees until `expires_at` (risk manager only)
+    /// `expires_at` = 0 never expires. Calling it again for a listed address renews the entry.
+    pub fn add_fee_exemption(
+        ctx: Context<AddFeeExemption>,
+        address: Pubkey,
+        expires_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at == 0 || expires_at > now, GusdError::InvalidExpiry);
+
+        let exemption = &mut ctx.accounts.fee_exemption;
+        exemption.address = address;
+        exemption.added_by = ctx.accounts.risk_manager.key();
+        exemption.added_at = now;
+        exemption.expires_at = expires_at;
+        exemption.bump = ctx.bumps.fee_exemption;
+
+        msg!("Fee exemption added for {} until {}", address, expires_at);
+
+        emit!(FeeExemptionAdded {
+            address,
+            authority: exemption.added_by,
+            expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Remove an address from the fee exemption allowlist (risk manager only)
+    pub fn remove_fee_exemption(ctx: Context<RemoveFeeExemption>) -> Result<()> {
+        let address = ctx.accounts.fee_exemption.address;
+
+        msg!("Fee exemption removed for {}", address);
+
+        emit!(FeeExemptionRemoved {
+            address,
+            authority: ctx.accounts.risk_manager.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the staker fee tier registry and its governance token escrow (admin only)
+    /// The governance mint is fixed here; no tiers apply until `set_fee_tiers`.
+    pub fn init_fee_tiers(ctx: Context<InitFeeTiers>) -> Result<()> {
+        let registry = &mut ctx.accounts.fee_tier_registry;
+        registry.governance_mint = ctx.accounts.governance_mint.key();
+        registry.tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        registry.tier_count = 0;
+        registry.total_staked = 0;
+        registry.bump = ctx.bumps.fee_tier_registry;
+
+        msg!("Fee tiers initialized for governance mint {}", registry.governance_mint);
+
+        emit!(FeeTiersInitialized {
+            governance_mint: registry.governance_mint,
+            stake_escrow: ctx.accounts.stake_escrow.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Replace the staker fee tiers (risk manager only)
+    /// Tiers are listed by strictly increasing `min_stake`; discounts may not decrease
+    /// and are capped at `MAX_STAKER_DISCOUNT_BPS`. An empty list turns discounts off.
+    /// Each changed tier value is appended to the parameter changelog; removed tiers log 0.
+    pub fn set_fee_tiers(ctx: Context<SetFeeTiers>, tiers: Vec<FeeTier>) -> Result<()> {
+        require!(tiers.len() <= MAX_FEE_TIERS, GusdError::InvalidFeeTiers);
+        let mut previous = FeeTier::default();
+        for tier in &tiers {
+            require!(
+                tier.min_stake > previous.min_stake
+                    && tier.discount_bps >= previous.discount_bps
+                    && tier.discount_bps <= MAX_STAKER_DISCOUNT_BPS,
+                GusdError::InvalidFeeTiers
+            );
+            previous = *tier;
+        }
+
+        let registry = &mut ctx.accounts.fee_tier_registry;
+        let old_tiers = registry.tiers;
+        registry.tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        registry.tiers[..tiers.len()].copy_from_slice(&tiers);
+        registry.tier_count = tiers.len() as u8;
+
+        let mut tier_values = Vec::with_capacity(2 * MAX_FEE_TIERS);
+        for (index, (old, new)) in old_tiers.iter().zip(registry.tiers.iter()).enumerate() {
+            let index = index as u8;
+            tier_values.push((RiskParameter::FeeTierMinStake(index), old.min_stake, new.min_stake));
+            tier_values.push((RiskParameter::FeeTierDiscountBps(index), old.discount_bps, new.discount_bps));
+        }
+        let changes = changed_parameters(
+            Pubkey::default(),
+            ctx.accounts.risk_manager.key(),
+            &tier_values,
+        )?;
+        log_parameter_changes(
+            &mut ctx.accounts.protocol_state,
+            (&mut ctx.accounts.parameter_log, ctx.bumps.parameter_log),
+            (&mut ctx.accounts.next_parameter_log, ctx.bumps.next_parameter_log),
+            &changes,
+        )?;
+
+        msg!("Fee tiers set: {} tiers", tiers.len());
+
+        emit!(FeeTiersSet {
+            authority: ctx.accounts.risk_manager.key(),
+            tiers,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stake governance tokens toward a fee tier
+    /// Every stake restarts the whole position's `STAKE_WARMUP_SECS` warm-up.
+    pub fn stake_governance(ctx: Context<StakeGovernance>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    mint: ctx.accounts.governance_mint.to_account_info(),
+                    to: ctx.accounts.stake_escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.governance_mint.decimals,
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let stake = &mut ctx.accounts.governance_stake;
+        stake.owner = ctx.accounts.owner.key();
+        stake.amount = stake.amount
+            .checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+        stake.warm_at = now
+            .checked_add(STAKE_WARMUP_SECS)
+            .ok_or(GusdError::MathOverflow)?;
+        stake.bump = ctx.bumps.governance_stake;
+
+        let registry = &mut ctx.accounts.fee_tier_registry;
+        registry.total_staked = registry.total_staked
+            .checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Governance stake: {} by {} (warm at {})", amount, stake.owner, stake.warm_at);
+
+        emit!(GovernanceStaked {
+            owner: stake.owner,
+            amount,
+            total_stake: stake.amount,
+            warm_at: stake.warm_at,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw staked governance tokens
+    /// Vaults recording this stake re-read its discount at their next accrual, so the
+    /// lower discount also applies to interest not yet accrued.
+    pub fn unstake_governance(ctx: Context<UnstakeGovernance>, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= ctx.accounts.governance_stake.amount,
+            GusdError::InvalidAmount
+        );
+
+        let seeds = &[b"fee_tiers".as_ref(), &[ctx.accounts.fee_tier_registry.bump]];
+        let signer_seeds = &[&seeds[..]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.stake_escrow.to_account_info(),
+                    mint: ctx.accounts.governance_mint.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: ctx.accounts.fee_tier_registry.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.governance_mint.decimals,
+        )?;
+
+        let stake = &mut ctx.accounts.governance_stake;
+        stake.amount -= amount;
+        let registry = &mut ctx.accounts.fee_tier_registry;
+        registry.total_staked = registry.total_staked.saturating_sub(amount);
+
+        let discount_bps = registry.discount_bps(stake, Clock::get()?.unix_timestamp);
+
+        msg!("Governance unstake: {} by {}, {} remaining", amount, stake.owner, stake.amount);
+
+        emit!(GovernanceUnstaked {
+            owner: stake.owner,
+            amount,
+            remaining_stake: stake.amount,
+            discount_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Whitelist the swap program, USDC mint, and route accounts used for treasury
+    /// diversification (admin only)
+    /// `diversify_treasury` must forward exactly `route_accounts`, in order and with the same
+    /// writability, so the treasury PDA only ever signs for the approved route.
+    pub fn set_swap_route(
+        ctx: Context<SetSwapRoute>,
+        swap_program: Pubkey,
+        usdc_mint: Pubkey,
+        route_accounts: Vec<SwapRouteAccount>,
+    ) -> Result<()> {
+        require!(route_accounts.len() <= MAX_SWAP_ROUTE_ACCOUNTS, GusdError::InvalidSwapRoute);
+        let route_hash = swap_route_hash(
+            route_accounts.iter().map(|account| (account.pubkey, account.is_writable)),
+        );
+
+        let deployment = &mut ctx.accounts.deployment_config;
+        deployment.swap_program = swap_program;
+        deployment.usdc_mint = usdc_mint;
+        deployment.swap_route_hash = route_hash;
+
+        msg!(
+            "Treasury swap route set: program {} -> mint {} ({} accounts)",
+            swap_program,
+            usdc_mint,
+            route_accounts.len()
+        );
+
+        emit!(SwapRouteUpdated {
+            swap_program,
+            usdc_mint,
+            route_accounts,
+        });
+
+        Ok(())
+    }
+
+    /// Require a KYC attestation token to open a vault, or lift the requirement (admin only)
+    /// `attestation_mint` is the issuer's Token-2022 mint, which must be non-transferable so
+    /// attestations stay soul-bound; pass none to open vaults to anyone.
+    pub fn set_kyc_attestation(ctx: Context<SetKycAttestation>) -> Result<()> {
+        let (mint, issuer) = match &ctx.accounts.attestation_mint {
+            Some(attestation_mint) => {
+                require_keys_eq!(
+                    *attestation_mint.to_account_info().owner,
+                    anchor_spl::token_2022::ID,
+                    GusdError::InvalidKycAttestation
+                );
+                get_mint_extension_data::<NonTransferable>(&attestation_mint.to_account_info())
+                    .map_err(|_| error!(GusdError::InvalidKycAttestation))?;
+                let issuer = Option::<Pubkey>::from(attestation_mint.mint_authority)
+                    .ok_or(GusdError::InvalidKycAttestation)?;
+                (attestation_mint.key(), issuer)
+            }
+            None => (Pubkey::default(), Pubkey::default()),
+        };
+
+        ctx.accounts.deployment_config.kyc_attestation_mint = mint;
+
+        if mint == Pubkey::default() {
+            msg!("KYC attestation requirement lifted");
+        } else {
+            msg!("Vaults now require attestation mint {} (issuer {})", mint, issuer);
+        }
+
+        emit!(KycAttestationSet {
+            attestation_mint: mint,
+            issuer,
+        });
+
+        Ok(())
+    }
+
+    /// Create the treasury's GUSD account and fund the treasury PDA's rent (treasurer only)
+    pub fn init_treasury(ctx: Context<InitTreasury>) -> Result<()> {
+        let rent = Rent::get()?.minimum_balance(0);
+        let top_up = rent.saturating_sub(ctx.accounts.treasury.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.treasurer.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        msg!("Treasury initialized: GUSD account {}", ctx.accounts.treasury_gusd_account.key());
+
+        emit!(TreasuryInitialized {
+            treasury: ctx.accounts.treasury.key(),
+            treasury_gusd_account: ctx.accounts.treasury_gusd_account.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Set the owner of the accounts protocol revenue is collected to (admin only)
+    pub fn set_fee_destination(ctx: Context<TransferAdmin>, destination: Pubkey) -> Result<()> {
+        require!(destination != Pubkey::default(), GusdError::InvalidFeeDestination);
+        let protocol = &mut ctx.accounts.protocol_state;
+        let old_destination = std::mem::replace(&mut protocol.fee_destination, destination);
+
+        msg!("Fee destination changed: {} -> {}", old_destination, destination);
+
+        emit!(FeeDestinationSet {
+            old_destination,
+            new_destination: destination,
+        });
+
+        Ok(())
+    }
+
+    /// Realize accrued protocol revenue into the treasury (treasurer only)
+    /// Stability and mint fees accrued since the last collection are minted into the
+    /// treasury's GUSD account. Paying anything out of the treasury goes through
+    /// `propose_treasury_spend`.
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        // Accrued fees are debt already counted in total_debt; minting them keeps supply
+        // equal to total_debt. After a shutdown they stay with the settlement.
+        let realized = if ctx.accounts.protocol_state.shutdown_at == 0 {
+            ctx.accounts.protocol_state.accrued_fees
+        } else {
+            0
+        };
+        if realized > 0 {
+            let bump = ctx.accounts.protocol_state.bump;
+            let seeds = &[SEED_NAMESPACE, b"protocol".as_ref(), &[bump]];
+            let signer_seeds = &[&seeds[..]];
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        to: ctx.accounts.treasury_gusd_account.to_account_info(),
+                        authority: ctx.accounts.protocol_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                realized,
+            )?;
+            ctx.accounts.protocol_state.accrued_fees = 0;
+            record_supply(&mut ctx.accounts.supply_stats.minted_treasury_fees, realized)?;
+        }
+
+        msg!("Fees collected: {} GUSD realized into the treasury", realized);
+
+        emit!(FeesCollected {
+            treasurer: ctx.accounts.treasurer.key(),
+            realized,
+        });
+
+        Ok(())
+    }
+
+    /// Set the wait between proposing and executing a treasury spend (admin only)
+    /// Applies to spends proposed afterwards.
+    pub fn set_treasury_spend_delay(ctx: Context<TransferAdmin>, delay_secs: u64) -> Result<()> {
+        require!(
+            (MIN_TREASURY_SPEND_DELAY_SECS..=MAX_TREASURY_SPEND_DELAY_SECS).contains(&delay_secs),
+            GusdError::InvalidParameter
+        );
+        let protocol = &mut ctx.accounts.protocol_state;
+        let old_delay = std::mem::replace(&mut protocol.treasury_spend_delay_secs, delay_secs);
+
+        msg!("Treasury spend delay changed: {} -> {} seconds", old_delay, delay_secs);
+
+        emit!(TreasurySpendDelaySet {
+            old_delay_secs: old_delay,
+            new_delay_secs: delay_secs,
+        });
+
+        Ok(())
+    }
+
+    /// Propose paying `gusd_amount` GUSD and `lamports` GOR from the treasury to the fee
+    /// destination (treasurer only)
+    /// The recipient is fixed at proposal time. The spend can execute once
+    /// `treasury_spend_delay_secs` has passed, unless the guardian cancels it first.
+    pub fn propose_treasury_spend(
+        ctx: Context<ProposeTreasurySpend>,
+        gusd_amount: u64,
+        lamports: u64,
+    ) -> Result<()> {
+        require!(gusd_amount > 0 || lamports > 0, GusdError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        let id = protocol.treasury_spend_count;
+        protocol.treasury_spend_count = id.checked_add(1).ok_or(GusdError::MathOverflow)?;
+
+        let spend = &mut ctx.accounts.treasury_spend;
+        spend.id = id;
+        spend.proposer = ctx.accounts.treasurer.key();
+        spend.recipient = protocol.fee_destination;
+        spend.gusd_amount = gusd_amount;
+        spend.lamports = lamports;
+        spend.executable_at = now
+            .checked_add(protocol.treasury_spend_delay_secs as i64)
+            .ok_or(GusdError::MathOverflow)?;
+        spend.bump = ctx.bumps.treasury_spend;
+
+        msg!(
+            "Treasury spend {} proposed: {} GUSD and {} GOR to {}, executable at {}",
+            id,
+            gusd_amount,
+            lamports,
+            spend.recipient,
+            spend.executable_at
+        );
+
+        emit!(TreasurySpendProposed {
+            id,
+            recipient: spend.recipient,
+            gusd_amount,
+            lamports,
+            executable_at: spend.executable_at,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out a proposed treasury spend once its delay has passed (permissionless)
+    /// Closes the proposal, returning its rent to the proposer.
+    pub fn execute_treasury_spend(ctx: Context<ExecuteTreasurySpend>) -> Result<()> {
+        let spend = &ctx.accounts.treasury_spend;
+        require!(
+            Clock::get()?.unix_timestamp >= spend.executable_at,
+            GusdError::TreasurySpendTimelocked
+        );
+        require!(
+            spend.gusd_amount <= ctx.accounts.treasury_gusd_account.amount,
+            GusdError::InsufficientTreasuryBalance
+        );
+        let spendable_lamports = ctx.accounts.treasury.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(spend.lamports <= spendable_lamports, GusdError::InsufficientTreasuryBalance);
+
+        let seeds = &[b"treasury".as_ref(), &[ctx.bumps.treasury]];
+        let signer_seeds = &[&seeds[..]];
+        if spend.gusd_amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.treasury_gusd_account.to_account_info(),
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        to: ctx.accounts.recipient_gusd_account.to_account_info(),
+                        authority: ctx.accounts.treasury.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                spend.gusd_amount,
+                GUSD_DECIMALS,
+            )?;
+        }
+        if spend.lamports > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.treasury.to_account_info(),
+                        to: ctx.accounts.recipient.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                spend.lamports,
+            )?;
+        }
+
+        msg!(
+            "Treasury spend {} executed: {} GUSD and {} GOR sent to {}",
+            spend.id,
+            spend.gusd_amount,
+            spend.lamports,
+            spend.recipient
+        );
+
+        emit!(TreasurySpendExecuted {
+            id: spend.id,
+            recipient: spend.recipient,
+            gusd_amount: spend.gusd_amount,
+            lamports: spend.lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a proposed treasury spend (guardian only)
+    pub fn cancel_treasury_spend(ctx: Context<CancelTreasurySpend>) -> Result<()> {
+        let id = ctx.accounts.treasury_spend.id;
+
+        msg!("Treasury spend {} cancelled", id);
+
+        emit!(TreasurySpendCancelled {
+            id,
+            guardian: ctx.accounts.guardian.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Create the GUSD savings pool and its token account (admin only)
+    pub fn init_savings(ctx: Context<InitSavings>) -> Result<()> {
+        let pool = &mut ctx.accounts.savings_pool;
+        pool.total_shares = 0;
+        pool.total_assets = 0;
+        pool.bump = ctx.bumps.savings_pool;
+
+        msg!("Savings pool initialized: GUSD account {}", ctx.accounts.savings_gusd_account.key());
+
+        emit!(SavingsInitialized {
+            savings_pool: pool.key(),
+            savings_gusd_account: ctx.accounts.savings_gusd_account.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Deposit GUSD into the savings pool for sGUSD shares at the current share price
+    pub fn deposit_savings(ctx: Context<DepositSavings>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+        drip_savings(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.savings_pool,
+            &mut ctx.accounts.supply_stats,
+            &ctx.accounts.savings_gusd_account,
+            &ctx.accounts.gusd_mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        let pool = &ctx.accounts.savings_pool;
+        let shares = if pool.total_shares == 0 {
+            amount
+        } else {
+            ((amount as u128)
+                .checked_mul(pool.total_shares as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(pool.total_assets as u128)
+                .ok_or(GusdError::MathOverflow)?) as u64
+        };
+        require!(shares > 0, GusdError::InvalidAmount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.owner_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.savings_gusd_account.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            amount,
+            GUSD_DECIMALS,
+        )?;
+
+        let position = &mut ctx.accounts.savings_position;
+        position.owner = ctx.accounts.owner.key();
+        position.shares = position.shares
+            .checked_add(shares)
+            .ok_or(GusdError::MathOverflow)?;
+        position.bump = ctx.bumps.savings_position;
+
+        let pool = &mut ctx.accounts.savings_pool;
+        pool.total_shares = pool.total_shares
+            .checked_add(shares)
+            .ok_or(GusdError::MathOverflow)?;
+        pool.total_assets = pool.total_assets
+            .checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Savings deposit: {} GUSD for {} shares", amount, shares);
+
+        emit!(SavingsDeposited {
+            owner: position.owner,
+            amount,
+            shares,
+            total_assets: pool.total_assets,
+            total_shares: pool.total_shares,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem sGUSD shares from the savings pool for GUSD at the current share price
+    pub fn withdraw_savings(ctx: Context<WithdrawSavings>, shares: u64) -> Result<()> {
+        require!(shares > 0, GusdError::InvalidAmount);
+        require!(
+            shares <= ctx.accounts.savings_position.shares,
+            GusdError::InsufficientSavingsShares
+        );
+        drip_savings(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.savings_pool,
+            &mut ctx.accounts.supply_stats,
+            &ctx.accounts.savings_gusd_account,
+            &ctx.accounts.gusd_mint,
+            &ctx.accounts.token_program,
+        )?;
+
+        let pool = &ctx.accounts.savings_pool;
+        // Rounds down, leaving any dust with the remaining savers
+        let amount = ((shares as u128)
+            .checked_mul(pool.total_assets as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(pool.total_shares as u128)
+            .ok_or(GusdError::MathOverflow)?) as u64;
+
+        let seeds = &[b"savings".as_ref(), &[pool.bump]];
+        let signer_seeds = &[&seeds[..]];
+        if amount > 0 {
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.savings_gusd_account.to_account_info(),
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        to: ctx.accounts.owner_gusd_account.to_account_info(),
+                        authority: ctx.accounts.savings_pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                GUSD_DECIMALS,
+            )?;
+        }
+
+        ctx.accounts.savings_position.shares -= shares;
+        let pool = &mut ctx.accounts.savings_pool;
+        pool.total_shares -= shares;
+        pool.total_assets -= amount;
+
+        msg!("Savings withdrawal: {} shares for {} GUSD", shares, amount);
+
+        emit!(SavingsWithdrawn {
+            owner: ctx.accounts.owner.key(),
+            amount,
+            shares,
+            total_assets: pool.total_assets,
+            total_shares: pool.total_shares,
+        });
+
+        Ok(())
+    }
+
+    /// Start the one-time bootstrap auction of the initial GUSD tranche (admin only)
+    /// `collateral` lamports move from the treasury into a protocol-owned vault (owned by
+    /// the bootstrap PDA, exempt from stability fees), and `tranche` GUSD is minted against
+    /// it into the auction escrow, subject to the minimum collateral ratio and debt ceiling.
+    /// The price (lamports per whole GUSD) falls linearly from `start_price` to `end_price`
+    /// over `duration_secs`.
+    pub fn start_bootstrap_auction(
+        ctx: Context<StartBootstrapAuction>,
+        tranche: u64,
+        collateral: u64,
+        start_price: u64,
+        end_price: u64,
+        duration_secs: i64,
+    ) -> Result<()> {
+        require_lifecycle(
+            &ctx.accounts.protocol_state,
+            GatedAction::Operate,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(tranche > 0 && collateral > 0, GusdError::InvalidAmount);
+        require!(
+            start_price > end_price && end_price > 0 && duration_secs > 0,
+            GusdError::InvalidParameter
+        );
+        let collectable = ctx.accounts.treasury.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(collateral <= collectable, GusdError::InsufficientTreasuryBalance);
+
+        let price = get_price_with_fallback(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Lower,
+            PriceOperation::Mint,
+        )?;
+        let collateral_value_usd =
+            calculate_usd_value(collateral, price, ctx.accounts.price_feed.decimals)?;
+        let required_collateral = (tranche as u128)
+            .checked_mul(MIN_COLLATERAL_RATIO_BPS as u128)
+            .ok_or(GusdError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        require!(
+            collateral_value_usd as u128 >= required_collateral,
+            GusdError::InsufficientCollateral
+        );
+
+        let protocol = &ctx.accounts.protocol_state;
+        let new_total_debt = protocol.total_debt
+            .checked_add(tranche)
+            .ok_or(GusdError::MathOverflow)?;
+        require!(
+            protocol.debt_ceiling == 0 || new_total_debt <= protocol.debt_ceiling,
+            GusdError::DebtCeilingExceeded
+        );
+        require_within_max_supply(protocol, ctx.accounts.gusd_mint.supply, tranche)?;
+
+        // Move protocol-owned collateral from the treasury into the bootstrap vault
+        let treasury_seeds = &[b"treasury".as_ref(), &[ctx.bumps.treasury]];
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.treasury.to_account_info(),
+                    to: ctx.accounts.bootstrap_vault_collateral.to_account_info(),
+                },
+                &[&treasury_seeds[..]],
+            ),
+            collateral,
+        )?;
+
+        // Fund the protocol-owned liquidity PDA's rent so small first purchases can land
+        let rent = Rent::get()?.minimum_balance(0);
+        let top_up = rent.saturating_sub(ctx.accounts.protocol_liquidity.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: ctx.accounts.protocol_liquidity.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+
+        let bump = ctx.accounts.protocol_state.bump;
+        let seeds = &[SEED_NAMESPACE, b"protocol".as_ref(), &[bump]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.bootstrap_gusd_account.to_account_info(),
+                    authority: ctx.accounts.protocol_state.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            tranche,
+        )?;
+        record_supply(&mut ctx.accounts.supply_stats.minted_vault_debt, tranche)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let bootstrap_key = ctx.accounts.bootstrap_auction.key();
+        let vault = &mut ctx.accounts.bootstrap_vault;
+        vault.owner = bootstrap_key;
+        vault.collateral_mint = NATIVE_COLLATERAL_MINT;
+        vault.collateral_amount = collateral;
+        vault.debt_amount = tranche;
+        vault.bump = ctx.bumps.bootstrap_vault;
+        vault.collateral_bump = ctx.bumps.bootstrap_vault_collateral;
+        vault.last_liquidation_slot = 0;
+        vault.last_liquidation_left_unhealthy = false;
+        vault.last_activity_ts = now;
+        vault.abandoned_flagged_at = 0;
+        vault.repayment_streak = 0;
+        vault.streak_started_at = 0;
+        vault.last_repayment_ts = 0;
+        vault.insured_until = 0;
+        vault.collateral_rent_reserve = ctx.accounts.bootstrap_vault_collateral
+            .lamports()
+            .saturating_sub(collateral);
+        vault.margin_account = Pubkey::default();
+        vault.debt_index_snapshot = 0;
+        vault.flagged_slot = 0;
+        vault.retained_liquidation_fees = 0;
+        vault.last_accrued_at = 0;
+        vault.locked_rate_bps = 0;
+        vault.rate_locked_until = 0;
+        vault.rate_lock_principal = 0;
+        vault.interest_exempt = true; // The protocol would owe itself
+        vault.premium_index_snapshot = 0;
+        vault.unpaid_interest = 0;
+        vault.yield_routing = YieldRouting::Compound;
+        vault.stake_discount_bps = 0;
+        vault.delegate = Pubkey::default();
+        vault.delegate_scope = DelegateScope::None;
+        vault.delegated_by = Pubkey::default();
+        vault.position_mint = BOOTSTRAP_VAULT_POSITION;
+        vault.collateral_balances = Vec::new();
+        vault.discount_stake = Pubkey::default();
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_debt = new_total_debt;
+        protocol.total_collateral = protocol.total_collateral
+            .checked_add(collateral)
+            .ok_or(GusdError::MathOverflow)?;
+
+        let auction = &mut ctx.accounts.bootstrap_auction;
+        auction.tranche = tranche;
+        auction.sold = 0;
+        auction.start_price = start_price;
+        auction.end_price = end_price;
+        auction.starts_at = now;
+        auction.ends_at = now.checked_add(duration_secs).ok_or(GusdError::MathOverflow)?;
+        auction.proceeds = 0;
+        auction.finalized = false;
+        auction.bump = ctx.bumps.bootstrap_auction;
+
+        msg!(
+            "Bootstrap auction started: {} GUSD against {} lamports, {} -> {} lamports/GUSD until {}",
+            tranche,
+            collateral,
+            start_price,
+            end_price,
+            auction.ends_at
+        );
+
+        emit!(BootstrapAuctionStarted {
+            tranche,
+            collateral,
+            start_price,
+            end_price,
+            starts_at: now,
+            ends_at: auction.ends_at,
+        });
+
+        Ok(())
+    }
+
+    /// Buy `amount` GUSD from the bootstrap auction at the current curve price
+    /// The GOR paid is locked as protocol-owned liquidity; `max_cost` bounds it.
+    pub fn buy_bootstrap_gusd(
+        ctx: Context<BuyBootstrapGusd>,
+        amount: u64,
+        max_cost: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.bootstrap_auction;
+        require!(
+            !auction.finalized && now < auction.ends_at,
+            GusdError::AuctionBiddingClosed
+        );
+        let remaining = auction.tranche - auction.sold;
+        require!(amount <= remaining, GusdError::InvalidAmount);
+
+        let price = bootstrap_price(auction, now)?;
+        let cost = GusdUnits(amount).cost_in_lamports_ceil(price)?.0;
+        require!(cost <= max_cost, GusdError::SlippageExceeded);
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.protocol_liquidity.to_account_info(),
+                },
+            ),
+            cost,
+        )?;
+
+        let seeds = &[b"bootstrap".as_ref(), &[auction.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.bootstrap_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.buyer_gusd_account.to_account_info(),
+                    authority: ctx.accounts.bootstrap_auction.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+            GUSD_DECIMALS,
+        )?;
+
+        let auction = &mut ctx.accounts.bootstrap_auction;
+        auction.sold += amount;
+        auction.proceeds = auction.proceeds
+            .checked_add(cost)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Bootstrap purchase: {} GUSD for {} lamports ({} lamports/GUSD)", amount, cost, price);
+
+        emit!(BootstrapGusdBought {
+            buyer: ctx.accounts.buyer.key(),
+            amount,
+            cost,
+            price,
+            sold: auction.sold,
+        });
+
+        Ok(())
+    }
+
+    /// Close the bootstrap auction once it ends or sells out (permissionless)
+    /// Unsold GUSD is burned and repaid from the bootstrap vault's debt.
+    pub fn finalize_bootstrap_auction(ctx: Context<FinalizeBootstrapAuction>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.bootstrap_auction;
+        require!(!auction.finalized, GusdError::AuctionBiddingClosed);
+        let unsold = auction.tranche - auction.sold;
+        require!(bootstrap_finalizable(auction, now), GusdError::AuctionBiddingOpen);
+
+        if unsold > 0 {
+            let seeds = &[b"bootstrap".as_ref(), &[auction.bump]];
+            token_interface::burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        from: ctx.accounts.bootstrap_gusd_account.to_account_info(),
+                        authority: ctx.accounts.bootstrap_auction.to_account_info(),
+                    },
+                    &[&seeds[..]],
+                ),
+                unsold,
+            )?;
+            record_supply(&mut ctx.accounts.supply_stats.burned_repayments, unsold)?;
+
+            let vault = &mut ctx.accounts.bootstrap_vault;
+            vault.debt_amount = vault.debt_amount
+                .checked_sub(unsold)
+                .ok_or(GusdError::MathOverflow)?;
+            let protocol = &mut ctx.accounts.protocol_state;
+            protocol.total_debt = protocol.total_debt
+                .checked_sub(unsold)
+                .ok_or(GusdError::MathOverflow)?;
+        }
+
+        let auction = &mut ctx.accounts.bootstrap_auction;
+        auction.finalized = true;
+
+        msg!(
+            "Bootstrap auction finalized: {} GUSD sold for {} lamports, {} burned",
+            auction.sold,
+            auction.proceeds,
+            unsold
+        );
+
+        emit!(BootstrapAuctionFinalized {
+            sold: auction.sold,
+            proceeds: auction.proceeds,
+            unsold_burned: unsold,
+        });
+
+        Ok(())
+    }
+
+    /// Swap GOR from the treasury into USDC through the whitelisted route (treasurer only)
+    /// `remaining_accounts` and `route_data` are forwarded to the swap program with the
+    /// treasury PDA as signer. Swaps are capped per epoch at a share of the treasury balance,
+    /// and `min_amount_out` may not be looser than the max slippage from the protocol price.
+    pub fn diversify_treasury<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DiversifyTreasury<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        route_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(amount_in > 0, GusdError::InvalidAmount);
+        require!(
+            ctx.accounts.deployment_config.swap_program != Pubkey::default(),
+            GusdError::InvalidSwapRoute
+        );
+        // The treasury only signs for the route governance approved
+        let route_hash = swap_route_hash(
+            ctx.remaining_accounts.iter().map(|account| (account.key(), account.is_writable)),
+        );
+        require!(
+            route_hash == ctx.accounts.deployment_config.swap_route_hash,
+            GusdError::InvalidSwapRoute
+        );
+
+        // Slippage floor from the protocol price (USDC shares GUSD's 6 decimals)
+        let price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let expected_out = calculate_usd_value(
+            amount_in,
+            price,
+            ctx.accounts.price_feed.decimals,
+        )?;
+        let min_allowed_out = (expected_out as u128)
+            .checked_mul((BPS_DENOMINATOR - MAX_TREASURY_SWAP_SLIPPAGE_BPS) as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)? as u64;
+        require!(
+            min_amount_out > 0 && min_amount_out >= min_allowed_out,
+            GusdError::SlippageExceeded
+        );
+
+        // Per-epoch cap, fixed from the treasury balance at the first swap of the epoch
+        let lamports_before = ctx.accounts.treasury.lamports();
+        let epoch = Clock::get()?.epoch;
+        let protocol = &mut ctx.accounts.protocol_state;
+        if protocol.treasury_swap_epoch != epoch {
+            protocol.treasury_swap_epoch = epoch;
+            protocol.treasury_swapped_in_epoch = 0;
+            protocol.treasury_swap_epoch_limit = (lamports_before as u128)
+                .checked_mul(TREASURY_SWAP_EPOCH_CAP_BPS as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(GusdError::MathOverflow)? as u64;
+        }
+        let swapped = protocol.treasury_swapped_in_epoch
+            .checked_add(amount_in)
+            .ok_or(GusdError::MathOverflow)?;
+        require!(
+            swapped <= protocol.treasury_swap_epoch_limit,
+            GusdError::EpochOutflowCapExceeded
+        );
+        protocol.treasury_swapped_in_epoch = swapped;
+
+        let usdc_before = ctx.accounts.treasury_usdc_account.amount;
+
+        invoke_as_treasury(
+            ctx.accounts.swap_program.key(),
+            ctx.remaining_accounts,
+            route_data,
+            ctx.accounts.treasury.key(),
+            ctx.bumps.treasury,
+        )?;
+
+        ctx.accounts.treasury_usdc_account.reload()?;
+        let amount_out = ctx.accounts.treasury_usdc_account.amount
+            .checked_sub(usdc_before)
+            .ok_or(GusdError::SlippageExceeded)?;
+        let lamports_spent = lamports_before.saturating_sub(ctx.accounts.treasury.lamports());
+        require!(lamports_spent <= amount_in, GusdError::SlippageExceeded);
+        require!(amount_out >= min_amount_out, GusdError::SlippageExceeded);
+
+        msg!("Treasury swapped {} lamports for {} USDC", lamports_spent, amount_out);
+
+        emit!(TreasuryDiversified {
+            amount_in: lamports_spent,
+            amount_out,
+            epoch,
+            swapped_in_epoch: swapped,
+        });
+
+        Ok(())
+    }
+
+    /// Whitelist an AMM pool for protocol-owned liquidity (admin only)
+    /// Creates the pool's `PolPosition`; deposits and withdrawals CPI into `amm_program`
+    /// and settle in the pool's LP token.
+    pub fn register_pol_pool(ctx: Context<RegisterPolPool>, pool: Pubkey) -> Result<()> {
+        let position = &mut ctx.accounts.pol_position;
+        position.amm_program = ctx.accounts.amm_program.key();
+        position.pool = pool;
+        position.lp_mint = ctx.accounts.lp_mint.key();
+        position.lp_amount = 0;
+        position.gusd_deployed = 0;
+        position.gor_deployed = 0;
+        position.gusd_withdrawn = 0;
+        position.gor_withdrawn = 0;
+        position.registered_at = Clock::get()?.unix_timestamp;
+        position.bump = ctx.bumps.pol_position;
+
+        msg!("POL pool registered: {} on {}", pool, position.amm_program);
+
+        emit!(PolPoolRegistered {
+            pool,
+            amm_program: position.amm_program,
+            lp_mint: position.lp_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Add treasury GUSD and GOR to a registered pool (treasurer only)
+    /// `remaining_accounts` and `route_data` are forwarded to the pool's AMM program with
+    /// the treasury PDA as signer. The two legs must be within `MAX_POL_IMBALANCE_BPS` of
+    /// each other at the protocol price, so liquidity is never added at a skewed pool
+    /// ratio. The LP tokens must land in the treasury's LP account.
+    pub fn deploy_pol<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DeployPol<'info>>,
+        gusd_amount: u64,
+        lamports: u64,
+        min_lp_out: u64,
+        route_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(gusd_amount > 0 && lamports > 0, GusdError::InvalidAmount);
+        require!(ctx.accounts.protocol_state.price_disputed_at == 0, GusdError::PriceDisputed);
+
+        let price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let gor_value = calculate_usd_value(lamports, price, ctx.accounts.price_feed.decimals)?;
+        let imbalance_bps = (gor_value.abs_diff(gusd_amount) as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(gor_value.max(gusd_amount) as u128)
+            .ok_or(GusdError::MathOverflow)?;
+        require!(
+            imbalance_bps <= MAX_POL_IMBALANCE_BPS as u128,
+            GusdError::PolImbalanced
+        );
+
+        let lamports_before = ctx.accounts.treasury.lamports();
+        let gusd_before = ctx.accounts.treasury_gusd_account.amount;
+        let lp_before = ctx.accounts.treasury_lp_account.amount;
+
+        invoke_as_treasury(
+            ctx.accounts.amm_program.key(),
+            ctx.remaining_accounts,
+            route_data,
+            ctx.accounts.treasury.key(),
+            ctx.bumps.treasury,
+        )?;
+
+        ctx.accounts.treasury_gusd_account.reload()?;
+        ctx.accounts.treasury_lp_account.reload()?;
+        let gusd_spent = gusd_before.saturating_sub(ctx.accounts.treasury_gusd_account.amount);
+        let lamports_spent = lamports_before.saturating_sub(ctx.accounts.treasury.lamports());
+        let lp_received = ctx.accounts.treasury_lp_account.amount
+            .checked_sub(lp_before)
+            .ok_or(GusdError::SlippageExceeded)?;
+        require!(
+            gusd_spent <= gusd_amount && lamports_spent <= lamports,
+            GusdError::SlippageExceeded
+        );
+        require!(lp_received >= min_lp_out, GusdError::SlippageExceeded);
+
+        let position = &mut ctx.accounts.pol_position;
+        position.lp_amount = position.lp_amount
+            .checked_add(lp_received)
+            .ok_or(GusdError::MathOverflow)?;
+        position.gusd_deployed = position.gusd_deployed
+            .checked_add(gusd_spent)
+            .ok_or(GusdError::MathOverflow)?;
+        position.gor_deployed = position.gor_deployed
+            .checked_add(lamports_spent)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!(
+            "POL deployed to {}: {} GUSD + {} lamports for {} LP",
+            position.pool,
+            gusd_spent,
+            lamports_spent,
+            lp_received
+        );
+
+        emit!(PolDeployed {
+            pool: position.pool,
+            gusd_amount: gusd_spent,
+            lamports: lamports_spent,
+            lp_received,
+            lp_amount: position.lp_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Redeem LP tokens of a registered pool back into the treasury (treasurer only)
+    /// Forwards `remaining_accounts` and `route_data` like `deploy_pol`. At most
+    /// `lp_amount` LP tokens may be burned, and the treasury must receive at least
+    /// `min_gusd_out` GUSD and `min_lamports_out` GOR.
+    pub fn withdraw_pol<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawPol<'info>>,
+        lp_amount: u64,
+        min_gusd_out: u64,
+        min_lamports_out: u64,
+        route_data: Vec<u8>,
+    ) -> Result<()> {
+        require!(
+            lp_amount > 0 && lp_amount <= ctx.accounts.pol_position.lp_amount,
+            GusdError::InvalidAmount
+        );
+
+        let lamports_before = ctx.accounts.treasury.lamports();
+        let gusd_before = ctx.accounts.treasury_gusd_account.amount;
+        let lp_before = ctx.accounts.treasury_lp_account.amount;
+
+        invoke_as_treasury(
+            ctx.accounts.amm_program.key(),
+            ctx.remaining_accounts,
+            route_data,
+            ctx.accounts.treasury.key(),
+            ctx.bumps.treasury,
+        )?;
+
+        ctx.accounts.treasury_gusd_account.reload()?;
+        ctx.accounts.treasury_lp_account.reload()?;
+        let lp_burned = lp_before.saturating_sub(ctx.accounts.treasury_lp_account.amount);
+        let gusd_received = ctx.accounts.treasury_gusd_account.amount.saturating_sub(gusd_before);
+        let lamports_received = ctx.accounts.treasury.lamports().saturating_sub(lamports_before);
+        require!(lp_burned <= lp_amount, GusdError::SlippageExceeded);
+        require!(
+            gusd_received >= min_gusd_out && lamports_received >= min_lamports_out,
+            GusdError::SlippageExceeded
+        );
+
+        let position = &mut ctx.accounts.pol_position;
+        position.lp_amount -= lp_burned;
+        position.gusd_withdrawn = position.gusd_withdrawn
+            .checked_add(gusd_received)
+            .ok_or(GusdError::MathOverflow)?;
+        position.gor_withdrawn = position.gor_withdrawn
+            .checked_add(lamports_received)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!(
+            "POL withdrawn from {}: {} LP for {} GUSD + {} lamports",
+            position.pool,
+            lp_burned,
+            gusd_received,
+            lamports_received
+        );
+
+        emit!(PolWithdrawn {
+            pool: position.pool,
+            lp_burned,
+            gusd_amount: gusd_received,
+            lamports: lamports_received,
+            lp_amount: position.lp_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Register a keeper for crank fee rebates (treasurer only)
+    pub fn register_keeper(ctx: Context<RegisterKeeper>, keeper: Pubkey) -> Result<()> {
+        let account = &mut ctx.accounts.keeper_account;
+        account.keeper = keeper;
+        account.registered_at = Clock::get()?.unix_timestamp;
+        account.cranks = 0;
+        account.unclaimed_fee_lamports = 0;
+        account.total_fee_lamports = 0;
+        account.total_rebated = 0;
+        account.last_credited_slot = 0;
+        account.bump = ctx.bumps.keeper_account;
+
+        msg!("Keeper registered: {}", keeper);
+
+        emit!(KeeperRegistered {
+            keeper,
+            treasurer: ctx.accounts.treasurer.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Deregister a keeper, forfeiting its unclaimed fees (treasurer only)
+    pub fn remove_keeper(ctx: Context<RemoveKeeper>) -> Result<()> {
+        let account = &ctx.accounts.keeper_account;
+
+        msg!("Keeper removed: {}", account.keeper);
+
+        emit!(KeeperRemoved {
+            keeper: account.keeper,
+            forfeited_fee_lamports: account.unclaimed_fee_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Claim a GUSD rebate from the treasury for a keeper's credited crank fees (keeper)
+    /// Fees are valued at the protocol price. Rebates across all keepers are capped at
+    /// `keeper_rebate_epoch_cap` per epoch; fees beyond the remaining budget stay
+    /// credited for a later epoch.
+    pub fn claim_keeper_rebate(ctx: Context<ClaimKeeperRebate>) -> Result<()> {
+        let unclaimed = ctx.accounts.keeper_account.unclaimed_fee_lamports;
+        require!(unclaimed > 0, GusdError::InvalidAmount);
+
+        let price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let owed = calculate_usd_value(unclaimed, price, ctx.accounts.price_feed.decimals)?;
+
+        let epoch = Clock::get()?.epoch;
+        let protocol = &mut ctx.accounts.protocol_state;
+        if protocol.keeper_rebate_epoch != epoch {
+            protocol.keeper_rebate_epoch = epoch;
+            protocol.keeper_rebated_in_epoch = 0;
+        }
+        let budget = protocol.keeper_rebate_epoch_cap
+            .saturating_sub(protocol.keeper_rebated_in_epoch);
+        let rebate = owed.min(budget);
+        require!(rebate > 0, GusdError::KeeperRebateUnavailable);
+        require!(
+            rebate <= ctx.accounts.treasury_gusd_account.amount,
+            GusdError::InsufficientTreasuryBalance
+        );
+        protocol.keeper_rebated_in_epoch += rebate;
+
+        let seeds = &[b"treasury".as_ref(), &[ctx.bumps.treasury]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.treasury_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.keeper_gusd_account.to_account_info(),
+                    authority: ctx.accounts.treasury.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            rebate,
+            GUSD_DECIMALS,
+        )?;
+
+        // A partial rebate settles the matching share of the credited fees
+        let settled = if rebate == owed {
+            unclaimed
+        } else {
+            ((unclaimed as u128)
+                .checked_mul(rebate as u128)
+                .ok_or(GusdError::MathOverflow)?
+                / owed as u128) as u64
+        };
+        let account = &mut ctx.accounts.keeper_account;
+        account.unclaimed_fee_lamports -= settled;
+        account.total_rebated = account.total_rebated
+            .checked_add(rebate)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Keeper {} rebated {} GUSD for {} lamports of fees", account.keeper, rebate, settled);
+
+        emit!(KeeperRebateClaimed {
+            keeper: account.keeper,
+            rebate,
+            settled_fee_lamports: settled,
+            unclaimed_fee_lamports: account.unclaimed_fee_lamports,
+            rebated_in_epoch: ctx.accounts.protocol_state.keeper_rebated_in_epoch,
+        });
+
+        Ok(())
+    }
+
+    /// Announce a pending governance action that affects vaults (admin only)
+    /// Gives owners a query-able countdown (`get_vault_notice`) before it takes effect.
+    pub fn post_governance_notice(
+        ctx: Context<TransferAdmin>,
+        action: NoticeAction,
+        effective_at: i64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            effective_at >= now.saturating_add(GOVERNANCE_NOTICE_MIN_SECS),
+            GusdError::NoticePeriodTooShort
+        );
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.pending_notice = Some(GovernanceNotice {
+            action,
+            posted_at: now,
+            effective_at,
+        });
+
+        msg!("Governance notice posted: {:?} effective at {}", action, effective_at);
+
+        emit!(GovernanceNoticePosted {
+            action,
+            posted_at: now,
+            effective_at,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw the pending governance notice (admin only)
+    pub fn clear_governance_notice(ctx: Context<TransferAdmin>) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let notice = protocol.pending_notice.take().ok_or(GusdError::NoPendingNotice)?;
+
+        msg!("Governance notice cleared: {:?}", notice.action);
+
+        emit!(GovernanceNoticeCleared {
+            action: notice.action,
+        });
+
+        Ok(())
+    }
+
+    /// Publish frontend display metadata (admin only)
+    /// Decimals are taken from the program so they can never disagree with on-chain math.
+    pub fn set_display_config(
+        ctx: Context<SetDisplayConfig>,
+        collateral_symbol: String,
+        gusd_symbol: String,
+        explorer_account_url: String,
+        explorer_tx_url: String,
+        branding_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(
+            collateral_symbol.len() <= MAX_SYMBOL_LEN && gusd_symbol.len() <= MAX_SYMBOL_LEN,
+            GusdError::DisplayFieldTooLong
+        );
+        require!(
+            explorer_account_url.len() <= MAX_URL_LEN && explorer_tx_url.len() <= MAX_URL_LEN,
+            GusdError::DisplayFieldTooLong
+        );
+
+        let display = &mut ctx.accounts.display_config;
+        display.collateral_symbol = collateral_symbol;
+        display.gusd_symbol = gusd_symbol;
+        display.collateral_decimals = ctx.accounts.protocol_state.native_decimals;
+        display.gusd_decimals = GUSD_DECIMALS;
+        display.explorer_account_url = explorer_account_url;
+        display.explorer_tx_url = explorer_tx_url;
+        display.branding_hash = branding_hash;
+        display.updated_at = Clock::get()?.unix_timestamp;
+        display.bump = ctx.bumps.display_config;
+
+        msg!("Display config updated");
+
+        emit!(DisplayConfigUpdated {
+            admin: ctx.accounts.admin.key(),
+            branding_hash,
+            timestamp: display.updated_at,
+        });
+
+        Ok(())
+    }
+
+    /// Append an audit report hash and the audited program binary hash (admin only)
+    /// The registry is append-only so users can verify which audited build is live.
+    pub fn record_audit_hash(
+        ctx: Context<RecordAuditHash>,
+        report_hash: [u8; 32],
+        program_hash: [u8; 32],
+    ) -> Result<()> {
+        let registry = &mut ctx.accounts.audit_registry;
+        require!(
+            registry.records.len() < MAX_AUDIT_RECORDS,
+            GusdError::AuditRegistryFull
+        );
+
+        let recorded_at = Clock::get()?.unix_timestamp;
+        registry.records.push(AuditRecord {
+            report_hash,
+            program_hash,
+            recorded_at,
+        });
+        registry.bump = ctx.bumps.audit_registry;
+
+        msg!("Audit record #{} added", registry.records.len());
+
+        emit!(AuditHashRecorded {
+            index: (registry.records.len() - 1) as u8,
+            report_hash,
+            program_hash,
+            recorded_at,
+        });
+
+        Ok(())
+    }
+
+    /// Get all recorded audit hashes, oldest first (view function)
+    pub fn get_audit_records(ctx: Context<GetAuditRecords>) -> Result<Vec<AuditRecord>> {
+        Ok(ctx.accounts.audit_registry.records.clone())
+    }
+
+    /// Assign a protocol role to a new authority (admin only)
+    pub fn set_role(ctx: Context<TransferAdmin>, role: ProtocolRole, new_authority: Pubkey) -> Result<()> {
+        require!(new_authority != Pubkey::default(), GusdError::InvalidAmount);
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        let slot = match role {
+            ProtocolRole::Compliance => &mut protocol.compliance_authority,
+            ProtocolRole::RiskManager => &mut protocol.risk_manager,
+            ProtocolRole::Treasurer => &mut protocol.treasurer,
+            ProtocolRole::Guardian => &mut protocol.guardian,
+        };
+        let old_authority = *slot;
+        *slot = new_authority;
+
+        msg!("Role {:?} transferred from {} to {}", role, old_authority, new_authority);
+
+        emit!(RoleUpdated {
+            role,
+            old_authority,
+            new_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Freeze a GUSD token account and record it in the blocklist (compliance only)
+    pub fn add_to_blocklist(ctx: Context<AddToBlocklist>, reason_code: u16) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let seeds = &[
+            SEED_NAMESPACE,
+            b"protocol".as_ref(),
+            &[protocol.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token_interface::freeze_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: ctx.accounts.target_gusd_account.to_account_info(),
+                mint: ctx.accounts.gusd_mint.to_account_info(),
+                authority: ctx.accounts.protocol_state.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let entry = &mut ctx.accounts.blocklist_entry;
+        entry.token_account = ctx.accounts.target_gusd_account.key();
+        entry.owner = ctx.accounts.target_gusd_account.owner;
+        entry.reason_code = reason_code;
+        entry.added_by = ctx.accounts.compliance.key();
+        entry.added_at = now;
+        entry.bump = ctx.bumps.blocklist_entry;
+
+        msg!("Blocklisted {} (reason {})", entry.token_account, reason_code);
+
+        emit!(AccountBlocklisted {
+            token_account: entry.token_account,
+            owner: entry.owner,
+            reason_code,
+            authority: entry.added_by,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Thaw a GUSD token account and remove its blocklist entry (compliance only)
+    pub fn remove_from_blocklist(ctx: Context<RemoveFromBlocklist>) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let seeds = &[
+            SEED_NAMESPACE,
+            b"protocol".as_ref(),
+            &[protocol.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token_interface::thaw_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: ctx.accounts.target_gusd_account.to_account_info(),
+                mint: ctx.accounts.gusd_mint.to_account_info(),
+                authority: ctx.accounts.protocol_state.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let entry = &ctx.accounts.blocklist_entry;
+        msg!("Removed {} from blocklist", entry.token_account);
+
+        emit!(AccountUnblocklisted {
+            token_account: entry.token_account,
+            owner: entry.owner,
+            reason_code: entry.reason_code,
+            authority: ctx.accounts.compliance.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create a new vault for a user
+    /// [CRITICAL-4] Now initializes vault_collateral PDA
+    pub fn create_vault(ctx: Context<CreateVault>) -> Result<()> {
+        let attestation_mint = ctx.accounts.deployment_config.kyc_attestation_mint;
+        if attestation_mint != Pubkey::default() {
+            let attestation = ctx.accounts.kyc_attestation
+                .as_ref()
+                .ok_or(GusdError::KycAttestationRequired)?;
+            require_keys_eq!(attestation.mint, attestation_mint, GusdError::InvalidKycAttestation);
+            require_keys_eq!(
+                attestation.owner,
+                ctx.accounts.owner.key(),
+                GusdError::InvalidKycAttestation
+            );
+            require!(attestation.amount > 0, GusdError::InvalidKycAttestation);
+        }
+
+        let collateral_mint = ctx.accounts.collateral_config.collateral_mint;
+
+        let vault = &mut ctx.accounts.vault;
+        
+        vault.owner = ctx.accounts.owner.key();
+        vault.collateral_mint = collateral_mint;
+        vault.collateral_amount = 0;
+        vault.debt_amount = 0;
+        vault.bump = ctx.bumps.vault;
+        vault.collateral_bump = ctx.bumps.vault_collateral; // [CRITICAL-4] Store collateral bump
+        vault.last_liquidation_slot = 0;
+        vault.last_liquidation_left_unhealthy = false;
+        vault.last_activity_ts = Clock::get()?.unix_timestamp;
+        vault.abandoned_flagged_at = 0;
+        vault.repayment_streak = 0;
+        vault.streak_started_at = 0;
+        vault.last_repayment_ts = 0;
+        vault.insured_until = 0;
+        // Lamports funding the collateral PDA's rent exemption, kept apart from collateral
+        vault.collateral_rent_reserve = ctx.accounts.vault_collateral.lamports();
+        vault.margin_account = Pubkey::default();
+        vault.debt_index_snapshot = 0; // Taken at the first accrual
+        vault.flagged_slot = 0;
+        vault.retained_liquidation_fees = 0;
+        vault.last_accrued_at = 0;
+        vault.locked_rate_bps = 0;
+        vault.rate_locked_until = 0;
+        vault.rate_lock_principal = 0;
+        vault.interest_exempt = false;
+        vault.premium_index_snapshot = 0; // Taken at the first accrual
+        vault.unpaid_interest = 0;
+        vault.yield_routing = YieldRouting::Compound;
+        vault.stake_discount_bps = 0; // Set by accruals once a mint records a stake
+        vault.delegate = Pubkey::default();
+        vault.delegate_scope = DelegateScope::None;
+        vault.delegated_by = Pubkey::default();
+        vault.position_mint = ctx.accounts.position_mint.key();
+        vault.collateral_balances = Vec::new();
+        vault.discount_stake = Pubkey::default();
+
+        // Mint the position NFT, then drop the mint authority so the supply stays at one
+        let seeds = &[
+            SEED_NAMESPACE,
+            b"protocol".as_ref(),
+            &[ctx.accounts.protocol_state.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        token_interface::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.position_mint.to_account_info(),
+                    to: ctx.accounts.owner_position_account.to_account_info(),
+                    authority: ctx.accounts.protocol_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            1,
+        )?;
+        token_interface::set_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.protocol_state.to_account_info(),
+                    account_or_mint: ctx.accounts.position_mint.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        msg!("Vault created for user: {}", ctx.accounts.owner.key());
+
+        // [MEDIUM-3] Emit event
+        emit!(VaultCreated {
+            owner: ctx.accounts.owner.key(),
+            collateral_mint,
+            position_mint: ctx.accounts.position_mint.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        Ok(())
+    }
+
+    /// Create the token account holding a token vault's collateral (owner only)
+    /// Needed once before the first deposit into a vault bound to an SPL collateral type.
+    pub fn init_vault_token_account(ctx: Context<InitVaultTokenAccount>) -> Result<()> {
+        msg!(
+            "Vault token account created for {}: {}",
+            ctx.accounts.vault.owner,
+            ctx.accounts.vault_token_account.key()
+        );
+
+        emit!(VaultTokenAccountCreated {
+            owner: ctx.accounts.vault.owner,
+            collateral_mint: ctx.accounts.collateral_mint.key(),
+            vault_token_account: ctx.accounts.vault_token_account.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Deposit GOR collateral into a vault
+    /// When invoked via CPI, the transaction's top-level program is recorded in the event.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        let top_level_program = get_top_level_program(&ctx.accounts.instructions)?;
+
+        let authority = vault_signer(&ctx.accounts.owner, &ctx.accounts.delegate);
+        let accounts = &mut *ctx.accounts;
+        let received = deposit_to_vault(
+            &mut accounts.vault,
+            &accounts.vault_collateral,
+            &mut accounts.protocol_state,
+            &mut accounts.collateral_config,
+            accounts.collateral_mint.as_ref(),
+            accounts.vault_token_account.as_mut(),
+            accounts.owner_token_account.as_ref(),
+            accounts.collateral_token_program.as_ref(),
+            authority,
+            &accounts.system_program,
+            amount,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!(
+            "Deposited {} collateral ({} transfer fee). Total collateral: {}",
+            received,
+            amount - received,
+            vault.collateral_amount
+        );
+        if let Some(program_id) = top_level_program {
+            msg!("Deposit via CPI from program: {}", program_id);
+        }
+
+        // [MEDIUM-3] Emit event
+        emit!(CollateralDeposited {
+            owner: vault.owner,
+            amount: received,
+            transfer_fee: amount - received,
+            total_collateral: vault.collateral_amount,
+            top_level_program,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+        
+        Ok(())
+    }
+
+    /// Add collateral to any vault from the signer's funds, without the owner's signature
+    /// Extra collateral only improves the vault's health, so rescue services and treasuries
+    /// can top up positions they don't own. It doesn't count as owner activity.
+    pub fn deposit_collateral_for(ctx: Context<DepositCollateralFor>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+        // A third-party deposit isn't owner activity, so it would only be swept with the vault
+        require!(
+            ctx.accounts.vault.abandoned_flagged_at == 0,
+            GusdError::VaultFlaggedAbandoned
+        );
+
+        let authority = ctx.accounts.depositor.to_account_info();
+        let accounts = &mut *ctx.accounts;
+        let received = deposit_to_vault(
+            &mut accounts.vault,
+            &accounts.vault_collateral,
+            &mut accounts.protocol_state,
+            &mut accounts.collateral_config,
+            accounts.collateral_mint.as_ref(),
+            accounts.vault_token_account.as_mut(),
+            accounts.depositor_token_account.as_ref(),
+            accounts.collateral_token_program.as_ref(),
+            authority,
+            &accounts.system_program,
+            amount,
+        )?;
+
+        let vault = &ctx.accounts.vault;
+        msg!(
+            "{} deposited {} collateral into vault {} ({} transfer fee). Total collateral: {}",
+            ctx.accounts.depositor.key(),
+            received,
+            vault.owner,
+            amount - received,
+            vault.collateral_amount
+        );
+
+        emit!(CollateralDepositedFor {
+            owner: vault.owner,
+            depositor: ctx.accounts.depositor.key(),
+            amount: received,
+            transfer_fee: amount - received,
+            total_collateral: vault.collateral_amount,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Mint GUSD against deposited collateral
+    /// The origination fee (the collateral's mint fee plus the decayed base rate, capped at
+    /// `MAX_MINT_FEE_BPS`, of `amount`, rounded up) is added to the vault's debt on top of
+    /// `amount` and booked as protocol revenue; fee-exempt owners pass their `FeeExemption`
+    /// account to skip it. The mint then raises the base rate by its share of GUSD supply.
+    /// A vault in a margin account is checked across the whole account instead, against
+    /// the stricter margin ratio; member vaults are passed in `remaining_accounts`. A vault
+    /// with extra collateral balances passes `[collateral_balance, collateral_config,
+    /// price_feed, price_update]` there for each, in the vault's order.
+    pub fn mint_gusd<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MintGusd<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let destination = ctx.accounts.user_gusd_account.to_account_info();
+        mint_against_vault(ctx.accounts, ctx.remaining_accounts, amount, destination)
+    }
+
+    /// Issue a GUSD payment request to be paid by anyone before `expires_at`
+    /// `memo_hash` commits to off-chain invoice details (order id, line items).
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        invoice_id: u64,
+        amount: u64,
+        memo_hash: [u8; 32],
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, GusdError::InvoiceExpired);
+
+        let invoice = &mut ctx.accounts.invoice;
+        invoice.payee = ctx.accounts.payee.key();
+        invoice.invoice_id = invoice_id;
+        invoice.amount = amount;
+        invoice.memo_hash = memo_hash;
+        invoice.expires_at = expires_at;
+        invoice.paid_by = Pubkey::default();
+        invoice.paid_at = 0;
+        invoice.bump = ctx.bumps.invoice;
+
+        msg!("Invoice {} created: {} GUSD to {}", invoice_id, amount, invoice.payee);
+
+        emit!(InvoiceCreated {
+            invoice: invoice.key(),
+            payee: invoice.payee,
+            invoice_id,
+            amount,
+            memo_hash,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Pay an invoice from the payer's GUSD balance
+    pub fn pay_invoice(ctx: Context<PayInvoice>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require_invoice_payable(&ctx.accounts.invoice, now)?;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.payer_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.payee_gusd_account.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            ),
+            ctx.accounts.invoice.amount,
+            GUSD_DECIMALS,
+        )?;
+
+        mark_invoice_paid(&mut ctx.accounts.invoice, ctx.accounts.payer.key(), now, false);
+        Ok(())
+    }
+
+    /// Pay an invoice by minting GUSD against the payer's vault straight to the payee
+    /// Subject to every `mint_gusd` check; margin member vaults go in `remaining_accounts`.
+    pub fn pay_invoice_from_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PayInvoiceFromVault<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require_invoice_payable(&ctx.accounts.invoice, now)?;
+        require_keys_eq!(
+            ctx.accounts.payee_gusd_account.mint,
+            ctx.accounts.vault_mint.gusd_mint.key(),
+            GusdError::InvalidInvoicePayee
+        );
+
+        // Minting to a third party is the owner's call alone
+        require!(ctx.accounts.vault_mint.owner.is_signer, GusdError::Unauthorized);
+        let amount = ctx.accounts.invoice.amount;
+        let destination = ctx.accounts.payee_gusd_account.to_account_info();
+        mint_against_vault(&mut ctx.accounts.vault_mint, ctx.remaining_accounts, amount, destination)?;
+
+        let payer = ctx.accounts.vault_mint.owner.key();
+        mark_invoice_paid(&mut ctx.accounts.invoice, payer, now, true);
+        Ok(())
+    }
+
+    /// Close a paid or expired invoice, returning its rent to the payee
+    pub fn close_invoice(ctx: Context<CloseInvoice>) -> Result<()> {
+        let invoice = &ctx.accounts.invoice;
+        require!(
+            invoice.paid_at != 0 || Clock::get()?.unix_timestamp >= invoice.expires_at,
+            GusdError::InvoiceOutstanding
+        );
+        msg!("Invoice {} closed", invoice.invoice_id);
+        Ok(())
+    }
+
+    /// Repay GUSD debt (burns GUSD)
+    pub fn repay_gusd(ctx: Context<RepayGusd>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let authority = vault_signer(&ctx.accounts.owner, &ctx.accounts.delegate);
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        let accounts = &mut *ctx.accounts;
+        let repay_amount = repay_vault_debt(
+            &mut accounts.protocol_state,
+            &mut accounts.price_feed,
+            &mut accounts.vault,
+            &mut accounts.revenue_stats,
+            &mut accounts.supply_stats,
+            &mut accounts.collateral_config,
+            &accounts.gusd_mint,
+            &accounts.user_gusd_account,
+            authority,
+            &accounts.token_program,
+            stake_discount_bps,
+            amount,
+            now,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        record_vault_activity(vault, now);
+
+        msg!("Repaid {} GUSD. Remaining debt: {}", repay_amount, vault.debt_amount);
+
+        // [MEDIUM-3] Emit event
+        emit!(GusdRepaid {
+            owner: vault.owner,
+            amount: repay_amount,
+            remaining_debt: vault.debt_amount,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+        
+        Ok(())
+    }
+
+    /// Repay another vault's debt by burning the signer's own GUSD, without the owner's
+    /// signature
+    /// Lets rescue bots, employers, and DAO treasuries protect positions they care about.
+    /// Same rules as `repay_gusd`: repayments settle unpaid interest first and may not leave
+    /// dust debt. It doesn't count as owner activity.
+    pub fn repay_gusd_for(ctx: Context<RepayGusdFor>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let authority = ctx.accounts.payer.to_account_info();
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        let accounts = &mut *ctx.accounts;
+        let repay_amount = repay_vault_debt(
+            &mut accounts.protocol_state,
+            &mut accounts.price_feed,
+            &mut accounts.vault,
+            &mut accounts.revenue_stats,
+            &mut accounts.supply_stats,
+            &mut accounts.collateral_config,
+            &accounts.gusd_mint,
+            &accounts.payer_gusd_account,
+            authority,
+            &accounts.token_program,
+            stake_discount_bps,
+            amount,
+            now,
+        )?;
+
+        let vault = &ctx.accounts.vault;
+        msg!(
+            "{} repaid {} GUSD of vault {}. Remaining debt: {}",
+            ctx.accounts.payer.key(),
+            repay_amount,
+            vault.owner,
+            vault.debt_amount
+        );
+
+        emit!(GusdRepaidFor {
+            owner: vault.owner,
+            payer: ctx.accounts.payer.key(),
+            amount: repay_amount,
+            remaining_debt: vault.debt_amount,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a vault's unpaid stability fees with its own GOR collateral (vault owner)
+    /// The collateral, valued at the lower-bound oracle price, goes to the treasury PDA in
+    /// place of the GUSD those fees would have realized: the unminted revenue they were
+    /// booked as is reversed (`reverse_fee_revenue`), so supply stays within total debt.
+    /// Fees whose revenue was already minted stay unpaid, to be repaid in GUSD. Debt and
+    /// collateral fall by equal value, so the ratio only improves.
+    pub fn pay_fees_with_collateral(
+        ctx: Context<PayFeesWithCollateral>,
+        max_collateral: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require_lifecycle(&ctx.accounts.protocol_state, GatedAction::Operate, now)?;
+        require!(
+            ctx.accounts.protocol_state.price_disputed_at == 0,
+            GusdError::PriceDisputed
+        );
+        require_native_collateral(&ctx.accounts.vault)?;
+
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            now,
+        )?;
+        let unpaid = ctx.accounts.vault.unpaid_interest.min(ctx.accounts.vault.debt_amount);
+        require!(unpaid > 0, GusdError::InvalidAmount);
+
+        // The treasury takes GOR instead of GUSD for these fees
+        let reversed = reverse_fee_revenue(&mut ctx.accounts.protocol_state, unpaid)?;
+        let interest = reversed.total();
+        require!(interest > 0, GusdError::InvalidAmount);
+        // Like a repayment: clear the debt or leave at least the dust floor
+        let remaining_debt = ctx.accounts.vault.debt_amount - interest; // interest <= unpaid <= debt
+        require!(
+            remaining_debt == 0 || remaining_debt >= ctx.accounts.protocol_state.min_vault_debt,
+            GusdError::VaultDebtBelowMinimum
+        );
+
+        let price = get_price_with_fallback(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Lower,
+            PriceOperation::Withdraw,
+        )?;
+        let collateral = GusdUnits(interest)
+            .to_usd()
+            .to_lamports(UsdValue6(price), ctx.accounts.price_feed.decimals)?
+            .0;
+        require!(collateral > 0, GusdError::InvalidAmount);
+        require!(collateral <= max_collateral, GusdError::SlippageExceeded);
+        require!(
+            collateral <= ctx.accounts.vault.collateral_amount,
+            GusdError::InsufficientCollateral
+        );
+
+        let owner_key = ctx.accounts.vault.owner;
+        let position_mint = ctx.accounts.vault.position_mint;
+        let seeds = &[
+            SEED_NAMESPACE,
+            b"vault_collateral".as_ref(),
+            position_mint.as_ref(),
+            &[ctx.accounts.vault.collateral_bump],
+        ];
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.vault_collateral.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            collateral,
+        )?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.collateral_amount -= collateral;
+        vault.debt_amount -= interest;
+        vault.unpaid_interest -= interest;
+        record_vault_activity(vault, now);
+        require_collateral_reserve(&ctx.accounts.vault_collateral, vault)?;
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_collateral = protocol.total_collateral.checked_sub(collateral)
+            .ok_or(GusdError::MathOverflow)?;
+        protocol.total_debt = protocol.total_debt.checked_sub(interest)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!(
+            "Paid {} GUSD of fees with {} GOR. Remaining debt: {}",
+            interest,
+            collateral,
+            vault.debt_amount
+        );
+
+        emit!(FeesPaidWithCollateral {
+            owner: owner_key,
+            interest,
+            collateral,
+            price,
+            forgone_fees: reversed.accrued_fees,
+            remaining_debt: vault.debt_amount,
+            forgone_savings: reversed.savings,
+            forgone_insurance: reversed.insurance,
+            forgone_surplus: reversed.surplus,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Accrue stability fees on a vault's debt (permissionless)
+    /// Mints, repayments, and liquidations accrue on their own; this lets keepers and
+    /// dashboards bring any vault's debt up to date.
+    pub fn accrue_interest(ctx: Context<AccrueInterest>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        let interest = accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            now,
+        )?;
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            ctx.accounts.keeper.as_ref().map(|keeper| keeper.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
+
+        msg!(
+            "Accrued {} GUSD interest. Total debt: {}",
+            interest,
+            ctx.accounts.vault.debt_amount
+        );
+
+        Ok(())
+    }
+
+    /// Lock the vault's stability fee at the current rate for `days` days (vault owner)
+    /// The upfront premium (`rate_lock_premium_bps` of current debt per year, pro rata,
+    /// rounded up) is paid in GUSD to the treasury. The lock covers the debt at lock time;
+    /// debt minted afterwards accrues at the floating rate.
+    pub fn lock_stability_fee(ctx: Context<LockStabilityFee>, days: u16) -> Result<()> {
+        require!(days > 0 && days <= MAX_RATE_LOCK_DAYS, GusdError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            ctx.accounts.protocol_state.rate_lock_premium_bps > 0,
+            GusdError::RateLockUnavailable
+        );
+        require!(now >= ctx.accounts.vault.rate_locked_until, GusdError::RateLockActive);
+
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            now,
+        )?;
+        let debt = ctx.accounts.vault.debt_amount;
+        require!(debt > 0, GusdError::InvalidAmount);
+
+        let protocol = &ctx.accounts.protocol_state;
+        let rate_bps = current_stability_fee_bps(protocol)?
+            .saturating_add(ctx.accounts.price_feed.fees.stability_fee_premium_bps);
+        let premium_u128 = (debt as u128)
+            .checked_mul(protocol.rate_lock_premium_bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_mul(days as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(BPS_DENOMINATOR as u128 * 365);
+        require!(premium_u128 <= u64::MAX as u128, GusdError::MathOverflow);
+        let premium = premium_u128 as u64;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.treasury_gusd_account.to_account_info(),
+                    authority: vault_signer(&ctx.accounts.owner, &ctx.accounts.delegate),
+                },
+            ),
+            premium,
+            GUSD_DECIMALS,
+        )?;
+        record_revenue(&mut ctx.accounts.revenue_stats.rate_lock_premiums, premium)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.locked_rate_bps = rate_bps;
+        vault.rate_locked_until = now
+            .checked_add(days as i64 * 24 * 60 * 60)
+            .ok_or(GusdError::MathOverflow)?;
+        vault.rate_lock_principal = debt;
+        record_vault_activity(vault, now);
+
+        msg!(
+            "Stability fee locked at {} bps on {} GUSD until {} for {} GUSD",
+            rate_bps,
+            debt,
+            vault.rate_locked_until,
+            premium
+        );
+
+        emit!(StabilityFeeLocked {
+            owner: vault.owner,
+            rate_bps,
+            principal: debt,
+            locked_until: vault.rate_locked_until,
+            premium,
+        });
+
+        Ok(())
+    }
+
+    /// Prepay liquidation insurance for `periods` 30-day periods (vault owner)
+    /// The premium (in GUSD, a share of current debt per period) goes to the insurance fund.
+    /// While covered, a liquidation of this vault uses a discounted penalty.
+    pub fn buy_liquidation_insurance(
+        ctx: Context<BuyLiquidationInsurance>,
+        periods: u16,
+    ) -> Result<()> {
+        require_lifecycle(
+            &ctx.accounts.protocol_state,
+            GatedAction::Operate,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(periods > 0, GusdError::InvalidAmount);
+
+        let protocol = &ctx.accounts.protocol_state;
+        require!(
+            protocol.insurance_premium_bps > 0 && protocol.insurance_discount_bps > 0,
+            GusdError::InsuranceUnavailable
+        );
+
+        let vault = &ctx.accounts.vault;
+        require!(vault.debt_amount > 0, GusdError::NoDebtToLiquidate);
+
+        let premium_u128 = (vault.debt_amount as u128)
+            .checked_mul(protocol.insurance_premium_bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_mul(periods as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(BPS_DENOMINATOR as u128);
+        require!(premium_u128 <= u64::MAX as u128, GusdError::MathOverflow);
+        let premium = premium_u128 as u64;
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.insurance_fund.to_account_info(),
+                    authority: vault_signer(&ctx.accounts.owner, &ctx.accounts.delegate),
+                },
+            ),
+            premium,
+            GUSD_DECIMALS,
+        )?;
+        record_revenue(&mut ctx.accounts.revenue_stats.insurance_premiums, premium)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let vault = &mut ctx.accounts.vault;
+        let coverage_start = vault.insured_until.max(now);
+        vault.insured_until = coverage_start
+            .checked_add(INSURANCE_PERIOD_SECS * periods as i64)
+            .ok_or(GusdError::MathOverflow)?;
+        record_vault_activity(vault, now);
+
+        msg!("Liquidation insurance bought: {} GUSD, covered until {}", premium, vault.insured_until);
+
+        emit!(LiquidationInsurancePurchased {
+            owner: vault.owner,
+            premium,
+            insured_until: vault.insured_until,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw collateral (if ratio remains healthy)
+    /// [CRITICAL-1] Fixed: Uses PDA-signed system transfer
+    /// A vault in a margin account is checked across the whole account instead, against
+    /// the stricter margin ratio; member vaults are passed in `remaining_accounts`, as are
+    /// the accounts of any extra collateral balances (see `mint_gusd`).
+    /// The withdrawal fee (`withdraw_fee_bps` of `amount`, rounded up) is sent to the
+    /// treasury instead of the owner; owners with a `FeeExemption` pass it to skip it.
+    pub fn withdraw_collateral<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawCollateral<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        // [MEDIUM-2] Check lifecycle state
+        require_lifecycle(
+            &ctx.accounts.protocol_state,
+            GatedAction::Withdraw,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        let now = Clock::get()?.unix_timestamp;
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        // Judge health against the debt with interest accrued to now
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            now,
+        )?;
+        let vault = &mut ctx.accounts.vault;
+        let protocol = &ctx.accounts.protocol_state;
+
+        require!(
+            amount <= vault.collateral_amount,
+            GusdError::InsufficientCollateral
+        );
+
+        // Calculate remaining collateral after withdrawal
+        let remaining_collateral = vault.collateral_amount.checked_sub(amount)
+            .ok_or(GusdError::MathOverflow)?;
+
+        // If there's debt, check that ratio stays healthy
+        if vault.margin_account != Pubkey::default() {
+            let margin_account = linked_margin_account(vault, &ctx.accounts.margin_account)?;
+            let (margin_collateral, margin_debt) = margin_totals(
+                margin_account,
+                ctx.remaining_accounts,
+                Some((vault.key(), remaining_collateral, vault.debt_amount)),
+            )?;
+            if margin_debt > 0 {
+                require!(protocol.price_disputed_at == 0, GusdError::PriceDisputed);
+                let price = get_price_with_fallback(
+                    protocol,
+                    &ctx.accounts.price_feed,
+                    &ctx.accounts.deployment_config,
+                    ctx.accounts.price_update.as_deref(),
+                    PriceBound::Lower,
+                    PriceOperation::Withdraw,
+                )?;
+                let margin_ratio_bps = calculate_collateral_ratio_bps(
+                    margin_collateral,
+                    margin_debt,
+                    price,
+                    ctx.accounts.price_feed.decimals,
+                )?;
+                require!(
+                    margin_ratio_bps
+                        >= margin_min_collateral_ratio_bps(protocol, &ctx.accounts.collateral_config)?,
+                    GusdError::WouldUndercollateralize
+                );
+            }
+        } else if vault.debt_amount > 0 {
+            require!(protocol.price_disputed_at == 0, GusdError::PriceDisputed);
+            let price = get_price_with_fallback(
+                protocol,
+                &ctx.accounts.price_feed,
+                &ctx.accounts.deployment_config,
+                ctx.accounts.price_update.as_deref(),
+                PriceBound::Lower,
+                PriceOperation::Withdraw,
+            )?;
+            let remaining_value_usd = calculate_usd_value(
+                remaining_collateral,
+                price,
+                ctx.accounts.price_feed.decimals,
+            )?;
+
+            // Extra collateral balances cover part of the debt; the primary collateral the rest
+            let uncovered_debt = vault.debt_amount.saturating_sub(balances_debt_capacity(
+                protocol,
+                &ctx.accounts.deployment_config,
+                vault,
+                ctx.remaining_accounts,
+                PriceOperation::Withdraw,
+                None,
+            )?);
+            let required_collateral = uncovered_debt
+                .checked_mul(ctx.accounts.collateral_config.min_collateral_ratio_bps)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR)
+                .ok_or(GusdError::MathOverflow)?;
+
+            require!(
+                remaining_value_usd >= required_collateral,
+                GusdError::WouldUndercollateralize
+            );
+        }
+
+        let exempt = ctx.accounts.fee_exemption
+            .as_ref()
+            .is_some_and(|exemption| exemption.is_active(now));
+        let fee = if exempt {
+            0
+        } else {
+            let fee = (amount as u128)
+                .checked_mul(protocol.withdraw_fee_bps as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .div_ceil(BPS_DENOMINATOR as u128);
+            fee as u64 // fee <= amount
+        };
+        let payout = amount - fee;
+
+        // [CRITICAL-1] Fixed: Use PDA-signed transfer instead of direct lamport manipulation
+        let position_mint = vault.position_mint;
+        let native = vault.collateral_mint == NATIVE_COLLATERAL_MINT;
+        let mut transfer_fee = 0;
+        if native {
+            let seeds = &[
+                SEED_NAMESPACE,
+                b"vault_collateral".as_ref(),
+                position_mint.as_ref(),
+                &[ctx.bumps.vault_collateral],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            for (to, lamports) in [
+                (ctx.accounts.owner.to_account_info(), payout),
+                (ctx.accounts.treasury.to_account_info(), fee),
+            ] {
+                if lamports == 0 {
+                    continue;
+                }
+                anchor_lang::system_program::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.vault_collateral.to_account_info(),
+                            to,
+                        },
+                        signer_seeds,
+                    ),
+                    lamports,
+                )?;
+            }
+        } else {
+            let custody = token_custody(
+                ctx.accounts.collateral_mint.as_ref(),
+                ctx.accounts.vault_token_account.as_ref(),
+                ctx.accounts.collateral_token_program.as_ref(),
+            )?;
+            let owner_token_account = ctx.accounts.owner_token_account
+                .as_ref()
+                .ok_or(GusdError::MissingCollateralAccounts)?;
+            transfer_fee = transfer_vault_tokens(
+                custody,
+                &ctx.accounts.vault_collateral,
+                position_mint,
+                ctx.bumps.vault_collateral,
+                owner_token_account.to_account_info(),
+                payout,
+            )?;
+            if fee > 0 {
+                let treasury_collateral_account = ctx.accounts.treasury_collateral_account
+                    .as_ref()
+                    .ok_or(GusdError::MissingCollateralAccounts)?;
+                transfer_vault_tokens(
+                    custody,
+                    &ctx.accounts.vault_collateral,
+                    position_mint,
+                    ctx.bumps.vault_collateral,
+                    treasury_collateral_account.to_account_info(),
+                    fee,
+                )?;
+            }
+        }
+
+        // Update vault state
+        vault.collateral_amount = remaining_collateral;
+        record_vault_activity(vault, now);
+        require_collateral_reserve(&ctx.accounts.vault_collateral, vault)?;
+
+        // Update collateral totals; the withdrawal fee counters are denominated in GOR
+        if native {
+            let protocol = &mut ctx.accounts.protocol_state;
+            protocol.total_collateral = protocol.total_collateral.checked_sub(amount)
+                .ok_or(GusdError::MathOverflow)?;
+            protocol.withdraw_fees_collected = protocol.withdraw_fees_collected
+                .checked_add(fee)
+                .ok_or(GusdError::MathOverflow)?;
+            record_revenue(&mut ctx.accounts.revenue_stats.withdraw_fees, fee)?;
+        } else {
+            let config = &mut ctx.accounts.collateral_config;
+            config.total_collateral = config.total_collateral.checked_sub(amount)
+                .ok_or(GusdError::MathOverflow)?;
+        }
+
+        msg!(
+            "Withdrew {} collateral ({} fee, {} transfer fee). Remaining collateral: {}",
+            amount,
+            fee,
+            transfer_fee,
+            vault.collateral_amount
+        );
+
+        // [MEDIUM-3] Emit event
+        emit!(CollateralWithdrawn {
+            owner: vault.owner,
+            amount,
+            fee,
+            transfer_fee,
+            remaining_collateral: vault.collateral_amount,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+        
+        Ok(())
+    }
+
+    /// Close an empty vault (debt == 0 and tracked collateral == 0)
+    /// Refunds the collateral PDA's rent reserve, plus any untracked lamports sent to it,
+    /// back to the owner.
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        require!(ctx.accounts.vault.debt_amount == 0, GusdError::VaultNotEmpty);
+        require!(ctx.accounts.vault.collateral_amount == 0, GusdError::VaultNotEmpty);
+        require!(
+            ctx.accounts.vault.margin_account == Pubkey::default(),
+            GusdError::VaultInMarginAccount
+        );
+        require!(
+            ctx.accounts.vault.collateral_balances.is_empty(),
+            GusdError::VaultHasCollateralBalances
+        );
+
+        require!(
+            ctx.accounts.vault.retained_liquidation_fees == 0,
+            GusdError::LiquidationFeesUnswept
+        );
+
+        let vault_owner_key = ctx.accounts.vault.owner;
+        let position_mint = ctx.accounts.vault.position_mint;
+        let vault_collateral_bump = ctx.accounts.vault.collateral_bump;
+        let rent_reserve = ctx.accounts.vault.collateral_rent_reserve;
+
+        let balance = **ctx.accounts.vault_collateral.lamports.borrow();
+        require!(balance >= rent_reserve, GusdError::RentReserveViolated);
+        if balance > 0 {
+            let seeds = &[
+                SEED_NAMESPACE,
+                b"vault_collateral".as_ref(),
+                position_mint.as_ref(),
+                &[vault_collateral_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.vault_collateral.to_account_info(),
+                        to: ctx.accounts.owner.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                balance,
+            )?;
+        }
+
+        // The position NFT goes with the vault: burned if the holder signed, else frozen
+        let holder = ctx.accounts.owner.to_account_info();
+        let position_burned = retire_position_nft(
+            &ctx.accounts.vault,
+            ctx.accounts.position_token_account.as_ref(),
+            ctx.accounts.position_mint.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            ctx.accounts.protocol_state.as_deref(),
+            holder.is_signer.then_some(&holder),
+        )?;
+
+        msg!("Vault closed: {} (position NFT burned: {})", vault_owner_key, position_burned);
+
+        emit!(VaultClosed {
+            owner: vault_owner_key,
+            rent_refunded: rent_reserve,
+            excess_refunded: balance - rent_reserve,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Flag an inactive, debt-free dust vault as abandoned (admin only)
+    /// Starts the owner reclaim window; the vault can be swept once it elapses.
+    pub fn flag_abandoned_vault(ctx: Context<FlagAbandonedVault>) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let vault = &mut ctx.accounts.vault;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(vault.debt_amount == 0, GusdError::VaultNotAbandoned);
+        require!(vault.abandoned_flagged_at == 0, GusdError::VaultNotAbandoned);
+        require!(vault.margin_account == Pubkey::default(), GusdError::VaultInMarginAccount);
+        require!(vault.collateral_balances.is_empty(), GusdError::VaultHasCollateralBalances);
+        require!(
+            now.saturating_sub(vault.last_activity_ts) >= VAULT_INACTIVITY_PERIOD_SECS,
+            GusdError::VaultNotAbandoned
+        );
+
+        require_dust_collateral(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            vault.collateral_amount,
+        )?;
+
+        vault.abandoned_flagged_at = now;
+        let sweep_eligible_at = now
+            .checked_add(VAULT_RECLAIM_WINDOW_SECS)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Vault {} flagged as abandoned", vault.owner);
+
+        emit!(VaultFlaggedAbandoned {
+            owner: vault.owner,
+            collateral_amount: vault.collateral_amount,
+            sweep_eligible_at,
+        });
+
+        Ok(())
+    }
+
+    /// Clear an abandoned flag on the owner's vault (owner only)
+    pub fn reclaim_vault(ctx: Context<ReclaimVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.abandoned_flagged_at != 0, GusdError::VaultNotFlagged);
+
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault reclaimed by owner: {}", vault.owner);
+
+        emit!(VaultReclaimed {
+            owner: vault.owner,
+        });
+
+        Ok(())
+    }
+
+    /// Choose where the vault's share of idle-collateral yield goes (owner only)
+    /// Read by the yield-distribution crank; no yield source is live yet, so the choice
+    /// takes effect once one is.
+    pub fn set_yield_routing(ctx: Context<ReclaimVault>, routing: YieldRouting) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.yield_routing = routing;
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault {} yield routing set to {:?}", vault.owner, routing);
+
+        emit!(YieldRoutingSet {
+            owner: vault.owner,
+            routing,
+        });
+
+        Ok(())
+    }
+
+    /// Let `delegate` act on the vault within `scope` (owner only)
+    /// Replaces any current delegate. Funds a delegate pays in come from its own accounts;
+    /// withdrawals, mints, and rent refunds still go to the owner.
+    pub fn approve_delegate(
+        ctx: Context<SetVaultDelegate>,
+        delegate: Pubkey,
+        scope: DelegateScope,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let owner = ctx.accounts.owner.key();
+        require!(
+            scope != DelegateScope::None && delegate != Pubkey::default() && delegate != owner,
+            GusdError::InvalidDelegate
+        );
+        vault.delegate = delegate;
+        vault.delegate_scope = scope;
+        vault.delegated_by = owner;
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault {} delegate set to {} ({:?})", vault.owner, delegate, scope);
+
+        emit!(DelegateApproved {
+            owner: vault.owner,
+            delegate,
+            scope,
+        });
+
+        Ok(())
+    }
+
+    /// Remove the vault's delegate (owner only)
+    pub fn revoke_delegate(ctx: Context<SetVaultDelegate>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.delegate_scope != DelegateScope::None, GusdError::InvalidDelegate);
+        let delegate = std::mem::take(&mut vault.delegate);
+        vault.delegate_scope = DelegateScope::None;
+        vault.delegated_by = Pubkey::default();
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault {} delegate {} revoked", vault.owner, delegate);
+
+        emit!(DelegateRevoked {
+            owner: vault.owner,
+            delegate,
+        });
+
+        Ok(())
+    }
+
+    /// Open an extra collateral balance of another enabled token type on the vault
+    /// The balance backs the vault's debt alongside its primary collateral, each valued
+    /// against its own type's collateral ratios. Margin account vaults can't hold one.
+    pub fn open_collateral_balance(ctx: Context<OpenCollateralBalance>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let collateral_mint = ctx.accounts.collateral_mint.key();
+        require!(
+            vault.collateral_balances.len() < MAX_COLLATERAL_BALANCES,
+            GusdError::CollateralBalancesFull
+        );
+
+        let balance = &mut ctx.accounts.collateral_balance;
+        balance.vault = vault.key();
+        balance.collateral_mint = collateral_mint;
+        balance.amount = 0;
+        balance.bump = ctx.bumps.collateral_balance;
+        balance.settled = false;
+
+        vault.collateral_balances.push(collateral_mint);
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault {} opened a collateral balance of {}", vault.owner, collateral_mint);
+
+        emit!(CollateralBalanceOpened {
+            owner: vault.owner,
+            collateral_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit tokens into one of the vault's extra collateral balances
+    /// Credited net of any Token-2022 transfer fee.
+    pub fn deposit_collateral_balance(
+        ctx: Context<DepositCollateralBalance>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        let balance_before = ctx.accounts.balance_token_account.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.collateral_token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.owner_token_account.to_account_info(),
+                    mint: ctx.accounts.collateral_mint.to_account_info(),
+                    to: ctx.accounts.balance_token_account.to_account_info(),
+                    authority: vault_signer(&ctx.accounts.owner, &ctx.accounts.delegate),
+                },
+            ),
+            amount,
+            ctx.accounts.collateral_mint.decimals,
+        )?;
+        ctx.accounts.balance_token_account.reload()?;
+        let received = ctx.accounts.balance_token_account.amount
+            .checked_sub(balance_before)
+            .ok_or(GusdError::MathOverflow)?;
+        require!(received > 0, GusdError::InvalidAmount);
+
+        let config = &mut ctx.accounts.collateral_config;
+        config.total_collateral = config.total_collateral.checked_add(received)
+            .ok_or(GusdError::MathOverflow)?;
+        let balance = &mut ctx.accounts.collateral_balance;
+        balance.amount = balance.amount.checked_add(received)
+            .ok_or(GusdError::MathOverflow)?;
+        let vault = &mut ctx.accounts.vault;
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!(
+            "Deposited {} {} collateral ({} transfer fee). Balance: {}",
+            received,
+            balance.collateral_mint,
+            amount - received,
+            balance.amount
+        );
+
+        emit!(CollateralBalanceDeposited {
+            owner: vault.owner,
+            collateral_mint: balance.collateral_mint,
+            amount: received,
+            transfer_fee: amount - received,
+            balance: balance.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw tokens from one of the vault's extra collateral balances
+    /// Charges the withdrawal fee like `withdraw_collateral`, paid to the treasury's token
+    /// account. With debt outstanding the vault must still meet every type's minimum ratio;
+    /// all of its balances are passed in `remaining_accounts`, this one included. Once a
+    /// shutdown has settled the vault, balances can be withdrawn in settlement too.
+    pub fn withdraw_collateral_balance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawCollateralBalance<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // After a shutdown, settled vaults still get their balances back
+        let settled = ctx.accounts.protocol_state.lifecycle == Lifecycle::Settlement
+            && ctx.accounts.vault.debt_amount == 0;
+        if !settled {
+            require_lifecycle(&ctx.accounts.protocol_state, GatedAction::Withdraw, now)?;
+        }
+        require!(amount > 0, GusdError::InvalidAmount);
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        // Judge health against the debt with interest accrued to now
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            now,
+        )?;
+
+        let collateral_mint = ctx.accounts.collateral_balance.collateral_mint;
+        let remaining_balance = ctx.accounts.collateral_balance.amount
+            .checked_sub(amount)
+            .ok_or(GusdError::InsufficientCollateral)?;
+
+        let vault = &ctx.accounts.vault;
+        let protocol = &ctx.accounts.protocol_state;
+        if vault.debt_amount > 0 {
+            require!(protocol.price_disputed_at == 0, GusdError::PriceDisputed);
+            let uncovered_debt = vault.debt_amount.saturating_sub(balances_debt_capacity(
+                protocol,
+                &ctx.accounts.deployment_config,
+                vault,
+                ctx.remaining_accounts,
+                PriceOperation::Withdraw,
+                Some((collateral_mint, remaining_balance)),
+            )?);
+            if uncovered_debt > 0 {
+                let price = get_price_with_fallback(
+                    protocol,
+                    &ctx.accounts.price_feed,
+                    &ctx.accounts.deployment_config,
+                    ctx.accounts.price_update.as_deref(),
+                    PriceBound::Lower,
+                    PriceOperation::Withdraw,
+                )?;
+                let collateral_value_usd = calculate_usd_value(
+                    vault.collateral_amount,
+                    price,
+                    ctx.accounts.price_feed.decimals,
+                )?;
+                let required_collateral = uncovered_debt
+                    .checked_mul(ctx.accounts.collateral_config.min_collateral_ratio_bps)
+                    .ok_or(GusdError::MathOverflow)?
+                    .checked_div(BPS_DENOMINATOR)
+                    .ok_or(GusdError::MathOverflow)?;
+                require!(
+                    collateral_value_usd >= required_collateral,
+                    GusdError::WouldUndercollateralize
+                );
+            }
+        }
+
+        let exempt = ctx.accounts.fee_exemption
+            .as_ref()
+            .is_some_and(|exemption| exemption.is_active(now));
+        let fee = if exempt {
+            0
+        } else {
+            let fee = (amount as u128)
+                .checked_mul(protocol.withdraw_fee_bps as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .div_ceil(BPS_DENOMINATOR as u128);
+            fee as u64 // fee <= amount
+        };
+        let payout = amount - fee;
+
+        let custody = (
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.balance_token_account,
+            &ctx.accounts.collateral_token_program,
+        );
+        let transfer_fee = transfer_vault_tokens(
+            custody,
+            &ctx.accounts.vault_collateral,
+            vault.position_mint,
+            vault.collateral_bump,
+            ctx.accounts.owner_token_account.to_account_info(),
+            payout,
+        )?;
+        if fee > 0 {
+            let treasury_collateral_account = ctx.accounts.treasury_collateral_account
+                .as_ref()
+                .ok_or(GusdError::MissingCollateralAccounts)?;
+            transfer_vault_tokens(
+                custody,
+                &ctx.accounts.vault_collateral,
+                vault.position_mint,
+                vault.collateral_bump,
+                treasury_collateral_account.to_account_info(),
+                fee,
+            )?;
+        }
+
+        ctx.accounts.collateral_balance.amount = remaining_balance;
+        let config = &mut ctx.accounts.balance_collateral_config;
+        config.total_collateral = config.total_collateral.checked_sub(amount)
+            .ok_or(GusdError::MathOverflow)?;
+        let vault = &mut ctx.accounts.vault;
+        record_vault_activity(vault, now);
+
+        msg!(
+            "Withdrew {} {} collateral ({} fee, {} transfer fee). Remaining balance: {}",
+            amount,
+            collateral_mint,
+            fee,
+            transfer_fee,
+            remaining_balance
+        );
+
+        emit!(CollateralBalanceWithdrawn {
+            owner: vault.owner,
+            collateral_mint,
+            amount,
+            fee,
+            transfer_fee,
+            remaining_balance,
+        });
+
+        Ok(())
+    }
+
+    /// Close an empty extra collateral balance, refunding its rent to the owner
+    /// Tokens sent to the balance's token account directly, never credited to it, go to the
+    /// owner's token account first.
+    pub fn close_collateral_balance(ctx: Context<CloseCollateralBalance>) -> Result<()> {
+        require!(ctx.accounts.collateral_balance.amount == 0, GusdError::VaultNotEmpty);
+
+        let position_mint = ctx.accounts.vault.position_mint;
+        let collateral_bump = ctx.accounts.vault.collateral_bump;
+        let untracked = ctx.accounts.balance_token_account.amount;
+        if untracked > 0 {
+            let owner_token_account = ctx.accounts.owner_token_account
+                .as_ref()
+                .ok_or(GusdError::MissingCollateralAccounts)?;
+            transfer_vault_tokens(
+                (
+                    &ctx.accounts.collateral_mint,
+                    &ctx.accounts.balance_token_account,
+                    &ctx.accounts.collateral_token_program,
+                ),
+                &ctx.accounts.vault_collateral,
+                position_mint,
+                collateral_bump,
+                owner_token_account.to_account_info(),
+                untracked,
+            )?;
+        }
+
+        let seeds = &[
+            SEED_NAMESPACE,
+            b"vault_collateral".as_ref(),
+            position_mint.as_ref(),
+            &[collateral_bump],
+        ];
+        token_interface::close_account(CpiContext::new_with_signer(
+            ctx.accounts.collateral_token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.balance_token_account.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.vault_collateral.to_account_info(),
+            },
+            &[&seeds[..]],
+        ))?;
+
+        let collateral_mint = ctx.accounts.collateral_balance.collateral_mint;
+        let vault = &mut ctx.accounts.vault;
+        vault.collateral_balances.retain(|mint| *mint != collateral_mint);
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault {} closed its collateral balance of {}", vault.owner, collateral_mint);
+
+        emit!(CollateralBalanceClosed {
+            owner: vault.owner,
+            collateral_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Close a flagged abandoned vault after the reclaim window (admin only)
+    /// Dust collateral and rent from both vault PDAs are swept to the treasury.
+    pub fn sweep_abandoned_vault(ctx: Context<SweepAbandonedVault>) -> Result<()> {
+        let vault_owner_key = ctx.accounts.vault_owner.key();
+        let position_mint = ctx.accounts.vault.position_mint;
+        let vault_collateral_bump = ctx.accounts.vault.collateral_bump;
+        let flagged_at = ctx.accounts.vault.abandoned_flagged_at;
+        let collateral_amount = ctx.accounts.vault.collateral_amount;
+        let now = Clock::get()?.unix_timestamp;
+
+        require!(flagged_at != 0, GusdError::VaultNotFlagged);
+        require!(ctx.accounts.vault.debt_amount == 0, GusdError::VaultNotAbandoned);
+        require_native_collateral(&ctx.accounts.vault)?;
+        require!(
+            now.saturating_sub(flagged_at) >= VAULT_RECLAIM_WINDOW_SECS,
+            GusdError::ReclaimWindowActive
+        );
+        // Tips and settlement excess held for the holder can grow the vault after flagging
+        require_dust_collateral(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            collateral_amount,
+        )?;
+
+        // The holder isn't here to burn the position NFT, so it's frozen
+        retire_position_nft(
+            &ctx.accounts.vault,
+            ctx.accounts.position_token_account.as_ref(),
+            ctx.accounts.position_mint.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            Some(&ctx.accounts.protocol_state),
+            None,
+        )?;
+
+        let balance = **ctx.accounts.vault_collateral.lamports.borrow();
+        if balance > 0 {
+            let seeds = &[
+                SEED_NAMESPACE,
+                b"vault_collateral".as_ref(),
+                position_mint.as_ref(),
+                &[vault_collateral_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.vault_collateral.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                balance,
+            )?;
+        }
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_collateral = protocol.total_collateral
+            .checked_sub(collateral_amount)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Swept abandoned vault {}: {} lamports to treasury", vault_owner_key, balance);
+
+        emit!(AbandonedVaultSwept {
+            owner: vault_owner_key,
+            collateral_swept: collateral_amount,
+            lamports_swept: balance,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Move a vault's retained liquidation fees from its collateral PDA to the treasury
+    /// Permissionless; the owner must sweep them before closing the vault.
+    pub fn sweep_liquidation_fees(ctx: Context<SweepLiquidationFees>) -> Result<()> {
+        let amount = ctx.accounts.vault.retained_liquidation_fees;
+        require!(amount > 0, GusdError::InvalidAmount);
+
+        let vault_owner_key = ctx.accounts.vault_owner.key();
+        let position_mint = ctx.accounts.vault.position_mint;
+        if ctx.accounts.vault.collateral_mint == NATIVE_COLLATERAL_MINT {
+            let seeds = &[
+                SEED_NAMESPACE,
+                b"vault_collateral".as_ref(),
+                position_mint.as_ref(),
+                &[ctx.accounts.vault.collateral_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            anchor_lang::system_program::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.vault_collateral.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+            )?;
+        } else {
+            let custody = token_custody(
+                ctx.accounts.collateral_mint.as_ref(),
+                ctx.accounts.vault_token_account.as_ref(),
+                ctx.accounts.collateral_token_program.as_ref(),
+            )?;
+            let treasury_collateral_account = ctx.accounts.treasury_collateral_account
+                .as_ref()
+                .ok_or(GusdError::MissingCollateralAccounts)?;
+            transfer_vault_tokens(
+                custody,
+                &ctx.accounts.vault_collateral,
+                position_mint,
+                ctx.accounts.vault.collateral_bump,
+                treasury_collateral_account.to_account_info(),
+                amount,
+            )?;
+        }
+
+        let vault = &mut ctx.accounts.vault;
+        vault.retained_liquidation_fees = 0;
+        require_collateral_reserve(&ctx.accounts.vault_collateral, vault)?;
+
+        msg!("Swept {} of liquidation fees from vault {}", amount, vault_owner_key);
+
+        emit!(LiquidationFeesSwept {
+            vault_owner: vault_owner_key,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Write off the debt of a vault liquidated down to zero collateral as bad debt
+    /// Permissionless. The GUSD stays in circulation, so `total_debt` keeps counting it
+    /// until `cover_bad_debt` burns insurance fund GUSD against it.
+    pub fn record_bad_debt(ctx: Context<RecordBadDebt>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let protocol = &mut ctx.accounts.protocol_state;
+        let vault = &mut ctx.accounts.vault;
+        require!(
+            vault.collateral_amount == 0 && vault.debt_amount > 0,
+            GusdError::NoBadDebt
+        );
+        require!(vault.margin_account == Pubkey::default(), GusdError::VaultInMarginAccount);
+
+        // Interest on unbacked debt is never collected, so none is booked
+        accrue_debt_index(protocol, now)?;
+        vault.debt_index_snapshot = protocol.debt_index;
+
+        let amount = std::mem::take(&mut vault.debt_amount);
+        protocol.bad_debt = protocol.bad_debt
+            .checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+        // Unbacked debt no longer counts as exposure to the collateral
+        let principal = settle_unpaid_interest(vault, amount);
+        release_collateral_debt(&mut ctx.accounts.collateral_config, principal);
+
+        msg!("Bad debt recorded: {} GUSD from vault {}", amount, vault.owner);
+
+        emit!(BadDebtRecorded {
+            vault_owner: vault.owner,
+            amount,
+            bad_debt: protocol.bad_debt,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Burn insurance fund GUSD against recorded bad debt (admin only)
+    /// The insurance fund's share of fee revenue is minted into it first.
+    pub fn cover_bad_debt(ctx: Context<CoverBadDebt>, amount: u64) -> Result<()> {
+        require!(amount > 0, GusdError::InvalidAmount);
+        require!(amount <= ctx.accounts.protocol_state.bad_debt, GusdError::NoBadDebt);
+
+        let bump = ctx.accounts.protocol_state.bump;
+        let seeds = &[SEED_NAMESPACE, b"protocol".as_ref(), &[bump]];
+        let signer_seeds = &[&seeds[..]];
+
+        // Like accrued_fees, the fund's share stays unminted after a shutdown
+        let realized = if ctx.accounts.protocol_state.shutdown_at == 0 {
+            std::mem::take(&mut ctx.accounts.protocol_state.insurance_accrued)
+        } else {
+            0
+        };
+        if realized > 0 {
+            token_interface::mint_to(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    MintTo {
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        to: ctx.accounts.insurance_fund.to_account_info(),
+                        authority: ctx.accounts.protocol_state.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                realized,
+            )?;
+            ctx.accounts.insurance_fund.reload()?;
+            record_supply(&mut ctx.accounts.supply_stats.minted_insurance_fees, realized)?;
+        }
+        require!(
+            amount <= ctx.accounts.insurance_fund.amount,
+            GusdError::InsufficientInsuranceFund
+        );
+
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    from: ctx.accounts.insurance_fund.to_account_info(),
+                    authority: ctx.accounts.protocol_state.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+        record_supply(&mut ctx.accounts.supply_stats.burned_bad_debt, amount)?;
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.bad_debt -= amount;
+        protocol.total_debt = protocol.total_debt
+            .checked_sub(amount)
+            .ok_or(GusdError::MathOverflow)?;
+
+        msg!("Covered {} GUSD of bad debt; {} remaining", amount, protocol.bad_debt);
+
+        emit!(BadDebtCovered {
+            amount,
+            realized,
+            bad_debt: protocol.bad_debt,
+            insurance_fund_balance: ctx.accounts.insurance_fund.amount - amount,
+        });
+
+        Ok(())
+    }
+
+    /// Create a margin account that vaults can join to share collateral
+    pub fn create_margin_account(ctx: Context<CreateMarginAccount>) -> Result<()> {
+        let margin_account = &mut ctx.accounts.margin_account;
+        margin_account.authority = ctx.accounts.authority.key();
+        margin_account.vaults = Vec::new();
+        margin_account.bump = ctx.bumps.margin_account;
+
+        msg!("Margin account created for: {}", margin_account.authority);
+
+        emit!(MarginAccountCreated {
+            margin_account: margin_account.key(),
+            authority: margin_account.authority,
+        });
+
+        Ok(())
+    }
+
+    /// Link a vault to a margin account (vault owner and margin authority)
+    /// From then on the vault's mints, withdrawals and liquidations are judged across the
+    /// whole margin account.
+    pub fn join_margin_account(ctx: Context<JoinMarginAccount>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let margin_account = &mut ctx.accounts.margin_account;
+
+        require!(vault.margin_account == Pubkey::default(), GusdError::VaultInMarginAccount);
+        // Margin accounts value all member vaults at the GOR price
+        require_native_collateral(vault)?;
+        require!(vault.collateral_balances.is_empty(), GusdError::VaultHasCollateralBalances);
+        require!(
+            margin_account.vaults.len() < MAX_MARGIN_VAULTS,
+            GusdError::MarginAccountFull
+        );
+
+        margin_account.vaults.push(vault.key());
+        vault.margin_account = margin_account.key();
+        record_vault_activity(vault, Clock::get()?.unix_timestamp);
+
+        msg!("Vault {} joined margin account {}", vault.owner, margin_account.key());
+
+        emit!(MarginVaultJoined {
+            margin_account: margin_account.key(),
+            vault: vault.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Unlink a vault from its margin account (vault owner and margin authority)
+    /// The vault must meet the minimum collateral ratio on its own, and the vaults left
+    /// behind (passed in `remaining_accounts`) must still meet the margin ratio.
+    pub fn leave_margin_account<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LeaveMarginAccount<'info>>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            now,
+        );
+        // Judge health against the debt with interest accrued to now
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            now,
+        )?;
+
+        let vault_key = ctx.accounts.vault.key();
+        let margin_account = &mut ctx.accounts.margin_account;
+        margin_account.vaults.retain(|member| *member != vault_key);
+
+        let vault = &ctx.accounts.vault;
+        let (margin_collateral, margin_debt) =
+            margin_totals(margin_account, ctx.remaining_accounts, None)?;
+
+        if vault.debt_amount > 0 || margin_debt > 0 {
+            let protocol = &ctx.accounts.protocol_state;
+            require!(protocol.price_disputed_at == 0, GusdError::PriceDisputed);
+            let price = get_price_with_fallback(
+                protocol,
+                &ctx.accounts.price_feed,
+                &ctx.accounts.deployment_config,
+                ctx.accounts.price_update.as_deref(),
+                PriceBound::Lower,
+                PriceOperation::Withdraw,
+            )?;
+            let decimals = ctx.accounts.price_feed.decimals;
+
+            let vault_ratio_bps = calculate_collateral_ratio_bps(
+                vault.collateral_amount,
+                vault.debt_amount,
+                price,
+                decimals,
+            )?;
+            let config = &ctx.accounts.collateral_config;
+            require!(
+                vault_ratio_bps >= config.min_collateral_ratio_bps,
+                GusdError::WouldUndercollateralize
+            );
+
+            let margin_ratio_bps =
+                calculate_collateral_ratio_bps(margin_collateral, margin_debt, price, decimals)?;
+            require!(
+                margin_ratio_bps >= margin_min_collateral_ratio_bps(protocol, config)?,
+                GusdError::WouldUndercollateralize
+            );
+        }
+
+        let margin_account_key = ctx.accounts.margin_account.key();
+        let vault = &mut ctx.accounts.vault;
+        vault.margin_account = Pubkey::default();
+        record_vault_activity(vault, now);
+
+        msg!("Vault {} left margin account {}", vault.owner, margin_account_key);
+
+        emit!(MarginVaultLeft {
+            margin_account: margin_account_key,
+            vault: vault_key,
+        });
+
+        Ok(())
+    }
+
+    /// Liquidate an undercollateralized vault
+    /// [CRITICAL-2] Fixed: Uses PDA-signed system transfer
+    /// [CRITICAL-3] Fixed: Correct liquidation math
+    /// A vault in a margin account is only liquidatable while the whole account is below
+    /// the threshold; member vaults are passed in `remaining_accounts`. Extra collateral
+    /// balances (passed there as for `mint_gusd`) cover debt at their own liquidation
+    /// thresholds, and only the rest is judged against the primary collateral.
+    /// `tip_lamports` of the liquidator's GOR payout go to the vault owner as a goodwill
+    /// rebate, up to `liquidation_tip_max_bps` of the liquidator's bonus. Without a valid
+    /// `position_token_account` for the current holder the tip stays in the vault as
+    /// collateral instead.
+    pub fn liquidate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Liquidate<'info>>,
+        tip_lamports: u64,
+    ) -> Result<()> {
+        // [MEDIUM-2] Check lifecycle state
+        require_lifecycle(
+            &ctx.accounts.protocol_state,
+            GatedAction::Liquidate,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(ctx.accounts.protocol_state.price_disputed_at == 0, GusdError::PriceDisputed);
+
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            Clock::get()?.unix_timestamp,
+        );
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        // Snapshot values we need before taking mutable borrows
+        let vault_owner_key = ctx.accounts.vault.owner;
+        let position_mint = ctx.accounts.vault.position_mint;
+        let owner_verified = is_vault_owner(
+            &ctx.accounts.vault,
+            &ctx.accounts.vault_owner,
+            ctx.accounts.position_token_account.as_ref(),
+        );
+        let spot_price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Upper,
+            PriceOperation::Liquidation,
+        )?;
+        // Optionally liquidate against the TWAP so a short-lived dip can't trigger liquidations
+        // (the accumulator tracks GOR)
+        let native = ctx.accounts.vault.collateral_mint == NATIVE_COLLATERAL_MINT;
+        let price = if native && ctx.accounts.protocol_state.twap_for_liquidations {
+            let twap = compute_twap(
+                &ctx.accounts.price_accumulator,
+                ctx.accounts.protocol_state.twap_window_secs,
+                Clock::get()?.unix_timestamp,
+            )?;
+            let twap = apply_fx_rate(&ctx.accounts.protocol_state, twap)?;
+            apply_price_spread(&ctx.accounts.protocol_state, twap, PriceOperation::Liquidation)?
+        } else {
+            spot_price
+        };
+        let native_decimals = ctx.accounts.price_feed.decimals;
+
+        let vault_collateral_amount = ctx.accounts.vault.collateral_amount;
+        let vault_debt_amount = ctx.accounts.vault.debt_amount;
+        let vault_collateral_bump = ctx.accounts.vault.collateral_bump;
+
+        require!(vault_debt_amount > 0, GusdError::NoDebtToLiquidate);
+
+        // Reject a second liquidation in the same slot unless the first one left the vault
+        // below threshold; a later tx in the slot may be acting on a stale read.
+        let clock = Clock::get()?;
+        let current_slot = clock.slot;
+        if ctx.accounts.vault.last_liquidation_slot == current_slot {
+            require!(
+                ctx.accounts.vault.last_liquidation_left_unhealthy,
+                GusdError::DuplicateLiquidationInSlot
+            );
+        }
+        require_priority_rights(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.vault,
+            ctx.accounts.liquidator.key(),
+            &clock,
+        )?;
+
+        // Check if vault is undercollateralized
+        let collateral_value_usd = calculate_usd_value(
+            vault_collateral_amount,
+            price,
+            native_decimals,
+        )?;
+
+        // Extra collateral balances cover part of the debt at their liquidation thresholds;
+        // the primary collateral is judged against the rest
+        let uncovered_debt = vault_debt_amount.saturating_sub(balances_debt_capacity(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.deployment_config,
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            PriceOperation::Liquidation,
+            None,
+        )?);
+        require!(uncovered_debt > 0, GusdError::VaultNotLiquidatable);
+
+        let collateral_ratio_bps = (collateral_value_usd as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(uncovered_debt as u128)
+            .ok_or(GusdError::MathOverflow)?;
+
+        let liquidation_threshold_bps = ctx.accounts.collateral_config.liquidation_threshold_bps;
+        require!(
+            collateral_ratio_bps < liquidation_threshold_bps as u128,
+            GusdError::VaultNotLiquidatable
+        );
+
+        // A vault in a margin account is backstopped by the account's other vaults
+        let position = if ctx.accounts.vault.margin_account != Pubkey::default() {
+            let margin_account =
+                linked_margin_account(&ctx.accounts.vault, &ctx.accounts.margin_account)?;
+            let (margin_collateral, margin_debt) =
+                margin_totals(margin_account, ctx.remaining_accounts, None)?;
+            let margin_ratio_bps = calculate_collateral_ratio_bps(
+                margin_collateral,
+                margin_debt,
+                price,
+                native_decimals,
+            )?;
+            require!(
+                margin_ratio_bps < liquidation_threshold_bps,
+                GusdError::VaultNotLiquidatable
+            );
+            (margin_collateral, margin_debt)
+        } else {
+            (vault_collateral_amount, uncovered_debt)
+        };
+
+        let insured = ctx.accounts.vault.insured_until >= clock.unix_timestamp;
+        let (repay_amount, collateral_seized, protocol_fee) = liquidation_amounts(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            vault_collateral_amount,
+            uncovered_debt,
+            position,
+            price,
+            native_decimals,
+            insured,
+        )?;
+
+        // Burn GUSD from liquidator
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.gusd_mint.to_account_info(),
+            from: ctx.accounts.liquidator_gusd_account.to_account_info(),
+            authority: ctx.accounts.liquidator.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+
+        token_interface::burn(
+            CpiContext::new(cpi_program, cpi_accounts),
+            repay_amount,
+        )?;
+        record_supply(&mut ctx.accounts.supply_stats.burned_liquidations, repay_amount)?;
+
+        // Enforce the per-epoch liquidation cap (lifted in recovery mode); the cap and the
+        // recovery check are denominated in GOR
+        if native {
+            let protocol = &mut ctx.accounts.protocol_state;
+            let recovery_mode = is_recovery_mode(protocol, &ctx.accounts.collateral_config, price)?;
+            roll_outflow_epoch(protocol, clock.epoch);
+            let cap = protocol.epoch_liquidation_cap;
+            consume_epoch_cap(
+                &mut protocol.epoch_liquidated_collateral,
+                cap,
+                collateral_seized,
+                recovery_mode,
+            )?;
+        }
+
+        // The tip comes out of the liquidator's bonus: the payout above the repaid debt's value
+        let payout = collateral_seized - protocol_fee;
+        if tip_lamports > 0 {
+            require!(native, GusdError::UnsupportedCollateral);
+            let repaid_collateral = GusdUnits(repay_amount)
+                .to_usd()
+                .to_lamports(UsdValue6(price), native_decimals)?
+                .0;
+            let max_tip = (payout.saturating_sub(repaid_collateral) as u128)
+                .checked_mul(ctx.accounts.protocol_state.liquidation_tip_max_bps as u128)
+                .ok_or(GusdError::MathOverflow)?
+                / BPS_DENOMINATOR as u128;
+            require!(tip_lamports as u128 <= max_tip, GusdError::LiquidationTipTooLarge);
+        }
+        // A tip for an unverified holder stays in the vault for the holder to withdraw
+        let held_tip = if owner_verified { 0 } else { tip_lamports };
+        let collateral_removed = collateral_seized - held_tip; // tip <= payout <= seized
+
+        // Transfer collateral to the liquidator and any tip to the owner (PDA signed); the
+        // protocol fee stays in custody until swept to the treasury
+        let mut transfer_fee = 0;
+        if native {
+            let seeds = &[
+                SEED_NAMESPACE,
+                b"vault_collateral".as_ref(),
+                position_mint.as_ref(),
+                &[vault_collateral_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            for (to, lamports) in [
+                (ctx.accounts.liquidator.to_account_info(), payout - tip_lamports),
+                (ctx.accounts.vault_owner.to_account_info(), tip_lamports - held_tip),
+            ] {
+                if lamports == 0 {
+                    continue;
+                }
+                anchor_lang::system_program::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.vault_collateral.to_account_info(),
+                            to,
+                        },
+                        signer_seeds,
+                    ),
+                    lamports,
+                )?;
+            }
+
+            let protocol = &mut ctx.accounts.protocol_state;
+            protocol.total_collateral = protocol.total_collateral
+                .checked_sub(collateral_removed)
+                .ok_or(GusdError::MathOverflow)?;
+        } else {
+            let custody = token_custody(
+                ctx.accounts.collateral_mint.as_ref(),
+                ctx.accounts.vault_token_account.as_ref(),
+                ctx.accounts.collateral_token_program.as_ref(),
+            )?;
+            let liquidator_collateral_account = ctx.accounts.liquidator_collateral_account
+                .as_ref()
+                .ok_or(GusdError::MissingCollateralAccounts)?;
+            transfer_fee = transfer_vault_tokens(
+                custody,
+                &ctx.accounts.vault_collateral,
+                position_mint,
+                vault_collateral_bump,
+                liquidator_collateral_account.to_account_info(),
+                payout,
+            )?;
+
+            let config = &mut ctx.accounts.collateral_config;
+            config.total_collateral = config.total_collateral
+                .checked_sub(collateral_seized)
+                .ok_or(GusdError::MathOverflow)?;
+        }
+
+        // Update protocol totals
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_debt = protocol.total_debt
+            .checked_sub(repay_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        let principal_repaid = settle_unpaid_interest(&mut ctx.accounts.vault, repay_amount);
+        release_collateral_debt(&mut ctx.accounts.collateral_config, principal_repaid);
+
+        // Update vault
+        let vault = &mut ctx.accounts.vault;
+        vault.collateral_amount = vault.collateral_amount
+            .checked_sub(collateral_removed)
+            .ok_or(GusdError::MathOverflow)?;
+        vault.debt_amount = vault.debt_amount
+            .checked_sub(repay_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        vault.retained_liquidation_fees = vault.retained_liquidation_fees
+            .checked_add(protocol_fee)
+            .ok_or(GusdError::MathOverflow)?;
+        require_collateral_reserve(&ctx.accounts.vault_collateral, vault)?;
+        record_revenue(&mut ctx.accounts.revenue_stats.liquidation_fees, protocol_fee)?;
+
+        // Record whether this liquidation left the vault still below threshold
+        let remaining_uncovered = uncovered_debt - repay_amount; // repay <= uncovered_debt
+        let left_unhealthy = if remaining_uncovered > 0 {
+            let remaining_value_usd = calculate_usd_value(
+                vault.collateral_amount,
+                price,
+                native_decimals,
+            )?;
+            let remaining_ratio_bps = (remaining_value_usd as u128)
+                .checked_mul(BPS_DENOMINATOR as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(remaining_uncovered as u128)
+                .ok_or(GusdError::MathOverflow)?;
+            remaining_ratio_bps < liquidation_threshold_bps as u128
+        } else {
+            false
+        };
+        vault.last_liquidation_slot = current_slot;
+        vault.last_liquidation_left_unhealthy = left_unhealthy;
+        if !left_unhealthy {
+            vault.flagged_slot = 0;
+        }
+
+        msg!(
+            "Liquidation: repaid {} GUSD, seized {} collateral ({} protocol fee, {} owner tip, {} transfer fee). Remaining debt: {}, remaining collateral: {}",
+            repay_amount,
+            collateral_seized,
+            protocol_fee,
+            tip_lamports,
+            transfer_fee,
+            vault.debt_amount,
+            vault.collateral_amount
+        );
+
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            Some(ctx.accounts.liquidator.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
+
+        // [MEDIUM-3] Emit event
+        emit!(VaultLiquidated {
+            vault_owner: vault_owner_key,
+            liquidator: ctx.accounts.liquidator.key(),
+            debt_repaid: repay_amount,
+            collateral_seized,
+            protocol_fee,
+            owner_tip: tip_lamports,
+            transfer_fee,
+            tip_held: held_tip > 0,
+            position_mint: ctx.accounts.vault.position_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Liquidate an undercollateralized vault by seizing from one of its extra collateral
+    /// balances
+    /// The vault is below threshold once its primary collateral and balances, each divided
+    /// by its type's liquidation threshold, no longer cover the debt. The seized tokens pay
+    /// the balance type's liquidation penalty; the protocol fee goes straight to the
+    /// treasury's token account. The vault's balances are passed in `remaining_accounts`
+    /// as for `liquidate`.
+    pub fn liquidate_collateral_balance<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateCollateralBalance<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require_lifecycle(&ctx.accounts.protocol_state, GatedAction::Liquidate, clock.unix_timestamp)?;
+        require!(ctx.accounts.protocol_state.price_disputed_at == 0, GusdError::PriceDisputed);
+
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            clock.unix_timestamp,
+        );
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            clock.unix_timestamp,
+        )?;
+
+        let vault_owner_key = ctx.accounts.vault.owner;
+        let position_mint = ctx.accounts.vault.position_mint;
+        let collateral_bump = ctx.accounts.vault.collateral_bump;
+        let debt_amount = ctx.accounts.vault.debt_amount;
+        require!(debt_amount > 0, GusdError::NoDebtToLiquidate);
+
+        // Same-slot and priority rules as `liquidate`
+        if ctx.accounts.vault.last_liquidation_slot == clock.slot {
+            require!(
+                ctx.accounts.vault.last_liquidation_left_unhealthy,
+                GusdError::DuplicateLiquidationInSlot
+            );
+        }
+        require_priority_rights(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.vault,
+            ctx.accounts.liquidator.key(),
+            &clock,
+        )?;
+
+        // Debt the primary collateral and the other balances cover at their thresholds
+        let spot_price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Upper,
+            PriceOperation::Liquidation,
+        )?;
+        let primary_price = if ctx.accounts.vault.collateral_mint == NATIVE_COLLATERAL_MINT
+            && ctx.accounts.protocol_state.twap_for_liquidations
+        {
+            let twap = compute_twap(
+                &ctx.accounts.price_accumulator,
+                ctx.accounts.protocol_state.twap_window_secs,
+                clock.unix_timestamp,
+            )?;
+            let twap = apply_fx_rate(&ctx.accounts.protocol_state, twap)?;
+            apply_price_spread(&ctx.accounts.protocol_state, twap, PriceOperation::Liquidation)?
+        } else {
+            spot_price
+        };
+        let primary_value_usd = calculate_usd_value(
+            ctx.accounts.vault.collateral_amount,
+            primary_price,
+            ctx.accounts.price_feed.decimals,
+        )?;
+        let primary_capacity = (primary_value_usd as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(ctx.accounts.collateral_config.liquidation_threshold_bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .min(u64::MAX as u128) as u64;
+        let collateral_mint = ctx.accounts.collateral_balance.collateral_mint;
+        let other_capacity = balances_debt_capacity(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.deployment_config,
+            &ctx.accounts.vault,
+            ctx.remaining_accounts,
+            PriceOperation::Liquidation,
+            Some((collateral_mint, 0)),
+        )?;
+        let uncovered_debt =
+            debt_amount.saturating_sub(primary_capacity.saturating_add(other_capacity));
+        require!(uncovered_debt > 0, GusdError::VaultNotLiquidatable);
+
+        // This balance is judged against the rest
+        let price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.balance_price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.balance_price_update.as_deref(),
+            PriceBound::Upper,
+            PriceOperation::Liquidation,
+        )?;
+        let decimals = ctx.accounts.balance_price_feed.decimals;
+        let balance_amount = ctx.accounts.collateral_balance.amount;
+        let liquidation_threshold_bps =
+            ctx.accounts.balance_collateral_config.liquidation_threshold_bps;
+        let balance_ratio_bps = calculate_collateral_ratio_bps(
+            balance_amount,
+            uncovered_debt,
+            price,
+            decimals,
+        )?;
+        require!(
+            balance_ratio_bps < liquidation_threshold_bps,
+            GusdError::VaultNotLiquidatable
+        );
+
+        let insured = ctx.accounts.vault.insured_until >= clock.unix_timestamp;
+        let (repay_amount, collateral_seized, protocol_fee) = liquidation_amounts(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.balance_price_feed,
+            balance_amount,
+            uncovered_debt,
+            (balance_amount, uncovered_debt),
+            price,
+            decimals,
+            insured,
+        )?;
+
+        // Burn GUSD from liquidator
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_gusd_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            repay_amount,
+        )?;
+        record_supply(&mut ctx.accounts.supply_stats.burned_liquidations, repay_amount)?;
+
+        // Seized tokens to the liquidator, the protocol fee to the treasury (PDA signed)
+        let custody = (
+            &ctx.accounts.collateral_mint,
+            &ctx.accounts.balance_token_account,
+            &ctx.accounts.collateral_token_program,
+        );
+        let transfer_fee = transfer_vault_tokens(
+            custody,
+            &ctx.accounts.vault_collateral,
+            position_mint,
+            collateral_bump,
+            ctx.accounts.liquidator_collateral_account.to_account_info(),
+            collateral_seized - protocol_fee,
+        )?;
+        transfer_vault_tokens(
+            custody,
+            &ctx.accounts.vault_collateral,
+            position_mint,
+            collateral_bump,
+            ctx.accounts.treasury_collateral_account.to_account_info(),
+            protocol_fee,
+        )?;
+
+        let balance = &mut ctx.accounts.collateral_balance;
+        balance.amount = balance.amount
+            .checked_sub(collateral_seized)
+            .ok_or(GusdError::MathOverflow)?;
+        let balance_config = &mut ctx.accounts.balance_collateral_config;
+        balance_config.total_collateral = balance_config.total_collateral
+            .checked_sub(collateral_seized)
+            .ok_or(GusdError::MathOverflow)?;
+
+        // Update protocol totals; the debt is booked against the vault's primary type
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_debt = protocol.total_debt
+            .checked_sub(repay_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        let principal_repaid = settle_unpaid_interest(&mut ctx.accounts.vault, repay_amount);
+        release_collateral_debt(&mut ctx.accounts.collateral_config, principal_repaid);
+        record_revenue(&mut ctx.accounts.revenue_stats.liquidation_fees, protocol_fee)?;
+
+        // Update vault, recording whether it is still below threshold
+        let remaining_balance = ctx.accounts.collateral_balance.amount;
+        let remaining_uncovered = uncovered_debt - repay_amount; // repay <= uncovered_debt
+        let left_unhealthy = remaining_uncovered > 0
+            && calculate_collateral_ratio_bps(
+                remaining_balance,
+                remaining_uncovered,
+                price,
+                decimals,
+            )? < liquidation_threshold_bps;
+        let vault = &mut ctx.accounts.vault;
+        vault.debt_amount = vault.debt_amount
+            .checked_sub(repay_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        vault.last_liquidation_slot = clock.slot;
+        vault.last_liquidation_left_unhealthy = left_unhealthy;
+        if !left_unhealthy {
+            vault.flagged_slot = 0;
+        }
+
+        msg!(
+            "Liquidation: repaid {} GUSD, seized {} {} collateral ({} protocol fee, {} transfer fee). Remaining debt: {}, remaining balance: {}",
+            repay_amount,
+            collateral_seized,
+            collateral_mint,
+            protocol_fee,
+            transfer_fee,
+            vault.debt_amount,
+            remaining_balance
+        );
+
+        emit!(CollateralBalanceLiquidated {
+            vault_owner: vault_owner_key,
+            collateral_mint,
+            liquidator: ctx.accounts.liquidator.key(),
+            debt_repaid: repay_amount,
+            collateral_seized,
+            protocol_fee,
+            transfer_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Liquidate a margin account whose combined collateral ratio is below the liquidation
+    /// threshold, repaying debt of `debt_vault` with collateral seized from
+    /// `collateral_vault`. Member vaults are passed in `remaining_accounts`. When the
+    /// same vault holds both, use `liquidate`.
+    pub fn liquidate_margin<'info>(
+        ctx: Context<'_, '_, 'info, 'info, LiquidateMargin<'info>>,
+    ) -> Result<()> {
+        require_lifecycle(
+            &ctx.accounts.protocol_state,
+            GatedAction::Liquidate,
+            Clock::get()?.unix_timestamp,
+        )?;
+        require!(ctx.accounts.protocol_state.price_disputed_at == 0, GusdError::PriceDisputed);
+        require_keys_neq!(
+            ctx.accounts.debt_vault.key(),
+            ctx.accounts.collateral_vault.key(),
+            GusdError::MarginVaultsNotDistinct
+        );
+
+        let stake_discount_bps = vault_stake_discount_bps(
+            &ctx.accounts.debt_vault,
+            ctx.accounts.fee_tier_registry.as_deref(),
+            ctx.accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake)),
+            Clock::get()?.unix_timestamp,
+        );
+        accrue_vault_interest(
+            &mut ctx.accounts.protocol_state,
+            &mut ctx.accounts.price_feed,
+            &mut ctx.accounts.debt_vault,
+            &mut ctx.accounts.revenue_stats,
+            stake_discount_bps,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let spot_price = get_price(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Upper,
+            PriceOperation::Liquidation,
+        )?;
+        let clock = Clock::get()?;
+        require_priority_rights(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.debt_vault,
+            ctx.accounts.liquidator.key(),
+            &clock,
+        )?;
+        let price = if ctx.accounts.protocol_state.twap_for_liquidations {
+            let twap = compute_twap(
+                &ctx.accounts.price_accumulator,
+                ctx.accounts.protocol_state.twap_window_secs,
+                clock.unix_timestamp,
+            )?;
+            let twap = apply_fx_rate(&ctx.accounts.protocol_state, twap)?;
+            apply_price_spread(&ctx.accounts.protocol_state, twap, PriceOperation::Liquidation)?
+        } else {
+            spot_price
+        };
+        let native_decimals = ctx.accounts.price_feed.decimals;
+
+        let debt_amount = ctx.accounts.debt_vault.debt_amount;
+        require!(debt_amount > 0, GusdError::NoDebtToLiquidate);
+
+        let (margin_collateral, margin_debt) =
+            margin_totals(&ctx.accounts.margin_account, ctx.remaining_accounts, None)?;
+        let margin_ratio_bps = calculate_collateral_ratio_bps(
+            margin_collateral,
+            margin_debt,
+            price,
+            native_decimals,
+        )?;
+        require!(
+            margin_ratio_bps < ctx.accounts.collateral_config.liquidation_threshold_bps,
+            GusdError::VaultNotLiquidatable
+        );
+
+        let insured = ctx.accounts.debt_vault.insured_until >= clock.unix_timestamp;
+        let (repay_amount, collateral_seized, protocol_fee) = liquidation_amounts(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            ctx.accounts.collateral_vault.collateral_amount,
+            debt_amount,
+            (margin_collateral, margin_debt),
+            price,
+            native_decimals,
+            insured,
+        )?;
+
+        // Burn GUSD from liquidator
+        token_interface::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    from: ctx.accounts.liquidator_gusd_account.to_account_info(),
+                    authority: ctx.accounts.liquidator.to_account_info(),
+                },
+            ),
+            repay_amount,
+        )?;
+        record_supply(&mut ctx.accounts.supply_stats.burned_liquidations, repay_amount)?;
+
+        // Enforce the per-epoch liquidation cap (lifted in recovery mode)
+        {
+            let protocol = &mut ctx.accounts.protocol_state;
+            let recovery_mode = is_recovery_mode(protocol, &ctx.accounts.collateral_config, price)?;
+            roll_outflow_epoch(protocol, clock.epoch);
+            let cap = protocol.epoch_liquidation_cap;
+            consume_epoch_cap(
+                &mut protocol.epoch_liquidated_collateral,
+                cap,
+                collateral_seized,
+                recovery_mode,
+            )?;
+        }
+
+        // Transfer collateral to the liquidator (PDA signed); the protocol fee stays in
+        // the collateral PDA until swept to the treasury
+        let collateral_position_mint = ctx.accounts.collateral_vault.position_mint;
+        let seeds = &[
+            SEED_NAMESPACE,
+            b"vault_collateral".as_ref(),
+            collateral_position_mint.as_ref(),
+            &[ctx.accounts.collateral_vault.collateral_bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.vault_collateral.to_account_info(),
+                    to: ctx.accounts.liquidator.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            collateral_seized - protocol_fee,
+        )?;
+
+        // Update protocol totals
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.total_collateral = protocol.total_collateral
+            .checked_sub(collateral_seized)
+            .ok_or(GusdError::MathOverflow)?;
+        protocol.total_debt = protocol.total_debt
+            .checked_sub(repay_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        let principal_repaid = settle_unpaid_interest(&mut ctx.accounts.debt_vault, repay_amount);
+        release_collateral_debt(&mut ctx.accounts.collateral_config, principal_repaid);
+
+        // Update vaults
+        let debt_vault = &mut ctx.accounts.debt_vault;
+        debt_vault.debt_amount = debt_vault.debt_amount
+            .checked_sub(repay_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        let collateral_vault = &mut ctx.accounts.collateral_vault;
+        collateral_vault.collateral_amount = collateral_vault.collateral_amount
+            .checked_sub(collateral_seized)
+            .ok_or(GusdError::MathOverflow)?;
+        collateral_vault.retained_liquidation_fees = collateral_vault.retained_liquidation_fees
+            .checked_add(protocol_fee)
+            .ok_or(GusdError::MathOverflow)?;
+        require_collateral_reserve(&ctx.accounts.vault_collateral, collateral_vault)?;
+        record_revenue(&mut ctx.accounts.revenue_stats.liquidation_fees, protocol_fee)?;
+
+        msg!(
+            "Margin liquidation: repaid {} GUSD, seized {} GOR ({} protocol fee)",
+            repay_amount,
+            collateral_seized,
+            protocol_fee
+        );
+
+        credit_keeper(
+            ctx.accounts.keeper_account.as_deref_mut(),
+            Some(ctx.accounts.liquidator.key()),
+            ctx.accounts.instructions.as_ref(),
+        )?;
+
+        emit!(MarginLiquidated {
+            margin_account: ctx.accounts.margin_account.key(),
+            debt_vault: ctx.accounts.debt_vault.key(),
+            collateral_vault: ctx.accounts.collateral_vault.key(),
+            liquidator: ctx.accounts.liquidator.key(),
+            debt_repaid: repay_amount,
+            collateral_seized,
+            protocol_fee,
+        });
+
+        Ok(())
+    }
+
+    /// Get vault health metrics (view function)
+    /// The ratio and liquidatable flag follow `liquidate`: extra collateral balances (passed
+    /// in `remaining_accounts` as for `liquidate`) cover debt at their liquidation
+    /// thresholds, and the primary collateral is judged against the rest at its type's
+    /// threshold.
+    pub fn get_vault_health<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetVaultHealth<'info>>,
+    ) -> Result<VaultHealth> {
+        let vault = &ctx.accounts.vault;
+        let protocol = &ctx.accounts.protocol_state;
+        let price = get_price_with_fallback(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+
+        let collateral_value_usd = calculate_usd_value(
+            vault.collateral_amount,
+            price,
+            ctx.accounts.price_feed.decimals,
+        )?;
+
+        let collateral_ratio = uncovered_collateral_ratio_bps(
+            protocol,
+            &ctx.accounts.deployment_config,
+            vault,
+            ctx.remaining_accounts,
+            PriceOperation::Liquidation,
+            price,
+            ctx.accounts.price_feed.decimals,
+        )?;
+        let liquidation_threshold_bps = ctx.accounts.collateral_config.liquidation_threshold_bps;
+        let is_liquidatable = vault.debt_amount > 0 && collateral_ratio < liquidation_threshold_bps;
+
+        let repayment_streak = active_repayment_streak(vault, Clock::get()?.unix_timestamp);
+
+        let health = VaultHealth {
+            collateral_amount: vault.collateral_amount,
+            collateral_value_usd,
+            debt_amount: vault.debt_amount,
+            collateral_ratio_bps: collateral_ratio,
+            health_factor: health_factor(collateral_ratio, liquidation_threshold_bps),
+            is_liquidatable,
+            repayment_streak,
+            fee_rebate_bps: repayment_rebate_bps(repayment_streak),
+            liquidation_threshold_bps,
+        };
+
+        msg!("Vault Health:");
+        msg!("  Collateral: {} GOR (${:.2})", 
+            vault.collateral_amount as f64 / 10f64.powi(ctx.accounts.price_feed.decimals as i32),
+            collateral_value_usd as f64 / 1e6
+        );
+        msg!("  Debt: {} GUSD", vault.debt_amount as f64 / 1e6);
+        msg!("  Ratio: {}%", collateral_ratio as f64 / 100.0);
+        msg!("  Health factor: {}", health.health_factor as f64 / HEALTH_FACTOR_ONE as f64);
+        msg!("  Liquidatable: {}", is_liquidatable);
+
+        Ok(health)
+    }
+
+    /// Get combined health of a margin account (view function)
+    /// Member vaults are passed in `remaining_accounts`, in the account's order.
+    pub fn get_margin_health<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetMarginHealth<'info>>,
+    ) -> Result<MarginHealth> {
+        let protocol = &ctx.accounts.protocol_state;
+        let price = get_price_with_fallback(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let decimals = ctx.accounts.price_feed.decimals;
+
+        let (collateral_amount, debt_amount) =
+            margin_totals(&ctx.accounts.margin_account, ctx.remaining_accounts, None)?;
+        let collateral_value_usd = calculate_usd_value(collateral_amount, price, decimals)?;
+        let collateral_ratio_bps =
+            calculate_collateral_ratio_bps(collateral_amount, debt_amount, price, decimals)?;
+        let config = &ctx.accounts.collateral_config;
+
+        Ok(MarginHealth {
+            vault_count: ctx.accounts.margin_account.vaults.len() as u8,
+            collateral_amount,
+            collateral_value_usd,
+            debt_amount,
+            collateral_ratio_bps,
+            health_factor: health_factor(collateral_ratio_bps, config.liquidation_threshold_bps),
+            min_collateral_ratio_bps: margin_min_collateral_ratio_bps(protocol, config)?,
+            is_liquidatable: debt_amount > 0
+                && collateral_ratio_bps < config.liquidation_threshold_bps,
+        })
+    }
+
+    /// Get the vault's collateral ratio across a ±50% price range (view function)
+    /// Points are evenly spaced from spot -50% to spot +50%, ascending by price.
+    pub fn get_cr_ladder(ctx: Context<GetVaultHealth>) -> Result<Vec<CrLadderPoint>> {
+        let vault = &ctx.accounts.vault;
+        let protocol = &ctx.accounts.protocol_state;
+
+        let spot_price = get_price_with_fallback(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let step_bps = (CR_LADDER_RANGE_BPS * 2) / (CR_LADDER_POINTS - 1);
+        let mut ladder = Vec::with_capacity(CR_LADDER_POINTS as usize);
+
+        for i in 0..CR_LADDER_POINTS {
+            let multiplier_bps = BPS_DENOMINATOR - CR_LADDER_RANGE_BPS + i * step_bps;
+            let price_u128 = (spot_price as u128)
+                .checked_mul(multiplier_bps as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(GusdError::MathOverflow)?;
+            require!(price_u128 <= u64::MAX as u128, GusdError::MathOverflow);
+            let price = price_u128 as u64;
+
+            let collateral_ratio_bps = calculate_collateral_ratio_bps(
+                vault.collateral_amount,
+                vault.debt_amount,
+                price,
+                ctx.accounts.price_feed.decimals,
+            )?;
+
+            ladder.push(CrLadderPoint {
+                gor_price_usd: price,
+                collateral_ratio_bps,
+            });
+        }
+
+        Ok(ladder)
+    }
+
+    /// Get the time-weighted average price over the trailing window (view function)
+    pub fn get_twap(ctx: Context<GetTwap>, window_secs: u64) -> Result<u64> {
+        require!(
+            window_secs > 0 && window_secs <= MAX_TWAP_WINDOW_SECS,
+            GusdError::InvalidParameter
+        );
+        let now = Clock::get()?.unix_timestamp;
+        compute_twap(&ctx.accounts.price_accumulator, window_secs, now)
+    }
+
+    /// Get the accepted prices held in the accumulator ring buffer, oldest first (view function)
+    /// Lets liquidation bots and auditors check grace periods and disputed liquidations on-chain.
+    pub fn get_price_history(ctx: Context<GetTwap>) -> Result<Vec<PricePoint>> {
+        let accumulator = &ctx.accounts.price_accumulator;
+        let head = accumulator.head as usize;
+
+        Ok((1..=PRICE_OBSERVATION_COUNT)
+            .map(|offset| &accumulator.observations[(head + offset) % PRICE_OBSERVATION_COUNT])
+            .filter(|obs| obs.timestamp != 0)
+            .map(|obs| PricePoint {
+                timestamp: obs.timestamp,
+                price: obs.price,
+            })
+            .collect())
+    }
+
+    /// Get the GUSD market price and de-peg status (view function)
+    pub fn get_peg_status(ctx: Context<GetPegState>) -> Result<PegStatus> {
+        let peg_state = &ctx.accounts.peg_state;
+        Ok(PegStatus {
+            price: peg_state.price,
+            peg_currency: ctx.accounts.protocol_state.peg_currency,
+            deviation_bps: peg_deviation_bps(peg_state.price)?,
+            is_depegged: peg_state.depegged_since != 0,
+            depegged_since: peg_state.depegged_since,
+            updated_at: peg_state.updated_at,
+        })
+    }
+
+    /// Get the current base rate and the mint fee it implies for a collateral (view function)
+    pub fn get_fee_rates(ctx: Context<GetFeeRates>) -> Result<FeeRates> {
+        let protocol = &ctx.accounts.protocol_state;
+        let base_rate = decayed_base_rate(protocol, Clock::get()?.unix_timestamp);
+        Ok(FeeRates {
+            base_rate,
+            mint_fee_bps: fee_with_base_rate_bps(
+                base_rate,
+                ctx.accounts.price_feed.mint_fee_bps(protocol),
+                MAX_MINT_FEE_BPS,
+            ),
+        })
+    }
+
+    /// Get the pending governance notice and countdown for a vault (view function)
+    /// `vault_affected` is true when the vault holds collateral or debt and should act.
+    pub fn get_vault_notice(ctx: Context<GetVaultHealth>) -> Result<VaultNotice> {
+        let vault = &ctx.accounts.vault;
+        let now = Clock::get()?.unix_timestamp;
+
+        let notice = match ctx.accounts.protocol_state.pending_notice {
+            Some(notice) => VaultNotice {
+                action: Some(notice.action),
+                effective_at: notice.effective_at,
+                seconds_remaining: notice.effective_at.saturating_sub(now).max(0),
+                vault_affected: vault.collateral_amount > 0 || vault.debt_amount > 0,
+            },
+            None => VaultNotice {
+                action: None,
+                effective_at: 0,
+                seconds_remaining: 0,
+                vault_affected: false,
+            },
+        };
+
+        Ok(notice)
+    }
+
+    /// Get a hash committing to a vault's balances at the current slot (view function)
+    /// Off-chain position statements embed this hash and are signed by the vault owner,
+    /// so auditors can check both the signature and the balances against chain state.
+    pub fn get_vault_state_hash(ctx: Context<GetVaultHealth>) -> Result<VaultStateHash> {
+        let vault = &ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        let state_hash = hashv(&[
+            VAULT_STATE_DOMAIN,
+            crate::ID.as_ref(),
+            vault.key().as_ref(),
+            vault.owner.as_ref(),
+            &vault.collateral_amount.to_le_bytes(),
+            &vault.debt_amount.to_le_bytes(),
+            &clock.slot.to_le_bytes(),
+        ])
+        .to_bytes();
+
+        Ok(VaultStateHash {
+            vault: vault.key(),
+            owner: vault.owner,
+            collateral_amount: vault.collateral_amount,
+            debt_amount: vault.debt_amount,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp,
+            state_hash,
+        })
+    }
+
+    /// Record a vault's current health in its attestation PDA (permissionless)
+    /// The PDA (seeds `["health_attestation", vault]`) keeps the last 16 (slot, CR, flags)
+    /// records. Only this program can write it, so insurers and lenders can read it as a
+    /// canonical health oracle. The ratio is judged as in `get_vault_health`, with the
+    /// vault's balances in `remaining_accounts`; the below-min-CR flag uses the
+    /// balances' minimum collateral ratios and the type's own.
+    pub fn attest_health<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AttestHealth<'info>>,
+    ) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let protocol = &ctx.accounts.protocol_state;
+        let slot = Clock::get()?.slot;
+
+        let price = get_price_with_fallback(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let decimals = ctx.accounts.price_feed.decimals;
+        let collateral_ratio_bps = uncovered_collateral_ratio_bps(
+            protocol,
+            &ctx.accounts.deployment_config,
+            vault,
+            ctx.remaining_accounts,
+            PriceOperation::Liquidation,
+            price,
+            decimals,
+        )?;
+        let min_ratio_bps = uncovered_collateral_ratio_bps(
+            protocol,
+            &ctx.accounts.deployment_config,
+            vault,
+            ctx.remaining_accounts,
+            PriceOperation::Other,
+            price,
+            decimals,
+        )?;
+        let config = &ctx.accounts.collateral_config;
+
+        let mut flags = 0u8;
+        if vault.debt_amount > 0 && collateral_ratio_bps < config.liquidation_threshold_bps {
+            flags |= HEALTH_FLAG_LIQUIDATABLE;
+        }
+        if vault.debt_amount > 0 && min_ratio_bps < config.min_collateral_ratio_bps {
+            flags |= HEALTH_FLAG_BELOW_MIN_CR;
+        }
+        let liquidation_threshold_bps = config.liquidation_threshold_bps;
+        if protocol.price_disputed_at != 0 {
+            flags |= HEALTH_FLAG_PRICE_DISPUTED;
+        }
+
+        let vault_key = vault.key();
+        let attestation = &mut ctx.accounts.attestation;
+        if attestation.vault == Pubkey::default() {
+            attestation.vault = vault_key;
+            attestation.bump = ctx.bumps.attestation;
+        }
+
+        let head = attestation.head as usize;
+        require!(
+            attestation.records[head].slot < slot,
+            GusdError::HealthAlreadyAttested
+        );
+        let next = (head + 1) % HEALTH_ATTESTATION_COUNT;
+        attestation.records[next] = HealthRecord {
+            slot,
+            collateral_ratio_bps,
+            flags,
+        };
+        attestation.head = next as u8;
+
+        msg!(
+            "Health attested for vault {}: {} bps, flags {}",
+            vault_key,
+            collateral_ratio_bps,
+            flags
+        );
+
+        emit!(HealthAttested {
+            vault: vault_key,
+            slot,
+            collateral_ratio_bps,
+            health_factor: health_factor(collateral_ratio_bps, liquidation_threshold_bps),
+            flags,
+        });
+
+        Ok(())
+    }
+
+    /// Preview the impact of hypothetical risk parameters on a page of vaults (view function)
+    /// Vault accounts are passed in `remaining_accounts`; callers page through the vault set.
+    pub fn preview_parameter_change<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PreviewParameterChange<'info>>,
+        min_collateral_ratio_bps: u64,
+        liquidation_threshold_bps: u64,
+    ) -> Result<ParameterChangePreview> {
+        require!(
+            liquidation_threshold_bps >= BPS_DENOMINATOR
+                && liquidation_threshold_bps <= min_collateral_ratio_bps,
+            GusdError::InvalidParameter
+        );
+
+        let protocol = &ctx.accounts.protocol_state;
+        let price = get_price_with_fallback(
+            protocol,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+        let mut preview = ParameterChangePreview::default();
+
+        for account_info in ctx.remaining_accounts.iter() {
+            let vault = Account::<Vault>::try_from(account_info)?;
+            preview.vaults_scanned = preview.vaults_scanned
+                .checked_add(1)
+                .ok_or(GusdError::MathOverflow)?;
+
+            // Vaults of other collateral types aren't valued by this feed
+            if vault.debt_amount == 0 || vault.collateral_mint != ctx.accounts.price_feed.collateral_mint {
+                continue;
+            }
+
+            let ratio_bps = calculate_collateral_ratio_bps(
+                vault.collateral_amount,
+                vault.debt_amount,
+                price,
+                ctx.accounts.price_feed.decimals,
+            )?;
+
+            if ratio_bps < min_collateral_ratio_bps {
+                preview.vaults_below_min_cr += 1;
+                preview.debt_below_min_cr = preview.debt_below_min_cr
+                    .checked_add(vault.debt_amount)
+                    .ok_or(GusdError::MathOverflow)?;
+            }
+
+            if ratio_bps < liquidation_threshold_bps {
+                preview.vaults_liquidatable += 1;
+                preview.debt_liquidatable = preview.debt_liquidatable
+                    .checked_add(vault.debt_amount)
+                    .ok_or(GusdError::MathOverflow)?;
+
+                if ratio_bps >= LIQUIDATION_THRESHOLD_BPS {
+                    preview.newly_liquidatable_vaults += 1;
+                    preview.newly_liquidatable_debt = preview.newly_liquidatable_debt
+                        .checked_add(vault.debt_amount)
+                        .ok_or(GusdError::MathOverflow)?;
+                }
+            }
+        }
+
+        msg!(
+            "Preview: {} scanned, {} liquidatable ({} newly), {} below min CR",
+            preview.vaults_scanned,
+            preview.vaults_liquidatable,
+            preview.newly_liquidatable_vaults,
+            preview.vaults_below_min_cr
+        );
+
+        Ok(preview)
+    }
+
+    /// Check a page of vaults and flag the liquidatable ones, advancing the shared scan
+    /// cursor (permissionless)
+    /// Vaults are passed in `remaining_accounts` in ascending key order, all after the
+    /// cursor's `last_vault`, so cooperating keepers pick up where the last page ended
+    /// instead of rescanning. Each vault is followed by its extra collateral balances'
+    /// accounts, as for `liquidate`, and is flagged when `liquidate` would accept it.
+    /// `restart` begins a new sweep from the lowest key.
+    pub fn check_and_flag_page<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CheckAndFlagPage<'info>>,
+        restart: bool,
+    ) -> Result<()> {
+        require!(!ctx.remaining_accounts.is_empty(), GusdError::InvalidParameter);
+
+        let price = get_price_with_fallback(
+            &ctx.accounts.protocol_state,
+            &ctx.accounts.price_feed,
+            &ctx.accounts.deployment_config,
+            ctx.accounts.price_update.as_deref(),
+            PriceBound::Mid,
+            PriceOperation::Other,
+        )?;
+
+        let cursor = &mut ctx.accounts.scan_cursor;
+        if cursor.bump == 0 {
+            cursor.bump = ctx.bumps.scan_cursor;
+        }
+        if restart {
+            cursor.last_vault = Pubkey::default();
+            cursor.sweep = cursor.sweep.checked_add(1).ok_or(GusdError::MathOverflow)?;
+        }
+
+        let slot = Clock::get()?.slot;
+        let liquidation_threshold_bps = ctx.accounts.collateral_config.liquidation_threshold_bps;
+        let mut scanned = 0u32;
+        let mut flagged = 0u32;
+        let mut index = 0;
+        while index < ctx.remaining_accounts.len() {
+            let account_info = &ctx.remaining_accounts[index];
+            require!(account_info.key() > cursor.last_vault, GusdError::ScanOutOfOrder);
+            cursor.last_vault = account_info.key();
+
+            let mut vault = Account::<Vault>::try_from(account_info)?;
+            let balances_end = index + 1 + vault.collateral_balances.len() * 4;
+            require!(
+                balances_end <= ctx.remaining_accounts.len(),
+                GusdError::CollateralBalancesMismatch
+            );
+            let balances = &ctx.remaining_accounts[index + 1..balances_end];
+            index = balances_end;
+            scanned += 1;
+            require!(scanned as usize <= MAX_SCAN_PAGE_VAULTS, GusdError::InvalidParameter);
+
+            if vault.collateral_mint != ctx.accounts.price_feed.collateral_mint {
+                continue;
+            }
+            let collateral_ratio_bps = uncovered_collateral_ratio_bps(
+                &ctx.accounts.protocol_state,
+                &ctx.accounts.deployment_config,
+                &vault,
+                balances,
+                PriceOperation::Liquidation,
+                price,
+                ctx.accounts.price_feed.decimals,
+            )?;
+            let unhealthy =
+                vault.debt_amount > 0 && collateral_ratio_bps < liquidation_threshold_bps;
+
+            // Writable vaults record when they were first flagged, which starts the
+            // priority liquidation window
+            let flagged_slot = match (unhealthy, vault.flagged_slot) {
+                (false, _) => 0,
+                (true, 0) => slot,
+                (true, since) => since,
+            };
+            if account_info.is_writable && flagged_slot != vault.flagged_slot {
+                vault.flagged_slot = flagged_slot;
+                vault.exit(&crate::ID)?;
+            }
+
+            if unhealthy {
+                flagged += 1;
+                emit!(VaultFlagged {
+                    vault: account_info.key(),
+                    owner: vault.owner,
+                    collateral_ratio_bps,
+                    health_factor: health_factor(collateral_ratio_bps, liquidation_threshold_bps),
+                    debt_amount: vault.debt_amount,
+                });
+            }
+        }
+        cursor.last_slot = slot;
+
+        msg!(
+            "Scanned {} vaults in sweep {}, {} flagged; cursor at {}",
+            scanned,
+            cursor.sweep,
+            flagged,
+            cursor.last_vault
+        );
+
+        emit!(ScanPageCompleted {
+            keeper: ctx.accounts.keeper.key(),
+            sweep: cursor.sweep,
+            last_vault: cursor.last_vault,
+            vaults_scanned: scanned,
+            vaults_flagged: flagged,
+            slot: cursor.last_slot,
+        });
+
+        Ok(())
+    }
+
+    /// Open the first priority liquidation rights auction (admin only)
+    /// Later rounds follow from `settle_priority_auction`, so this runs once.
+    pub fn start_priority_auctions(ctx: Context<StartPriorityAuctions>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &mut ctx.accounts.liquidation_auction;
+        auction.round = 1;
+        auction.bidding_ends_at = now + PRIORITY_AUCTION_BIDDING_SECS;
+        auction.leader = Pubkey::default();
+        auction.leading_bid = 0;
+        auction.bump = ctx.bumps.liquidation_auction;
+
+        msg!("Priority liquidation auction round 1 open until {}", auction.bidding_ends_at);
+
+        emit!(PriorityAuctionOpened {
+            round: auction.round,
+            bidding_ends_at: auction.bidding_ends_at,
+        });
+
+        Ok(())
+    }
+
+    /// Bid GUSD for the next priority liquidation rights period
+    /// The bid is escrowed; an outbid leader is refunded to `previous_leader_gusd_account`.
+    pub fn bid_priority_rights(ctx: Context<BidPriorityRights>, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.liquidation_auction;
+        require!(now < auction.bidding_ends_at, GusdError::AuctionBiddingClosed);
+
+        let min_bid = auction.leading_bid
+            .checked_mul(BPS_DENOMINATOR + MIN_PRIORITY_BID_INCREMENT_BPS)
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(BPS_DENOMINATOR)
+            .max(1);
+        require!(amount >= min_bid, GusdError::BidTooLow);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.bidder_gusd_account.to_account_info(),
+                    mint: ctx.accounts.gusd_mint.to_account_info(),
+                    to: ctx.accounts.auction_escrow.to_account_info(),
+                    authority: ctx.accounts.bidder.to_account_info(),
+                },
+            ),
+            amount,
+            GUSD_DECIMALS,
+        )?;
+
+        // Refund the outbid leader
+        if auction.leader != Pubkey::default() {
+            let refund_account = ctx.accounts.previous_leader_gusd_account
+                .as_ref()
+                .ok_or(GusdError::InvalidRefundAccount)?;
+            require_keys_eq!(refund_account.owner, auction.leader, GusdError::InvalidRefundAccount);
+
+            let seeds = &[b"liquidation_auction".as_ref(), &[auction.bump]];
+            let signer_seeds = &[&seeds[..]];
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.auction_escrow.to_account_info(),
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        to: refund_account.to_account_info(),
+                        authority: ctx.accounts.liquidation_auction.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                auction.leading_bid,
+                GUSD_DECIMALS,
+            )?;
+        }
+
+        let auction = &mut ctx.accounts.liquidation_auction;
+        auction.leader = ctx.accounts.bidder.key();
+        auction.leading_bid = amount;
+
+        msg!("Priority rights bid: {} GUSD by {} in round {}", amount, auction.leader, auction.round);
+
+        emit!(PriorityBidPlaced {
+            round: auction.round,
+            bidder: auction.leader,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Close the bidding round, award the rights, and open the next round (permissionless)
+    /// The winning bid goes to the insurance fund. The winner holds the rights for
+    /// `PRIORITY_RIGHTS_SECS` from the close of bidding, and bidding for the period after
+    /// that runs until they expire.
+    pub fn settle_priority_auction(ctx: Context<SettlePriorityAuction>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let auction = &ctx.accounts.liquidation_auction;
+        require!(now >= auction.bidding_ends_at, GusdError::AuctionBiddingOpen);
+
+        let winner = auction.leader;
+        let winning_bid = auction.leading_bid;
+        if winning_bid > 0 {
+            let seeds = &[b"liquidation_auction".as_ref(), &[auction.bump]];
+            let signer_seeds = &[&seeds[..]];
+            token_interface::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.auction_escrow.to_account_info(),
+                        mint: ctx.accounts.gusd_mint.to_account_info(),
+                        to: ctx.accounts.insurance_fund.to_account_info(),
+                        authority: ctx.accounts.liquidation_auction.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                winning_bid,
+                GUSD_DECIMALS,
+            )?;
+        }
+
+        let rights_until = auction.bidding_ends_at
+            .checked_add(PRIORITY_RIGHTS_SECS)
+            .ok_or(GusdError::MathOverflow)?;
+        let protocol = &mut ctx.accounts.protocol_state;
+        protocol.priority_liquidator = winner;
+        protocol.priority_rights_until = rights_until;
+
+        let auction = &mut ctx.accounts.liquidation_auction;
+        let round = auction.round;
+        auction.round = round.checked_add(1).ok_or(GusdError::MathOverflow)?;
+        auction.bidding_ends_at = rights_until;
+        auction.leader = Pubkey::default();
+        auction.leading_bid = 0;
+
+        msg!(
+            "Priority auction round {} settled: {} won with {} GUSD, rights until {}",
+            round,
+            winner,
+            winning_bid,
+            rights_until
+        );
+
+        emit!(PriorityAuctionSettled {
+            round,
+            winner,
+            winning_bid,
+            rights_until,
+        });
+        emit!(PriorityAuctionOpened {
+            round: auction.round,
+            bidding_ends_at: auction.bidding_ends_at,
+        });
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// Validate and set one risk parameter, returning its old value
+fn apply_risk_parameter(
+    protocol: &mut ProtocolState,
+    parameter: RiskParameter,
+    value: u64,
+) -> Result<u64> {
+    // Interest up to now accrues at the old rate
+    if matches!(
+        parameter,
+        RiskParameter::StabilityFeeBps
+            | RiskParameter::RateTargetDebt
+            | RiskParameter::RateBaseBps
+            | RiskParameter::RateSlope1Bps
+            | RiskParameter::RateSlope2Bps
+            | RiskParameter::RateKinkBps
+    ) {
+        accrue_debt_index(protocol, Clock::get()?.unix_timestamp)?;
+    }
+
+    let old_value = match parameter {
+        RiskParameter::MinLiquidationProfitBps => {
+            // Liquidators can never earn more than their bonus
+            require!(value <= protocol.liquidation_bonus_bps, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.min_liquidation_profit_bps, value)
+        }
+        RiskParameter::TwapWindowSecs => {
+            // 0 = spot pricing for mints
+            require!(value <= MAX_TWAP_WINDOW_SECS, GusdError::InvalidParameter);
+            require!(
+                value > 0 || !protocol.twap_for_liquidations,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.twap_window_secs, value)
+        }
+        RiskParameter::TwapForLiquidations => {
+            require!(value <= 1, GusdError::InvalidParameter);
+            require!(
+                value == 0 || protocol.twap_window_secs > 0,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.twap_for_liquidations, value == 1) as u64
+        }
+        RiskParameter::MaxPriceAgeSecs => {
+            require!(
+                value > 0 && value <= MAX_PRICE_AGE_LIMIT_SECS,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.max_price_age_secs, value)
+        }
+        RiskParameter::MintMaxPriceAgeSecs => {
+            // 0 = use MaxPriceAgeSecs
+            require!(value <= MAX_PRICE_AGE_LIMIT_SECS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.mint_max_price_age_secs, value)
+        }
+        RiskParameter::WithdrawMaxPriceAgeSecs => {
+            require!(value <= MAX_PRICE_AGE_LIMIT_SECS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.withdraw_max_price_age_secs, value)
+        }
+        RiskParameter::LiquidationMaxPriceAgeSecs => {
+            require!(value <= MAX_PRICE_AGE_LIMIT_SECS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.liquidation_max_price_age_secs, value)
+        }
+        RiskParameter::StalePriceHaircutBps => {
+            // 0 = reject mints/withdrawals while the oracle is stale
+            require!(value <= MAX_STALE_PRICE_HAIRCUT_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.stale_price_haircut_bps, value)
+        }
+        RiskParameter::InsurancePremiumBps => {
+            // 0 = insurance unavailable
+            require!(value <= MAX_INSURANCE_PREMIUM_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.insurance_premium_bps, value)
+        }
+        RiskParameter::InsuranceDiscountBps => {
+            require!(value <= MAX_INSURANCE_DISCOUNT_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.insurance_discount_bps, value)
+        }
+        RiskParameter::CircuitBreakerDeviationBps => {
+            // 0 = circuit breaker disabled
+            require!(value <= BPS_DENOMINATOR, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.circuit_breaker_deviation_bps, value)
+        }
+        RiskParameter::MaxConfidenceBps => {
+            require!(
+                value > 0 && value <= MAX_CONFIDENCE_LIMIT_BPS,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.max_confidence_bps, value)
+        }
+        RiskParameter::MarginBufferBps => {
+            require!(value <= MAX_MARGIN_BUFFER_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.margin_buffer_bps, value)
+        }
+        RiskParameter::PriceSpreadBps => {
+            // 0 = one price for every operation
+            require!(value <= MAX_PRICE_SPREAD_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.price_spread_bps, value)
+        }
+        RiskParameter::LiquidationTargetCrBps => {
+            // 0 = no target; must leave the vault above the liquidation threshold
+            require!(
+                value == 0
+                    || (value > LIQUIDATION_THRESHOLD_BPS
+                        && value <= MAX_LIQUIDATION_TARGET_CR_BPS),
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.liquidation_target_cr_bps, value)
+        }
+        RiskParameter::MintFeeBps => {
+            require!(value <= MAX_MINT_FEE_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.mint_fee_bps, value)
+        }
+        RiskParameter::SurplusBufferCap => {
+            let old_cap = std::mem::replace(&mut protocol.surplus_buffer_cap, value);
+            // Lowering the cap releases the excess right away
+            book_fee_revenue(protocol, 0)?;
+            old_cap
+        }
+        RiskParameter::PriorityWindowSlots => {
+            require!(value <= MAX_PRIORITY_WINDOW_SLOTS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.priority_window_slots, value)
+        }
+        RiskParameter::LiquidationBonusBps => {
+            require!(
+                value > 0
+                    && value >= protocol.min_liquidation_profit_bps
+                    && value.saturating_add(protocol.liquidation_fee_bps)
+                        <= MAX_LIQUIDATION_PENALTY_BPS,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.liquidation_bonus_bps, value)
+        }
+        RiskParameter::LiquidationFeeBps => {
+            require!(
+                value.saturating_add(protocol.liquidation_bonus_bps)
+                    <= MAX_LIQUIDATION_PENALTY_BPS,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.liquidation_fee_bps, value)
+        }
+        RiskParameter::WithdrawFeeBps => {
+            require!(value <= MAX_WITHDRAW_FEE_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.withdraw_fee_bps, value)
+        }
+        RiskParameter::StabilityFeeBps => {
+            require!(value <= MAX_STABILITY_FEE_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.stability_fee_bps, value)
+        }
+        RiskParameter::RateTargetDebt => {
+            std::mem::replace(&mut protocol.rate_target_debt, value)
+        }
+        RiskParameter::RateBaseBps => {
+            require!(value <= MAX_STABILITY_FEE_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.rate_base_bps, value)
+        }
+        RiskParameter::RateSlope1Bps => {
+            require!(value <= MAX_STABILITY_FEE_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.rate_slope1_bps, value)
+        }
+        RiskParameter::RateSlope2Bps => {
+            require!(value <= MAX_RATE_SLOPE2_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.rate_slope2_bps, value)
+        }
+        RiskParameter::RateKinkBps => {
+            require!(
+                value > 0 && value < BPS_DENOMINATOR,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.rate_kink_bps, value)
+        }
+        RiskParameter::MaxPauseSecs => {
+            require!(
+                value == 0 || (MIN_MAX_PAUSE_SECS..=MAX_MAX_PAUSE_SECS).contains(&value),
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.max_pause_secs, value)
+        }
+        RiskParameter::SavingsFeeShareBps => {
+            require!(
+                value.saturating_add(protocol.insurance_fee_share_bps) <= BPS_DENOMINATOR,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.savings_fee_share_bps, value)
+        }
+        RiskParameter::RateLockPremiumBps => {
+            require!(value <= MAX_RATE_LOCK_PREMIUM_BPS, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.rate_lock_premium_bps, value)
+        }
+        RiskParameter::BaseRateMintWeightBps => {
+            require!(value <= BPS_DENOMINATOR, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.base_rate_mint_weight_bps, value)
+        }
+        RiskParameter::MintFeeUpfront => {
+            require!(value <= 1, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.mint_fee_upfront, value == 1) as u64
+        }
+        RiskParameter::StabilityFeeUpfront => {
+            require!(value <= 1, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.stability_fee_upfront, value == 1) as u64
+        }
+        RiskParameter::LiquidationTipMaxBps => {
+            require!(value <= BPS_DENOMINATOR, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.liquidation_tip_max_bps, value)
+        }
+        RiskParameter::KeeperRebateEpochCap => {
+            std::mem::replace(&mut protocol.keeper_rebate_epoch_cap, value)
+        }
+        RiskParameter::MaxSupply => {
+            require!(value <= MAX_GUSD_SUPPLY_LIMIT, GusdError::InvalidParameter);
+            std::mem::replace(&mut protocol.max_supply, value)
+        }
+        RiskParameter::MinVaultDebt => {
+            require!(value <= MAX_MIN_VAULT_DEBT, GusdError::InvalidParameter);
+            require!(
+                protocol.max_vault_debt == 0 || value <= protocol.max_vault_debt,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.min_vault_debt, value)
+        }
+        RiskParameter::MaxVaultDebt => {
+            // A cap under the dust floor would leave no valid vault size
+            require!(
+                value == 0 || value >= protocol.min_vault_debt,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.max_vault_debt, value)
+        }
+        RiskParameter::InsuranceFeeShareBps => {
+            require!(
+                value.saturating_add(protocol.savings_fee_share_bps) <= BPS_DENOMINATOR,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.insurance_fee_share_bps, value)
+        }
+        RiskParameter::DepegThresholdBps => {
+            require!(
+                value > 0 && value <= MAX_DEPEG_THRESHOLD_BPS,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.depeg_threshold_bps, value)
+        }
+        RiskParameter::PriceSlewBpsPerSec => {
+            require!(
+                value > 0 && value <= MAX_PRICE_SLEW_BPS_PER_SEC,
+                GusdError::InvalidParameter
+            );
+            std::mem::replace(&mut protocol.price_slew_bps_per_sec, value)
+        }
+        // Logged by their own instructions, which validate them together
+        RiskParameter::CollateralMinRatioBps
+        | RiskParameter::CollateralLiquidationThresholdBps
+        | RiskParameter::CollateralDebtCeiling
+        | RiskParameter::CollateralEnabled
+        | RiskParameter::CollateralStabilityFeePremiumBps
+        | RiskParameter::CollateralMintFeeBps
+        | RiskParameter::CollateralLiquidationBonusBps
+        | RiskParameter::CollateralLiquidationFeeBps
+        | RiskParameter::EpochLiquidationCap
+        | RiskParameter::EpochRedemptionCap
+        | RiskParameter::DebtCeiling
+        | RiskParameter::LaunchRampStepBps
+        | RiskParameter::LaunchRampTarget
+        | RiskParameter::PriceMode
+        | RiskParameter::FeeTierMinStake(_)
+        | RiskParameter::FeeTierDiscountBps(_)
+        | RiskParameter::OracleKind => return err!(GusdError::InvalidParameter),
+    };
+    Ok(old_value)
+}
+
+/// Append a parameter change to its changelog page and announce it
+fn log_parameter_change(
+    protocol: &mut ProtocolState,
+    page: &mut ParameterLogPage,
+    bump: u8,
+    change: ParameterChange,
+) -> Result<()> {
+    let index = protocol.parameter_log_entries;
+    protocol.parameter_log_entries = index.checked_add(1).ok_or(GusdError::MathOverflow)?;
+
+    if page.entries.is_empty() {
+        page.page = index / PARAMETER_LOG_PAGE_SIZE as u64;
+        page.bump = bump;
+    }
+    page.entries.push(change);
+
+    msg!(
+        "Risk parameter {:?} updated: {} -> {}",
+        change.parameter,
+        change.old_value,
+        change.new_value
+    );
+
+    emit!(RiskParameterUpdated {
+        parameter: change.parameter,
+        old_value: change.old_value,
+        new_value: change.new_value,
+        target: change.target,
+    });
+
+    Ok(())
+}
+
+/// Append the changes one instruction made to the changelog, continuing on the next page
+/// when they cross a page boundary
+fn log_parameter_changes(
+    protocol: &mut ProtocolState,
+    (page, page_bump): (&mut ParameterLogPage, u8),
+    (next_page, next_page_bump): (&mut ParameterLogPage, u8),
+    changes: &[ParameterChange],
+) -> Result<()> {
+    require!(changes.len() <= PARAMETER_LOG_PAGE_SIZE, GusdError::InvalidParameter);
+    let first_page = protocol.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64;
+    for &change in changes {
+        if protocol.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64 == first_page {
+            log_parameter_change(protocol, page, page_bump, change)?;
+        } else {
+            log_parameter_change(protocol, next_page, next_page_bump, change)?;
+        }
+    }
+    Ok(())
+}
+
+/// Changelog entries for the `(parameter, old, new)` values that changed, made by `actor`
+/// to the parameters of `target` (default = protocol-wide)
+fn changed_parameters(
+    target: Pubkey,
+    actor: Pubkey,
+    values: &[(RiskParameter, u64, u64)],
+) -> Result<Vec<ParameterChange>> {
+    let slot = Clock::get()?.slot;
+    Ok(values
+        .iter()
+        .filter(|(_, old_value, new_value)| old_value != new_value)
+        .map(|&(parameter, old_value, new_value)| ParameterChange {
+            parameter,
+            old_value,
+            new_value,
+            actor,
+            slot,
+            target,
+        })
+        .collect())
+}
+
+/// Whether `owner` owns the vault: holds its position NFT in `position`, or for a vault
+/// without one, is the key that created it
+fn is_vault_owner(
+    vault: &Vault,
+    owner: &AccountInfo,
+    position: Option<&InterfaceAccount<TokenAccount>>,
+) -> bool {
+    if vault.position_mint == Pubkey::default() {
+        return vault.owner == owner.key();
+    }
+    position.is_some_and(|position| {
+        position.mint == vault.position_mint && position.owner == owner.key() && position.amount == 1
+    })
+}
+
+/// Require `collateral_amount` of a feed's collateral to be worth at most
+/// `ABANDONED_VAULT_DUST_USD` at the upper-bound price
+fn require_dust_collateral(
+    protocol: &ProtocolState,
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+    collateral_amount: u64,
+) -> Result<()> {
+    let price = get_price(
+        protocol,
+        feed,
+        deployment,
+        price_update,
+        PriceBound::Upper,
+        PriceOperation::Other,
+    )?;
+    let collateral_value_usd = calculate_usd_value(collateral_amount, price, feed.decimals)?;
+    require!(
+        collateral_value_usd <= ABANDONED_VAULT_DUST_USD,
+        GusdError::VaultNotAbandoned
+    );
+    Ok(())
+}
+
+/// Retire a closing vault's position NFT so it can't pass for a live position: burned, and
+/// its token account closed to the holder, when `signing_holder` is given; frozen otherwise.
+/// Returns whether it was burned. Vaults without a position NFT, or whose NFT the holder
+/// already burned, need no position account.
+fn retire_position_nft<'info>(
+    vault: &Vault,
+    position: Option<&InterfaceAccount<'info, TokenAccount>>,
+    mint: Option<&InterfaceAccount<'info, Mint>>,
+    token_program: Option<&Interface<'info, TokenInterface>>,
+    protocol_state: Option<&Account<'info, ProtocolState>>,
+    signing_holder: Option<&AccountInfo<'info>>,
+) -> Result<bool> {
+    if vault.position_mint == Pubkey::default() {
+        return Ok(false);
+    }
+    let (Some(mint), Some(token_program)) = (mint, token_program) else {
+        return err!(GusdError::PositionAccountsRequired);
+    };
+    if mint.supply == 0 {
+        return Ok(false);
+    }
+    let Some(position) = position else {
+        return err!(GusdError::PositionAccountsRequired);
+    };
+    require!(
+        position.mint == vault.position_mint && position.amount == 1,
+        GusdError::PositionAccountsRequired
+    );
+
+    if let Some(holder) = signing_holder {
+        token_interface::burn(
+            CpiContext::new(
+                token_program.to_account_info(),
+                Burn {
+                    mint: mint.to_account_info(),
+                    from: position.to_account_info(),
+                    authority: holder.clone(),
+                },
+            ),
+            1,
+        )?;
+        token_interface::close_account(CpiContext::new(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: position.to_account_info(),
+                destination: holder.clone(),
+                authority: holder.clone(),
+            },
+        ))?;
+        return Ok(true);
+    }
+
+    let protocol_state = protocol_state.ok_or(GusdError::PositionAccountsRequired)?;
+    let seeds = &[SEED_NAMESPACE, b"protocol".as_ref(), &[protocol_state.bump]];
+    token_interface::freeze_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        FreezeAccount {
+            account: position.to_account_info(),
+            mint: mint.to_account_info(),
+            authority: protocol_state.to_account_info(),
+        },
+        &[&seeds[..]],
+    ))?;
+    Ok(false)
+}
+
+/// Whether the owner signed, or `delegate` is the delegate they approved with at least `scope`
+fn vault_authorized(
+    vault: &Vault,
+    owner: &AccountInfo,
+    delegate: Option<&Signer>,
+    scope: DelegateScope,
+) -> bool {
+    owner.is_signer
+        || delegate.is_some_and(|delegate| {
+            delegate.key() == vault.delegate
+                && vault.delegated_by == owner.key()
+                && vault.delegate_scope >= scope
+        })
+}
+
+/// The account signing for a vault: the owner, or else their delegate
+fn vault_signer<'info>(
+    owner: &UncheckedAccount<'info>,
+    delegate: &Option<Signer<'info>>,
+) -> AccountInfo<'info> {
+    match delegate {
+        Some(delegate) if !owner.is_signer => delegate.to_account_info(),
+        _ => owner.to_account_info(),
+    }
+}
+
+/// Burn up to `amount` of GUSD from `from`, signed by `authority`, against the vault's debt
+/// Accrues interest first and caps the repayment at the debt; unpaid interest is settled
+/// before principal. Returns the amount repaid.
+#[allow(clippy::too_many_arguments)]
+fn repay_vault_debt<'info>(
+    protocol: &mut ProtocolState,
+    feed: &mut PriceFeed,
+    vault: &mut Vault,
+    revenue_stats: &mut RevenueStats,
+    supply_stats: &mut SupplyStats,
+    config: &mut CollateralConfig,
+    gusd_mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    stake_discount_bps: u64,
+    amount: u64,
+    now: i64,
+) -> Result<u64> {
+    accrue_vault_interest(protocol, feed, vault, revenue_stats, stake_discount_bps, now)?;
+
+    // Can't repay more than owed
+    let repay_amount = amount.min(vault.debt_amount);
+    // Repay in full or leave at least the dust floor
+    let remaining_debt = vault.debt_amount - repay_amount;
+    require!(
+        remaining_debt == 0 || remaining_debt >= protocol.min_vault_debt,
+        GusdError::VaultDebtBelowMinimum
+    );
+
+    token_interface::burn(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Burn {
+                mint: gusd_mint.to_account_info(),
+                from: from.to_account_info(),
+                authority,
+            },
+        ),
+        repay_amount,
+    )?;
+    record_supply(&mut supply_stats.burned_repayments, repay_amount)?;
+
+    // Update vault debt
+    vault.debt_amount = vault.debt_amount.checked_sub(repay_amount)
+        .ok_or(GusdError::MathOverflow)?;
+    let principal_repaid = settle_unpaid_interest(vault, repay_amount);
+    if repay_amount > 0 {
+        record_repayment(vault, now);
+    }
+
+    // Update protocol totals
+    protocol.total_debt = protocol.total_debt.checked_sub(repay_amount)
+        .ok_or(GusdError::MathOverflow)?;
+    release_collateral_debt(config, principal_repaid);
+
+    Ok(repay_amount)
+}
+
+/// Move `amount` of the vault's collateral into custody, signed by `authority`, and credit
+/// what arrives to the vault and its collateral totals
+/// GOR comes from `authority` itself, tokens from `from`. Returns the collateral received:
+/// a Token-2022 transfer fee is withheld from the amount sent.
+#[allow(clippy::too_many_arguments)]
+fn deposit_to_vault<'info>(
+    vault: &mut Vault,
+    vault_collateral: &AccountInfo<'info>,
+    protocol: &mut ProtocolState,
+    config: &mut CollateralConfig,
+    collateral_mint: Option<&InterfaceAccount<'info, Mint>>,
+    vault_token_account: Option<&mut InterfaceAccount<'info, TokenAccount>>,
+    from: Option<&InterfaceAccount<'info, TokenAccount>>,
+    collateral_token_program: Option<&Interface<'info, TokenInterface>>,
+    authority: AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<u64> {
+    let received = if vault.collateral_mint == NATIVE_COLLATERAL_MINT {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: authority,
+                    to: vault_collateral.clone(),
+                },
+            ),
+            amount,
+        )?;
+
+        protocol.total_collateral = protocol.total_collateral.checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+        amount
+    } else {
+        let (Some(mint), Some(vault_token_account), Some(from), Some(token_program)) =
+            (collateral_mint, vault_token_account, from, collateral_token_program)
+        else {
+            return err!(GusdError::MissingCollateralAccounts);
+        };
+        let balance_before = vault_token_account.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                token_program.to_account_info(),
+                TransferChecked {
+                    from: from.to_account_info(),
+                    mint: mint.to_account_info(),
+                    to: vault_token_account.to_account_info(),
+                    authority,
+                },
+            ),
+            amount,
+            mint.decimals,
+        )?;
+
+        vault_token_account.reload()?;
+        let received = vault_token_account.amount
+            .checked_sub(balance_before)
+            .ok_or(GusdError::MathOverflow)?;
+        require!(received > 0, GusdError::InvalidAmount);
+
+        config.total_collateral = config.total_collateral.checked_add(received)
+            .ok_or(GusdError::MathOverflow)?;
+        received
+    };
+
+    vault.collateral_amount = vault.collateral_amount.checked_add(received)
+        .ok_or(GusdError::MathOverflow)?;
+    Ok(received)
+}
+
+/// Mint `amount` GUSD to `destination` against the vault in `accounts`, enforcing the same
+/// pause, price, fee, debt ceiling, and collateral checks as `mint_gusd`
+fn mint_against_vault<'info>(
+    accounts: &mut MintGusd<'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    amount: u64,
+    destination: AccountInfo<'info>,
+) -> Result<()> {
+    // [MEDIUM-2] Check lifecycle state
+    require_lifecycle(
+        &accounts.protocol_state,
+        GatedAction::Mint,
+        Clock::get()?.unix_timestamp,
+    )?;
+    require!(accounts.collateral_config.enabled, GusdError::CollateralDisabled);
+    require!(accounts.protocol_state.price_disputed_at == 0, GusdError::PriceDisputed);
+    require!(amount > 0, GusdError::InvalidAmount);
+
+    let now = Clock::get()?.unix_timestamp;
+    let registry = accounts.fee_tier_registry.as_deref();
+    let stake = accounts.governance_stake.as_ref().map(|stake| (stake.key(), &**stake));
+    let accrual_discount_bps = vault_stake_discount_bps(&accounts.vault, registry, stake, now);
+    accrue_vault_interest(
+        &mut accounts.protocol_state,
+        &mut accounts.price_feed,
+        &mut accounts.vault,
+        &mut accounts.revenue_stats,
+        accrual_discount_bps,
+        now,
+    )?;
+    let spot_price = get_price_with_fallback(
+        &accounts.protocol_state,
+        &accounts.price_feed,
+        &accounts.deployment_config,
+        accounts.price_update.as_deref(),
+        PriceBound::Lower,
+        PriceOperation::Mint,
+    )?;
+
+    // With a TWAP window set, value collateral at the lower of spot and TWAP so a
+    // short-lived upward spike can't be used to over-mint (the accumulator tracks GOR)
+    let twap_window_secs = accounts.protocol_state.twap_window_secs;
+    let price = if twap_window_secs > 0 && accounts.vault.collateral_mint == NATIVE_COLLATERAL_MINT {
+        let twap = compute_twap(&accounts.price_accumulator, twap_window_secs, now)?;
+        let twap = apply_fx_rate(&accounts.protocol_state, twap)?;
+        spot_price.min(apply_price_spread(
+            &accounts.protocol_state,
+            twap,
+            PriceOperation::Mint,
+        )?)
+    } else {
+        spot_price
+    };
+
+    let exempt = accounts.fee_exemption
+        .as_ref()
+        .is_some_and(|exemption| exemption.is_active(now));
+    // Staker tier discount; each mint also records the stake for the vault's later
+    // stability fees
+    let stake_discount_bps = match (&accounts.fee_tier_registry, &accounts.governance_stake) {
+        (Some(registry), Some(stake)) => registry.discount_bps(stake, now),
+        _ => 0,
+    };
+    decay_base_rate(&mut accounts.protocol_state, now);
+    let fee = if exempt {
+        0
+    } else {
+        let fee_bps = fee_with_base_rate_bps(
+            accounts.protocol_state.base_rate,
+            accounts.price_feed.mint_fee_bps(&accounts.protocol_state),
+            MAX_MINT_FEE_BPS,
+        );
+        let fee_bps = fee_bps - fee_bps * stake_discount_bps / BPS_DENOMINATOR;
+        let fee = (amount as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(BPS_DENOMINATOR as u128);
+        require!(fee <= u64::MAX as u128, GusdError::MathOverflow);
+        fee as u64
+    };
+    // Upfront fees are burned from the owner's GUSD after the mint instead of added to debt
+    let upfront_fee = if accounts.protocol_state.mint_fee_upfront { fee } else { 0 };
+    let upfront_interest = if accounts.protocol_state.stability_fee_upfront {
+        accounts.vault.unpaid_interest.min(accounts.vault.debt_amount)
+    } else {
+        0
+    };
+    let debt_increase = amount.checked_add(fee - upfront_fee).ok_or(GusdError::MathOverflow)?;
+
+    let vault = &mut accounts.vault;
+    vault.discount_stake = match (&accounts.fee_tier_registry, &accounts.governance_stake) {
+        (Some(_), Some(stake)) => stake.key(),
+        _ => Pubkey::default(),
+    };
+
+    // Calculate new debt
+    let new_debt = vault.debt_amount.checked_add(debt_increase)
+        .ok_or(GusdError::MathOverflow)?
+        - upfront_interest;
+    // Dust vaults cost more to liquidate than they pay
+    require!(
+        new_debt >= accounts.protocol_state.min_vault_debt,
+        GusdError::VaultDebtBelowMinimum
+    );
+    let max_vault_debt = accounts.protocol_state.max_vault_debt;
+    require!(
+        max_vault_debt == 0 || new_debt <= max_vault_debt,
+        GusdError::VaultDebtAboveMaximum
+    );
+
+    // Global debt ceiling, raised first by any launch ramp steps now due
+    let protocol = &mut accounts.protocol_state;
+    let old_ceiling = protocol.debt_ceiling;
+    advance_launch_ramp(protocol, now)?;
+    if protocol.debt_ceiling != old_ceiling {
+        emit!(DebtCeilingRamped {
+            old_ceiling,
+            new_ceiling: protocol.debt_ceiling,
+            timestamp: now,
+        });
+    }
+    if protocol.debt_ceiling > 0 {
+        let new_total_debt = protocol.total_debt
+            .checked_add(debt_increase)
+            .ok_or(GusdError::MathOverflow)?;
+        require!(new_total_debt <= protocol.debt_ceiling, GusdError::DebtCeilingExceeded);
+    }
+    let protocol = &accounts.protocol_state;
+
+    // Supply cap, on the gross mint before any upfront burn
+    require_within_max_supply(protocol, accounts.gusd_mint.supply, amount)?;
+
+    // Debt ceiling of the vault's collateral type
+    let config = &accounts.collateral_config;
+    let new_collateral_debt = config.total_debt
+        .checked_add(debt_increase)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(
+        config.debt_ceiling == 0 || new_collateral_debt <= config.debt_ceiling,
+        GusdError::CollateralDebtCeilingExceeded
+    );
+
+    // Check collateral ratio after minting
+    let collateral_value_usd = calculate_usd_value(
+        vault.collateral_amount,
+        price,
+        accounts.price_feed.decimals,
+    )?;
+
+    if vault.margin_account == Pubkey::default() {
+        // Extra collateral balances cover part of the debt; the primary collateral the rest
+        let uncovered_debt = new_debt.saturating_sub(balances_debt_capacity(
+            protocol,
+            &accounts.deployment_config,
+            vault,
+            remaining_accounts,
+            PriceOperation::Mint,
+            None,
+        )?);
+        let required_collateral = uncovered_debt
+            .checked_mul(accounts.collateral_config.min_collateral_ratio_bps)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR)
+            .ok_or(GusdError::MathOverflow)?;
+
+        require!(
+            collateral_value_usd >= required_collateral,
+            GusdError::InsufficientCollateral
+        );
+    } else {
+        let margin_account = linked_margin_account(vault, &accounts.margin_account)?;
+        let (margin_collateral, margin_debt) = margin_totals(
+            margin_account,
+            remaining_accounts,
+            Some((vault.key(), vault.collateral_amount, new_debt)),
+        )?;
+        let margin_ratio_bps = calculate_collateral_ratio_bps(
+            margin_collateral,
+            margin_debt,
+            price,
+            accounts.price_feed.decimals,
+        )?;
+        require!(
+            margin_ratio_bps >= margin_min_collateral_ratio_bps(protocol, &accounts.collateral_config)?,
+            GusdError::InsufficientCollateral
+        );
+    }
+
+    // Mint GUSD to user
+    let seeds = &[
+        SEED_NAMESPACE,
+        b"protocol".as_ref(),
+        &[protocol.bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = MintTo {
+        mint: accounts.gusd_mint.to_account_info(),
+        to: destination,
+        authority: accounts.protocol_state.to_account_info(),
+    };
+    let cpi_program = accounts.token_program.to_account_info();
+    
+    token_interface::mint_to(
+        CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds),
+        amount,
+    )?;
+    record_supply(&mut accounts.supply_stats.minted_vault_debt, amount)?;
+
+    let upfront = upfront_fee + upfront_interest;
+    if upfront > 0 {
+        // Burned from the owner's GUSD account, so only the owner can pay it
+        require!(accounts.owner.is_signer, GusdError::Unauthorized);
+        token_interface::burn(
+            CpiContext::new(
+                accounts.token_program.to_account_info(),
+                Burn {
+                    mint: accounts.gusd_mint.to_account_info(),
+                    from: accounts.user_gusd_account.to_account_info(),
+                    authority: accounts.owner.to_account_info(),
+                },
+            ),
+            upfront,
+        )?;
+        record_supply(&mut accounts.supply_stats.burned_repayments, upfront)?;
+    }
+
+    // Update vault debt
+    vault.debt_amount = new_debt;
+    vault.unpaid_interest -= upfront_interest;
+    record_vault_activity(vault, now);
+
+    // [HIGH-3] Fixed: Don't clone protocol_state
+    let protocol = &mut accounts.protocol_state;
+    protocol.total_debt = protocol.total_debt
+        .checked_add(debt_increase)
+        .ok_or(GusdError::MathOverflow)?
+        - upfront_interest;
+    book_fee_revenue(protocol, fee)?;
+    record_revenue(&mut accounts.revenue_stats.mint_fees, fee)?;
+    accounts.collateral_config.total_debt = new_collateral_debt;
+    let supply = accounts.gusd_mint.supply
+        .checked_add(amount)
+        .ok_or(GusdError::MathOverflow)?;
+    let weight_bps = protocol.base_rate_mint_weight_bps;
+    bump_base_rate(protocol, amount, supply, weight_bps)?;
+
+    // Calculate collateral ratio for event
+    let collateral_ratio_bps = collateral_value_usd
+        .checked_mul(BPS_DENOMINATOR)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(new_debt)
+        .ok_or(GusdError::MathOverflow)?;
+
+    msg!(
+        "Minted {} GUSD (fee {}, {} paid upfront). Total debt: {}",
+        amount,
+        fee,
+        upfront,
+        vault.debt_amount
+    );
+    msg!("Collateral ratio: {}%", collateral_ratio_bps as f64 / 100.0);
+
+    // [MEDIUM-3] Emit event
+    emit!(GusdMinted {
+        owner: vault.owner,
+        amount,
+        fee,
+        upfront,
+        total_debt: vault.debt_amount,
+        collateral_ratio_bps,
+        health_factor: health_factor(
+            collateral_ratio_bps,
+            accounts.collateral_config.liquidation_threshold_bps,
+        ),
+        position_mint: vault.position_mint,
+    });
+    
+    Ok(())
+}
+
+/// Fee of the current transaction (lamports): one signature plus the priority fee set by
+/// its compute budget instructions
+fn transaction_fee_lamports(instructions: &AccountInfo) -> Result<u64> {
+    let mut unit_price: u64 = 0;
+    let mut unit_limit: Option<u64> = None;
+    let mut other_instructions: u64 = 0;
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        index += 1;
+        if ix.program_id != compute_budget::ID {
+            other_instructions += 1;
+            continue;
+        }
+        match ix.data.split_first() {
+            // SetComputeUnitLimit(u32)
+            Some((2, rest)) if rest.len() >= 4 => {
+                unit_limit = Some(u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as u64);
+            }
+            // SetComputeUnitPrice(u64), in micro-lamports per unit
+            Some((3, rest)) if rest.len() >= 8 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&rest[..8]);
+                unit_price = u64::from_le_bytes(bytes);
+            }
+            _ => {}
+        }
+    }
+    let units = unit_limit
+        .unwrap_or(other_instructions.saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNITS))
+        .min(MAX_TRANSACTION_COMPUTE_UNITS);
+    let priority_fee = (unit_price as u128 * units as u128).div_ceil(1_000_000);
+    Ok(BASE_SIGNATURE_FEE_LAMPORTS.saturating_add(priority_fee.min(u64::MAX as u128) as u64))
+}
+
+/// Credit a registered keeper with this transaction's fee, capped at
+/// `MAX_KEEPER_CRANK_FEE_LAMPORTS` and at most once per slot
+/// A no-op without a keeper account; with one, `signer` must be its keeper and the
+/// instructions sysvar must be passed.
+fn credit_keeper(
+    keeper_account: Option<&mut KeeperAccount>,
+    signer: Option<Pubkey>,
+    instructions: Option<&AccountInfo>,
+) -> Result<()> {
+    let Some(account) = keeper_account else {
+        return Ok(());
+    };
+    require!(signer == Some(account.keeper), GusdError::InvalidKeeperCredit);
+    let instructions = instructions.ok_or(GusdError::InvalidKeeperCredit)?;
+
+    let slot = Clock::get()?.slot;
+    if account.last_credited_slot == slot {
+        return Ok(());
+    }
+    let fee = transaction_fee_lamports(instructions)?.min(MAX_KEEPER_CRANK_FEE_LAMPORTS);
+    account.last_credited_slot = slot;
+    account.cranks = account.cranks.saturating_add(1);
+    account.unclaimed_fee_lamports = account.unclaimed_fee_lamports
+        .checked_add(fee)
+        .ok_or(GusdError::MathOverflow)?;
+    account.total_fee_lamports = account.total_fee_lamports
+        .checked_add(fee)
+        .ok_or(GusdError::MathOverflow)?;
+
+    emit!(KeeperCredited {
+        keeper: account.keeper,
+        fee_lamports: fee,
+        unclaimed_fee_lamports: account.unclaimed_fee_lamports,
+        slot,
+    });
+    Ok(())
+}
+
+/// CPI into `program_id` with `accounts` forwarded as-is and the treasury PDA signing
+/// Commitment to a swap route's ordered `(account, writable)` list
+fn swap_route_hash(accounts: impl Iterator<Item = (Pubkey, bool)>) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    for (pubkey, is_writable) in accounts {
+        bytes.extend_from_slice(pubkey.as_ref());
+        bytes.push(is_writable as u8);
+    }
+    hashv(&[&bytes]).to_bytes()
+}
+
+fn invoke_as_treasury<'info>(
+    program_id: Pubkey,
+    accounts: &[AccountInfo<'info>],
+    data: Vec<u8>,
+    treasury_key: Pubkey,
+    treasury_bump: u8,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id,
+        accounts: accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer || account.key() == treasury_key,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    let seeds = &[b"treasury".as_ref(), &[treasury_bump]];
+    invoke_signed(&ix, accounts, &[&seeds[..]])?;
+    Ok(())
+}
+
+/// Lifecycle state in force at `now`
+/// A pause lapses `max_pause_secs` after it began (0 = never), so a lost admin key cannot
+/// freeze vaults indefinitely; the protocol then runs in the state it would resume to.
+fn lifecycle_in_effect(protocol: &ProtocolState, now: i64) -> Lifecycle {
+    match protocol.lifecycle {
+        Lifecycle::Paused
+            if protocol.max_pause_secs > 0
+                && now >= protocol.paused_at.saturating_add(protocol.max_pause_secs as i64) =>
+        {
+            resume_lifecycle(protocol)
+        }
+        state => state,
+    }
+}
+
+/// Whether a pause or shutdown still blocks operations at `now`
+fn pause_in_effect(protocol: &ProtocolState, now: i64) -> bool {
+    matches!(
+        lifecycle_in_effect(protocol, now),
+        Lifecycle::Paused | Lifecycle::Settlement
+    )
+}
+
+/// State a pause ends in: guarded launch while a launch ramp runs, active otherwise
+fn resume_lifecycle(protocol: &ProtocolState) -> Lifecycle {
+    if protocol.launch_ramp_step_bps > 0 {
+        Lifecycle::GuardedLaunch
+    } else {
+        Lifecycle::Active
+    }
+}
+
+/// Fail unless the lifecycle state in force at `now` allows `action`
+fn require_lifecycle(protocol: &ProtocolState, action: GatedAction, now: i64) -> Result<()> {
+    let state = lifecycle_in_effect(protocol, now);
+    if state.allows(action) {
+        return Ok(());
+    }
+    Err(match state {
+        Lifecycle::RecoveryMode => GusdError::RecoveryModeRestricted,
+        Lifecycle::Settlement => GusdError::ProtocolShutDown,
+        _ => GusdError::ProtocolPaused,
+    }
+    .into())
+}
+
+/// Move the protocol to `next` if `Lifecycle::can_transition_to` allows it (no-op if
+/// already there)
+fn transition_lifecycle(protocol: &mut ProtocolState, next: Lifecycle, now: i64) -> Result<()> {
+    let previous = protocol.lifecycle;
+    if previous == next {
+        return Ok(());
+    }
+    require!(
+        previous.can_transition_to(next),
+        GusdError::InvalidLifecycleTransition
+    );
+    protocol.lifecycle = next;
+
+    emit!(LifecycleChanged {
+        previous,
+        next,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Pause the protocol, keeping the start of a pause still in effect
+/// A shut-down protocol stays in settlement.
+fn enter_pause(protocol: &mut ProtocolState, now: i64) -> Result<()> {
+    if protocol.lifecycle == Lifecycle::Settlement {
+        return Ok(());
+    }
+    if !pause_in_effect(protocol, now) {
+        protocol.paused_at = now;
+    }
+    transition_lifecycle(protocol, Lifecycle::Paused, now)
+}
+
+/// Switch between guarded launch and active as a launch ramp starts or ends; other
+/// states pick the ramp up when they resume
+fn sync_launch_lifecycle(protocol: &mut ProtocolState, now: i64) -> Result<()> {
+    match protocol.lifecycle {
+        Lifecycle::GuardedLaunch | Lifecycle::Active => {
+            let next = resume_lifecycle(protocol);
+            transition_lifecycle(protocol, next, now)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether redemption claims are open: every vault's debt has settled, or the
+/// settlement window after the price was marked has ended
+fn redemption_claims_open(settlement: &Settlement, total_debt: u64, now: i64) -> bool {
+    settlement.price > 0
+        && (total_debt == 0
+            || now >= settlement.marked_at.saturating_add(SETTLEMENT_WINDOW_SECS))
+}
+
+/// GOR owed so far to a redemption of `amount` GUSD, and its face value
+/// See `pool_redemption_entitlement`; the request closes once the full face value is paid,
+/// which only happens when every settled vault held GOR.
+fn redemption_entitlement(settlement: &Settlement, amount: u64, native_decimals: u8) -> Result<(u64, u64)> {
+    pool_redemption_entitlement(
+        settlement,
+        (settlement.collateral, settlement.debt),
+        settlement.price,
+        native_decimals,
+        amount,
+    )
+}
+
+/// Tokens owed so far to a redemption of `amount` GUSD from a token settlement pool, and
+/// their face value
+fn token_redemption_entitlement(
+    settlement: &Settlement,
+    pool: &SettlementTokenPool,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    pool_redemption_entitlement(settlement, (pool.collateral, pool.debt), pool.price, pool.decimals, amount)
+}
+
+/// Collateral owed so far to a redemption of `amount` GUSD from a pool holding `collateral`
+/// for `debt` settled GUSD, and the face value of `amount` at the pool's `price`
+/// The request's pro-rata share of the pool, capped at the face value of the pool's part of
+/// the burned GUSD: `amount` weighted by the pool's share of `Settlement.marked_debt`. The
+/// debt credited to all pools never exceeds `marked_debt`, so a request's caps add up to at
+/// most its face value however many collateral types settle.
+fn pool_redemption_entitlement(
+    settlement: &Settlement,
+    (collateral, debt): (u64, u64),
+    price: u64,
+    decimals: u8,
+    amount: u64,
+) -> Result<(u64, u64)> {
+    let face_value = UsdValue6(amount).to_lamports(UsdValue6(price), decimals)?.0;
+    let pool_face_value = if settlement.marked_debt == 0 {
+        face_value
+    } else {
+        let weighted = (amount as u128)
+            .checked_mul(debt.min(settlement.marked_debt) as u128)
+            .ok_or(GusdError::MathOverflow)?
+            / settlement.marked_debt as u128;
+        UsdValue6(weighted as u64).to_lamports(UsdValue6(price), decimals)?.0
+    };
+    let pro_rata = (collateral as u128)
+        .checked_mul(amount as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(settlement.requested as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    Ok(((pro_rata as u64).min(pool_face_value), face_value))
+}
+
+/// Credit `debt` settled into a pool against `marked_debt`, returning the part credited
+/// Interest accrued after the mark is settled but not credited, so the debt credited to all
+/// pools stays within `marked_debt`.
+fn credit_settled_debt(settlement: &mut Settlement, debt: u64) -> u64 {
+    let credited = debt.min(settlement.marked_debt.saturating_sub(settlement.settled_debt));
+    settlement.settled_debt += credited;
+    credited
+}
+
+/// Check that each of the vault's extra collateral balances has settled its part of the
+/// debt or holds nothing. `balances` lists every `CollateralBalance` in the vault's order.
+fn require_balances_settled<'info>(
+    vault: &Account<Vault>,
+    balances: &'info [AccountInfo<'info>],
+) -> Result<()> {
+    require!(
+        balances.len() == vault.collateral_balances.len(),
+        GusdError::CollateralBalancesMismatch
+    );
+    for (account_info, mint) in balances.iter().zip(vault.collateral_balances.iter()) {
+        let balance = Account::<CollateralBalance>::try_from(account_info)?;
+        require!(
+            balance.vault == vault.key() && balance.collateral_mint == *mint,
+            GusdError::CollateralBalancesMismatch
+        );
+        require!(
+            balance.settled || balance.amount == 0,
+            GusdError::CollateralBalanceUnsettled
+        );
+    }
+    Ok(())
+}
+
+/// Bootstrap auction price at `now` (lamports per whole GUSD), falling linearly from
+/// `start_price` to `end_price`
+fn bootstrap_price(auction: &BootstrapAuction, now: i64) -> Result<u64> {
+    let duration = auction.ends_at.saturating_sub(auction.starts_at).max(1) as u128;
+    let elapsed = (now.saturating_sub(auction.starts_at).max(0) as u128).min(duration);
+    let drop = ((auction.start_price - auction.end_price) as u128)
+        .checked_mul(elapsed)
+        .ok_or(GusdError::MathOverflow)?
+        / duration;
+    Ok(auction.start_price - drop as u64)
+}
+
+/// Check that issuing `amount` new GUSD keeps supply within `max_supply`
+/// Run by every mint against new debt: `mint_gusd`, `pay_invoice_from_vault`, and the
+/// bootstrap tranche. Fee, savings, and insurance revenue accrued but not yet minted counts
+/// as supply, since realizing it only mints GUSD already owed; those realizations are never
+/// blocked.
+fn require_within_max_supply(protocol: &ProtocolState, supply: u64, amount: u64) -> Result<()> {
+    if protocol.max_supply == 0 {
+        return Ok(());
+    }
+    let new_supply = [protocol.accrued_fees, protocol.savings_accrued, protocol.insurance_accrued, amount]
+        .into_iter()
+        .try_fold(supply, u64::checked_add)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(new_supply <= protocol.max_supply, GusdError::MaxSupplyExceeded);
+    Ok(())
+}
+
+/// Whether the bootstrap auction's bidding is over: it ended or sold out
+fn bootstrap_finalizable(auction: &BootstrapAuction, now: i64) -> bool {
+    now >= auction.ends_at || auction.sold == auction.tranche
+}
+
+/// Check that an invoice is unpaid and not yet expired
+fn require_invoice_payable(invoice: &Invoice, now: i64) -> Result<()> {
+    require!(invoice.paid_at == 0, GusdError::InvoiceAlreadyPaid);
+    require!(now < invoice.expires_at, GusdError::InvoiceExpired);
+    Ok(())
+}
+
+/// Record an invoice payment and emit `InvoicePaid`
+fn mark_invoice_paid(invoice: &mut Account<Invoice>, payer: Pubkey, now: i64, minted: bool) {
+    invoice.paid_by = payer;
+    invoice.paid_at = now;
+
+    msg!("Invoice {} paid by {}", invoice.invoice_id, payer);
+
+    emit!(InvoicePaid {
+        invoice: invoice.key(),
+        payee: invoice.payee,
+        payer,
+        amount: invoice.amount,
+        memo_hash: invoice.memo_hash,
+        minted,
+    });
+}
+
+/// Calculate USD value of GOR amount
+/// [HIGH-1] Fixed: Now checks for u128 -> u64 overflow
+fn calculate_usd_value(gor_amount: u64, gor_price_usd: u64, gor_decimals: u8) -> Result<u64> {
+    // gor_amount is in native base units (10^-gor_decimals)
+    // gor_price_usd has 6 decimals
+    // Result should have 6 decimals (GUSD decimals)
+    // [HIGH-1] units::GorLamports::to_usd checks the u128 -> u64 narrowing
+    Ok(GorLamports(gor_amount)
+        .to_usd(UsdValue6(gor_price_usd), gor_decimals)?
+        .0)
+}
+
+/// Read a feed's Pyth or Switchboard account and cache the normalized price in the feed
+/// Shared by `crank_price` and `update_prices_batch`; returns the old and new cached price.
+fn cache_oracle_price(
+    protocol: &ProtocolState,
+    deployment: &DeploymentConfig,
+    feed: &mut PriceFeed,
+    price_update: &AccountInfo,
+) -> Result<(u64, u64)> {
+    require!(
+        feed.oracle_kind != OracleKind::ChainlinkStreams,
+        GusdError::OracleNotConfigured
+    );
+
+    let reading = read_oracle_price(feed, deployment, Some(price_update))?;
+    require!(
+        reading.age_secs <= protocol.max_price_age_secs,
+        GusdError::StaleOraclePrice
+    );
+    // Rejects readings whose confidence interval is too wide
+    apply_confidence(protocol, &reading, PriceBound::Mid)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let observed_at = now.saturating_sub(reading.age_secs as i64);
+    require!(observed_at > feed.cached_observed_at, GusdError::StaleOraclePrice);
+
+    let old_price = feed.cached_price;
+    feed.cached_price = reading.price;
+    feed.cached_conf_bps = reading.conf_bps;
+    feed.cached_observed_at = observed_at;
+    Ok((old_price, reading.price))
+}
+
+/// Validate a submitted protocol price and start ramping toward it, returning the price
+/// in effect before the update
+/// The ramp starts from the current interpolated price and moves `price_slew_bps_per_sec`
+/// of that price per second, so large moves are spread out rather than rejected.
+/// Shared by `update_price` and `update_price_signed`.
+fn apply_price_update(
+    protocol: &mut ProtocolState,
+    feed: &mut PriceFeed,
+    accumulator: &mut PriceAccumulator,
+    new_gor_price_usd: u64,
+    now: i64,
+) -> Result<u64> {
+    require!(new_gor_price_usd > 0, GusdError::InvalidPrice);
+
+    // Enforce a minimum update interval (helps mitigate admin compromise / fat-finger risk)
+    let elapsed = now.saturating_sub(feed.admin_price_ts);
+    require!(
+        elapsed >= MIN_PRICE_UPDATE_INTERVAL_SECS,
+        GusdError::PriceUpdateTooFrequent
+    );
+
+    let old_price = current_admin_price(feed, now);
+
+    // [MEDIUM-1] Ramp rate (ceiling division so small prices still move)
+    let ramp_rate = (old_price as u128)
+        .checked_mul(protocol.price_slew_bps_per_sec as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .div_ceil(BPS_DENOMINATOR as u128)
+        .max(1);
+    require!(ramp_rate <= u64::MAX as u128, GusdError::MathOverflow);
+
+    feed.admin_ramp_price = old_price;
+    feed.admin_ramp_rate = ramp_rate as u64;
+    feed.admin_price = new_gor_price_usd;
+    feed.admin_price_ts = now;
+
+    // Admin prices only feed the TWAP and EMA while they drive valuation; record the
+    // price in effect now, and later samples pick up the ramp. Both track the native price.
+    if feed.use_admin_price && feed.collateral_mint == NATIVE_COLLATERAL_MINT {
+        let spacing = observation_spacing_secs(protocol.twap_window_secs);
+        record_price_observation(accumulator, old_price, now, spacing)?;
+        update_ema_price(protocol, old_price, now)?;
+    }
+
+    Ok(old_price)
+}
+
+/// Count distinct registered feeders whose ed25519 signature over `message` was verified
+/// by an ed25519 program instruction in this transaction
+/// Only signatures with pubkey, signature, and message inline in the ed25519 instruction
+/// are accepted, so the verified bytes are exactly the ones inspected here.
+fn count_feeder_signatures(
+    instructions: &AccountInfo,
+    feeders: &[Pubkey],
+    message: &[u8],
+) -> Result<usize> {
+    const OFFSETS_START: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+
+    let read_u16 = |data: &[u8], at: usize| -> Result<usize> {
+        let bytes = data.get(at..at + 2).ok_or(GusdError::InvalidPriceSignature)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+    };
+
+    let mut signed_by: Vec<Pubkey> = Vec::new();
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        index += 1;
+        if ix.program_id != anchor_lang::solana_program::ed25519_program::ID {
+            continue;
+        }
+
+        let data = &ix.data;
+        let count = *data.first().ok_or(GusdError::InvalidPriceSignature)? as usize;
+        for i in 0..count {
+            let base = OFFSETS_START + i * OFFSETS_LEN;
+            let pubkey_offset = read_u16(data, base + 4)?;
+            let message_offset = read_u16(data, base + 8)?;
+            let message_size = read_u16(data, base + 10)?;
+            // Signature, pubkey, and message must all live in this instruction
+            let inline = [base + 2, base + 6, base + 12]
+                .iter()
+                .map(|&at| read_u16(data, at))
+                .collect::<Result<Vec<_>>>()?
+                .iter()
+                .all(|&ix_index| ix_index == u16::MAX as usize);
+            if !inline {
+                continue;
+            }
+
+            let signed_message = data
+                .get(message_offset..message_offset + message_size)
+                .ok_or(GusdError::InvalidPriceSignature)?;
+            let pubkey_bytes = data
+                .get(pubkey_offset..pubkey_offset + 32)
+                .ok_or(GusdError::InvalidPriceSignature)?;
+            let pubkey = Pubkey::try_from(pubkey_bytes).map_err(|_| GusdError::InvalidPriceSignature)?;
+
+            if signed_message == message && feeders.contains(&pubkey) && !signed_by.contains(&pubkey) {
+                signed_by.push(pubkey);
+            }
+        }
+    }
+
+    Ok(signed_by.len())
+}
+
+/// Return the program of the top-level instruction when this one runs via CPI
+/// The instructions sysvar only records top-level instructions, so with nested CPIs this is
+/// the outermost program, not necessarily the direct caller.
+fn get_top_level_program(instructions: &AccountInfo) -> Result<Option<Pubkey>> {
+    if get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Ok(None);
+    }
+    let current_index = load_current_index_checked(instructions)?;
+    let top_level_ix = load_instruction_at_checked(current_index as usize, instructions)?;
+
+    if top_level_ix.program_id == crate::ID {
+        Ok(None)
+    } else {
+        Ok(Some(top_level_ix.program_id))
+    }
+}
+
+/// Whether a collateral type as a whole is below its own minimum collateral ratio at `price`
+fn is_recovery_mode(protocol: &ProtocolState, config: &CollateralConfig, price: u64) -> Result<bool> {
+    let total_collateral = if config.collateral_mint == NATIVE_COLLATERAL_MINT {
+        protocol.total_collateral
+    } else {
+        config.total_collateral
+    };
+    let type_ratio_bps = calculate_collateral_ratio_bps(
+        total_collateral,
+        config.total_debt,
+        price,
+        config.decimals,
+    )?;
+    Ok(type_ratio_bps < config.min_collateral_ratio_bps)
+}
+
+/// Reset per-epoch outflow counters when a new epoch starts
+fn roll_outflow_epoch(protocol: &mut ProtocolState, epoch: u64) {
+    if protocol.outflow_epoch != epoch {
+        protocol.outflow_epoch = epoch;
+        protocol.epoch_liquidated_collateral = 0;
+        protocol.epoch_redeemed_collateral = 0;
+    }
+}
+
+/// Add `amount` to a per-epoch outflow counter, enforcing `cap` unless exempt (cap 0 = unlimited)
+fn consume_epoch_cap(counter: &mut u64, cap: u64, amount: u64, exempt: bool) -> Result<()> {
+    let new_total = counter.checked_add(amount).ok_or(GusdError::MathOverflow)?;
+    if cap > 0 && !exempt {
+        require!(new_total <= cap, GusdError::EpochOutflowCapExceeded);
+    }
+    *counter = new_total;
+    Ok(())
+}
+
+/// Apply every launch ramp step due by `now`, stopping once the target ceiling is reached
+fn advance_launch_ramp(protocol: &mut ProtocolState, now: i64) -> Result<()> {
+    while protocol.launch_ramp_step_bps > 0
+        && now.saturating_sub(protocol.launch_ramp_last_step_ts) >= LAUNCH_RAMP_INTERVAL_SECS
+    {
+        protocol.launch_ramp_last_step_ts += LAUNCH_RAMP_INTERVAL_SECS;
+
+        let raised = (protocol.debt_ceiling as u128)
+            .checked_mul((BPS_DENOMINATOR + protocol.launch_ramp_step_bps) as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?;
+        protocol.debt_ceiling = raised.min(protocol.launch_ramp_target as u128) as u64;
+
+        if protocol.debt_ceiling >= protocol.launch_ramp_target {
+            protocol.launch_ramp_step_bps = 0;
+            protocol.launch_ramp_target = 0;
+        }
+    }
+    sync_launch_lifecycle(protocol, now)
+}
+
+/// Lift a guardian price dispute
+fn clear_price_dispute_state(protocol: &mut ProtocolState, now: i64) {
+    let disputed_at = std::mem::replace(&mut protocol.price_disputed_at, 0);
+    emit!(PriceDisputeCleared {
+        disputed_at,
+        timestamp: now,
+    });
+}
+
+/// Fill a newly created price feed; it starts on the admin price with no oracle
+/// Bounds on a collateral type's ratios: no looser than the protocol's floors, and the
+/// liquidation threshold below the minimum collateral ratio
+fn validate_collateral_ratios(
+    min_collateral_ratio_bps: u64,
+    liquidation_threshold_bps: u64,
+) -> Result<()> {
+    require!(
+        liquidation_threshold_bps >= LIQUIDATION_THRESHOLD_BPS
+            && min_collateral_ratio_bps >= MIN_COLLATERAL_RATIO_BPS
+            && liquidation_threshold_bps < min_collateral_ratio_bps
+            && min_collateral_ratio_bps <= MAX_COLLATERAL_RATIO_BPS,
+        GusdError::InvalidParameter
+    );
+    Ok(())
+}
+
+/// Settle `amount` of repaid or written-off debt against the vault's unpaid interest first,
+/// returning the rest: the principal part, which `release_collateral_debt` takes off its
+/// collateral type
+fn settle_unpaid_interest(vault: &mut Vault, amount: u64) -> u64 {
+    let interest = amount.min(vault.unpaid_interest);
+    vault.unpaid_interest -= interest;
+    amount - interest
+}
+
+/// Lower a collateral type's debt counter by repaid or written-off principal
+/// Saturates at zero: vaults opened before the counter existed were never added to it.
+fn release_collateral_debt(config: &mut CollateralConfig, amount: u64) {
+    config.total_debt = config.total_debt.saturating_sub(amount);
+}
+
+fn init_collateral_config(
+    config: &mut CollateralConfig,
+    feed: &Account<PriceFeed>,
+    min_collateral_ratio_bps: u64,
+    liquidation_threshold_bps: u64,
+    now: i64,
+    bump: u8,
+) {
+    config.collateral_mint = feed.collateral_mint;
+    config.decimals = feed.decimals;
+    config.price_feed = feed.key();
+    config.min_collateral_ratio_bps = min_collateral_ratio_bps;
+    config.liquidation_threshold_bps = liquidation_threshold_bps;
+    config.enabled = true;
+    config.total_collateral = 0;
+    config.debt_ceiling = 0; // Unlimited until the risk manager caps it
+    config.total_debt = 0;
+    config.added_at = now;
+    config.bump = bump;
+}
+
+fn init_price_feed_state(
+    feed: &mut PriceFeed,
+    collateral_mint: Pubkey,
+    decimals: u8,
+    initial_price: u64,
+    now: i64,
+    accrual_clock: i64,
+    bump: u8,
+) {
+    feed.collateral_mint = collateral_mint;
+    feed.decimals = decimals;
+    feed.oracle_kind = OracleKind::Pyth;
+    feed.oracle_account = Pubkey::default();
+    feed.oracle_feed_id = [0u8; 32];
+    feed.use_admin_price = true; // No oracle configured yet
+    feed.admin_price = initial_price;
+    feed.admin_price_ts = now;
+    feed.admin_ramp_price = initial_price;
+    feed.admin_ramp_rate = 0;
+    feed.last_good_price = 0;
+    feed.last_good_price_ts = 0;
+    feed.cached_price = 0;
+    feed.cached_conf_bps = 0;
+    feed.cached_observed_at = 0;
+    feed.fallback_oracles = [OracleSource::UNSET; MAX_FALLBACK_ORACLES];
+    clear_oracle_migration(feed);
+    feed.fees = CollateralFees::default(); // Protocol-wide fees
+    feed.premium_index = DEBT_INDEX_ONE;
+    feed.premium_index_updated_at = accrual_clock;
+    feed.stake_pool = Pubkey::default(); // Not liquid-staked GOR
+    feed.exchange_rate = EXCHANGE_RATE_ONE;
+    feed.exchange_rate_epoch = 0;
+    feed.bump = bump;
+}
+
+/// Reset a feed's shadow-mode and scheduled-switch state
+fn clear_oracle_migration(feed: &mut PriceFeed) {
+    feed.shadow_started_at = 0;
+    feed.shadow_period_secs = 0;
+    feed.shadow_alert_bps = 0;
+    feed.shadow_samples = 0;
+    feed.shadow_max_divergence_bps = 0;
+    feed.oracle_activation_at = 0;
+}
+
+/// Reject vaults holding SPL token collateral in paths that move lamports
+fn require_native_collateral(vault: &Vault) -> Result<()> {
+    require_keys_eq!(vault.collateral_mint, NATIVE_COLLATERAL_MINT, GusdError::UnsupportedCollateral);
+    Ok(())
+}
+
+/// A token vault's collateral mint, token account, and the mint's token program
+type TokenCustody<'a, 'info> = (
+    &'a InterfaceAccount<'info, Mint>,
+    &'a InterfaceAccount<'info, TokenAccount>,
+    &'a Interface<'info, TokenInterface>,
+);
+
+/// The token custody accounts a token vault's collateral moves need
+fn token_custody<'a, 'info>(
+    mint: Option<&'a InterfaceAccount<'info, Mint>>,
+    vault_token_account: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    token_program: Option<&'a Interface<'info, TokenInterface>>,
+) -> Result<TokenCustody<'a, 'info>> {
+    match (mint, vault_token_account, token_program) {
+        (Some(mint), Some(vault_token_account), Some(token_program)) => {
+            Ok((mint, vault_token_account, token_program))
+        }
+        _ => err!(GusdError::MissingCollateralAccounts),
+    }
+}
+
+/// Token-2022 transfer fee the collateral mint withholds from a transfer of `amount` in the
+/// current epoch (0 for mints without the transfer-fee extension)
+fn collateral_transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => Ok(config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(GusdError::MathOverflow)?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Transfer `amount` of a token vault's collateral to `to`, signed by the vault's
+/// collateral PDA
+/// The vault's token account is debited the full `amount`; returns the transfer fee withheld
+/// from what `to` receives.
+fn transfer_vault_tokens<'info>(
+    (mint, vault_token_account, token_program): TokenCustody<'_, 'info>,
+    vault_collateral: &AccountInfo<'info>,
+    position_mint: Pubkey,
+    collateral_bump: u8,
+    to: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    if amount == 0 {
+        return Ok(0);
+    }
+    let seeds = &[
+        SEED_NAMESPACE,
+        b"vault_collateral".as_ref(),
+        position_mint.as_ref(),
+        &[collateral_bump],
+    ];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                to,
+                authority: vault_collateral.clone(),
+            },
+            &[&seeds[..]],
+        ),
+        amount,
+        mint.decimals,
+    )?;
+    collateral_transfer_fee(mint, amount)
+}
+
+/// Ensure the collateral PDA still holds the vault's tracked collateral, unswept liquidation
+/// fees, and rent reserve, so outflows never dip into rent and get the account garbage
+/// collected
+fn require_collateral_reserve(vault_collateral: &AccountInfo, vault: &Vault) -> Result<()> {
+    // A token vault's collateral and fees sit in its token account
+    if vault.collateral_mint != NATIVE_COLLATERAL_MINT {
+        require!(
+            vault_collateral.lamports() >= vault.collateral_rent_reserve,
+            GusdError::RentReserveViolated
+        );
+        return Ok(());
+    }
+    let required = vault.collateral_amount
+        .checked_add(vault.retained_liquidation_fees)
+        .and_then(|amount| amount.checked_add(vault.collateral_rent_reserve))
+        .ok_or(GusdError::MathOverflow)?;
+    require!(vault_collateral.lamports() >= required, GusdError::RentReserveViolated);
+    Ok(())
+}
+
+/// Debt to repay and collateral to seize when liquidating `debt_amount` against
+/// `collateral_amount`. Repayment is capped so the seized collateral covers it plus the
+/// penalty (discounted for insured vaults), and must clear the liquidator profit floor.
+/// With `liquidation_target_cr_bps` set, it is also capped at the amount that restores
+/// `position` (total collateral and debt of the vault or margin account) to the target.
+/// The penalty is the collateral's liquidator bonus plus protocol fee. Returns the debt
+/// repaid, the collateral seized, and the protocol fee's part of the seized collateral; the
+/// liquidator gets the rest.
+#[allow(clippy::too_many_arguments)]
+fn liquidation_amounts(
+    protocol: &ProtocolState,
+    feed: &PriceFeed,
+    collateral_amount: u64,
+    debt_amount: u64,
+    position: (u64, u64),
+    price: u64,
+    native_decimals: u8,
+    insured: bool,
+) -> Result<(u64, u64, u64)> {
+    let collateral_value_usd = calculate_usd_value(collateral_amount, price, native_decimals)?;
+
+    // Insured vaults pay a discounted penalty
+    let (bonus_bps, fee_bps) = feed.liquidation_penalty_bps(protocol);
+    let full_penalty_bps = bonus_bps
+        .checked_add(fee_bps)
+        .ok_or(GusdError::MathOverflow)?;
+    let penalty_bps = if insured {
+        full_penalty_bps
+            .checked_mul(BPS_DENOMINATOR - protocol.insurance_discount_bps)
+            .ok_or(GusdError::MathOverflow)?
+            / BPS_DENOMINATOR
+    } else {
+        full_penalty_bps
+    };
+
+    // Determine the maximum profitable repay amount given available collateral.
+    // We only allow liquidations where: collateral_seized >= repay_amount * (1 + penalty)
+    let bonus_denominator = (BPS_DENOMINATOR + penalty_bps) as u128;
+
+    let max_repay_u128 = (collateral_value_usd as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(bonus_denominator)
+        .ok_or(GusdError::MathOverflow)?;
+
+    let mut repay_u128 = (debt_amount as u128).min(max_repay_u128);
+
+    // Repaying R moves the position to (C - R × (1 + penalty)) / (D - R), so the target T
+    // is reached at R = (T × D - C) / (T - (1 + penalty)), rounded up
+    let target_cr_bps = protocol.liquidation_target_cr_bps as u128;
+    if target_cr_bps > 0 {
+        let (position_collateral, position_debt) = position;
+        let position_value_usd = calculate_usd_value(position_collateral, price, native_decimals)?;
+        let shortfall = (position_debt as u128)
+            .checked_mul(target_cr_bps)
+            .ok_or(GusdError::MathOverflow)?
+            .saturating_sub(
+                (position_value_usd as u128)
+                    .checked_mul(BPS_DENOMINATOR as u128)
+                    .ok_or(GusdError::MathOverflow)?,
+            );
+        let divisor = target_cr_bps
+            .checked_sub(bonus_denominator)
+            .ok_or(GusdError::MathOverflow)?;
+        repay_u128 = repay_u128.min(shortfall.div_ceil(divisor));
+    }
+
+    require!(repay_u128 > 0, GusdError::LiquidationNotProfitable);
+    require!(repay_u128 <= u64::MAX as u128, GusdError::MathOverflow);
+    let repay_amount = repay_u128 as u64;
+
+    // Calculate USD value with liquidation bonus
+    let repay_with_bonus_u128 = (repay_amount as u128)
+        .checked_mul(bonus_denominator)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?;
+
+    // Convert USD (6 decimals) to native base units (native_decimals)
+    require!(repay_with_bonus_u128 <= u64::MAX as u128, GusdError::MathOverflow);
+    let collateral_seized = UsdValue6(repay_with_bonus_u128 as u64)
+        .to_lamports(UsdValue6(price), native_decimals)?
+        .0;
+
+    // Final sanity check: do not seize more than tracked collateral
+    require!(
+        collateral_seized <= collateral_amount,
+        GusdError::MathOverflow
+    );
+
+    // Split the penalty between the liquidator bonus and the protocol fee
+    let repay_collateral = UsdValue6(repay_amount)
+        .to_lamports(UsdValue6(price), native_decimals)?
+        .0;
+    let protocol_fee = (collateral_seized.saturating_sub(repay_collateral) as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(full_penalty_bps as u128)
+        .ok_or(GusdError::MathOverflow)? as u64;
+    let collateral_to_liquidator = collateral_seized - protocol_fee;
+
+    // Require the liquidator's profit (after rounding) to meet the configured floor,
+    // scaled down in proportion to an insurance-discounted penalty. A collateral bonus set
+    // below the floor caps it, so liquidations never become impossible.
+    let min_profit_bps = (protocol.min_liquidation_profit_bps.min(bonus_bps) as u128)
+        .checked_mul(penalty_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(full_penalty_bps as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    let seized_value_usd = calculate_usd_value(
+        collateral_to_liquidator,
+        price,
+        native_decimals,
+    )?;
+    let profit_bps = (seized_value_usd.saturating_sub(repay_amount) as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(repay_amount as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(profit_bps >= min_profit_bps, GusdError::LiquidationNotProfitable);
+
+    Ok((repay_amount, collateral_seized, protocol_fee))
+}
+
+/// Reject a liquidation inside a flagged vault's priority window unless `liquidator`
+/// holds the current priority liquidation rights
+fn require_priority_rights(
+    protocol: &ProtocolState,
+    vault: &Vault,
+    liquidator: Pubkey,
+    clock: &Clock,
+) -> Result<()> {
+    let window_open = protocol.priority_window_slots > 0
+        && vault.flagged_slot != 0
+        && clock.slot < vault.flagged_slot.saturating_add(protocol.priority_window_slots)
+        && protocol.priority_liquidator != Pubkey::default()
+        && clock.unix_timestamp < protocol.priority_rights_until;
+    if window_open {
+        require_keys_eq!(liquidator, protocol.priority_liquidator, GusdError::PriorityWindowActive);
+    }
+    Ok(())
+}
+
+/// The margin account a vault belongs to, which the caller must have passed
+fn linked_margin_account<'a, 'info>(
+    vault: &Vault,
+    margin_account: &'a Option<Account<'info, MarginAccount>>,
+) -> Result<&'a Account<'info, MarginAccount>> {
+    let margin_account = margin_account.as_ref().ok_or(GusdError::MarginAccountRequired)?;
+    require_keys_eq!(
+        margin_account.key(),
+        vault.margin_account,
+        GusdError::MarginAccountMismatch
+    );
+    Ok(margin_account)
+}
+
+/// Total collateral and debt across a margin account. `members` must list every member
+/// vault in the account's order; `current` overrides the stored balances of a vault
+/// whose update hasn't been written back yet.
+fn margin_totals<'info>(
+    margin_account: &MarginAccount,
+    members: &'info [AccountInfo<'info>],
+    current: Option<(Pubkey, u64, u64)>,
+) -> Result<(u64, u64)> {
+    require!(
+        members.len() == margin_account.vaults.len(),
+        GusdError::MarginVaultsMismatch
+    );
+
+    let mut total_collateral: u64 = 0;
+    let mut total_debt: u64 = 0;
+    for (account_info, member) in members.iter().zip(margin_account.vaults.iter()) {
+        require_keys_eq!(account_info.key(), *member, GusdError::MarginVaultsMismatch);
+        let (collateral_amount, debt_amount) = match current {
+            Some((key, collateral_amount, debt_amount)) if key == *member => {
+                (collateral_amount, debt_amount)
+            }
+            _ => {
+                let vault = Account::<Vault>::try_from(account_info)?;
+                (vault.collateral_amount, vault.debt_amount)
+            }
+        };
+        total_collateral = total_collateral
+            .checked_add(collateral_amount)
+            .ok_or(GusdError::MathOverflow)?;
+        total_debt = total_debt
+            .checked_add(debt_amount)
+            .ok_or(GusdError::MathOverflow)?;
+    }
+
+    Ok((total_collateral, total_debt))
+}
+
+/// Debt (GUSD) the vault's extra collateral balances support, each valued at its own
+/// price and divided by its type's minimum collateral ratio, or its liquidation threshold
+/// for liquidations (see `balance_debt_capacity`)
+/// `balances` holds `[collateral_balance, collateral_config, price_feed, price_update]` for
+/// each of `vault.collateral_balances`, in order. `current` overrides one balance's amount.
+fn balances_debt_capacity<'info>(
+    protocol: &ProtocolState,
+    deployment: &DeploymentConfig,
+    vault: &Account<Vault>,
+    balances: &'info [AccountInfo<'info>],
+    operation: PriceOperation,
+    current: Option<(Pubkey, u64)>,
+) -> Result<u64> {
+    if vault.collateral_balances.is_empty() {
+        return Ok(0);
+    }
+    require!(
+        balances.len() == vault.collateral_balances.len() * 4,
+        GusdError::CollateralBalancesMismatch
+    );
+
+    let liquidation = matches!(operation, PriceOperation::Liquidation);
+    let mut capacity: u64 = 0;
+    for (group, mint) in balances.chunks_exact(4).zip(vault.collateral_balances.iter()) {
+        let balance = Account::<CollateralBalance>::try_from(&group[0])?;
+        require!(
+            balance.vault == vault.key() && balance.collateral_mint == *mint,
+            GusdError::CollateralBalancesMismatch
+        );
+        let amount = match current {
+            Some((key, amount)) if key == *mint => amount,
+            _ => balance.amount,
+        };
+        if amount == 0 {
+            continue;
+        }
+
+        let config = Account::<CollateralConfig>::try_from(&group[1])?;
+        let feed = Account::<PriceFeed>::try_from(&group[2])?;
+        require!(
+            config.collateral_mint == *mint && feed.collateral_mint == *mint,
+            GusdError::CollateralBalancesMismatch
+        );
+        // Same price bounds as the primary collateral: liquidations need a fresh price
+        let price = if liquidation {
+            get_price(protocol, &feed, deployment, Some(&group[3]), PriceBound::Upper, operation)?
+        } else {
+            get_price_with_fallback(
+                protocol,
+                &feed,
+                deployment,
+                Some(&group[3]),
+                PriceBound::Lower,
+                operation,
+            )?
+        };
+        let value_usd = calculate_usd_value(amount, price, feed.decimals)?;
+        capacity = capacity.saturating_add(balance_debt_capacity(&config, value_usd, liquidation)?);
+    }
+
+    Ok(capacity)
+}
+
+/// Debt (GUSD) one balance worth `value_usd` supports at its type's minimum collateral
+/// ratio, or its liquidation threshold for liquidations
+/// Debt a balance backs is charged to the vault's primary type, so outside liquidations
+/// the balance's own type can't back more than the room left under its debt ceiling.
+fn balance_debt_capacity(config: &CollateralConfig, value_usd: u64, liquidation: bool) -> Result<u64> {
+    let ratio_bps = if liquidation {
+        config.liquidation_threshold_bps
+    } else {
+        config.min_collateral_ratio_bps
+    };
+    let supported = (value_usd as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(ratio_bps as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    let supported = supported.min(u64::MAX as u128) as u64;
+    if liquidation || config.debt_ceiling == 0 {
+        return Ok(supported);
+    }
+    Ok(supported.min(config.debt_ceiling.saturating_sub(config.total_debt)))
+}
+
+/// Distance of a GUSD market price from $1 (bps)
+fn peg_deviation_bps(price: u64) -> Result<u64> {
+    let deviation = (price.abs_diff(GUSD_PEG_PRICE) as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / GUSD_PEG_PRICE as u128;
+    Ok(deviation.min(u64::MAX as u128) as u64)
+}
+
+/// Collateral ratio a margin account must keep after mints and withdrawals: the GOR
+/// type's minimum plus the margin buffer
+fn margin_min_collateral_ratio_bps(protocol: &ProtocolState, config: &CollateralConfig) -> Result<u64> {
+    config.min_collateral_ratio_bps
+        .checked_add(protocol.margin_buffer_bps)
+        .ok_or(error!(GusdError::MathOverflow))
+}
+
+/// Record owner activity on a vault, clearing any abandoned flag
+fn record_vault_activity(vault: &mut Vault, now: i64) {
+    vault.last_activity_ts = now;
+    vault.abandoned_flagged_at = 0;
+}
+
+/// Extend the vault's repayment streak, or restart it after a missed period
+/// The streak counts the 30-day periods since it started, as long as no gap between
+/// repayments exceeds one period.
+fn record_repayment(vault: &mut Vault, now: i64) {
+    let missed = vault.last_repayment_ts == 0
+        || now.saturating_sub(vault.last_repayment_ts) > REPAYMENT_PERIOD_SECS;
+    if missed {
+        vault.streak_started_at = now;
+        vault.repayment_streak = 1;
+    } else {
+        let periods = now.saturating_sub(vault.streak_started_at) / REPAYMENT_PERIOD_SECS + 1;
+        vault.repayment_streak = vault.repayment_streak.max(periods.min(u16::MAX as i64) as u16);
+    }
+    vault.last_repayment_ts = now;
+}
+
+/// Current repayment streak, or 0 once the last repayment is more than one period old
+fn active_repayment_streak(vault: &Vault, now: i64) -> u16 {
+    if vault.last_repayment_ts == 0
+        || now.saturating_sub(vault.last_repayment_ts) > REPAYMENT_PERIOD_SECS
+    {
+        return 0;
+    }
+    vault.repayment_streak
+}
+
+/// Stability-fee rebate (bps of the fee) earned by a repayment streak
+fn repayment_rebate_bps(streak: u16) -> u64 {
+    REPAYMENT_REBATE_TIERS
+        .iter()
+        .rev()
+        .find(|(min_streak, _)| streak >= *min_streak)
+        .map_or(0, |(_, rebate_bps)| *rebate_bps)
+}
+
+/// Annual stability fee in force (bps)
+/// With a rate model configured, the rate follows a kinked curve of utilization
+/// (`total_debt / rate_target_debt`, capped at 100%): it rises from the base rate by
+/// `rate_slope1_bps` up to the kink, then by `rate_slope2_bps` up to full utilization.
+fn current_stability_fee_bps(protocol: &ProtocolState) -> Result<u64> {
+    if protocol.rate_target_debt == 0 {
+        return Ok(protocol.stability_fee_bps);
+    }
+
+    let utilization_bps = ((protocol.total_debt as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / protocol.rate_target_debt as u128)
+        .min(BPS_DENOMINATOR as u128) as u64;
+    let kink = protocol.rate_kink_bps;
+    let below_kink = utilization_bps.min(kink);
+    let above_kink = utilization_bps.saturating_sub(kink);
+
+    // Bounded by the parameter limits, so no overflow in u64
+    Ok(protocol.rate_base_bps
+        + protocol.rate_slope1_bps * below_kink / kink
+        + protocol.rate_slope2_bps * above_kink / (BPS_DENOMINATOR - kink))
+}
+
+/// Advance the global debt index to `now` at the annual stability fee in force
+/// Time while accrual is paused is skipped rather than charged.
+fn accrue_debt_index(protocol: &mut ProtocolState, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(protocol.debt_index_updated_at).max(0) as u128;
+    protocol.debt_index_updated_at = protocol.debt_index_updated_at.max(now);
+    let rate_bps = current_stability_fee_bps(protocol)?;
+    if elapsed == 0 || protocol.accrual_paused_at != 0 || rate_bps == 0 {
+        return Ok(());
+    }
+
+    let growth = protocol.debt_index
+        .checked_mul(rate_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_mul(elapsed)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    protocol.debt_index = protocol.debt_index
+        .checked_add(growth)
+        .ok_or(GusdError::MathOverflow)?;
+    Ok(())
+}
+
+/// Add `amount` to a lifetime revenue counter
+fn record_revenue(counter: &mut u64, amount: u64) -> Result<()> {
+    *counter = counter.checked_add(amount).ok_or(GusdError::MathOverflow)?;
+    Ok(())
+}
+
+/// Add `amount` to a lifetime supply counter
+fn record_supply(counter: &mut u64, amount: u64) -> Result<()> {
+    *counter = counter.checked_add(amount).ok_or(GusdError::MathOverflow)?;
+    Ok(())
+}
+
+/// Interest accrual clock: wall-clock time less every interval accrual was paused
+fn accrual_clock(protocol: &ProtocolState, now: i64) -> i64 {
+    let paused_secs = if protocol.accrual_paused_at != 0 {
+        now.saturating_sub(protocol.accrual_paused_at).max(0)
+    } else {
+        0
+    };
+    now.saturating_sub(protocol.accrual_skipped_secs as i64)
+        .saturating_sub(paused_secs)
+}
+
+/// Advance a collateral's premium index to `now` at its `stability_fee_premium_bps`
+/// Runs on the accrual clock, so paused accrual skips the premium as well.
+fn accrue_premium_index(protocol: &ProtocolState, feed: &mut PriceFeed, now: i64) -> Result<()> {
+    let clock = accrual_clock(protocol, now);
+    let elapsed = clock.saturating_sub(feed.premium_index_updated_at).max(0) as u128;
+    feed.premium_index_updated_at = feed.premium_index_updated_at.max(clock);
+    let rate_bps = feed.fees.stability_fee_premium_bps;
+    if elapsed == 0 || rate_bps == 0 {
+        return Ok(());
+    }
+
+    let growth = feed.premium_index
+        .checked_mul(rate_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_mul(elapsed)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    feed.premium_index = feed.premium_index
+        .checked_add(growth)
+        .ok_or(GusdError::MathOverflow)?;
+    Ok(())
+}
+
+/// Book GUSD fee revenue. `savings_fee_share_bps` of it is set aside for savers and
+/// `insurance_fee_share_bps` for the insurance fund; the rest fills the surplus buffer up
+/// to `surplus_buffer_cap`, and anything above the cap overflows to `accrued_fees`, which
+/// `collect_fees` mints to the treasury.
+fn book_fee_revenue(protocol: &mut ProtocolState, amount: u64) -> Result<()> {
+    let to_savings = (amount as u128)
+        .checked_mul(protocol.savings_fee_share_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    let to_savings = to_savings as u64;
+    protocol.savings_accrued = protocol.savings_accrued
+        .checked_add(to_savings)
+        .ok_or(GusdError::MathOverflow)?;
+    let to_insurance = (amount as u128)
+        .checked_mul(protocol.insurance_fee_share_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    let to_insurance = to_insurance as u64;
+    protocol.insurance_accrued = protocol.insurance_accrued
+        .checked_add(to_insurance)
+        .ok_or(GusdError::MathOverflow)?;
+    let amount = amount - to_savings - to_insurance;
+
+    let surplus = protocol.surplus_buffer
+        .checked_add(amount)
+        .ok_or(GusdError::MathOverflow)?;
+    let overflow = surplus.saturating_sub(protocol.surplus_buffer_cap);
+    protocol.surplus_buffer = surplus - overflow;
+    protocol.accrued_fees = protocol.accrued_fees
+        .checked_add(overflow)
+        .ok_or(GusdError::MathOverflow)?;
+
+    // Without a buffer every fee overflows; only report releases from a configured one
+    if overflow > 0 && protocol.surplus_buffer_cap > 0 {
+        emit!(SurplusReleased {
+            amount: overflow,
+            surplus_buffer: protocol.surplus_buffer,
+            accrued_fees: protocol.accrued_fees,
+        });
+    }
+    Ok(())
+}
+
+/// Unminted fee revenue taken back by `reverse_fee_revenue`, by where it was booked
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ReversedFeeRevenue {
+    savings: u64,
+    insurance: u64,
+    surplus: u64,
+    accrued_fees: u64,
+}
+
+impl ReversedFeeRevenue {
+    fn total(&self) -> u64 {
+        self.savings + self.insurance + self.surplus + self.accrued_fees
+    }
+}
+
+/// Take back the revenue `book_fee_revenue` booked for up to `amount` of fees that won't
+/// be paid in GUSD. The savers' and insurance shares come out of their unminted accruals
+/// and the rest out of the surplus buffer, then `accrued_fees`; shares already minted are
+/// covered from those two as well. Revenue minted from every pool can't be taken back, so
+/// the total may fall short of `amount`.
+fn reverse_fee_revenue(protocol: &mut ProtocolState, amount: u64) -> Result<ReversedFeeRevenue> {
+    let share = |bps: u64| -> Result<u64> {
+        let share = (amount as u128)
+            .checked_mul(bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        Ok(share as u64)
+    };
+    let savings = share(protocol.savings_fee_share_bps)?.min(protocol.savings_accrued);
+    let insurance = share(protocol.insurance_fee_share_bps)?.min(protocol.insurance_accrued);
+    let rest = amount - savings - insurance;
+    let surplus = rest.min(protocol.surplus_buffer);
+    let accrued_fees = (rest - surplus).min(protocol.accrued_fees);
+
+    protocol.savings_accrued -= savings;
+    protocol.insurance_accrued -= insurance;
+    protocol.surplus_buffer -= surplus;
+    protocol.accrued_fees -= accrued_fees;
+    Ok(ReversedFeeRevenue {
+        savings,
+        insurance,
+        surplus,
+        accrued_fees,
+    })
+}
+
+/// Mint the savers' share of fee revenue into the savings pool, raising the share price
+/// With no shares outstanding it goes to the treasury instead. After a shutdown it stays
+/// unminted with the settlement, like `accrued_fees`.
+fn drip_savings<'info>(
+    protocol_state: &mut Account<'info, ProtocolState>,
+    savings_pool: &mut Account<'info, SavingsPool>,
+    supply_stats: &mut Account<'info, SupplyStats>,
+    savings_gusd_account: &InterfaceAccount<'info, TokenAccount>,
+    gusd_mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let amount = protocol_state.savings_accrued;
+    if amount == 0 || protocol_state.shutdown_at != 0 {
+        return Ok(());
+    }
+    protocol_state.savings_accrued = 0;
+    if savings_pool.total_shares == 0 {
+        protocol_state.accrued_fees = protocol_state.accrued_fees
+            .checked_add(amount)
+            .ok_or(GusdError::MathOverflow)?;
+        return Ok(());
+    }
+
+    let seeds = &[SEED_NAMESPACE, b"protocol".as_ref(), &[protocol_state.bump]];
+    let signer_seeds = &[&seeds[..]];
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            MintTo {
+                mint: gusd_mint.to_account_info(),
+                to: savings_gusd_account.to_account_info(),
+                authority: protocol_state.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+    )?;
+    record_supply(&mut supply_stats.minted_savings_yield, amount)?;
+    savings_pool.total_assets = savings_pool.total_assets
+        .checked_add(amount)
+        .ok_or(GusdError::MathOverflow)?;
+
+    emit!(SavingsDripped {
+        amount,
+        total_assets: savings_pool.total_assets,
+        total_shares: savings_pool.total_shares,
+    });
+    Ok(())
+}
+
+/// Staker discount off a vault's stability fees at `now`: the tier discount of the stake
+/// recorded on the vault, read from the stake itself so unstaking, tier changes, and the
+/// warm-up take effect. Without the registry and the recorded stake there is none.
+fn vault_stake_discount_bps(
+    vault: &Vault,
+    registry: Option<&FeeTierRegistry>,
+    stake: Option<(Pubkey, &GovernanceStake)>,
+    now: i64,
+) -> u64 {
+    match (registry, stake) {
+        (Some(registry), Some((stake_key, stake)))
+            if vault.discount_stake != Pubkey::default() && stake_key == vault.discount_stake =>
+        {
+            registry.discount_bps(stake, now)
+        }
+        _ => 0,
+    }
+}
+
+/// Bring a vault's debt up to the current debt index and its collateral's premium index,
+/// returning the interest charged
+/// The vault's repayment-streak rebate is taken off first, then the staker discount from
+/// `vault_stake_discount_bps`; the rest is added to its debt and to `total_debt`, and
+/// booked as protocol revenue.
+fn accrue_vault_interest(
+    protocol: &mut ProtocolState,
+    feed: &mut PriceFeed,
+    vault: &mut Vault,
+    stats: &mut RevenueStats,
+    stake_discount_bps: u64,
+    now: i64,
+) -> Result<u64> {
+    accrue_debt_index(protocol, now)?;
+    accrue_premium_index(protocol, feed, now)?;
+    if vault.interest_exempt {
+        return Ok(0);
+    }
+    let snapshot = std::mem::replace(&mut vault.debt_index_snapshot, protocol.debt_index);
+    let premium_snapshot =
+        std::mem::replace(&mut vault.premium_index_snapshot, feed.premium_index);
+    let last_accrued_at = std::mem::replace(&mut vault.last_accrued_at, now);
+    if snapshot == 0 || vault.debt_amount == 0 {
+        return Ok(0);
+    }
+
+    let base = (vault.debt_amount as u128)
+        .checked_mul(protocol.debt_index.saturating_sub(snapshot))
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(snapshot)
+        .ok_or(GusdError::MathOverflow)?;
+    let premium = if premium_snapshot == 0 {
+        0
+    } else {
+        (vault.debt_amount as u128)
+            .checked_mul(feed.premium_index.saturating_sub(premium_snapshot))
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(premium_snapshot)
+            .ok_or(GusdError::MathOverflow)?
+    };
+    let floating = base.checked_add(premium).ok_or(GusdError::MathOverflow)?;
+    let gross = apply_rate_lock(protocol, vault, floating, last_accrued_at, now)?;
+    let rebate_bps = repayment_rebate_bps(active_repayment_streak(vault, now));
+    let rebate = gross
+        .checked_mul(rebate_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    vault.stake_discount_bps = stake_discount_bps;
+    let staker_discount = (gross - rebate)
+        .checked_mul(stake_discount_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / BPS_DENOMINATOR as u128;
+    let interest = gross - rebate - staker_discount;
+    require!(interest <= u64::MAX as u128, GusdError::MathOverflow);
+    let interest = interest as u64;
+    if interest == 0 {
+        return Ok(0);
+    }
+
+    vault.debt_amount = vault.debt_amount
+        .checked_add(interest)
+        .ok_or(GusdError::MathOverflow)?;
+    vault.unpaid_interest = vault.unpaid_interest.saturating_add(interest);
+    protocol.total_debt = protocol.total_debt
+        .checked_add(interest)
+        .ok_or(GusdError::MathOverflow)?;
+    book_fee_revenue(protocol, interest)?;
+    record_revenue(&mut stats.stability_fees, interest)?;
+
+    emit!(InterestAccrued {
+        owner: vault.owner,
+        interest,
+        rebate: rebate as u64,
+        staker_discount: staker_discount as u64,
+        total_debt: vault.debt_amount,
+        debt_index: protocol.debt_index,
+        premium_index: feed.premium_index,
+        position_mint: vault.position_mint,
+    });
+
+    Ok(interest)
+}
+
+/// Replace the floating interest on a vault's locked principal with interest at its
+/// locked rate for the part of `last_accrued_at..now` the lock covered. The floating
+/// rate is taken as uniform over the period when splitting it at the lock's expiry.
+fn apply_rate_lock(
+    protocol: &ProtocolState,
+    vault: &Vault,
+    floating: u128,
+    last_accrued_at: i64,
+    now: i64,
+) -> Result<u128> {
+    let locked_secs = now.min(vault.rate_locked_until).saturating_sub(last_accrued_at);
+    let total_secs = now.saturating_sub(last_accrued_at);
+    if last_accrued_at == 0 || locked_secs <= 0 || total_secs <= 0 {
+        return Ok(floating);
+    }
+
+    let principal = vault.debt_amount.min(vault.rate_lock_principal) as u128;
+    let floating_on_locked = floating
+        .checked_mul(principal)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_mul(locked_secs as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(vault.debt_amount as u128 * total_secs as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    // Paused accrual skips locked interest too
+    let locked = if protocol.accrual_paused_at != 0 {
+        0
+    } else {
+        principal
+            .checked_mul(vault.locked_rate_bps as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_mul(locked_secs as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(BPS_DENOMINATOR as u128 * SECONDS_PER_YEAR as u128)
+            .ok_or(GusdError::MathOverflow)?
+    };
+    Ok(floating - floating_on_locked + locked)
+}
+
+/// Calculate collateral ratio (BPS) of a position at the given price
+/// Returns u64::MAX when there is no debt (infinite ratio).
+fn calculate_collateral_ratio_bps(
+    collateral_amount: u64,
+    debt_amount: u64,
+    gor_price_usd: u64,
+    gor_decimals: u8,
+) -> Result<u64> {
+    if debt_amount == 0 {
+        return Ok(u64::MAX);
+    }
+
+    GorLamports(collateral_amount)
+        .to_usd(UsdValue6(gor_price_usd), gor_decimals)?
+        .ratio_bps(GusdUnits(debt_amount))
+}
+
+/// Collateral ratio of the vault's primary collateral against the debt its extra
+/// balances don't cover, as `liquidate` (for `PriceOperation::Liquidation`) or mints and
+/// withdrawals judge it; `u64::MAX` once the balances cover all of it
+fn uncovered_collateral_ratio_bps<'info>(
+    protocol: &ProtocolState,
+    deployment: &DeploymentConfig,
+    vault: &Account<Vault>,
+    balances: &'info [AccountInfo<'info>],
+    operation: PriceOperation,
+    price: u64,
+    decimals: u8,
+) -> Result<u64> {
+    let uncovered_debt = vault.debt_amount.saturating_sub(balances_debt_capacity(
+        protocol,
+        deployment,
+        vault,
+        balances,
+        operation,
+        None,
+    )?);
+    calculate_collateral_ratio_bps(vault.collateral_amount, uncovered_debt, price, decimals)
+}
+
+// ============================================================================
+// ACCOUNTS
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + ProtocolState::INIT_SPACE,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        mint::decimals = GUSD_DECIMALS,
+        mint::authority = protocol_state,
+        mint::freeze_authority = protocol_state,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump
+    )]
+    pub gusd_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + DeploymentConfig::INIT_SPACE,
+        seeds = [b"deployment"],
+        bump
+    )]
+    pub deployment_config: Box<Account<'info, DeploymentConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceAccumulator::INIT_SPACE,
+        seeds = [b"price_accumulator"],
+        bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + CollateralConfig::INIT_SPACE,
+        seeds = [b"collateral_config", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump
+    )]
+    pub collateral_config: Box<Account<'info, CollateralConfig>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RevenueStats::INIT_SPACE,
+        seeds = [b"revenue_stats"],
+        bump
+    )]
+    pub revenue_stats: Box<Account<'info, RevenueStats>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SupplyStats::INIT_SPACE,
+        seeds = [b"supply_stats"],
+        bump
+    )]
+    pub supply_stats: Box<Account<'info, SupplyStats>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePrice<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+}
+
+#[derive(Accounts)]
+#[instruction(collateral_mint: Pubkey)]
+pub struct InitPriceFeed<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PriceFeed::INIT_SPACE,
+        seeds = [b"price_feed", collateral_mint.as_ref()],
+        bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(collateral_mint: Pubkey)]
+pub struct AddCollateralType<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Feed valuing the collateral, registered first with `init_price_feed`
+    #[account(
+        seeds = [b"price_feed", collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + CollateralConfig::INIT_SPACE,
+        seeds = [b"collateral_config", collateral_mint.as_ref()],
+        bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralType<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_config.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// Changelog page the first entry lands in
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    /// Following changelog page, for entries past the end of `parameter_log`
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64 + 1)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub next_parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetStakePoolProgram<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetLiquidStakingPool<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = native_price_feed.bump
+    )]
+    pub native_price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump,
+        constraint = price_feed.collateral_mint != NATIVE_COLLATERAL_MINT @ GusdError::InvalidStakePool
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Stake pool account; its owner is checked here, its layout in read_stake_pool_rate
+    #[account(
+        owner = deployment_config.stake_pool_program @ GusdError::InvalidStakePool
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefreshExchangeRate<'info> {
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: The feed's stake pool, bound and validated by set_liquid_staking_pool
+    #[account(address = price_feed.stake_pool @ GusdError::InvalidStakePool)]
+    pub stake_pool: UncheckedAccount<'info>,
+
+    /// Registered keeper cranking this instruction; pass with `keeper_account` for a
+    /// fee rebate credit
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigurePriceFeed<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+}
+
+#[derive(Accounts)]
+pub struct SetOracle<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Changelog page the entry lands in
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Admin-set protocol parameters that are appended to the parameter changelog
+#[derive(Accounts)]
+pub struct SetAdminParameters<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Changelog page the first entry lands in
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    /// Following changelog page, for entries past the end of `parameter_log`
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64 + 1)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub next_parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPriceFeeders<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + FeederRegistry::INIT_SPACE,
+        seeds = [b"feeders"],
+        bump
+    )]
+    pub feeder_registry: Account<'info, FeederRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceSigned<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        mut,
+        seeds = [b"feeders"],
+        bump = feeder_registry.bump
+    )]
+    pub feeder_registry: Account<'info, FeederRegistry>,
+
+    /// CHECK: Instructions sysvar, used to find the ed25519 signature checks
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceChainlink<'info> {
+    pub submitter: Signer<'info>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Chainlink verifier program; address checked against deployment_config
+    #[account(
+        executable,
+        address = deployment_config.chainlink_verifier_program @ GusdError::InvalidOracleAccount
+    )]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// CHECK: Verifier state account configured via `set_oracle`
+    #[account(
+        address = price_feed.oracle_account @ GusdError::InvalidOracleAccount
+    )]
+    pub verifier_account: UncheckedAccount<'info>,
+
+    /// CHECK: Verifier access controller; validated by the verifier program
+    pub access_controller: UncheckedAccount<'info>,
+
+    /// CHECK: Report config account; validated by the verifier program
+    pub config_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CrankPrice<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in read_oracle_price
+    pub price_update: UncheckedAccount<'info>,
+
+    /// Registered keeper cranking this instruction; pass with `keeper_account` for a
+    /// fee rebate credit
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePricesBatch<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    /// Registered keeper cranking this instruction; pass with `keeper_account` for a
+    /// fee rebate credit
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPriceSample<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// Registered keeper cranking this instruction; pass with `keeper_account` for a
+    /// fee rebate credit
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct GetTwap<'info> {
+    #[account(
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+}
+
+#[derive(Accounts)]
+pub struct InitPegState<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PegState::INIT_SPACE,
+        seeds = [b"peg_state"],
+        bump
+    )]
+    pub peg_state: Account<'info, PegState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePegPrice<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"peg_state"],
+        bump = peg_state.bump
+    )]
+    pub peg_state: Account<'info, PegState>,
+
+    /// Price feed registered for the GUSD mint
+    #[account(
+        seeds = [b"price_feed", protocol_state.gusd_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFxRate<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    /// Price feed quoting the peg currency in USD
+    #[account(
+        constraint = fx_price_feed.key() == protocol_state.fx_price_feed @ GusdError::InvalidFxPriceFeed,
+        seeds = [b"price_feed", fx_price_feed.collateral_mint.as_ref()],
+        bump = fx_price_feed.bump
+    )]
+    pub fx_price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against fx_price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct GetPegState<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"peg_state"],
+        bump = peg_state.bump
+    )]
+    pub peg_state: Account<'info, PegState>,
+}
+
+#[derive(Accounts)]
+pub struct GetFeeRates<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+}
+
+/// [LOW-2] Admin transfer accounts struct
+#[derive(Accounts)]
+pub struct TransferAdmin<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+}
+
+#[derive(Accounts)]
+pub struct RiskManagerOnly<'info> {
+    #[account(
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+}
+
+#[derive(Accounts)]
+pub struct SetCollateralFees<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Changelog page the first entry lands in
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    /// Following changelog page, for entries past the end of `parameter_log`
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64 + 1)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub next_parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetRiskParameter<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Changelog page the next entry lands in
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyRiskTemplate<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Changelog page the first entry lands in
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    /// Following changelog page, for entries past the end of `parameter_log`
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64 + 1)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub next_parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencySetPrice<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    /// Must be a different key from the admin, so one compromised key cannot act alone
+    #[account(
+        constraint = guardian.key() == protocol_state.guardian @ GusdError::Unauthorized,
+        constraint = guardian.key() != admin.key() @ GusdError::Unauthorized
+    )]
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", price_feed.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianOnly<'info> {
+    #[account(
+        constraint = guardian.key() == protocol_state.guardian @ GusdError::Unauthorized
+    )]
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+}
+
+#[derive(Accounts)]
+#[instruction(address: Pubkey)]
+pub struct AddFeeExemption<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + FeeExemption::INIT_SPACE,
+        seeds = [b"fee_exempt", address.as_ref()],
+        bump
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFeeExemption<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        close = risk_manager,
+        seeds = [b"fee_exempt", fee_exemption.address.as_ref()],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Account<'info, FeeExemption>,
+}
+
+#[derive(Accounts)]
+pub struct InitFeeTiers<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + FeeTierRegistry::INIT_SPACE,
+        seeds = [b"fee_tiers"],
+        bump
+    )]
+    pub fee_tier_registry: Account<'info, FeeTierRegistry>,
+
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+
+    /// Staked governance tokens (owned by the registry PDA)
+    #[account(
+        init,
+        payer = admin,
+        token::mint = governance_mint,
+        token::authority = fee_tier_registry,
+        token::token_program = token_program,
+        seeds = [b"gov_stake_escrow"],
+        bump
+    )]
+    pub stake_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFeeTiers<'info> {
+    #[account(
+        mut,
+        constraint = risk_manager.key() == protocol_state.risk_manager @ GusdError::Unauthorized
+    )]
+    pub risk_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Account<'info, FeeTierRegistry>,
+
+    /// Changelog page the first entry lands in
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub parameter_log: Account<'info, ParameterLogPage>,
+
+    /// Following changelog page, for entries past the end of `parameter_log`
+    #[account(
+        init_if_needed,
+        payer = risk_manager,
+        space = 8 + ParameterLogPage::INIT_SPACE,
+        seeds = [
+            b"parameter_log",
+            (protocol_state.parameter_log_entries / PARAMETER_LOG_PAGE_SIZE as u64 + 1)
+                .to_le_bytes()
+                .as_ref()
+        ],
+        bump
+    )]
+    pub next_parameter_log: Account<'info, ParameterLogPage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StakeGovernance<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump,
+        has_one = governance_mint
+    )]
+    pub fee_tier_registry: Account<'info, FeeTierRegistry>,
+
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = governance_mint,
+        token::authority = owner,
+        token::token_program = token_program
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"gov_stake_escrow"],
+        bump
+    )]
+    pub stake_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + GovernanceStake::INIT_SPACE,
+        seeds = [b"gov_stake", owner.key().as_ref()],
+        bump
+    )]
+    pub governance_stake: Account<'info, GovernanceStake>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeGovernance<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump,
+        has_one = governance_mint
+    )]
+    pub fee_tier_registry: Account<'info, FeeTierRegistry>,
+
+    pub governance_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = governance_mint,
+        token::authority = owner,
+        token::token_program = token_program
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"gov_stake_escrow"],
+        bump
+    )]
+    pub stake_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"gov_stake", owner.key().as_ref()],
+        bump = governance_stake.bump
+    )]
+    pub governance_stake: Account<'info, GovernanceStake>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetDisplayConfig<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + DisplayConfig::INIT_SPACE,
+        seeds = [b"display_config"],
+        bump
+    )]
+    pub display_config: Account<'info, DisplayConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(keeper: Pubkey)]
+pub struct RegisterKeeper<'info> {
+    #[account(
+        mut,
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = treasurer,
+        space = 8 + KeeperAccount::INIT_SPACE,
+        seeds = [b"keeper", keeper.as_ref()],
+        bump
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveKeeper<'info> {
+    #[account(
+        mut,
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        close = treasurer,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimKeeperRebate<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper.key().as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Account<'info, KeeperAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint
+    )]
+    pub keeper_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(pool: Pubkey)]
+pub struct RegisterPolPool<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + PolPosition::INIT_SPACE,
+        seeds = [b"pol_position", pool.as_ref()],
+        bump
+    )]
+    pub pol_position: Account<'info, PolPosition>,
+
+    /// The pool's LP token
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: AMM program the pool belongs to; whitelisted here
+    #[account(executable)]
+    pub amm_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DeployPol<'info> {
+    #[account(
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"pol_position", pol_position.pool.as_ref()],
+        bump = pol_position.bump
+    )]
+    pub pol_position: Account<'info, PolPosition>,
+
+    #[account(
+        address = pol_position.lp_mint @ GusdError::InvalidPolRoute
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    /// Treasury's LP tokens for the pool
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = treasury
+    )]
+    pub treasury_lp_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: The pool's AMM program; address checked against pol_position
+    #[account(
+        executable,
+        address = pol_position.amm_program @ GusdError::InvalidPolRoute
+    )]
+    pub amm_program: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPol<'info> {
+    #[account(
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"pol_position", pol_position.pool.as_ref()],
+        bump = pol_position.bump
+    )]
+    pub pol_position: Account<'info, PolPosition>,
+
+    #[account(
+        address = pol_position.lp_mint @ GusdError::InvalidPolRoute
+    )]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    /// Treasury's LP tokens for the pool
+    #[account(
+        mut,
+        token::mint = lp_mint,
+        token::authority = treasury
+    )]
+    pub treasury_lp_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: The pool's AMM program; address checked against pol_position
+    #[account(
+        executable,
+        address = pol_position.amm_program @ GusdError::InvalidPolRoute
+    )]
+    pub amm_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetSwapRoute<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetKycAttestation<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    /// Issuer's attestation mint; omit to lift the requirement
+    pub attestation_mint: Option<InterfaceAccount<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+pub struct InitTreasury<'info> {
+    #[account(
+        mut,
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// Treasury GUSD account (owned by the treasury PDA)
+    #[account(
+        init,
+        payer = treasurer,
+        token::mint = gusd_mint,
+        token::authority = treasury,
+        token::token_program = token_program,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeTreasurySpend<'info> {
+    #[account(
+        mut,
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = treasurer,
+        space = 8 + TreasurySpend::INIT_SPACE,
+        seeds = [b"treasury_spend", protocol_state.treasury_spend_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub treasury_spend: Account<'info, TreasurySpend>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTreasurySpend<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"treasury_spend", treasury_spend.id.to_le_bytes().as_ref()],
+        bump = treasury_spend.bump
+    )]
+    pub treasury_spend: Account<'info, TreasurySpend>,
+
+    /// Receives the proposal's rent
+    #[account(
+        mut,
+        address = treasury_spend.proposer @ GusdError::Unauthorized
+    )]
+    pub proposer: SystemAccount<'info>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        address = treasury_spend.recipient @ GusdError::InvalidFeeDestination
+    )]
+    pub recipient: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::authority = recipient
+    )]
+    pub recipient_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTreasurySpend<'info> {
+    #[account(
+        constraint = guardian.key() == protocol_state.guardian @ GusdError::Unauthorized
+    )]
+    pub guardian: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"treasury_spend", treasury_spend.id.to_le_bytes().as_ref()],
+        bump = treasury_spend.bump
+    )]
+    pub treasury_spend: Account<'info, TreasurySpend>,
+
+    /// Receives the proposal's rent
+    #[account(
+        mut,
+        address = treasury_spend.proposer @ GusdError::Unauthorized
+    )]
+    pub proposer: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitSavings<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SavingsPool::INIT_SPACE,
+        seeds = [b"savings"],
+        bump
+    )]
+    pub savings_pool: Account<'info, SavingsPool>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// Pool GUSD account (owned by the savings pool PDA)
+    #[account(
+        init,
+        payer = admin,
+        token::mint = gusd_mint,
+        token::authority = savings_pool,
+        token::token_program = token_program,
+        seeds = [b"savings_gusd"],
+        bump
+    )]
+    pub savings_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSavings<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [b"savings"],
+        bump = savings_pool.bump
+    )]
+    pub savings_pool: Account<'info, SavingsPool>,
+
+    #[account(
+        mut,
+        seeds = [b"savings_gusd"],
+        bump
+    )]
+    pub savings_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + SavingsPosition::INIT_SPACE,
+        seeds = [b"savings_position", owner.key().as_ref()],
+        bump
+    )]
+    pub savings_position: Account<'info, SavingsPosition>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawSavings<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [b"savings"],
+        bump = savings_pool.bump
+    )]
+    pub savings_pool: Account<'info, SavingsPool>,
+
+    #[account(
+        mut,
+        seeds = [b"savings_gusd"],
+        bump
+    )]
+    pub savings_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"savings_position", owner.key().as_ref()],
+        bump = savings_position.bump
+    )]
+    pub savings_position: Account<'info, SavingsPosition>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct StartBootstrapAuction<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + BootstrapAuction::INIT_SPACE,
+        seeds = [b"bootstrap"],
+        bump
+    )]
+    pub bootstrap_auction: Account<'info, BootstrapAuction>,
+
+    /// Protocol-owned vault backing the tranche, owned by the bootstrap PDA
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [SEED_NAMESPACE, b"vault", BOOTSTRAP_VAULT_POSITION.as_ref()],
+        bump
+    )]
+    pub bootstrap_vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 0,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", BOOTSTRAP_VAULT_POSITION.as_ref()],
+        bump
+    )]
+    /// CHECK: PDA that holds GOR collateral as lamports
+    pub bootstrap_vault_collateral: AccountInfo<'info>,
+
+    /// Protocol treasury (lamport PDA); funds the collateral
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Protocol-owned liquidity (lamport PDA); receives the proceeds, no outflows
+    #[account(
+        mut,
+        seeds = [b"protocol_liquidity"],
+        bump
+    )]
+    pub protocol_liquidity: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// Auction escrow (GUSD token account owned by the bootstrap PDA)
+    #[account(
+        init,
+        payer = admin,
+        token::mint = gusd_mint,
+        token::authority = bootstrap_auction,
+        token::token_program = token_program,
+        seeds = [b"bootstrap_gusd"],
+        bump
+    )]
+    pub bootstrap_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBootstrapGusd<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"bootstrap"],
+        bump = bootstrap_auction.bump
+    )]
+    pub bootstrap_auction: Account<'info, BootstrapAuction>,
+
+    #[account(
+        mut,
+        seeds = [b"bootstrap_gusd"],
+        bump
+    )]
+    pub bootstrap_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::authority = buyer
+    )]
+    pub buyer_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Protocol-owned liquidity (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"protocol_liquidity"],
+        bump
+    )]
+    pub protocol_liquidity: SystemAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeBootstrapAuction<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [b"bootstrap"],
+        bump = bootstrap_auction.bump
+    )]
+    pub bootstrap_auction: Account<'info, BootstrapAuction>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", BOOTSTRAP_VAULT_POSITION.as_ref()],
+        bump = bootstrap_vault.bump
+    )]
+    pub bootstrap_vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"bootstrap_gusd"],
+        bump
+    )]
+    pub bootstrap_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct DiversifyTreasury<'info> {
+    #[account(
+        constraint = treasurer.key() == protocol_state.treasurer @ GusdError::Unauthorized
+    )]
+    pub treasurer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        address = deployment_config.usdc_mint @ GusdError::InvalidSwapRoute
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = treasury
+    )]
+    pub treasury_usdc_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Whitelisted swap program; address checked against deployment_config
+    #[account(
+        executable,
+        address = deployment_config.swap_program @ GusdError::InvalidSwapRoute
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordAuditHash<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + AuditRegistry::INIT_SPACE,
+        seeds = [b"audit_registry"],
+        bump
+    )]
+    pub audit_registry: Account<'info, AuditRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetAuditRecords<'info> {
+    #[account(
+        seeds = [b"audit_registry"],
+        bump = audit_registry.bump
+    )]
+    pub audit_registry: Account<'info, AuditRegistry>,
+}
+
+#[derive(Accounts)]
+pub struct AddToBlocklist<'info> {
+    #[account(
+        mut,
+        constraint = compliance.key() == protocol_state.compliance_authority @ GusdError::Unauthorized
+    )]
+    pub compliance: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::token_program = token_program
+    )]
+    pub target_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = compliance,
+        space = 8 + BlocklistEntry::INIT_SPACE,
+        seeds = [b"blocklist", target_gusd_account.key().as_ref()],
+        bump
+    )]
+    pub blocklist_entry: Account<'info, BlocklistEntry>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromBlocklist<'info> {
+    #[account(
+        mut,
+        constraint = compliance.key() == protocol_state.compliance_authority @ GusdError::Unauthorized
+    )]
+    pub compliance: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::token_program = token_program
+    )]
+    pub target_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        close = compliance,
+        seeds = [b"blocklist", target_gusd_account.key().as_ref()],
+        bump = blocklist_entry.bump
+    )]
+    pub blocklist_entry: Account<'info, BlocklistEntry>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// [CRITICAL-4] Fixed: Now initializes vault_collateral PDA
+#[derive(Accounts)]
+pub struct CreateVault<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [SEED_NAMESPACE, b"vault", position_mint.key().as_ref()],
+        bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// [CRITICAL-4] Initialize the vault_collateral PDA
+    #[account(
+        init,
+        payer = owner,
+        space = 0,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", position_mint.key().as_ref()],
+        bump
+    )]
+    /// CHECK: PDA that holds GOR collateral as lamports
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    /// Collateral type the vault binds to
+    #[account(
+        seeds = [b"collateral_config", collateral_config.collateral_mint.as_ref()],
+        bump = collateral_config.bump,
+        constraint = collateral_config.enabled @ GusdError::CollateralDisabled
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// Owner's attestation token account; required when a KYC attestation mint is set
+    pub kyc_attestation: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Position NFT mint, a fresh keypair; its single token carries ownership of the vault.
+    /// The protocol keeps the freeze authority to retire the NFT with the vault.
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = protocol_state,
+        mint::freeze_authority = protocol_state,
+        mint::token_program = token_program
+    )]
+    pub position_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = position_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub owner_position_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitVaultTokenAccount<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// Pays rent for the new accounts: the owner, their delegate, or anyone else
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault.collateral_mint != NATIVE_COLLATERAL_MINT @ GusdError::UnsupportedCollateral,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::DepositRepay)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: The vault's collateral PDA; owns the token account
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = vault_collateral,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::DepositRepay)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA that holds lamports (GOR)
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// CHECK: Instructions sysvar, used to attribute CPI deposits
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    /// The vault's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// Token collateral only: the vault's collateral mint
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token collateral only: the vault's token account
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the signer's token account of the collateral mint
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint's token program
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateralFor<'info> {
+    /// Pays the collateral; needs no relation to the vault
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: This is a PDA that holds lamports (GOR)
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// The vault's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// Token collateral only: the vault's collateral mint
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token collateral only: the vault's token account
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the depositor's token account of the collateral mint
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub depositor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint's token program
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MintGusd<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// Required when `vault` belongs to a margin account
+    pub margin_account: Option<Account<'info, MarginAccount>>,
+
+    /// Present when the owner is exempt from the mint fee
+    #[account(
+        seeds = [b"fee_exempt", owner.key().as_ref()],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    /// Staker fee tiers; with `governance_stake`, discounts the owner's fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The owner's governance stake
+    #[account(
+        seeds = [b"gov_stake", owner.key().as_ref()],
+        bump = governance_stake.bump
+    )]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(invoice_id: u64)]
+pub struct CreateInvoice<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payee,
+        space = 8 + Invoice::INIT_SPACE,
+        seeds = [b"invoice", payee.key().as_ref(), invoice_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"invoice", invoice.payee.as_ref(), invoice.invoice_id.to_le_bytes().as_ref()],
+        bump = invoice.bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program
+    )]
+    pub payer_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        constraint = payee_gusd_account.owner == invoice.payee @ GusdError::InvalidInvoicePayee
+    )]
+    pub payee_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct PayInvoiceFromVault<'info> {
+    /// The payer's vault and everything `mint_gusd` needs
+    pub vault_mint: MintGusd<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"invoice", invoice.payee.as_ref(), invoice.invoice_id.to_le_bytes().as_ref()],
+        bump = invoice.bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    #[account(
+        mut,
+        constraint = payee_gusd_account.owner == invoice.payee @ GusdError::InvalidInvoicePayee
+    )]
+    pub payee_gusd_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CloseInvoice<'info> {
+    #[account(mut)]
+    pub payee: Signer<'info>,
+
+    #[account(
+        mut,
+        close = payee,
+        seeds = [b"invoice", payee.key().as_ref(), invoice.invoice_id.to_le_bytes().as_ref()],
+        bump = invoice.bump
+    )]
+    pub invoice: Account<'info, Invoice>,
+}
+
+#[derive(Accounts)]
+pub struct RepayGusd<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::DepositRepay)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    /// Price feed of the vault's collateral; carries its stability fee premium
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's collateral type; carries its debt counter
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// The signer's GUSD account (the owner's, or their delegate's)
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::token_program = token_program
+    )]
+    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct RepayGusdFor<'info> {
+    /// Burns its own GUSD; needs no relation to the vault
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    /// Price feed of the vault's collateral; carries its stability fee premium
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's collateral type; carries its debt counter
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::authority = payer,
+        token::token_program = token_program
+    )]
+    pub payer_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct PayFeesWithCollateral<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: PDA that holds GOR collateral as lamports
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    /// Price feed of the vault's collateral; carries its stability fee premium
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// Protocol treasury (lamport PDA); receives the collateral
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct BuyLiquidationInsurance<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// The signer's GUSD account (the owner's, or their delegate's)
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::token_program = token_program
+    )]
+    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Insurance fund (GUSD token account owned by the protocol PDA)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        token::mint = gusd_mint,
+        token::authority = protocol_state,
+        token::token_program = token_program,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCollateral<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA that holds lamports (GOR)
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// Required when `vault` belongs to a margin account
+    pub margin_account: Option<Account<'info, MarginAccount>>,
+
+    /// Protocol treasury (lamport PDA); receives the withdrawal fee
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Present when the owner is exempt from the withdrawal fee
+    #[account(
+        seeds = [b"fee_exempt", owner.key().as_ref()],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    /// Token collateral only: the vault's collateral mint
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token collateral only: the vault's token account
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the owner's token account of the collateral mint
+    #[account(
+        mut,
+        token::mint = vault.collateral_mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the treasury's token account of the collateral mint
+    #[account(
+        mut,
+        token::mint = vault.collateral_mint,
+        token::authority = treasury
+    )]
+    pub treasury_collateral_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint's token program
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one);
+    /// retired with the vault
+    #[account(mut)]
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::InvalidVaultOwner,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: PDA that holds GOR collateral as lamports (0-data account)
+    pub vault_collateral: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Vaults with a position NFT: the protocol, the NFT's freeze authority
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Option<Box<Account<'info, ProtocolState>>>,
+
+    /// Vaults with a position NFT: its mint
+    #[account(mut, address = vault.position_mint)]
+    pub position_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Vaults with a position NFT: its token program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct FlagAbandonedVault<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// CHECK: The owner of the vault being flagged
+    pub vault_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == vault_owner.key() @ GusdError::InvalidVaultOwner
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimVault<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct SetVaultDelegate<'info> {
+    pub owner: Signer<'info>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+}
+
+#[derive(Accounts)]
+pub struct OpenCollateralBalance<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// Pays rent for the new accounts: the owner, their delegate, or anyone else
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault.margin_account == Pubkey::default() @ GusdError::VaultInMarginAccount,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::DepositRepay)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: The vault's collateral PDA; owns the balance's token account
+    pub vault_collateral: AccountInfo<'info>,
+
+    /// The balance's collateral type: an enabled token type other than the vault's own
+    #[account(
+        seeds = [b"collateral_config", collateral_mint.key().as_ref()],
+        bump = collateral_config.bump,
+        constraint = collateral_config.enabled @ GusdError::CollateralDisabled,
+        constraint = collateral_mint.key() != vault.collateral_mint
+            && collateral_mint.key() != NATIVE_COLLATERAL_MINT @ GusdError::UnsupportedCollateral
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CollateralBalance::INIT_SPACE,
+        seeds = [
+            SEED_NAMESPACE,
+            b"collateral_balance",
+            vault.key().as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump
+    )]
+    pub collateral_balance: Account<'info, CollateralBalance>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            SEED_NAMESPACE,
+            b"balance_token",
+            vault.key().as_ref(),
+            collateral_mint.key().as_ref()
+        ],
+        bump,
+        token::mint = collateral_mint,
+        token::authority = vault_collateral,
+        token::token_program = collateral_token_program
+    )]
+    pub balance_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateralBalance<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::DepositRepay)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"collateral_balance",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump = collateral_balance.bump
+    )]
+    pub collateral_balance: Account<'info, CollateralBalance>,
+
+    /// The balance's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_balance.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    #[account(address = collateral_balance.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"balance_token",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump
+    )]
+    pub balance_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The signer's token account of the collateral mint
+    #[account(mut, token::mint = collateral_balance.collateral_mint)]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawCollateralBalance<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: The vault's collateral PDA; owns the balance's token account
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's primary collateral type
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"collateral_balance",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump = collateral_balance.bump
+    )]
+    pub collateral_balance: Account<'info, CollateralBalance>,
+
+    /// The balance's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_balance.collateral_mint.as_ref()],
+        bump = balance_collateral_config.bump
+    )]
+    pub balance_collateral_config: Account<'info, CollateralConfig>,
+
+    #[account(address = collateral_balance.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"balance_token",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump
+    )]
+    pub balance_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// The owner's token account of the collateral mint
+    #[account(
+        mut,
+        token::mint = collateral_balance.collateral_mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Protocol treasury (lamport PDA); owns the account receiving the withdrawal fee
+    #[account(
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Required when a withdrawal fee is charged: the treasury's token account of the
+    /// collateral mint
+    #[account(
+        mut,
+        token::mint = collateral_balance.collateral_mint,
+        token::authority = treasury
+    )]
+    pub treasury_collateral_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Present when the owner is exempt from the withdrawal fee
+    #[account(
+        seeds = [b"fee_exempt", owner.key().as_ref()],
+        bump = fee_exemption.bump
+    )]
+    pub fee_exemption: Option<Account<'info, FeeExemption>>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCollateralBalance<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: The vault's collateral PDA; owns the balance's token account
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"collateral_balance",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump = collateral_balance.bump,
+        close = owner
+    )]
+    pub collateral_balance: Account<'info, CollateralBalance>,
+
+    #[account(address = collateral_balance.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"balance_token",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump
+    )]
+    pub balance_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives tokens sent to the balance's token account directly, if there are any
+    #[account(
+        mut,
+        token::mint = collateral_balance.collateral_mint,
+        token::authority = owner
+    )]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateCollateralBalance<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: The vault's collateral PDA; owns the balance's token account
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program
+    )]
+    pub liquidator_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's primary collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"collateral_balance",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump = collateral_balance.bump
+    )]
+    pub collateral_balance: Account<'info, CollateralBalance>,
+
+    #[account(
+        seeds = [b"price_feed", collateral_balance.collateral_mint.as_ref()],
+        bump = balance_price_feed.bump
+    )]
+    pub balance_price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The balance's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_balance.collateral_mint.as_ref()],
+        bump = balance_collateral_config.bump
+    )]
+    pub balance_collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against balance_price_feed in get_price
+    pub balance_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(address = collateral_balance.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"balance_token",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump
+    )]
+    pub balance_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Receives the seized collateral
+    #[account(mut, token::mint = collateral_balance.collateral_mint)]
+    pub liquidator_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Protocol treasury (lamport PDA); owns the account receiving the protocol fee
+    #[account(
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = collateral_balance.collateral_mint,
+        token::authority = treasury
+    )]
+    pub treasury_collateral_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct SweepAbandonedVault<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// CHECK: The owner of the vault being swept
+    pub vault_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = treasury,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == vault_owner.key() @ GusdError::InvalidVaultOwner
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: PDA that holds GOR collateral as lamports (0-data account)
+    pub vault_collateral: AccountInfo<'info>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Vaults with a position NFT: the token account holding it, frozen with the sweep
+    #[account(mut)]
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vaults with a position NFT: its mint
+    #[account(address = vault.position_mint)]
+    pub position_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Vaults with a position NFT: its token program
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SweepLiquidationFees<'info> {
+    /// CHECK: The owner of the vault being swept
+    pub vault_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == vault_owner.key() @ GusdError::InvalidVaultOwner
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: PDA that holds GOR collateral as lamports (0-data account)
+    pub vault_collateral: AccountInfo<'info>,
+
+    /// Protocol treasury (lamport PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury"],
+        bump
+    )]
+    pub treasury: SystemAccount<'info>,
+
+    /// Token collateral only: the vault's collateral mint
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token collateral only: the vault's token account
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the treasury's token account of the collateral mint
+    #[account(
+        mut,
+        token::mint = vault.collateral_mint,
+        token::authority = treasury
+    )]
+    pub treasury_collateral_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint's token program
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordBadDebt<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// CHECK: The owner of the vault being written off
+    pub vault_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == vault_owner.key() @ GusdError::InvalidVaultOwner
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    /// The vault's collateral type; carries its debt counter
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CoverBadDebt<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// Insurance fund (GUSD token account owned by the protocol PDA)
+    #[account(
+        mut,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Liquidate<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    /// CHECK: Receives any liquidator tip if it is the vault's current owner (see
+    /// `is_vault_owner`); otherwise the tip stays in the vault
+    #[account(mut)]
+    pub vault_owner: AccountInfo<'info>,
+
+    /// The current owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump
+    )]
+    /// CHECK: This is a PDA that holds lamports (GOR)
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program
+    )]
+    pub liquidator_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// The vault's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// Required when `vault` belongs to a margin account
+    pub margin_account: Option<Account<'info, MarginAccount>>,
+
+    /// Token collateral only: the vault's collateral mint
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token collateral only: the vault's token account
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: receives the seized collateral
+    #[account(mut, token::mint = vault.collateral_mint)]
+    pub liquidator_collateral_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint's token program
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// The liquidator's keeper registration, for a fee rebate credit
+    #[account(
+        mut,
+        seeds = [b"keeper", liquidator.key().as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMarginAccount<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MarginAccount::INIT_SPACE,
+        seeds = [b"margin_account", authority.key().as_ref()],
+        bump
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct JoinMarginAccount<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"margin_account", authority.key().as_ref()],
+        bump = margin_account.bump
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+}
+
+#[derive(Accounts)]
+pub struct LeaveMarginAccount<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized,
+        constraint = vault.margin_account == margin_account.key() @ GusdError::MarginAccountMismatch
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [b"margin_account", authority.key().as_ref()],
+        bump = margin_account.bump
+    )]
+    pub margin_account: Account<'info, MarginAccount>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+
+    /// The vault's collateral config (GOR, the only type margin accounts hold)
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidateMargin<'info> {
+    #[account(mut)]
+    pub liquidator: Signer<'info>,
+
+    pub margin_account: Account<'info, MarginAccount>,
+
+    /// CHECK: The owner of the vault whose debt is repaid
+    pub debt_vault_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", debt_vault.position_mint.as_ref()],
+        bump = debt_vault.bump,
+        constraint = debt_vault.owner == debt_vault_owner.key() @ GusdError::InvalidVaultOwner,
+        constraint = debt_vault.margin_account == margin_account.key() @ GusdError::MarginAccountMismatch
+    )]
+    pub debt_vault: Box<Account<'info, Vault>>,
+
+    /// CHECK: The owner of the vault whose collateral is seized
+    pub collateral_vault_owner: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", collateral_vault.position_mint.as_ref()],
+        bump = collateral_vault.bump,
+        constraint = collateral_vault.owner == collateral_vault_owner.key() @ GusdError::InvalidVaultOwner,
+        constraint = collateral_vault.margin_account == margin_account.key() @ GusdError::MarginAccountMismatch
+    )]
+    pub collateral_vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", collateral_vault.position_mint.as_ref()],
+        bump = collateral_vault.collateral_bump
+    )]
+    /// CHECK: This is a PDA that holds lamports (GOR)
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = liquidator,
+        associated_token::token_program = token_program
+    )]
+    pub liquidator_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        seeds = [b"price_accumulator"],
+        bump = price_accumulator.bump
+    )]
+    pub price_accumulator: Box<Account<'info, PriceAccumulator>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// GOR's collateral type, whose debt counter the repayment releases
+    #[account(
+        mut,
+        seeds = [b"collateral_config", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+
+    /// The liquidator's keeper registration, for a fee rebate credit
+    #[account(
+        mut,
+        seeds = [b"keeper", liquidator.key().as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = debt_vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct GetMarginHealth<'info> {
+    pub margin_account: Account<'info, MarginAccount>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// GOR's collateral config, whose ratios margin accounts are held to
+    #[account(
+        seeds = [b"collateral_config", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueInterest<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Price feed of the vault's collateral; carries its stability fee premium
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// Registered keeper cranking this instruction; pass with `keeper_account` for a
+    /// fee rebate credit
+    pub keeper: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        seeds = [b"keeper", keeper_account.keeper.as_ref()],
+        bump = keeper_account.bump
+    )]
+    pub keeper_account: Option<Account<'info, KeeperAccount>>,
+
+    /// CHECK: Instructions sysvar, used to price the transaction fee
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: Option<AccountInfo<'info>>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct SyncLifecycle<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// GOR's collateral type, whose own ratio decides recovery mode
+    #[account(
+        seeds = [b"collateral_config", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct LockStabilityFee<'info> {
+    /// CHECK: The vault owner; signs unless an authorized `delegate` does
+    pub owner: UncheckedAccount<'info>,
+
+    /// The owner's delegate, when acting in their place
+    pub delegate: Option<Signer<'info>>,
+
+    /// The owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump,
+        constraint = is_vault_owner(&vault, &owner, position_token_account.as_ref())
+            @ GusdError::Unauthorized,
+        constraint = vault_authorized(&vault, &owner, delegate.as_ref(), DelegateScope::Full)
+            @ GusdError::Unauthorized
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime revenue counters
+    #[account(
+        mut,
+        seeds = [b"revenue_stats"],
+        bump = revenue_stats.bump
+    )]
+    pub revenue_stats: Account<'info, RevenueStats>,
+
+    /// Price feed of the vault's collateral; carries its stability fee premium
+    #[account(
+        mut,
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// The signer's GUSD account (the owner's, or their delegate's)
+    #[account(
+        mut,
+        token::mint = gusd_mint,
+        token::token_program = token_program
+    )]
+    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Treasury GUSD account (owned by the treasury PDA)
+    #[account(
+        mut,
+        seeds = [b"treasury_gusd"],
+        bump
+    )]
+    pub treasury_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Staker fee tiers; with the vault's recorded stake, discounts its stability fees
+    #[account(
+        seeds = [b"fee_tiers"],
+        bump = fee_tier_registry.bump
+    )]
+    pub fee_tier_registry: Option<Account<'info, FeeTierRegistry>>,
+
+    /// The governance stake recorded on the vault (`Vault::discount_stake`)
+    #[account(address = vault.discount_stake)]
+    pub governance_stake: Option<Account<'info, GovernanceStake>>,
+}
+
+#[derive(Accounts)]
+pub struct GetVaultHealth<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    /// The vault's collateral type
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct StartPriorityAuctions<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + LiquidationAuction::INIT_SPACE,
+        seeds = [b"liquidation_auction"],
+        bump
+    )]
+    pub liquidation_auction: Account<'info, LiquidationAuction>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    /// Bid escrow (GUSD token account owned by the auction PDA)
+    #[account(
+        init,
+        payer = admin,
+        token::mint = gusd_mint,
+        token::authority = liquidation_auction,
+        token::token_program = token_program,
+        seeds = [b"auction_escrow"],
+        bump
+    )]
+    pub auction_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BidPriorityRights<'info> {
+    pub bidder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"liquidation_auction"],
+        bump = liquidation_auction.bump
+    )]
+    pub liquidation_auction: Account<'info, LiquidationAuction>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program
+    )]
+    pub bidder_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"auction_escrow"],
+        bump
+    )]
+    pub auction_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// GUSD account of the current leader; required once a bid exists
+    #[account(
+        mut,
+        token::mint = gusd_mint
+    )]
+    pub previous_leader_gusd_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SettlePriorityAuction<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"liquidation_auction"],
+        bump = liquidation_auction.bump
+    )]
+    pub liquidation_auction: Account<'info, LiquidationAuction>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"auction_escrow"],
+        bump
+    )]
+    pub auction_escrow: InterfaceAccount<'info, TokenAccount>,
+
+    /// Insurance fund (GUSD token account owned by the protocol PDA)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        token::mint = gusd_mint,
+        token::authority = protocol_state,
+        token::token_program = token_program,
+        seeds = [b"insurance_fund"],
+        bump
+    )]
+    pub insurance_fund: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TriggerShutdown<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Settlement::INIT_SPACE,
+        seeds = [b"settlement"],
+        bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement_pool"],
+        bump
+    )]
+    pub settlement_pool: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestRedemption<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + RedemptionRequest::INIT_SPACE,
+        seeds = [b"redemption", owner.key().as_ref()],
+        bump
+    )]
+    pub redemption_request: Account<'info, RedemptionRequest>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    /// Lifetime GUSD minted by origin and burned by reason
+    #[account(
+        mut,
+        seeds = [b"supply_stats"],
+        bump = supply_stats.bump
+    )]
+    pub supply_stats: Account<'info, SupplyStats>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"gusd_mint"],
+        bump = protocol_state.mint_bump
+    )]
+    pub gusd_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = gusd_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program
+    )]
+    pub user_gusd_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MarkSettlement<'info> {
+    #[account(
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleVault<'info> {
+    /// CHECK: Receives the vault's excess collateral if it is the vault's current owner
+    /// (see `is_vault_owner`); otherwise the excess stays in the vault
+    #[account(mut)]
+    pub vault_owner: AccountInfo<'info>,
+
+    /// The current owner's token account holding the vault's position NFT (vaults created with one)
+    pub position_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: This is a PDA that holds lamports (GOR)
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement_pool"],
+        bump = settlement.pool_bump
+    )]
+    pub settlement_pool: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// The vault's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    /// Token collateral only: the mint's settlement pool, opened by `mark_token_settlement`
+    #[account(
+        mut,
+        seeds = [b"settlement_pool", vault.collateral_mint.as_ref()],
+        bump = settlement_token_pool.bump
+    )]
+    pub settlement_token_pool: Option<Account<'info, SettlementTokenPool>>,
+
+    /// Token collateral only: the settlement pool's token account
+    #[account(
+        mut,
+        seeds = [b"settlement_pool_tokens", vault.collateral_mint.as_ref()],
+        bump
+    )]
+    pub settlement_pool_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint
+    #[account(address = vault.collateral_mint)]
+    pub collateral_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Token collateral only: the vault's token account
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault_token", vault.position_mint.as_ref()],
+        bump
+    )]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the owner's token account of the collateral mint; receives
+    /// the excess collateral (not needed while the excess is held)
+    #[account(
+        mut,
+        token::mint = vault.collateral_mint,
+        token::authority = vault_owner
+    )]
+    pub owner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Token collateral only: the collateral mint's token program
+    pub collateral_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct SettleCollateralBalance<'info> {
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault_collateral", vault.position_mint.as_ref()],
+        bump = vault.collateral_bump
+    )]
+    /// CHECK: The vault's collateral PDA; owns the balance's token account
+    pub vault_collateral: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    /// The vault's primary collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"collateral_balance",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump = collateral_balance.bump
+    )]
+    pub collateral_balance: Account<'info, CollateralBalance>,
+
+    /// The balance's collateral type
+    #[account(
+        mut,
+        seeds = [b"collateral_config", collateral_balance.collateral_mint.as_ref()],
+        bump = balance_collateral_config.bump
+    )]
+    pub balance_collateral_config: Account<'info, CollateralConfig>,
+
+    /// The balance mint's settlement pool, opened by `mark_token_settlement`
+    #[account(
+        mut,
+        seeds = [b"settlement_pool", collateral_balance.collateral_mint.as_ref()],
+        bump = settlement_token_pool.bump
+    )]
+    pub settlement_token_pool: Account<'info, SettlementTokenPool>,
+
+    #[account(
         mut,
-        seeds = [b"vault_collateral", owner.key().as_ref()],
+        seeds = [b"settlement_pool_tokens", collateral_balance.collateral_mint.as_ref()],
         bump
     )]
-    /// CHECK: This is a PDA that holds lamports (GOR)
-    pub vault_collateral: AccountInfo<'info>,
+    pub settlement_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = collateral_balance.collateral_mint)]
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_NAMESPACE,
+            b"balance_token",
+            vault.key().as_ref(),
+            collateral_balance.collateral_mint.as_ref()
+        ],
+        bump
+    )]
+    pub balance_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MarkTokenSettlement<'info> {
+    #[account(
+        mut,
+        constraint = admin.key() == protocol_state.admin @ GusdError::Unauthorized
+    )]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"price_feed", collateral_mint.key().as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SettlementTokenPool::INIT_SPACE,
+        seeds = [b"settlement_pool", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub settlement_token_pool: Account<'info, SettlementTokenPool>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = collateral_mint,
+        token::authority = settlement_token_pool,
+        token::token_program = collateral_token_program,
+        seeds = [b"settlement_pool_tokens", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub settlement_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTokenRedemption<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"redemption", owner.key().as_ref()],
+        bump = redemption_request.bump
+    )]
+    pub redemption_request: Account<'info, RedemptionRequest>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + RedemptionTokenClaim::INIT_SPACE,
+        seeds = [b"redemption_claim", collateral_mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub token_claim: Account<'info, RedemptionTokenClaim>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    pub collateral_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement_pool", collateral_mint.key().as_ref()],
+        bump = settlement_token_pool.bump
+    )]
+    pub settlement_token_pool: Account<'info, SettlementTokenPool>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement_pool_tokens", collateral_mint.key().as_ref()],
+        bump
+    )]
+    pub settlement_pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = collateral_mint,
+        token::authority = owner,
+        token::token_program = collateral_token_program
+    )]
+    pub owner_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub collateral_token_program: Interface<'info, TokenInterface>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRedemption<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"redemption", owner.key().as_ref()],
+        bump = redemption_request.bump
+    )]
+    pub redemption_request: Account<'info, RedemptionRequest>,
+
+    #[account(
+        mut,
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement"],
+        bump = settlement.bump
+    )]
+    pub settlement: Account<'info, Settlement>,
+
+    #[account(
+        mut,
+        seeds = [b"settlement_pool"],
+        bump = settlement.pool_bump
+    )]
+    pub settlement_pool: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckAndFlagPage<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        space = 8 + ScanCursor::INIT_SPACE,
+        seeds = [b"scan_cursor"],
+        bump
+    )]
+    pub scan_cursor: Account<'info, ScanCursor>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// The scanned collateral type
+    #[account(
+        seeds = [b"collateral_config", price_feed.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AttestHealth<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"vault", vault.position_mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Box<Account<'info, Vault>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + HealthAttestation::INIT_SPACE,
+        seeds = [b"health_attestation", vault.key().as_ref()],
+        bump
+    )]
+    pub attestation: Box<Account<'info, HealthAttestation>>,
+
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", vault.collateral_mint.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// The vault's collateral type
+    #[account(
+        seeds = [b"collateral_config", vault.collateral_mint.as_ref()],
+        bump = collateral_config.bump
+    )]
+    pub collateral_config: Account<'info, CollateralConfig>,
+}
+
+#[derive(Accounts)]
+pub struct PreviewParameterChange<'info> {
+    #[account(
+        seeds = [SEED_NAMESPACE, b"protocol"],
+        bump = protocol_state.bump
+    )]
+    pub protocol_state: Box<Account<'info, ProtocolState>>,
+
+    #[account(
+        seeds = [b"deployment"],
+        bump = deployment_config.bump
+    )]
+    pub deployment_config: Account<'info, DeploymentConfig>,
+
+    #[account(
+        seeds = [b"price_feed", NATIVE_COLLATERAL_MINT.as_ref()],
+        bump = price_feed.bump
+    )]
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    /// CHECK: Oracle price account; validated against price_feed in get_price
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// [MEDIUM-2] Updated: Added protocol lifecycle state
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolState {
+    /// Protocol admin (can update price for MVP)
+    pub admin: Pubkey,
+    /// GUSD mint address
+    pub gusd_mint: Pubkey,
+    /// Total GOR collateral locked in protocol
+    pub total_collateral: u64,
+    /// Total GUSD debt outstanding
+    pub total_debt: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Mint PDA bump
+    pub mint_bump: u8,
+    /// [MEDIUM-2] Lifecycle state; gates which instructions may run
+    pub lifecycle: Lifecycle,
+    /// Decimals of the native collateral token (9 for GOR lamports)
+    pub native_decimals: u8,
+    /// Compliance role (can freeze/thaw GUSD accounts via the blocklist)
+    pub compliance_authority: Pubkey,
+    /// Max collateral seized by liquidations per epoch (0 = unlimited)
+    pub epoch_liquidation_cap: u64,
+    /// Max collateral paid out by redemptions per epoch (0 = unlimited)
+    pub epoch_redemption_cap: u64,
+    /// Epoch the outflow counters below belong to
+    pub outflow_epoch: u64,
+    /// Collateral seized by liquidations in `outflow_epoch`
+    pub epoch_liquidated_collateral: u64,
+    /// Collateral paid out by redemptions in `outflow_epoch`
+    pub epoch_redeemed_collateral: u64,
+    /// Risk manager role (tunes risk parameters, can pause interest accrual)
+    pub risk_manager: Pubkey,
+    /// Timestamp interest accrual was paused (0 = accruing)
+    pub accrual_paused_at: i64,
+    /// Total seconds for which interest accrual has been skipped
+    pub accrual_skipped_secs: u64,
+    /// Minimum liquidator profit on repaid debt, in BPS of the repay amount
+    pub min_liquidation_profit_bps: u64,
+    /// Treasurer role (manages fee exemptions and protocol revenue)
+    pub treasurer: Pubkey,
+    /// Announced governance action affecting vaults, if any
+    pub pending_notice: Option<GovernanceNotice>,
+    /// TWAP window for mint valuation in seconds (0 = spot price)
+    pub twap_window_secs: u64,
+    /// Whether liquidations use the TWAP instead of the spot price
+    pub twap_for_liquidations: bool,
+    /// Oracle prices older than this are stale (seconds)
+    pub max_price_age_secs: u64,
+    /// Haircut on a feed's `last_good_price` used while its oracle is stale (0 = reject)
+    pub stale_price_haircut_bps: u64,
+    /// Pyth confidence interval above which pricing is rejected (bps of price)
+    pub max_confidence_bps: u64,
+    /// Exponential moving average of the native collateral price (USD, 6 decimals)
+    pub ema_price: u64,
+    /// Timestamp of the last EMA update (unix seconds)
+    pub ema_updated_at: i64,
+    /// How spot and EMA combine for collateral valuation
+    pub price_mode: PriceMode,
+    /// Oracle deviation from a recent accepted price that pauses the protocol (0 = off)
+    pub circuit_breaker_deviation_bps: u64,
+    /// Epoch of the treasury swap counter
+    pub treasury_swap_epoch: u64,
+    /// Lamports swappable from the treasury this epoch
+    pub treasury_swap_epoch_limit: u64,
+    /// Lamports swapped from the treasury this epoch
+    pub treasury_swapped_in_epoch: u64,
+    /// Liquidation insurance premium per 30-day period (bps of debt, 0 = unavailable)
+    pub insurance_premium_bps: u64,
+    /// Liquidation penalty discount for insured vaults (bps of the penalty)
+    pub insurance_discount_bps: u64,
+    /// Guardian role (can halt the launch ramp and veto prices)
+    pub guardian: Pubkey,
+    /// Global cap on outstanding GUSD debt (0 = unlimited)
+    pub debt_ceiling: u64,
+    /// Cap on GUSD supply, counting unminted fee revenue, after a mint against new debt
+    /// (0 = unlimited)
+    pub max_supply: u64,
+    /// Smallest debt a vault may carry after a mint or partial repayment (0 = no floor)
+    pub min_vault_debt: u64,
+    /// Largest debt a mint may leave on a single vault (0 = unlimited)
+    pub max_vault_debt: u64,
+    /// Weekly debt ceiling increase during the launch ramp (bps, 0 = no ramp)
+    pub launch_ramp_step_bps: u64,
+    /// Debt ceiling at which the launch ramp ends
+    pub launch_ramp_target: u64,
+    /// Timestamp of the last applied launch ramp step (unix seconds)
+    pub launch_ramp_last_step_ts: i64,
+    /// Timestamp the guardian vetoed the price (0 = no dispute)
+    pub price_disputed_at: i64,
+    /// Price age limit for mints (seconds, 0 = `max_price_age_secs`)
+    pub mint_max_price_age_secs: u64,
+    /// Price age limit for withdrawals against debt (seconds, 0 = `max_price_age_secs`)
+    pub withdraw_max_price_age_secs: u64,
+    /// Price age limit for liquidations (seconds, 0 = `max_price_age_secs`)
+    pub liquidation_max_price_age_secs: u64,
+    /// Extra collateral ratio margin accounts must keep above the minimum (bps)
+    pub margin_buffer_bps: u64,
+    /// Discount on mint/withdraw prices and premium on liquidation prices (bps)
+    pub price_spread_bps: u64,
+    /// GUSD market price deviation from $1 that counts as a de-peg (bps)
+    pub depeg_threshold_bps: u64,
+    /// Collateral ratio a liquidation restores the position to (bps, 0 = no target)
+    pub liquidation_target_cr_bps: u64,
+    /// Speed at which admin prices ramp toward a new target (bps of the price per second)
+    pub price_slew_bps_per_sec: u64,
+    /// ISO 4217 code of the currency the debt token is pegged to and prices are quoted in
+    pub peg_currency: [u8; 3],
+    /// Annual stability fee charged on vault debt (bps)
+    pub stability_fee_bps: u64,
+    /// Cumulative stability-fee growth of one unit of debt (`DEBT_INDEX_ONE` = 1.0)
+    pub debt_index: u128,
+    /// Timestamp `debt_index` was last advanced (unix seconds)
+    pub debt_index_updated_at: i64,
+    /// Stability and mint fees above the surplus buffer, not yet collected (GUSD)
+    pub accrued_fees: u64,
+    /// Origination fee added to the debt of each mint (bps of the minted amount)
+    pub mint_fee_bps: u64,
+    /// Timestamp of the emergency shutdown (0 = running)
+    pub shutdown_at: i64,
+    /// Fee kept from each collateral withdrawal (bps of the withdrawn amount)
+    pub withdraw_fee_bps: u64,
+    /// Collateral kept as withdrawal fees and moved to the treasury (lamports)
+    pub withdraw_fees_collected: u64,
+    /// Liquidation penalty paid to the liquidator (bps of the debt repaid)
+    pub liquidation_bonus_bps: u64,
+    /// Liquidation penalty retained for the treasury (bps of the debt repaid)
+    pub liquidation_fee_bps: u64,
+    /// Owner of the accounts treasury spends pay out to
+    pub fee_destination: Pubkey,
+    /// Slots after a vault is flagged during which only the priority liquidator may liquidate it
+    pub priority_window_slots: u64,
+    /// Winner of the current priority liquidation rights (default = none)
+    pub priority_liquidator: Pubkey,
+    /// End of the current priority liquidation rights (unix seconds)
+    pub priority_rights_until: i64,
+    /// Fee revenue retained as a protocol surplus buffer (GUSD, unminted)
+    pub surplus_buffer: u64,
+    /// Surplus buffer size above which fee revenue overflows to the treasury (GUSD)
+    pub surplus_buffer_cap: u64,
+    /// Risk parameter changes recorded in the changelog pages
+    pub parameter_log_entries: u64,
+    /// Debt at 100% utilization for the rate model (GUSD, 0 = flat `stability_fee_bps`)
+    pub rate_target_debt: u64,
+    /// Annual rate at zero utilization (bps)
+    pub rate_base_bps: u64,
+    /// Rate added from zero utilization up to the kink (bps)
+    pub rate_slope1_bps: u64,
+    /// Rate added from the kink up to full utilization (bps)
+    pub rate_slope2_bps: u64,
+    /// Utilization at which `rate_slope2_bps` takes over (bps)
+    pub rate_kink_bps: u64,
+    /// Share of fee revenue paid to the savings pool (bps)
+    pub savings_fee_share_bps: u64,
+    /// Savers' share of fee revenue not yet minted into the savings pool (GUSD)
+    pub savings_accrued: u64,
+    /// Start of the current pause (unix seconds, 0 = not paused)
+    pub paused_at: i64,
+    /// Time after which a pause lapses on its own (seconds, 0 = never)
+    pub max_pause_secs: u64,
+    /// Debt written off from vaults left without collateral, not yet covered (GUSD)
+    pub bad_debt: u64,
+    /// Share of fee revenue paid to the insurance fund (bps)
+    pub insurance_fee_share_bps: u64,
+    /// Insurance fund's share of fee revenue not yet minted into it (GUSD)
+    pub insurance_accrued: u64,
+    /// Upfront premium for locking a vault's stability fee (bps of debt per year locked)
+    pub rate_lock_premium_bps: u64,
+    /// Wait between proposing and executing a treasury spend (seconds)
+    pub treasury_spend_delay_secs: u64,
+    /// Treasury spends proposed so far; the next proposal's id
+    pub treasury_spend_count: u64,
+    /// Volume-driven fee rate added to fee floors, decayed as of `last_fee_op_ts`
+    /// (18 decimals, 1e18 = 100%)
+    pub base_rate: u64,
+    /// Time the base rate was last decayed to (unix seconds, whole minutes since)
+    pub last_fee_op_ts: i64,
+    /// Share of a mint's size relative to GUSD supply added to the base rate (bps)
+    pub base_rate_mint_weight_bps: u64,
+    /// Burn the mint fee from the owner's GUSD instead of adding it to vault debt
+    pub mint_fee_upfront: bool,
+    /// Burn a vault's unpaid stability fees from the owner's GUSD at its next mint instead
+    /// of leaving them in vault debt
+    pub stability_fee_upfront: bool,
+    /// Largest GOR tip a liquidator may route to the vault owner, as a share of the
+    /// liquidator's bonus (bps, 0 = tips disabled)
+    pub liquidation_tip_max_bps: u64,
+    /// GUSD the treasury may rebate to keepers per epoch (0 = rebates disabled)
+    pub keeper_rebate_epoch_cap: u64,
+    /// Epoch of `keeper_rebated_in_epoch`
+    pub keeper_rebate_epoch: u64,
+    /// GUSD rebated to keepers so far in `keeper_rebate_epoch`
+    pub keeper_rebated_in_epoch: u64,
+    /// Price feed quoting one unit of the peg currency in USD; when set, prices are quoted
+    /// in USD and converted into the peg currency (default = quoted in the peg currency)
+    pub fx_price_feed: Pubkey,
+    /// USD price of one unit of the peg currency cached by `update_fx_rate` (6 decimals)
+    pub fx_rate: u64,
+    /// Time `fx_rate` was read (unix seconds)
+    pub fx_rate_updated_at: i64,
+}
+
+/// [CRITICAL-4] Updated: Added collateral_bump field
+#[account]
+#[derive(InitSpace)]
+pub struct Vault {
+    /// Key the vault was created by. With a position NFT, the NFT's holder owns the vault
+    /// instead.
+    pub owner: Pubkey,
+    /// Collateral type the vault is bound to (`NATIVE_COLLATERAL_MINT` = GOR)
+    pub collateral_mint: Pubkey,
+    /// Amount of GOR collateral (in lamports)
+    pub collateral_amount: u64,
+    /// Amount of GUSD debt (in GUSD smallest unit, 6 decimals)
+    pub debt_amount: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// [CRITICAL-4] Collateral PDA bump
+    pub collateral_bump: u8,
+    /// Slot of the most recent liquidation of this vault
+    pub last_liquidation_slot: u64,
+    /// Whether the most recent liquidation left the vault below the liquidation threshold
+    pub last_liquidation_left_unhealthy: bool,
+    /// Timestamp of the last owner action on this vault (unix seconds)
+    pub last_activity_ts: i64,
+    /// Timestamp the vault was flagged as abandoned (0 = not flagged)
+    pub abandoned_flagged_at: i64,
+    /// Consecutive 30-day periods with a repayment
+    pub repayment_streak: u16,
+    /// Start of the current repayment streak (unix seconds)
+    pub streak_started_at: i64,
+    /// Timestamp of the last repayment (unix seconds, 0 = never)
+    pub last_repayment_ts: i64,
+    /// Liquidation insurance coverage end (unix seconds, 0 = never insured)
+    pub insured_until: i64,
+    /// Rent-exempt lamports in the collateral PDA, not counted as collateral
+    pub collateral_rent_reserve: u64,
+    /// Margin account this vault belongs to (default = standalone)
+    pub margin_account: Pubkey,
+    /// Debt index at the vault's last accrual (0 = not yet accrued)
+    pub debt_index_snapshot: u128,
+    /// Slot `check_and_flag_page` first found the vault below the liquidation threshold
+    /// (0 = not flagged)
+    pub flagged_slot: u64,
+    /// Liquidation protocol fees held in the collateral PDA until swept to the treasury
+    /// (lamports, not counted as collateral)
+    pub retained_liquidation_fees: u64,
+    /// Timestamp of the vault's last interest accrual (unix seconds, 0 = never)
+    pub last_accrued_at: i64,
+    /// Stability fee locked by `lock_stability_fee` (annual bps)
+    pub locked_rate_bps: u64,
+    /// End of the stability fee lock (unix seconds, 0 = never locked)
+    pub rate_locked_until: i64,
+    /// Debt covered by the lock; debt above it accrues at the floating rate
+    pub rate_lock_principal: u64,
+    /// Protocol-owned vault (the bootstrap auction's) that never accrues stability fees
+    pub interest_exempt: bool,
+    /// Collateral premium index at the vault's last accrual (0 = not yet accrued)
+    pub premium_index_snapshot: u128,
+    /// Accrued stability fees in `debt_amount` not yet repaid; repayments settle it first,
+    /// and it is capped at `debt_amount` when read
+    pub unpaid_interest: u64,
+    /// Where the vault's share of idle-collateral yield goes
+    pub yield_routing: YieldRouting,
+    /// Staker tier discount off stability fees applied at the vault's last accrual
+    pub stake_discount_bps: u64,
+    /// Key approved to act on the vault by `approve_delegate` (default = none)
+    pub delegate: Pubkey,
+    /// What `delegate` may do
+    pub delegate_scope: DelegateScope,
+    /// Owner who approved `delegate`; the approval lapses once the position changes hands
+    pub delegated_by: Pubkey,
+    /// Mint of the vault's position NFT, whose holder owns the vault (default = none; the
+    /// vault is owned by `owner`). Seeds the vault's PDAs, so one wallet can create any
+    /// number of vaults.
+    pub position_mint: Pubkey,
+    /// Mints of the extra collateral balances backing the debt alongside `collateral_mint`,
+    /// each held in a `CollateralBalance`
+    #[max_len(MAX_COLLATERAL_BALANCES)]
+    pub collateral_balances: Vec<Pubkey>,
+    /// Governance stake recorded by the owner's last mint (default = none); its tier
+    /// discount is re-read from the stake at every accrual
+    pub discount_stake: Pubkey,
+}
+
+/// A vault's balance of an extra collateral type, seeds
+/// `["collateral_balance", vault, collateral_mint]`
+/// The tokens sit in `["balance_token", vault, collateral_mint]`, owned by the vault's
+/// collateral PDA.
+#[account]
+#[derive(InitSpace)]
+pub struct CollateralBalance {
+    /// Vault the balance backs
+    pub vault: Pubkey,
+    /// Collateral mint held
+    pub collateral_mint: Pubkey,
+    /// Collateral held (base units)
+    pub amount: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Whether `settle_collateral_balance` has settled the balance's part of the vault's debt
+    pub settled: bool,
+}
+
+/// Rolling record of a vault's attested health, written only by `attest_health`
+#[account]
+#[derive(InitSpace)]
+pub struct HealthAttestation {
+    /// Vault the records describe
+    pub vault: Pubkey,
+    /// Ring buffer of health records
+    pub records: [HealthRecord; HEALTH_ATTESTATION_COUNT],
+    /// Index of the newest record
+    pub head: u8,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct HealthRecord {
+    /// Slot the health was attested at (0 = empty)
+    pub slot: u64,
+    /// Collateral ratio at the attestation price (u64::MAX = no debt)
+    pub collateral_ratio_bps: u64,
+    /// `HEALTH_FLAG_*` bits
+    pub flags: u8,
+}
+
+/// Global settlement after an emergency shutdown
+#[account]
+#[derive(InitSpace)]
+pub struct Settlement {
+    /// Timestamp of the shutdown (unix seconds)
+    pub shutdown_at: i64,
+    /// Collateral price vaults settle and redemptions pay out at (0 = not yet marked)
+    pub price: u64,
+    /// Timestamp the price was marked (unix seconds)
+    pub marked_at: i64,
+    /// GUSD burned by queued redemption requests
+    pub requested: u64,
+    /// Collateral moved into the settlement pool by `settle_vault`
+    pub collateral: u64,
+    /// Collateral paid out by `claim_redemption`
+    pub claimed: u64,
+    /// Settlement pool PDA bump
+    pub pool_bump: u8,
+    /// PDA bump
+    pub bump: u8,
+    /// Debt outstanding at the mark, less uncovered bad debt; pools' shares of it weight
+    /// what redemptions are owed from each
+    pub marked_debt: u64,
+    /// Debt credited to every settlement pool, GOR and token, up to `marked_debt`
+    pub settled_debt: u64,
+    /// Debt credited to the GOR pool
+    pub debt: u64,
+}
+
+/// Settlement pool of a token collateral type, seeds `["settlement_pool", mint]`
+/// Holds its tokens in `["settlement_pool_tokens", mint]`, owned by this PDA.
+#[account]
+#[derive(InitSpace)]
+pub struct SettlementTokenPool {
+    pub collateral_mint: Pubkey,
+    /// Decimals of the collateral mint
+    pub decimals: u8,
+    /// Collateral price the mint's vaults settle and redemptions pay out at
+    pub price: u64,
+    /// Timestamp the price was marked (unix seconds)
+    pub marked_at: i64,
+    /// Debt credited to the pool by `settle_vault`
+    pub debt: u64,
+    /// Collateral received by `settle_vault`, net of transfer fees
+    pub collateral: u64,
+    /// Collateral paid out by `claim_token_redemption`
+    pub claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Collateral a redemption request has claimed from a token settlement pool, seeds
+/// `["redemption_claim", mint, owner]`
+#[account]
+#[derive(InitSpace)]
+pub struct RedemptionTokenClaim {
+    pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    /// Collateral paid out so far by `claim_token_redemption`
+    pub claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// A holder's queued redemption, closed once its face value is claimed
+#[account]
+#[derive(InitSpace)]
+pub struct RedemptionRequest {
+    pub owner: Pubkey,
+    /// GUSD burned into the queue
+    pub amount: u64,
+    /// Collateral paid out so far by `claim_redemption`
+    pub claimed: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// A GUSD payment request, seeds `["invoice", payee, invoice_id]`
+#[account]
+#[derive(InitSpace)]
+pub struct Invoice {
+    pub payee: Pubkey,
+    /// Payee-chosen id, unique per payee
+    pub invoice_id: u64,
+    /// Amount due (GUSD)
+    pub amount: u64,
+    /// Hash of the off-chain invoice details
+    pub memo_hash: [u8; 32],
+    /// Payment deadline (unix seconds)
+    pub expires_at: i64,
+    /// Who paid (default = unpaid)
+    pub paid_by: Pubkey,
+    /// Payment timestamp (0 = unpaid)
+    pub paid_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Rolling auction of priority liquidation rights
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidationAuction {
+    /// Current bidding round
+    pub round: u64,
+    /// End of bidding for the current round (unix seconds)
+    pub bidding_ends_at: i64,
+    /// Leading bidder (default = no bids)
+    pub leader: Pubkey,
+    /// Leading bid, escrowed in `auction_escrow` (GUSD)
+    pub leading_bid: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Shared progress of keepers scanning vault health in ascending key order
+#[account]
+#[derive(InitSpace)]
+pub struct ScanCursor {
+    /// Highest vault key scanned in the current sweep (default = sweep just started)
+    pub last_vault: Pubkey,
+    /// Slot of the last scanned page
+    pub last_slot: u64,
+    /// Sweeps started with `restart`
+    pub sweep: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Latest GUSD secondary-market price and de-peg status
+#[account]
+#[derive(InitSpace)]
+pub struct PegState {
+    /// GUSD market price (USD, 6 decimals)
+    pub price: u64,
+    /// Timestamp of the last price update (unix seconds, 0 = never)
+    pub updated_at: i64,
+    /// Timestamp the current de-peg began (0 = on peg)
+    pub depegged_since: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Group of vaults whose collateral and debt are judged together
+#[account]
+#[derive(InitSpace)]
+pub struct MarginAccount {
+    /// Authority that approves vaults joining or leaving
+    pub authority: Pubkey,
+    /// Member vault addresses
+    #[max_len(MAX_MARGIN_VAULTS)]
+    pub vaults: Vec<Pubkey>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Frozen GUSD token account with the reason it was frozen
+#[account]
+#[derive(InitSpace)]
+pub struct BlocklistEntry {
+    /// Frozen GUSD token account
+    pub token_account: Pubkey,
+    /// Owner of the frozen token account at the time of freezing
+    pub owner: Pubkey,
+    /// Compliance reason code (meaning defined off-chain)
+    pub reason_code: u16,
+    /// Compliance authority that added the entry
+    pub added_by: Pubkey,
+    /// Timestamp the entry was added (unix seconds)
+    pub added_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Address exempt from protocol fees (e.g., PSM, protocol-owned liquidity, partners)
+#[account]
+#[derive(InitSpace)]
+pub struct FeeExemption {
+    /// Exempt address (fee payer / vault owner)
+    pub address: Pubkey,
+    /// Risk manager that added or last renewed the exemption
+    pub added_by: Pubkey,
+    /// Timestamp the exemption was added or last renewed (unix seconds)
+    pub added_at: i64,
+    /// PDA bump
+    pub bump: u8,
+    /// End of the exemption (unix seconds, 0 = never expires)
+    pub expires_at: i64,
+}
+
+impl FeeExemption {
+    /// Whether the exemption still waives fees at `now`
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at == 0 || now < self.expires_at
+    }
+}
+
+/// Fee discount for stakes of at least `min_stake` governance tokens
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default, Debug)]
+pub struct FeeTier {
+    /// Smallest stake qualifying for the tier (governance token base units)
+    pub min_stake: u64,
+    /// Discount off mint and stability fees
+    pub discount_bps: u64,
+}
+
+/// Staker fee tiers, seeds `["fee_tiers"]`; also the authority of the stake escrow
+#[account]
+#[derive(InitSpace)]
+pub struct FeeTierRegistry {
+    /// Token staked for discounts
+    pub governance_mint: Pubkey,
+    /// Tiers by increasing `min_stake`; only the first `tier_count` apply
+    pub tiers: [FeeTier; MAX_FEE_TIERS],
+    pub tier_count: u8,
+    /// Governance tokens held in the stake escrow
+    pub total_staked: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl FeeTierRegistry {
+    /// Discount `stake` earns at `now`: that of the highest tier it reaches, once warm
+    pub fn discount_bps(&self, stake: &GovernanceStake, now: i64) -> u64 {
+        if now < stake.warm_at {
+            return 0;
+        }
+        self.tiers[..self.tier_count as usize]
+            .iter()
+            .take_while(|tier| stake.amount >= tier.min_stake)
+            .last()
+            .map_or(0, |tier| tier.discount_bps)
+    }
+}
+
+/// A holder's staked governance tokens, seeds `["gov_stake", owner]`
+#[account]
+#[derive(InitSpace)]
+pub struct GovernanceStake {
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// When the stake starts earning its tier discount (unix seconds)
+    pub warm_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Protocol lifecycle, stored in `ProtocolState.lifecycle`
+/// The state only moves along `can_transition_to`; `Settlement` is final.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Lifecycle {
+    /// Before `initialize`
+    #[default]
+    Uninitialized,
+    /// A launch ramp is raising the debt ceiling
+    GuardedLaunch,
+    /// Normal operation
+    Active,
+    /// Total collateral ratio below the minimum: no new debt or withdrawals
+    RecoveryMode,
+    /// Paused by the admin or the circuit breaker
+    Paused,
+    /// Shut down for global settlement
+    Settlement,
+}
+
+/// Instructions gated by the lifecycle state
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GatedAction {
+    /// Minting GUSD against a vault
+    Mint,
+    /// Withdrawing vault collateral
+    Withdraw,
+    /// Liquidating vaults
+    Liquidate,
+    /// Insurance purchases, fee payments in collateral, and bootstrap auctions
+    Operate,
+}
+
+impl Lifecycle {
+    /// Whether the protocol may move from `self` to `next`
+    pub fn can_transition_to(self, next: Lifecycle) -> bool {
+        use Lifecycle::*;
+        matches!(
+            (self, next),
+            (Uninitialized, GuardedLaunch | Active)
+                | (GuardedLaunch, Active | RecoveryMode | Paused | Settlement)
+                | (Active, GuardedLaunch | RecoveryMode | Paused | Settlement)
+                | (RecoveryMode, GuardedLaunch | Active | Paused | Settlement)
+                | (Paused, GuardedLaunch | Active | Settlement)
+        )
+    }
+
+    /// Whether `action` may run in this state
+    /// Deposits, repayments, and settlement flows are not gated.
+    pub fn allows(self, action: GatedAction) -> bool {
+        match self {
+            Lifecycle::GuardedLaunch | Lifecycle::Active => true,
+            Lifecycle::RecoveryMode => {
+                matches!(action, GatedAction::Liquidate | GatedAction::Operate)
+            }
+            Lifecycle::Uninitialized | Lifecycle::Paused | Lifecycle::Settlement => false,
+        }
+    }
+}
+
+/// Governance actions that change vaults' effective parameters
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NoticeAction {
+    /// Collateral stops being accepted for new debt
+    CollateralSunset,
+    /// Collateral ratio / liquidation parameters change
+    RiskParameterChange,
+}
+
+/// Pending governance action with its countdown
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy)]
+pub struct GovernanceNotice {
+    pub action: NoticeAction,
+    pub posted_at: i64,
+    pub effective_at: i64,
+}
+
+/// Collateral valuation mode selectable via `set_price_mode`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PriceMode {
+    Spot,
+    Ema,
+    /// Lower of spot and EMA
+    MinSpotEma,
+}
+
+/// Destination of a vault's idle-collateral yield, selectable via `set_yield_routing`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum YieldRouting {
+    /// Added to the vault's collateral
+    Compound,
+    /// Sold for GUSD that repays the vault's debt; compounds once the debt is cleared
+    RepayDebt,
+    /// Paid to the owner's wallet
+    Stream,
+}
+
+/// What a vault's delegate may do, granted by `approve_delegate`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum DelegateScope {
+    /// No delegate
+    None,
+    /// `deposit_collateral` and `repay_gusd`
+    DepositRepay,
+    /// Every owner-gated vault instruction except delegation itself
+    Full,
+}
+
+/// Oracle backend selectable via `set_oracle`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OracleKind {
+    Pyth,
+    Switchboard,
+    /// Reports pushed through `update_price_chainlink`
+    ChainlinkStreams,
+}
+
+/// An oracle price account and the feed id expected from it
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct OracleSource {
+    pub kind: OracleKind,
+    /// Price account (default = unused slot)
+    pub account: Pubkey,
+    /// Pyth feed id or Switchboard feed hash
+    pub feed_id: [u8; 32],
+}
+
+impl OracleSource {
+    /// Empty fallback slot
+    pub const UNSET: Self = Self {
+        kind: OracleKind::Pyth,
+        account: Pubkey::new_from_array([0u8; 32]),
+        feed_id: [0u8; 32],
+    };
+}
+
+/// Roles assignable by the admin via `set_role`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProtocolRole {
+    Compliance,
+    RiskManager,
+    Treasurer,
+    Guardian,
+}
+
+/// Risk parameters recorded in the parameter changelog
+/// Those up to `MaxVaultDebt` are tunable via `set_risk_parameter`; the rest are set by
+/// the instruction named on them.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RiskParameter {
+    MinLiquidationProfitBps,
+    TwapWindowSecs,
+    /// 1 = liquidate at the TWAP, 0 = spot price
+    TwapForLiquidations,
+    MaxPriceAgeSecs,
+    StalePriceHaircutBps,
+    MaxConfidenceBps,
+    CircuitBreakerDeviationBps,
+    InsurancePremiumBps,
+    InsuranceDiscountBps,
+    /// 0 = use MaxPriceAgeSecs
+    MintMaxPriceAgeSecs,
+    /// 0 = use MaxPriceAgeSecs
+    WithdrawMaxPriceAgeSecs,
+    /// 0 = use MaxPriceAgeSecs
+    LiquidationMaxPriceAgeSecs,
+    MarginBufferBps,
+    /// 0 = no mint/liquidation price asymmetry
+    PriceSpreadBps,
+    DepegThresholdBps,
+    /// 0 = liquidations repay as much as collateral allows
+    LiquidationTargetCrBps,
+    PriceSlewBpsPerSec,
+    /// Annual rate; 0 = no interest
+    StabilityFeeBps,
+    MintFeeBps,
+    WithdrawFeeBps,
+    /// Penalty paid to the liquidator
+    LiquidationBonusBps,
+    /// Penalty retained for the treasury; 0 = liquidators keep the whole penalty
+    LiquidationFeeBps,
+    /// Slots after a vault is flagged reserved for the priority liquidator; 0 = none
+    PriorityWindowSlots,
+    /// GUSD amount; 0 = all fee revenue goes to the treasury
+    SurplusBufferCap,
+    /// GUSD amount utilization is measured against; 0 = flat `StabilityFeeBps`
+    RateTargetDebt,
+    RateBaseBps,
+    RateSlope1Bps,
+    RateSlope2Bps,
+    RateKinkBps,
+    /// Share of stability and mint fees paid to savers
+    SavingsFeeShareBps,
+    /// 0 = pauses last until unpaused
+    MaxPauseSecs,
+    /// Share of stability and mint fees paid to the insurance fund
+    InsuranceFeeShareBps,
+    /// Annual premium for stability fee rate locks; 0 = locks unavailable
+    RateLockPremiumBps,
+    /// Weight of mint volume in the base rate; 0 = mints don't raise it
+    BaseRateMintWeightBps,
+    /// 1 = mint fees are paid from the owner's GUSD, 0 = added to debt
+    MintFeeUpfront,
+    /// 1 = unpaid stability fees are paid from the owner's GUSD at mints, 0 = left in debt
+    StabilityFeeUpfront,
+    /// Cap on liquidator tips to vault owners, as a share of the liquidator's bonus
+    LiquidationTipMaxBps,
+    /// GUSD rebated to keepers per epoch; 0 = rebates disabled
+    KeeperRebateEpochCap,
+    /// GUSD supply cap checked by mints; 0 = unlimited
+    MaxSupply,
+    /// GUSD amount; 0 = no dust floor
+    MinVaultDebt,
+    /// GUSD amount; 0 = no per-vault cap
+    MaxVaultDebt,
+    /// `set_collateral_type`, for the collateral in the entry's `target`
+    CollateralMinRatioBps,
+    /// `set_collateral_type`
+    CollateralLiquidationThresholdBps,
+    /// `set_collateral_type`; 0 = no ceiling
+    CollateralDebtCeiling,
+    /// `set_collateral_type`; 1 = enabled
+    CollateralEnabled,
+    /// `set_collateral_fees`, for the collateral in the entry's `target`
+    CollateralStabilityFeePremiumBps,
+    /// `set_collateral_fees`; `PARAMETER_UNSET` = protocol-wide fee
+    CollateralMintFeeBps,
+    /// `set_collateral_fees`; `PARAMETER_UNSET` = protocol-wide penalty
+    CollateralLiquidationBonusBps,
+    /// `set_collateral_fees`; `PARAMETER_UNSET` = protocol-wide penalty
+    CollateralLiquidationFeeBps,
+    /// `set_epoch_caps`; 0 = unlimited
+    EpochLiquidationCap,
+    /// `set_epoch_caps`; 0 = unlimited
+    EpochRedemptionCap,
+    /// `set_launch_ramp`; 0 = unlimited
+    DebtCeiling,
+    /// `set_launch_ramp`
+    LaunchRampStepBps,
+    /// `set_launch_ramp`
+    LaunchRampTarget,
+    /// `set_price_mode`; the `PriceMode` variant index
+    PriceMode,
+    /// `set_fee_tiers`, for the tier at this index
+    FeeTierMinStake(u8),
+    /// `set_fee_tiers`, for the tier at this index
+    FeeTierDiscountBps(u8),
+    /// `set_oracle`, for the collateral in the entry's `target`; the `OracleKind` variant index
+    OracleKind,
+}
+
+/// Named risk parameter presets applied by `apply_risk_template`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RiskTemplate {
+    /// Tighter oracle limits, earlier circuit breaker, larger liquidation incentives
+    Conservative,
+    /// The defaults set at initialize
+    Standard,
+    /// Looser oracle limits and leaner liquidation incentives
+    Aggressive,
+}
+
+impl RiskTemplate {
+    /// Parameters the template sets, applied in this order
+    pub fn parameters(self) -> &'static [(RiskParameter, u64)] {
+        match self {
+            RiskTemplate::Conservative => &[
+                (RiskParameter::MinLiquidationProfitBps, 500),
+                (RiskParameter::LiquidationBonusBps, 1200),
+                (RiskParameter::LiquidationFeeBps, 300),
+                (RiskParameter::MaxPriceAgeSecs, 30),
+                (RiskParameter::MaxConfidenceBps, 100),
+                (RiskParameter::PriceSpreadBps, 100),
+                (RiskParameter::StalePriceHaircutBps, 0),
+                (RiskParameter::CircuitBreakerDeviationBps, 1000),
+                (RiskParameter::MarginBufferBps, 5000),
+            ],
+            RiskTemplate::Standard => &[
+                (RiskParameter::MinLiquidationProfitBps, DEFAULT_MIN_LIQUIDATION_PROFIT_BPS),
+                (RiskParameter::LiquidationBonusBps, DEFAULT_LIQUIDATION_BONUS_BPS),
+                (RiskParameter::LiquidationFeeBps, 0),
+                (RiskParameter::MaxPriceAgeSecs, DEFAULT_MAX_PRICE_AGE_SECS),
+                (RiskParameter::MaxConfidenceBps, DEFAULT_MAX_CONFIDENCE_BPS),
+                (RiskParameter::PriceSpreadBps, DEFAULT_PRICE_SPREAD_BPS),
+                (RiskParameter::StalePriceHaircutBps, 0),
+                (
+                    RiskParameter::CircuitBreakerDeviationBps,
+                    DEFAULT_CIRCUIT_BREAKER_DEVIATION_BPS,
+                ),
+                (RiskParameter::MarginBufferBps, DEFAULT_MARGIN_BUFFER_BPS),
+            ],
+            RiskTemplate::Aggressive => &[
+                (RiskParameter::MinLiquidationProfitBps, 300),
+                (RiskParameter::LiquidationBonusBps, 800),
+                (RiskParameter::LiquidationFeeBps, 0),
+                (RiskParameter::MaxPriceAgeSecs, 120),
+                (RiskParameter::MaxConfidenceBps, 400),
+                (RiskParameter::PriceSpreadBps, 25),
+                (RiskParameter::StalePriceHaircutBps, 500),
+                (RiskParameter::CircuitBreakerDeviationBps, 3000),
+                (RiskParameter::MarginBufferBps, 1000),
+            ],
+        }
+    }
+}
+
+/// Cumulative price × time record and recent price history backing `get_twap` and `get_price_history`
+#[account]
+#[derive(InitSpace)]
+pub struct PriceAccumulator {
+    /// Sum of price × seconds up to `last_update_ts`
+    pub cumulative_price: u128,
+    /// Most recent price (USD, 6 decimals)
+    pub last_price: u64,
+    /// Timestamp of the most recent price (unix seconds)
+    pub last_update_ts: i64,
+    /// Ring buffer of (timestamp, cumulative price, accepted price) snapshots
+    pub observations: [PriceObservation; PRICE_OBSERVATION_COUNT],
+    /// Index of the newest observation
+    pub head: u8,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct PriceObservation {
+    pub timestamp: i64,
+    pub cumulative_price: u128,
+    /// Price accepted at `timestamp` (USD, 6 decimals)
+    pub price: u64,
+}
+
+/// Price source for one collateral type, keyed by its mint
+/// A registered collateral type, seeds `["collateral_config", mint]`
+/// Vaults bind to one at creation; its price feed values their collateral and its ratios
+/// govern their mints, withdrawals, and liquidations. GOR's is created at initialize.
+#[account]
+#[derive(InitSpace)]
+pub struct CollateralConfig {
+    /// Collateral mint (`NATIVE_COLLATERAL_MINT` = GOR)
+    pub collateral_mint: Pubkey,
+    /// Decimals of the collateral's base units
+    pub decimals: u8,
+    /// Price feed valuing the collateral, seeds `["price_feed", collateral_mint]`
+    pub price_feed: Pubkey,
+    /// Collateral ratio mints and withdrawals must leave (bps)
+    pub min_collateral_ratio_bps: u64,
+    /// Collateral ratio below which vaults can be liquidated (bps)
+    pub liquidation_threshold_bps: u64,
+    /// Whether new vaults and mints may use this type
+    pub enabled: bool,
+    /// Collateral held by token vaults of this type (base units); GOR's is tracked in
+    /// `ProtocolState.total_collateral`
+    pub total_collateral: u64,
+    /// Cap on `total_debt` enforced by `mint_gusd` (0 = unlimited)
+    pub debt_ceiling: u64,
+    /// GUSD principal minted against this type and not yet repaid or liquidated; accrued
+    /// interest is not counted
+    pub total_debt: u64,
+    /// Registration time (unix seconds)
+    pub added_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// The native GOR feed (key `NATIVE_COLLATERAL_MINT`) is created at initialize; other
+/// feeds are registered with `init_price_feed`.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    /// Collateral mint this feed prices
+    pub collateral_mint: Pubkey,
+    /// Decimals of the collateral's base units
+    pub decimals: u8,
+    /// Oracle backend used when the admin price fallback is off
+    pub oracle_kind: OracleKind,
+    /// Oracle price account (default = not configured)
+    pub oracle_account: Pubkey,
+    /// Pyth feed id, Switchboard feed hash, or Chainlink feed id expected from the oracle
+    pub oracle_feed_id: [u8; 32],
+    /// Value collateral with the admin-set price instead of the oracle (fallback flag)
+    pub use_admin_price: bool,
+    /// Admin- or feeder-submitted target price (USD, 6 decimals, e.g., 1_000_000 = $1.00)
+    pub admin_price: u64,
+    /// Timestamp of the last admin price update, when the current ramp began (unix seconds)
+    pub admin_price_ts: i64,
+    /// Effective admin price when the current ramp began (USD, 6 decimals)
+    pub admin_ramp_price: u64,
+    /// Speed of the current ramp toward `admin_price` (USD, 6 decimals, per second)
+    pub admin_ramp_rate: u64,
+    /// Last fresh oracle price cached by `record_price_sample` (USD, 6 decimals)
+    pub last_good_price: u64,
+    /// Timestamp of `last_good_price` (unix seconds)
+    pub last_good_price_ts: i64,
+    /// Oracle price cached by `crank_price` or `update_price_chainlink` (USD, 6 decimals)
+    pub cached_price: u64,
+    /// Confidence of the cached price (bps of price; half the spread for Chainlink)
+    pub cached_conf_bps: u64,
+    /// Oracle publish time of the cached price (unix seconds, 0 = nothing cached)
+    pub cached_observed_at: i64,
+    /// Start of the oracle's shadow run (unix seconds, 0 = not shadowing)
+    pub shadow_started_at: i64,
+    /// Minimum shadow run before the switch can be scheduled (seconds)
+    pub shadow_period_secs: u64,
+    /// Oracle/admin price divergence that raises an alert and blocks the switch (bps)
+    pub shadow_alert_bps: u64,
+    /// Oracle prices compared against the admin price during the shadow run
+    pub shadow_samples: u32,
+    /// Largest divergence seen during the shadow run (bps)
+    pub shadow_max_divergence_bps: u64,
+    /// Time the oracle may become authoritative (unix seconds, 0 = not scheduled)
+    pub oracle_activation_at: i64,
+    /// Sources tried in order when the primary oracle is stale or invalid
+    pub fallback_oracles: [OracleSource; MAX_FALLBACK_ORACLES],
+    /// Fee overrides for vaults backed by this collateral
+    pub fees: CollateralFees,
+    /// Compounded `stability_fee_premium_bps` growth, like `debt_index` (1e18 = 1.0)
+    pub premium_index: u128,
+    /// Accrual clock when `premium_index` was last advanced (unix seconds less paused accrual)
+    pub premium_index_updated_at: i64,
+    /// Stake pool whose token this feed prices as liquid-staked GOR (default = not an LST)
+    pub stake_pool: Pubkey,
+    /// GOR lamports per `EXCHANGE_RATE_ONE` pool token base units; the oracle price is GOR's
+    pub exchange_rate: u64,
+    /// Epoch `exchange_rate` was read in
+    pub exchange_rate_epoch: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl PriceFeed {
+    /// Mint fee for vaults backed by this collateral (bps)
+    pub fn mint_fee_bps(&self, protocol: &ProtocolState) -> u64 {
+        self.fees.mint_fee_bps.unwrap_or(protocol.mint_fee_bps)
+    }
+
+    /// Liquidator bonus and protocol fee for vaults backed by this collateral (bps)
+    pub fn liquidation_penalty_bps(&self, protocol: &ProtocolState) -> (u64, u64) {
+        match (self.fees.liquidation_bonus_bps, self.fees.liquidation_fee_bps) {
+            (Some(bonus_bps), Some(fee_bps)) => (bonus_bps, fee_bps),
+            _ => (protocol.liquidation_bonus_bps, protocol.liquidation_fee_bps),
+        }
+    }
+}
+
+/// Per-collateral fees set by `set_collateral_fees`; `None` falls back to the
+/// protocol-wide parameter
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CollateralFees {
+    /// Stability fee charged on top of the protocol rate (annual bps)
+    pub stability_fee_premium_bps: u64,
+    /// Origination fee on minted GUSD (bps)
+    pub mint_fee_bps: Option<u64>,
+    /// Liquidator bonus (bps of repaid debt); set together with `liquidation_fee_bps`
+    pub liquidation_bonus_bps: Option<u64>,
+    /// Protocol's cut of the liquidation penalty (bps of repaid debt)
+    pub liquidation_fee_bps: Option<u64>,
+}
+
+/// Cluster-specific program addresses, set at initialize
+/// Keeps environment values out of the binary so one build deploys to any SVM cluster.
+#[account]
+#[derive(InitSpace)]
+pub struct DeploymentConfig {
+    /// Pyth receiver program (owner of `PriceUpdateV2` accounts)
+    pub pyth_receiver_program: Pubkey,
+    /// Switchboard on-demand program (owner of `PullFeedAccountData` accounts)
+    pub switchboard_program: Pubkey,
+    /// Chainlink Data Streams verifier program
+    pub chainlink_verifier_program: Pubkey,
+    /// Whitelisted swap program for treasury diversification (default = disabled)
+    pub swap_program: Pubkey,
+    /// USDC mint on this cluster
+    pub usdc_mint: Pubkey,
+    /// Hash of the whitelisted swap route's ordered `(account, writable)` list
+    pub swap_route_hash: [u8; 32],
+    /// Non-transferable attestation mint required to open a vault (default = no KYC gate)
+    pub kyc_attestation_mint: Pubkey,
+    /// Stake pool program backing liquid-staked GOR collateral (default = none)
+    pub stake_pool_program: Pubkey,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Feeder keys allowed to co-sign protocol prices via `update_price_signed`
+#[account]
+#[derive(InitSpace)]
+pub struct FeederRegistry {
+    #[max_len(MAX_PRICE_FEEDERS)]
+    pub feeders: Vec<Pubkey>,
+    /// Signatures required per price update
+    pub threshold: u8,
+    /// Timestamp of the last applied signed price (replay protection)
+    pub last_signed_ts: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Append-only list of audit report and program binary hashes
+#[account]
+#[derive(InitSpace)]
+pub struct AuditRegistry {
+    #[max_len(MAX_AUDIT_RECORDS)]
+    pub records: Vec<AuditRecord>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone)]
+pub struct AuditRecord {
+    /// Hash of the published audit report
+    pub report_hash: [u8; 32],
+    /// Hash of the audited program binary (compare with the deployed program data)
+    pub program_hash: [u8; 32],
+    /// Timestamp the record was added (unix seconds)
+    pub recorded_at: i64,
+}
+
+/// One-time bootstrap auction of the initial GUSD tranche, seeds `["bootstrap"]`
+#[account]
+#[derive(InitSpace)]
+pub struct BootstrapAuction {
+    /// GUSD minted for sale
+    pub tranche: u64,
+    /// GUSD sold so far
+    pub sold: u64,
+    /// Opening price (lamports per whole GUSD)
+    pub start_price: u64,
+    /// Closing price (lamports per whole GUSD)
+    pub end_price: u64,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    /// GOR paid into protocol-owned liquidity (lamports)
+    pub proceeds: u64,
+    /// Unsold GUSD has been burned
+    pub finalized: bool,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Lifetime protocol revenue by source, seeds `["revenue_stats"]`
+/// Counts revenue when it is charged, before any of it is realized or paid out.
+#[account]
+#[derive(InitSpace)]
+pub struct RevenueStats {
+    /// Stability fees charged, after repayment-streak rebates (GUSD)
+    pub stability_fees: u64,
+    /// Mint origination fees (GUSD)
+    pub mint_fees: u64,
+    /// Stability fee rate lock premiums (GUSD)
+    pub rate_lock_premiums: u64,
+    /// Liquidation insurance premiums paid into the insurance fund (GUSD)
+    pub insurance_premiums: u64,
+    /// Protocol fee part of liquidation penalties (lamports)
+    pub liquidation_fees: u64,
+    /// Collateral withdrawal fees (lamports)
+    pub withdraw_fees: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Lifetime GUSD minted by origin and burned by reason, seeds `["supply_stats"]`
+/// Updated with every mint and burn, so total minted less total burned is the GUSD supply.
+#[account]
+#[derive(InitSpace)]
+pub struct SupplyStats {
+    /// Borrowed against vaults, including the bootstrap auction tranche
+    pub minted_vault_debt: u64,
+    /// Fee revenue realized into the treasury by `collect_fees`
+    pub minted_treasury_fees: u64,
+    /// Fee revenue dripped into the savings pool
+    pub minted_savings_yield: u64,
+    /// Fee revenue realized into the insurance fund
+    pub minted_insurance_fees: u64,
+    /// Debt repayments, including fees paid upfront and unsold bootstrap GUSD
+    pub burned_repayments: u64,
+    /// Debt repaid by liquidators
+    pub burned_liquidations: u64,
+    /// Burned into the shutdown redemption queue
+    pub burned_redemptions: u64,
+    /// Unused: fees paid in collateral now reverse unminted revenue instead of burning
+    /// treasury GUSD. Kept for the account layout.
+    pub burned_treasury: u64,
+    /// Insurance fund GUSD burned against bad debt
+    pub burned_bad_debt: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Timelocked payout from the treasury, seeds `["treasury_spend", id]`
+#[account]
+#[derive(InitSpace)]
+pub struct TreasurySpend {
+    pub id: u64,
+    /// Treasurer that proposed the spend; refunded the rent on close
+    pub proposer: Pubkey,
+    /// Fee destination at proposal time
+    pub recipient: Pubkey,
+    pub gusd_amount: u64,
+    /// GOR to send (lamports)
+    pub lamports: u64,
+    /// Earliest execution time (unix seconds)
+    pub executable_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// A registered keeper's crank fee credits, seeds `["keeper", keeper]`
+#[account]
+#[derive(InitSpace)]
+pub struct KeeperAccount {
+    pub keeper: Pubkey,
+    /// Registration time (unix seconds)
+    pub registered_at: i64,
+    /// Cranks credited
+    pub cranks: u64,
+    /// Credited transaction fees not yet rebated (lamports)
+    pub unclaimed_fee_lamports: u64,
+    /// Lifetime credited transaction fees (lamports)
+    pub total_fee_lamports: u64,
+    /// Lifetime GUSD rebated
+    pub total_rebated: u64,
+    /// Slot of the last credit; one crank per slot is credited
+    pub last_credited_slot: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Protocol-owned liquidity in one whitelisted AMM pool, seeds `["pol_position", pool]`
+#[account]
+#[derive(InitSpace)]
+pub struct PolPosition {
+    /// AMM program the pool belongs to
+    pub amm_program: Pubkey,
+    pub pool: Pubkey,
+    /// The pool's LP token, held by the treasury PDA
+    pub lp_mint: Pubkey,
+    /// LP tokens received for deployments, less those redeemed
+    pub lp_amount: u64,
+    /// Lifetime GUSD added to the pool
+    pub gusd_deployed: u64,
+    /// Lifetime GOR added to the pool (lamports)
+    pub gor_deployed: u64,
+    /// Lifetime GUSD returned to the treasury
+    pub gusd_withdrawn: u64,
+    /// Lifetime GOR returned to the treasury (lamports)
+    pub gor_withdrawn: u64,
+    /// Registration time (unix seconds)
+    pub registered_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// GUSD savings pool, seeds `["savings"]`
+/// Share price is `total_assets / total_shares`; direct transfers to the pool's GUSD
+/// account are not counted, so they cannot move it.
+#[account]
+#[derive(InitSpace)]
+pub struct SavingsPool {
+    /// sGUSD shares outstanding
+    pub total_shares: u64,
+    /// GUSD owed to shareholders (deposits plus dripped fees, less withdrawals)
+    pub total_assets: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// A saver's sGUSD shares, seeds `["savings_position", owner]`
+#[account]
+#[derive(InitSpace)]
+pub struct SavingsPosition {
+    pub owner: Pubkey,
+    pub shares: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// One page of the append-only risk parameter changelog
+/// Page `n` (seeds `["parameter_log", n as little-endian u64]`) holds changes
+/// `n * PARAMETER_LOG_PAGE_SIZE` onwards, oldest first.
+#[account]
+#[derive(InitSpace)]
+pub struct ParameterLogPage {
+    /// Page number
+    pub page: u64,
+    #[max_len(PARAMETER_LOG_PAGE_SIZE)]
+    pub entries: Vec<ParameterChange>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy)]
+pub struct ParameterChange {
+    pub parameter: RiskParameter,
+    pub old_value: u64,
+    pub new_value: u64,
+    /// Risk manager or admin that made the change
+    pub actor: Pubkey,
+    /// Slot the change took effect
+    pub slot: u64,
+    /// Collateral mint of a per-collateral parameter (default = protocol-wide)
+    pub target: Pubkey,
+}
+
+/// Deployment-specific display metadata shared by all frontends
+#[account]
+#[derive(InitSpace)]
+pub struct DisplayConfig {
+    /// Symbol of the collateral token (e.g., "GOR")
+    #[max_len(MAX_SYMBOL_LEN)]
+    pub collateral_symbol: String,
+    /// Symbol of the stablecoin (e.g., "GUSD")
+    #[max_len(MAX_SYMBOL_LEN)]
+    pub gusd_symbol: String,
+    /// Collateral decimals used by on-chain math
+    pub collateral_decimals: u8,
+    /// GUSD mint decimals
+    pub gusd_decimals: u8,
+    /// Explorer URL prefix for accounts (address is appended)
+    #[max_len(MAX_URL_LEN)]
+    pub explorer_account_url: String,
+    /// Explorer URL prefix for transactions (signature is appended)
+    #[max_len(MAX_URL_LEN)]
+    pub explorer_tx_url: String,
+    /// Hash of the off-chain branding bundle (logos, colors)
+    pub branding_hash: [u8; 32],
+    /// Timestamp of last update (unix seconds)
+    pub updated_at: i64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+// ============================================================================
+// RETURN TYPES
+// ============================================================================
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VaultHealth {
+    pub collateral_amount: u64,
+    pub collateral_value_usd: u64,
+    pub debt_amount: u64,
+    pub collateral_ratio_bps: u64,
+    /// `collateral_ratio_bps` normalized so 1.0 (`HEALTH_FACTOR_ONE`) is the liquidation threshold
+    pub health_factor: u64,
+    pub is_liquidatable: bool,
+    pub repayment_streak: u16,
+    pub fee_rebate_bps: u64,
+    /// Liquidation threshold of the vault's collateral type (bps)
+    pub liquidation_threshold_bps: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PegStatus {
+    pub peg_currency: [u8; 3],
+    pub price: u64,
+    pub deviation_bps: u64,
+    pub is_depegged: bool,
+    pub depegged_since: i64,
+    pub updated_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FeeRates {
+    /// Decayed base rate (18 decimals, 1e18 = 100%)
+    pub base_rate: u64,
+    /// Mint fee a non-exempt mint would pay now (bps)
+    pub mint_fee_bps: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MarginHealth {
+    pub vault_count: u8,
+    pub collateral_amount: u64,
+    pub collateral_value_usd: u64,
+    pub debt_amount: u64,
+    pub collateral_ratio_bps: u64,
+    /// `collateral_ratio_bps` normalized so 1.0 (`HEALTH_FACTOR_ONE`) is the liquidation threshold
+    pub health_factor: u64,
+    /// Ratio mints and withdrawals must preserve (minimum plus margin buffer)
+    pub min_collateral_ratio_bps: u64,
+    pub is_liquidatable: bool,
+}
+
+/// Vault balances and the hash committing to them at `slot`
+/// `state_hash` = sha256(`VAULT_STATE_DOMAIN` || program id || vault || owner ||
+/// collateral (u64 LE) || debt (u64 LE) || slot (u64 LE)).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VaultStateHash {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub collateral_amount: u64,
+    pub debt_amount: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub state_hash: [u8; 32],
+}
+
+/// One account of a whitelisted treasury swap route
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapRouteAccount {
+    pub pubkey: Pubkey,
+    pub is_writable: bool,
+}
+
+/// One accepted price from the accumulator ring buffer
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PricePoint {
+    pub timestamp: i64,
+    pub price: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VaultNotice {
+    pub action: Option<NoticeAction>,
+    pub effective_at: i64,
+    pub seconds_remaining: i64,
+    pub vault_affected: bool,
+}
+
+/// Impact of hypothetical risk parameters on one page of vaults
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct ParameterChangePreview {
+    pub vaults_scanned: u32,
+    /// Vaults that could no longer mint or withdraw under the proposed min CR
+    pub vaults_below_min_cr: u32,
+    pub debt_below_min_cr: u64,
+    /// Vaults liquidatable under the proposed threshold
+    pub vaults_liquidatable: u32,
+    pub debt_liquidatable: u64,
+    /// Vaults liquidatable under the proposed threshold but not the current one
+    pub newly_liquidatable_vaults: u32,
+    pub newly_liquidatable_debt: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CrLadderPoint {
+    pub gor_price_usd: u64,
+    pub collateral_ratio_bps: u64,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum GusdError {
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    #[msg("Invalid amount")]
+    InvalidAmount,
+    #[msg("Invalid price")]
+    InvalidPrice,
+    #[msg("Insufficient collateral for this operation")]
+    InsufficientCollateral,
+    #[msg("This withdrawal would undercollateralize the vault")]
+    WouldUndercollateralize,
+    #[msg("Vault is not eligible for liquidation")]
+    VaultNotLiquidatable,
+    #[msg("No debt to liquidate")]
+    NoDebtToLiquidate,
+    #[msg("Liquidation is not profitable with current collateral")]
+    LiquidationNotProfitable,
+    #[msg("Math overflow")]
+    MathOverflow,
+    /// [HIGH-2] New error
+    #[msg("Invalid vault owner")]
+    InvalidVaultOwner,
+    /// [MEDIUM-1] New error
+    #[msg("Price change exceeds maximum allowed limit")]
+    PriceChangeExceedsLimit,
+    #[msg("Price update is too frequent")]
+    PriceUpdateTooFrequent,
+    /// [MEDIUM-2] New error
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Vault must have zero debt and zero collateral")]
+    VaultNotEmpty,
+    #[msg("Display config field exceeds maximum length")]
+    DisplayFieldTooLong,
+    #[msg("Vault was already liquidated to health in this slot")]
+    DuplicateLiquidationInSlot,
+    #[msg("Native token decimals out of range")]
+    InvalidNativeDecimals,
+    #[msg("Vault does not meet the abandoned-vault criteria")]
+    VaultNotAbandoned,
+    #[msg("Vault is not flagged as abandoned")]
+    VaultNotFlagged,
+    #[msg("Owner reclaim window has not elapsed")]
+    ReclaimWindowActive,
+    #[msg("Invalid protocol parameter")]
+    InvalidParameter,
+    #[msg("Per-epoch collateral outflow cap exceeded")]
+    EpochOutflowCapExceeded,
+    #[msg("Interest accrual is already paused")]
+    AccrualAlreadyPaused,
+    #[msg("Interest accrual is not paused")]
+    AccrualNotPaused,
+    #[msg("Oracle is not configured or price account missing")]
+    OracleNotConfigured,
+    #[msg("Invalid oracle price account")]
+    InvalidOracleAccount,
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    #[msg("Governance notice period is too short")]
+    NoticePeriodTooShort,
+    #[msg("No pending governance notice")]
+    NoPendingNotice,
+    #[msg("Not enough price history for the requested TWAP window")]
+    InsufficientPriceHistory,
+    #[msg("Audit registry is full")]
+    AuditRegistryFull,
+    #[msg("Oracle confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    #[msg("Swap route is not whitelisted")]
+    InvalidSwapRoute,
+    #[msg("Swap output below the allowed slippage")]
+    SlippageExceeded,
+    #[msg("Signed price is missing feeder signatures or was already used")]
+    InvalidPriceSignature,
+    #[msg("Liquidation insurance is not available")]
+    InsuranceUnavailable,
+    #[msg("Mint would exceed the global debt ceiling")]
+    DebtCeilingExceeded,
+    #[msg("Launch ramp is not active")]
+    LaunchRampInactive,
+    #[msg("Price is disputed by the guardian")]
+    PriceDisputed,
+    #[msg("No price dispute to clear")]
+    NoPriceDispute,
+    #[msg("Collateral account would fall below its rent reserve")]
+    RentReserveViolated,
+    #[msg("Vault belongs to a margin account")]
+    VaultInMarginAccount,
+    #[msg("Margin account is full")]
+    MarginAccountFull,
+    #[msg("Vault's margin account must be provided")]
+    MarginAccountRequired,
+    #[msg("Margin account does not match the vault")]
+    MarginAccountMismatch,
+    #[msg("Remaining accounts must list every margin account vault in order")]
+    MarginVaultsMismatch,
+    #[msg("Debt and collateral vaults must differ; use liquidate")]
+    MarginVaultsNotDistinct,
+    #[msg("Vault health was already attested this slot")]
+    HealthAlreadyAttested,
+    #[msg("Feed is already priced by its oracle")]
+    OracleAlreadyActive,
+    #[msg("No oracle shadow run in progress")]
+    NoOracleShadow,
+    #[msg("Oracle shadow run has not completed")]
+    OracleShadowIncomplete,
+    #[msg("Shadow oracle diverged from the admin price")]
+    OracleShadowDiverged,
+    #[msg("Oracle switch is already scheduled")]
+    OracleSwitchAlreadyScheduled,
+    #[msg("Oracle switch timelock has not elapsed")]
+    OracleSwitchTimelocked,
+    #[msg("Treasury spend timelock has not elapsed")]
+    TreasurySpendTimelocked,
+    #[msg("Too many fallback oracles")]
+    TooManyFallbackOracles,
+    #[msg("Fallback oracle must be a distinct Pyth or Switchboard account")]
+    InvalidFallbackOracle,
+    #[msg("Peg currency must be a three-letter uppercase code")]
+    InvalidPegCurrency,
+    #[msg("Peg currency cannot change while debt is outstanding")]
+    PegCurrencyLocked,
+    #[msg("Scan page vaults must be in ascending key order after the cursor")]
+    ScanOutOfOrder,
+    #[msg("Protocol has been shut down")]
+    ProtocolShutDown,
+    #[msg("Settlement price already marked")]
+    SettlementAlreadyMarked,
+    #[msg("Settlement price not yet marked")]
+    SettlementNotMarked,
+    #[msg("Redemption request window is still open")]
+    RedemptionWindowOpen,
+    #[msg("Vaults with debt remain to be settled and the settlement window is open")]
+    SettlementIncomplete,
+    #[msg("Fee destination does not match the configured destination")]
+    InvalidFeeDestination,
+    #[msg("Treasury balance is too low")]
+    InsufficientTreasuryBalance,
+    #[msg("A KYC attestation token is required to open a vault")]
+    KycAttestationRequired,
+    #[msg("Invalid KYC attestation")]
+    InvalidKycAttestation,
+    #[msg("Stability fee rate locks are not offered")]
+    RateLockUnavailable,
+    #[msg("Vault already has an active rate lock")]
+    RateLockActive,
+    #[msg("Expiry must be in the future")]
+    InvalidExpiry,
+    #[msg("No bad debt to record or cover")]
+    NoBadDebt,
+    #[msg("Insurance fund balance too low")]
+    InsufficientInsuranceFund,
+    #[msg("Sweep the vault's liquidation fees first")]
+    LiquidationFeesUnswept,
+    #[msg("A lapsed pause cannot be renewed yet")]
+    PauseCooldownActive,
+    #[msg("Not enough savings shares")]
+    InsufficientSavingsShares,
+    #[msg("Invoice has already been paid")]
+    InvoiceAlreadyPaid,
+    #[msg("Invoice has expired")]
+    InvoiceExpired,
+    #[msg("Invoice is still payable")]
+    InvoiceOutstanding,
+    #[msg("Destination account does not belong to the invoice payee")]
+    InvalidInvoicePayee,
+    #[msg("Vault is in its priority liquidation window")]
+    PriorityWindowActive,
+    #[msg("Bidding for this priority rights round has closed")]
+    AuctionBiddingClosed,
+    #[msg("Bidding for this priority rights round is still open")]
+    AuctionBiddingOpen,
+    #[msg("Bid must beat the leading bid by the minimum increment")]
+    BidTooLow,
+    #[msg("Refund account must belong to the leading bidder")]
+    InvalidRefundAccount,
+    #[msg("Liquidation tip exceeds the allowed share of the liquidator's bonus")]
+    LiquidationTipTooLarge,
+    #[msg("Fee tiers must have increasing stakes, non-decreasing discounts, and discounts within the cap")]
+    InvalidFeeTiers,
+    #[msg("The protocol lifecycle cannot move to that state")]
+    InvalidLifecycleTransition,
+    #[msg("Recovery mode: new debt and collateral withdrawals are suspended")]
+    RecoveryModeRestricted,
+    #[msg("AMM program or LP mint does not match the registered pool")]
+    InvalidPolRoute,
+    #[msg("GUSD and GOR legs of the deployment are too far apart in value")]
+    PolImbalanced,
+    #[msg("Keeper credit needs the registered keeper's signature and the instructions sysvar")]
+    InvalidKeeperCredit,
+    #[msg("Keeper rebates are disabled or this epoch's budget is spent")]
+    KeeperRebateUnavailable,
+    #[msg("Collateral type is disabled")]
+    CollateralDisabled,
+    #[msg("Not supported for vaults holding this collateral type")]
+    UnsupportedCollateral,
+    #[msg("Token collateral needs its mint, token accounts, and token program")]
+    MissingCollateralAccounts,
+    #[msg("Not a stake pool of the whitelisted program for this feed's collateral")]
+    InvalidStakePool,
+    #[msg("Stake pool has not been updated for the current epoch")]
+    StakePoolNotUpdated,
+    #[msg("Liquid staking exchange rate is stale; run refresh_exchange_rate")]
+    StaleExchangeRate,
+    #[msg("Mint would exceed the collateral type's debt ceiling")]
+    CollateralDebtCeilingExceeded,
+    #[msg("Mint would exceed the GUSD max supply")]
+    MaxSupplyExceeded,
+    #[msg("Vault debt would fall below the minimum; repay in full instead")]
+    VaultDebtBelowMinimum,
+    #[msg("Mint would take the vault's debt above the per-vault maximum")]
+    VaultDebtAboveMaximum,
+    #[msg("Delegate must be a key other than the owner, approved with a scope")]
+    InvalidDelegate,
+    #[msg("Collateral balance accounts must be passed in the vault's order")]
+    CollateralBalancesMismatch,
+    #[msg("Vault already holds the maximum number of collateral balances")]
+    CollateralBalancesFull,
+    #[msg("Vault still has open collateral balances")]
+    VaultHasCollateralBalances,
+    #[msg("Price feed batch must be 1-8 writable [price_feed, price_update] pairs")]
+    InvalidPriceFeedBatch,
+    #[msg("Account is not the protocol's FX price feed")]
+    InvalidFxPriceFeed,
+    #[msg("Collateral balance has already settled")]
+    CollateralBalanceSettled,
+    #[msg("Vault's collateral balances must settle first")]
+    CollateralBalanceUnsettled,
+    #[msg("Vault's position NFT accounts are required")]
+    PositionAccountsRequired,
+    #[msg("Vault is flagged as abandoned")]
+    VaultFlaggedAbandoned,
+}
+
+// ============================================================================
+// EVENTS [MEDIUM-3]
+// ============================================================================
+
+#[event]
+pub struct VaultClosed {
+    pub owner: Pubkey,
+    pub rent_refunded: u64,
+    pub excess_refunded: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct VaultCreated {
+    pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub position_mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollateralDeposited {
+    pub owner: Pubkey,
+    /// Collateral credited to the vault, net of any transfer fee
+    pub amount: u64,
+    /// Token-2022 transfer fee withheld from the deposit
+    pub transfer_fee: u64,
+    pub total_collateral: u64,
+    /// Program of the transaction's top-level instruction when the deposit came via CPI
+    /// (None for direct deposits). With nested CPIs this is the outermost program, not the
+    /// direct caller.
+    pub top_level_program: Option<Pubkey>,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct CollateralDepositedFor {
+    pub owner: Pubkey,
+    /// Signer who paid the collateral
+    pub depositor: Pubkey,
+    /// Collateral credited to the vault, net of any transfer fee
+    pub amount: u64,
+    /// Token-2022 transfer fee withheld from the deposit
+    pub transfer_fee: u64,
+    pub total_collateral: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct GusdMinted {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    /// Mint fee and unpaid stability fees burned from the owner's GUSD instead of added to
+    /// debt
+    pub upfront: u64,
+    pub total_debt: u64,
+    pub collateral_ratio_bps: u64,
+    pub health_factor: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct GusdRepaid {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub remaining_debt: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct GusdRepaidFor {
+    pub owner: Pubkey,
+    /// Signer whose GUSD was burned
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub remaining_debt: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct CollateralWithdrawn {
+    pub owner: Pubkey,
+    /// Collateral removed from the vault, fee included
+    pub amount: u64,
+    /// Part of `amount` kept by the treasury
+    pub fee: u64,
+    /// Token-2022 transfer fee withheld from the owner's payout
+    pub transfer_fee: u64,
+    pub remaining_collateral: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct VaultLiquidated {
+    pub vault_owner: Pubkey,
+    pub liquidator: Pubkey,
+    pub debt_repaid: u64,
+    pub collateral_seized: u64,
+    /// Part of `collateral_seized` retained as the protocol fee
+    pub protocol_fee: u64,
+    /// GOR the liquidator routed to the vault owner out of its share (lamports)
+    pub owner_tip: u64,
+    /// Token-2022 transfer fee withheld from the liquidator's payout
+    pub transfer_fee: u64,
+    /// Whether `owner_tip` stayed in the vault as collateral (no verified holder)
+    pub tip_held: bool,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct MarginLiquidated {
+    pub margin_account: Pubkey,
+    pub debt_vault: Pubkey,
+    pub collateral_vault: Pubkey,
+    pub liquidator: Pubkey,
+    pub debt_repaid: u64,
+    pub collateral_seized: u64,
+    /// Part of `collateral_seized` retained as the protocol fee
+    pub protocol_fee: u64,
+}
+
+#[event]
+pub struct BadDebtRecorded {
+    pub vault_owner: Pubkey,
+    pub amount: u64,
+    /// Outstanding bad debt after this write-off
+    pub bad_debt: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct BadDebtCovered {
+    /// Insurance fund GUSD burned
+    pub amount: u64,
+    /// Fee revenue minted into the fund first
+    pub realized: u64,
+    pub bad_debt: u64,
+    pub insurance_fund_balance: u64,
+}
+
+#[event]
+pub struct LiquidationFeesSwept {
+    pub vault_owner: Pubkey,
+    /// Lamports moved to the treasury
+    pub amount: u64,
+}
+
+#[event]
+pub struct MarginAccountCreated {
+    pub margin_account: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct MarginVaultJoined {
+    pub margin_account: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct MarginVaultLeft {
+    pub margin_account: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct LiquidationInsurancePurchased {
+    pub owner: Pubkey,
+    pub premium: u64,
+    pub insured_until: i64,
+}
+
+#[event]
+pub struct VaultFlaggedAbandoned {
+    pub owner: Pubkey,
+    pub collateral_amount: u64,
+    pub sweep_eligible_at: i64,
+}
+
+#[event]
+pub struct VaultReclaimed {
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct AbandonedVaultSwept {
+    pub owner: Pubkey,
+    pub collateral_swept: u64,
+    pub lamports_swept: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct PriceUpdated {
+    /// Collateral mint of the updated feed
+    pub collateral_mint: Pubkey,
+    pub old_price: u64,
+    pub new_price: u64,
+}
+
+#[event]
+pub struct KycAttestationSet {
+    /// Default pubkey when the requirement is lifted
+    pub attestation_mint: Pubkey,
+    pub issuer: Pubkey,
+}
+
+#[event]
+pub struct SurplusReleased {
+    pub amount: u64,
+    pub surplus_buffer: u64,
+    /// Treasury-bound fees after the release
+    pub accrued_fees: u64,
+}
+
+#[event]
+pub struct TreasuryInitialized {
+    pub treasury: Pubkey,
+    pub treasury_gusd_account: Pubkey,
+}
+
+#[event]
+pub struct FeeDestinationSet {
+    pub old_destination: Pubkey,
+    pub new_destination: Pubkey,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub treasurer: Pubkey,
+    /// Accrued stability and mint fees minted into the treasury
+    pub realized: u64,
+}
+
+#[event]
+pub struct TreasurySpendDelaySet {
+    pub old_delay_secs: u64,
+    pub new_delay_secs: u64,
+}
+
+#[event]
+pub struct TreasurySpendProposed {
+    pub id: u64,
+    pub recipient: Pubkey,
+    pub gusd_amount: u64,
+    pub lamports: u64,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct TreasurySpendExecuted {
+    pub id: u64,
+    pub recipient: Pubkey,
+    pub gusd_amount: u64,
+    pub lamports: u64,
+}
+
+#[event]
+pub struct TreasurySpendCancelled {
+    pub id: u64,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct BootstrapAuctionStarted {
+    pub tranche: u64,
+    /// Protocol-owned collateral backing the tranche (lamports)
+    pub collateral: u64,
+    pub start_price: u64,
+    pub end_price: u64,
+    pub starts_at: i64,
+    pub ends_at: i64,
+}
+
+#[event]
+pub struct BootstrapGusdBought {
+    pub buyer: Pubkey,
+    pub amount: u64,
+    /// Lamports paid into protocol-owned liquidity
+    pub cost: u64,
+    /// Lamports per whole GUSD
+    pub price: u64,
+    pub sold: u64,
+}
+
+#[event]
+pub struct BootstrapAuctionFinalized {
+    pub sold: u64,
+    pub proceeds: u64,
+    pub unsold_burned: u64,
+}
+
+#[event]
+pub struct SavingsInitialized {
+    pub savings_pool: Pubkey,
+    pub savings_gusd_account: Pubkey,
+}
+
+#[event]
+pub struct SavingsDeposited {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub total_assets: u64,
+    pub total_shares: u64,
+}
+
+#[event]
+pub struct SavingsWithdrawn {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub shares: u64,
+    pub total_assets: u64,
+    pub total_shares: u64,
+}
+
+#[event]
+pub struct SavingsDripped {
+    /// Fee revenue minted into the pool
+    pub amount: u64,
+    pub total_assets: u64,
+    pub total_shares: u64,
+}
+
+#[event]
+pub struct ShutdownTriggered {
+    pub admin: Pubkey,
+    pub total_debt: u64,
+    pub total_collateral: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RedemptionRequested {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_requested: u64,
+}
+
+#[event]
+pub struct SettlementMarked {
+    pub price: u64,
+    pub requested: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultSettled {
+    pub owner: Pubkey,
+    pub debt: u64,
+    pub collateral_to_pool: u64,
+    pub collateral_returned: u64,
+    pub collateral_mint: Pubkey,
+    /// Token-2022 transfer fee withheld from `collateral_to_pool`
+    pub transfer_fee: u64,
+    /// Collateral left in the vault for its holder (no verified holder was passed)
+    pub collateral_held: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct CollateralBalanceSettled {
+    pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    /// Debt the balance settled (GUSD)
+    pub debt: u64,
+    pub collateral_to_pool: u64,
+    /// Token-2022 transfer fee withheld from `collateral_to_pool`
+    pub transfer_fee: u64,
+    /// Collateral left in the balance
+    pub remaining_balance: u64,
+    pub remaining_debt: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct RedemptionClaimed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Collateral paid by this claim
+    pub collateral: u64,
+    /// Collateral paid to the request across all its claims
+    pub total_claimed: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+#[event]
+pub struct TokenSettlementMarked {
+    pub collateral_mint: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct TokenRedemptionClaimed {
+    pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub amount: u64,
+    /// Collateral paid by this claim
+    pub collateral: u64,
+    /// Collateral paid to the request from this pool across all its claims
+    pub total_claimed: u64,
 }
 
-#[derive(Accounts)]
+#[event]
+pub struct InvoiceCreated {
+    pub invoice: Pubkey,
+    pub payee: Pubkey,
+    pub invoice_id: u64,
+    pub amount: u64,
+    pub memo_hash: [u8; 32],
+    pub expires_at: i64,
+}
 
+#[event]
+pub struct InvoicePaid {
+    pub invoice: Pubkey,
+    pub payee: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub memo_hash: [u8; 32],
+    /// Whether the GUSD was minted against the payer's vault
+    pub minted: bool,
+}
 
-#[derive(Accounts)]
-pub struct CloseVault<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+#[event]
+pub struct PriorityAuctionOpened {
+    pub round: u64,
+    pub bidding_ends_at: i64,
+}
 
-    #[account(
-        mut,
-        close = owner,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == owner.key() @ GusdError::InvalidVaultOwner
-    )]
-    pub vault: Account<'info, Vault>,
+#[event]
+pub struct PriorityBidPlaced {
+    pub round: u64,
+    pub bidder: Pubkey,
+    pub amount: u64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault_collateral", owner.key().as_ref()],
-        bump = vault.collateral_bump
-    )]
-    /// CHECK: PDA that holds GOR collateral as lamports (0-data account)
-    pub vault_collateral: AccountInfo<'info>,
+#[event]
+pub struct PriorityAuctionSettled {
+    pub round: u64,
+    /// Default pubkey when the round had no bids
+    pub winner: Pubkey,
+    pub winning_bid: u64,
+    pub rights_until: i64,
+}
 
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct VaultFlagged {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub collateral_ratio_bps: u64,
+    pub health_factor: u64,
+    pub debt_amount: u64,
 }
-pub struct Liquidate<'info> {
-    #[account(mut)]
-    pub liquidator: Signer<'info>,
 
-    /// CHECK: The owner of the vault being liquidated
-    pub vault_owner: AccountInfo<'info>,
+#[event]
+pub struct ScanPageCompleted {
+    pub keeper: Pubkey,
+    pub sweep: u64,
+    pub last_vault: Pubkey,
+    pub vaults_scanned: u32,
+    pub vaults_flagged: u32,
+    pub slot: u64,
+}
 
-    /// [HIGH-2] Fixed: Added vault owner constraint
-    #[account(
-        mut,
-        seeds = [b"vault", vault_owner.key().as_ref()],
-        bump = vault.bump,
-        constraint = vault.owner == vault_owner.key() @ GusdError::InvalidVaultOwner
-    )]
-    pub vault: Account<'info, Vault>,
+#[event]
+pub struct HealthAttested {
+    pub vault: Pubkey,
+    pub slot: u64,
+    pub collateral_ratio_bps: u64,
+    pub health_factor: u64,
+    pub flags: u8,
+}
 
-    #[account(
-        mut,
-        seeds = [b"vault_collateral", vault_owner.key().as_ref()],
-        bump
-    )]
-    /// CHECK: This is a PDA that holds lamports (GOR)
-    pub vault_collateral: AccountInfo<'info>,
+#[event]
+pub struct PegPriceUpdated {
+    pub price: u64,
+    pub deviation_bps: u64,
+    pub depegged_since: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+#[event]
+pub struct PegStatusChanged {
+    pub depegged: bool,
+    pub price: u64,
+    pub timestamp: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"gusd_mint"],
-        bump = protocol_state.mint_bump
-    )]
-    pub gusd_mint: InterfaceAccount<'info, Mint>,
+#[event]
+pub struct RoleUpdated {
+    pub role: ProtocolRole,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
 
-    #[account(
-        mut,
-        associated_token::mint = gusd_mint,
-        associated_token::authority = liquidator,
-        associated_token::token_program = token_program
-    )]
-    pub liquidator_gusd_account: InterfaceAccount<'info, TokenAccount>,
+#[event]
+pub struct AccountBlocklisted {
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub reason_code: u16,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
 
-    pub token_program: Interface<'info, TokenInterface>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct AccountUnblocklisted {
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub reason_code: u16,
+    pub authority: Pubkey,
+    pub timestamp: i64,
 }
 
-#[derive(Accounts)]
-pub struct GetVaultHealth<'info> {
-    /// CHECK: Can query any vault
-    pub vault_owner: AccountInfo<'info>,
+#[event]
+pub struct GovernanceNoticePosted {
+    pub action: NoticeAction,
+    pub posted_at: i64,
+    pub effective_at: i64,
+}
 
-    #[account(
-        seeds = [b"vault", vault_owner.key().as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
+#[event]
+pub struct GovernanceNoticeCleared {
+    pub action: NoticeAction,
+}
 
-    #[account(
-        seeds = [b"protocol"],
-        bump = protocol_state.bump
-    )]
-    pub protocol_state: Account<'info, ProtocolState>,
+#[event]
+pub struct FeeExemptionAdded {
+    pub address: Pubkey,
+    pub authority: Pubkey,
+    /// 0 = never expires
+    pub expires_at: i64,
+    pub timestamp: i64,
 }
 
-// ============================================================================
-// STATE
-// ============================================================================
+#[event]
+pub struct FeeExemptionRemoved {
+    pub address: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
 
-/// [MEDIUM-2] Updated: Added is_paused field
-#[account]
-#[derive(InitSpace)]
-pub struct ProtocolState {
-    /// Protocol admin (can update price for MVP)
-    pub admin: Pubkey,
-    /// GUSD mint address
-    pub gusd_mint: Pubkey,
-    /// Current GOR price in USD (6 decimals, e.g., 1_000_000 = $1.00)
-    pub gor_price_usd: u64,
-    /// Total GOR collateral locked in protocol
-    pub total_collateral: u64,
-    /// Total GUSD debt outstanding
-    pub total_debt: u64,
-    /// PDA bump
-    pub bump: u8,
-    /// Mint PDA bump
-    pub mint_bump: u8,
-    /// [MEDIUM-2] Protocol pause state
-    pub is_paused: bool,
-    /// Timestamp of last price update (unix seconds)
-    pub last_price_update_ts: i64,
+#[event]
+pub struct LifecycleChanged {
+    pub previous: Lifecycle,
+    pub next: Lifecycle,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeTiersInitialized {
+    pub governance_mint: Pubkey,
+    pub stake_escrow: Pubkey,
+}
+
+#[event]
+pub struct FeeTiersSet {
+    pub authority: Pubkey,
+    pub tiers: Vec<FeeTier>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceStaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub total_stake: u64,
+    pub warm_at: i64,
+}
+
+#[event]
+pub struct GovernanceUnstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub remaining_stake: u64,
+    /// Tier discount of the remaining stake
+    pub discount_bps: u64,
+}
+
+#[event]
+pub struct OracleConfigured {
+    pub collateral_mint: Pubkey,
+    pub kind: OracleKind,
+    pub price_account: Pubkey,
+    pub feed_id: [u8; 32],
+}
+
+#[event]
+pub struct PegCurrencySet {
+    pub old_currency: [u8; 3],
+    pub new_currency: [u8; 3],
+    /// Feed quoting the new currency in USD (default = prices quoted in it directly)
+    pub fx_price_feed: Pubkey,
+}
+
+#[event]
+pub struct FxRateUpdated {
+    pub peg_currency: [u8; 3],
+    /// USD per unit of the peg currency (6 decimals)
+    pub rate: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FallbackOraclesSet {
+    pub collateral_mint: Pubkey,
+    pub fallbacks: Vec<OracleSource>,
+}
+
+#[event]
+pub struct OracleFallbackUsed {
+    pub collateral_mint: Pubkey,
+    /// 1-based position in `fallback_oracles`
+    pub source_index: u8,
+    pub price: u64,
+}
+
+#[event]
+pub struct OracleShadowStarted {
+    pub collateral_mint: Pubkey,
+    pub kind: OracleKind,
+    pub price_account: Pubkey,
+    pub feed_id: [u8; 32],
+    pub shadow_period_secs: u64,
+}
+
+#[event]
+pub struct OracleShadowSampled {
+    pub collateral_mint: Pubkey,
+    pub oracle_price: u64,
+    pub admin_price: u64,
+    pub divergence_bps: u64,
+}
+
+#[event]
+pub struct OracleShadowDiverged {
+    pub collateral_mint: Pubkey,
+    pub oracle_price: u64,
+    pub admin_price: u64,
+    pub divergence_bps: u64,
+    pub alert_bps: u64,
+}
+
+#[event]
+pub struct OracleSwitchScheduled {
+    pub collateral_mint: Pubkey,
+    pub activation_at: i64,
+    pub shadow_samples: u32,
+    pub max_divergence_bps: u64,
+}
+
+#[event]
+pub struct OracleMigrationCancelled {
+    pub collateral_mint: Pubkey,
+}
+
+#[event]
+pub struct AdminPriceFallbackSet {
+    pub collateral_mint: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct PriceFeedRegistered {
+    pub collateral_mint: Pubkey,
+    pub decimals: u8,
+    pub initial_price: u64,
+}
+
+#[event]
+pub struct ChainlinkReportVerified {
+    pub price: u64,
+    pub conf_bps: u64,
+    pub observations_timestamp: i64,
+    pub native_fee: u128,
+    pub link_fee: u128,
+}
+
+#[event]
+pub struct PriceFeedersUpdated {
+    pub feeders: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct SwapRouteUpdated {
+    pub swap_program: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub route_accounts: Vec<SwapRouteAccount>,
+}
+
+#[event]
+pub struct TreasuryDiversified {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub epoch: u64,
+    pub swapped_in_epoch: u64,
+}
+
+#[event]
+pub struct VaultTokenAccountCreated {
+    pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    pub vault_token_account: Pubkey,
+}
+
+#[event]
+pub struct CollateralTypeAdded {
+    pub collateral_mint: Pubkey,
+    pub decimals: u8,
+    pub price_feed: Pubkey,
+    pub min_collateral_ratio_bps: u64,
+    pub liquidation_threshold_bps: u64,
+}
+
+#[event]
+pub struct CollateralTypeUpdated {
+    pub collateral_mint: Pubkey,
+    pub min_collateral_ratio_bps: u64,
+    pub liquidation_threshold_bps: u64,
+    /// 0 = unlimited
+    pub debt_ceiling: u64,
+    pub enabled: bool,
+    pub risk_manager: Pubkey,
+}
+
+#[event]
+pub struct StakePoolProgramUpdated {
+    pub stake_pool_program: Pubkey,
+}
+
+#[event]
+pub struct LiquidStakingPoolSet {
+    pub collateral_mint: Pubkey,
+    pub stake_pool: Pubkey,
+    pub exchange_rate: u64,
+}
+
+#[event]
+pub struct ExchangeRateRefreshed {
+    pub collateral_mint: Pubkey,
+    pub old_rate: u64,
+    pub new_rate: u64,
+    pub epoch: u64,
+}
+
+#[event]
+pub struct KeeperRegistered {
+    pub keeper: Pubkey,
+    pub treasurer: Pubkey,
+}
+
+#[event]
+pub struct KeeperRemoved {
+    pub keeper: Pubkey,
+    pub forfeited_fee_lamports: u64,
+}
+
+#[event]
+pub struct KeeperCredited {
+    pub keeper: Pubkey,
+    pub fee_lamports: u64,
+    pub unclaimed_fee_lamports: u64,
+    pub slot: u64,
+}
+
+#[event]
+pub struct KeeperRebateClaimed {
+    pub keeper: Pubkey,
+    pub rebate: u64,
+    /// Credited fees the rebate covered (lamports)
+    pub settled_fee_lamports: u64,
+    pub unclaimed_fee_lamports: u64,
+    pub rebated_in_epoch: u64,
+}
+
+#[event]
+pub struct PolPoolRegistered {
+    pub pool: Pubkey,
+    pub amm_program: Pubkey,
+    pub lp_mint: Pubkey,
+}
+
+#[event]
+pub struct PolDeployed {
+    pub pool: Pubkey,
+    pub gusd_amount: u64,
+    pub lamports: u64,
+    pub lp_received: u64,
+    /// LP tokens held after the deployment
+    pub lp_amount: u64,
+}
+
+#[event]
+pub struct PolWithdrawn {
+    pub pool: Pubkey,
+    pub lp_burned: u64,
+    pub gusd_amount: u64,
+    pub lamports: u64,
+    /// LP tokens held after the withdrawal
+    pub lp_amount: u64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub last_price: u64,
+    pub new_price: u64,
+    pub deviation_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceModeUpdated {
+    pub old_mode: PriceMode,
+    pub new_mode: PriceMode,
+}
+
+#[event]
+pub struct RiskTemplateApplied {
+    pub template: RiskTemplate,
+    /// Parameters set, each also reported by `RiskParameterUpdated`
+    pub parameters: u8,
 }
 
-/// [CRITICAL-4] Updated: Added collateral_bump field
-#[account]
-#[derive(InitSpace)]
-pub struct Vault {
-    /// Owner of this vault
+#[event]
+pub struct RiskParameterUpdated {
+    pub parameter: RiskParameter,
+    pub old_value: u64,
+    pub new_value: u64,
+    /// Collateral mint of a per-collateral parameter (default = protocol-wide)
+    pub target: Pubkey,
+}
+
+#[event]
+pub struct AccrualPaused {
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StabilityFeeLocked {
     pub owner: Pubkey,
-    /// Amount of GOR collateral (in lamports)
-    pub collateral_amount: u64,
-    /// Amount of GUSD debt (in GUSD smallest unit, 6 decimals)
-    pub debt_amount: u64,
-    /// PDA bump
-    pub bump: u8,
-    /// [CRITICAL-4] Collateral PDA bump
-    pub collateral_bump: u8,
+    /// Locked annual stability fee (bps)
+    pub rate_bps: u64,
+    /// Debt covered by the lock
+    pub principal: u64,
+    pub locked_until: i64,
+    /// GUSD paid to the treasury
+    pub premium: u64,
 }
 
-// ============================================================================
-// RETURN TYPES
-// ============================================================================
+#[event]
+pub struct CollateralFeesUpdated {
+    pub collateral_mint: Pubkey,
+    pub fees: CollateralFees,
+}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct VaultHealth {
-    pub collateral_amount: u64,
-    pub collateral_value_usd: u64,
-    pub debt_amount: u64,
-    pub collateral_ratio_bps: u64,
-    pub is_liquidatable: bool,
+#[event]
+pub struct InterestAccrued {
+    pub owner: Pubkey,
+    pub interest: u64,
+    pub rebate: u64,
+    pub staker_discount: u64,
+    pub total_debt: u64,
+    pub debt_index: u128,
+    pub premium_index: u128,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
 }
 
-// ============================================================================
-// ERRORS
-// ============================================================================
+#[event]
+pub struct AccrualResumed {
+    pub paused_at: i64,
+    pub resumed_at: i64,
+    pub skipped_secs: u64,
+}
 
-#[error_code]
-pub enum GusdError {
-    #[msg("Unauthorized access")]
-    Unauthorized,
-    #[msg("Invalid amount")]
-    InvalidAmount,
-    #[msg("Invalid price")]
-    InvalidPrice,
-    #[msg("Insufficient collateral for this operation")]
-    InsufficientCollateral,
-    #[msg("This withdrawal would undercollateralize the vault")]
-    WouldUndercollateralize,
-    #[msg("Vault is not eligible for liquidation")]
-    VaultNotLiquidatable,
-    #[msg("No debt to liquidate")]
-    NoDebtToLiquidate,
-    #[msg("Liquidation is not profitable with current collateral")]
-    LiquidationNotProfitable,
-    #[msg("Math overflow")]
-    MathOverflow,
-    /// [HIGH-2] New error
-    #[msg("Invalid vault owner")]
-    InvalidVaultOwner,
-    /// [MEDIUM-1] New error
-    #[msg("Price change exceeds maximum allowed limit")]
-    PriceChangeExceedsLimit,
-    #[msg("Price update is too frequent")]
-    PriceUpdateTooFrequent,
-    /// [MEDIUM-2] New error
-    #[msg("Protocol is paused")]
-    ProtocolPaused,
-    #[msg("Vault must have zero debt and zero collateral")]
-    VaultNotEmpty,
+#[event]
+pub struct YieldRoutingSet {
+    pub owner: Pubkey,
+    pub routing: YieldRouting,
 }
 
-// ============================================================================
-// EVENTS [MEDIUM-3]
-// ============================================================================
+#[event]
+pub struct DelegateApproved {
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub scope: DelegateScope,
+}
 
 #[event]
-pub struct VaultCreated {
+pub struct DelegateRevoked {
     pub owner: Pubkey,
-    pub timestamp: i64,
+    pub delegate: Pubkey,
 }
 
 #[event]
-pub struct CollateralDeposited {
+pub struct CollateralBalanceOpened {
     pub owner: Pubkey,
-    pub amount: u64,
-    pub total_collateral: u64,
+    pub collateral_mint: Pubkey,
 }
 
 #[event]
-pub struct GusdMinted {
+pub struct CollateralBalanceDeposited {
     pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    /// Collateral credited, net of the transfer fee
     pub amount: u64,
-    pub total_debt: u64,
-    pub collateral_ratio_bps: u64,
+    /// Token-2022 transfer fee withheld from the deposit
+    pub transfer_fee: u64,
+    pub balance: u64,
 }
 
 #[event]
-pub struct GusdRepaid {
+pub struct CollateralBalanceWithdrawn {
     pub owner: Pubkey,
+    pub collateral_mint: Pubkey,
+    /// Collateral removed from the balance, fee included
     pub amount: u64,
-    pub remaining_debt: u64,
+    /// Part of `amount` kept by the treasury
+    pub fee: u64,
+    /// Token-2022 transfer fee withheld from the owner's payout
+    pub transfer_fee: u64,
+    pub remaining_balance: u64,
 }
 
 #[event]
-pub struct CollateralWithdrawn {
+pub struct CollateralBalanceClosed {
     pub owner: Pubkey,
-    pub amount: u64,
-    pub remaining_collateral: u64,
+    pub collateral_mint: Pubkey,
 }
 
 #[event]
-pub struct VaultLiquidated {
+pub struct CollateralBalanceLiquidated {
     pub vault_owner: Pubkey,
+    pub collateral_mint: Pubkey,
     pub liquidator: Pubkey,
     pub debt_repaid: u64,
+    /// Collateral taken from the balance, protocol fee included
     pub collateral_seized: u64,
+    /// Part of `collateral_seized` paid to the treasury
+    pub protocol_fee: u64,
+    /// Token-2022 transfer fee withheld from the liquidator's payout
+    pub transfer_fee: u64,
 }
 
 #[event]
-pub struct PriceUpdated {
+pub struct FeesPaidWithCollateral {
+    pub owner: Pubkey,
+    /// Stability fees settled (GUSD)
+    pub interest: u64,
+    /// GOR moved from the vault to the treasury (lamports)
+    pub collateral: u64,
+    pub price: u64,
+    /// Unminted treasury fees (`accrued_fees`) given up in exchange
+    pub forgone_fees: u64,
+    pub remaining_debt: u64,
+    /// Unminted savers' share given up in exchange
+    pub forgone_savings: u64,
+    /// Unminted insurance fund share given up in exchange
+    pub forgone_insurance: u64,
+    /// Surplus buffer given up in exchange
+    pub forgone_surplus: u64,
+    /// Position NFT mint the vault's PDAs are keyed by
+    pub position_mint: Pubkey,
+}
+
+#[event]
+pub struct EmergencyPriceSet {
+    /// Collateral mint of the reset feed
+    pub collateral_mint: Pubkey,
+    pub admin: Pubkey,
+    pub guardian: Pubkey,
     pub old_price: u64,
     pub new_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceVetoed {
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceDisputeCleared {
+    pub disputed_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LaunchRampUpdated {
+    pub debt_ceiling: u64,
+    pub step_bps: u64,
+    pub target_ceiling: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LaunchRampHalted {
+    pub guardian: Pubkey,
+    pub debt_ceiling: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DebtCeilingRamped {
+    pub old_ceiling: u64,
+    pub new_ceiling: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochCapsUpdated {
+    pub epoch_liquidation_cap: u64,
+    pub epoch_redemption_cap: u64,
+}
+
+#[event]
+pub struct AuditHashRecorded {
+    pub index: u8,
+    pub report_hash: [u8; 32],
+    pub program_hash: [u8; 32],
+    pub recorded_at: i64,
+}
+
+#[event]
+pub struct DisplayConfigUpdated {
+    pub admin: Pubkey,
+    pub branding_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// An account with every field zeroed, as freshly allocated space deserializes
+#[cfg(test)]
+fn zeroed<T: AnchorDeserialize + Space>() -> T {
+    T::deserialize(&mut &vec![0u8; T::INIT_SPACE][..]).unwrap()
+}
+
+#[cfg(test)]
+mod accrual_tests {
+    use super::*;
+
+    /// Protocol charging 10% a year from t = 0, with `total_debt` outstanding
+    fn protocol(total_debt: u64) -> ProtocolState {
+        let mut protocol: ProtocolState = zeroed();
+        protocol.debt_index = DEBT_INDEX_ONE;
+        protocol.stability_fee_bps = 1_000;
+        protocol.total_debt = total_debt;
+        protocol
+    }
+
+    /// Vault owing `debt` GUSD, last accrued at the unit index
+    fn vault(debt: u64) -> Vault {
+        let mut vault: Vault = zeroed();
+        vault.debt_amount = debt;
+        vault.debt_index_snapshot = DEBT_INDEX_ONE;
+        vault.premium_index_snapshot = DEBT_INDEX_ONE;
+        vault
+    }
+
+    fn feed() -> PriceFeed {
+        let mut feed: PriceFeed = zeroed();
+        feed.premium_index = DEBT_INDEX_ONE;
+        feed
+    }
+
+    const YEAR: i64 = SECONDS_PER_YEAR as i64;
+    const DEBT: u64 = 1_000 * 1_000_000;
+
+    #[test]
+    fn debt_index_grows_at_the_stability_fee() {
+        let mut protocol = protocol(DEBT);
+        accrue_debt_index(&mut protocol, YEAR).unwrap();
+        assert_eq!(protocol.debt_index, DEBT_INDEX_ONE / 10 * 11);
+        assert_eq!(protocol.debt_index_updated_at, YEAR);
+
+        // A second accrual at the same time adds nothing
+        accrue_debt_index(&mut protocol, YEAR).unwrap();
+        assert_eq!(protocol.debt_index, DEBT_INDEX_ONE / 10 * 11);
+    }
+
+    #[test]
+    fn paused_interval_is_skipped() {
+        let mut protocol = protocol(DEBT);
+        protocol.accrual_paused_at = 1;
+        accrue_debt_index(&mut protocol, YEAR).unwrap();
+        assert_eq!(protocol.debt_index, DEBT_INDEX_ONE);
+        // The clock still moves, so resuming doesn't charge the paused year
+        assert_eq!(protocol.debt_index_updated_at, YEAR);
+
+        protocol.accrual_paused_at = 0;
+        accrue_debt_index(&mut protocol, 2 * YEAR).unwrap();
+        assert_eq!(protocol.debt_index, DEBT_INDEX_ONE / 10 * 11);
+    }
+
+    #[test]
+    fn vault_interest_follows_the_index() {
+        let mut protocol = protocol(DEBT);
+        let (mut feed, mut vault) = (feed(), vault(DEBT));
+        let mut stats: RevenueStats = zeroed();
+
+        let interest = accrue_vault_interest(&mut protocol, &mut feed, &mut vault, &mut stats, 0, YEAR).unwrap();
+        assert_eq!(interest, DEBT / 10);
+        assert_eq!(vault.debt_amount, DEBT + DEBT / 10);
+        assert_eq!(vault.unpaid_interest, DEBT / 10);
+        assert_eq!(vault.debt_index_snapshot, protocol.debt_index);
+        assert_eq!(protocol.total_debt, DEBT + DEBT / 10);
+        assert_eq!(stats.stability_fees, DEBT / 10);
+    }
+
+    #[test]
+    fn first_accrual_only_snapshots_the_index() {
+        let mut protocol = protocol(DEBT);
+        let (mut feed, mut vault) = (feed(), vault(DEBT));
+        vault.debt_index_snapshot = 0;
+        let mut stats: RevenueStats = zeroed();
+
+        let interest = accrue_vault_interest(&mut protocol, &mut feed, &mut vault, &mut stats, 0, YEAR).unwrap();
+        assert_eq!(interest, 0);
+        assert_eq!(vault.debt_amount, DEBT);
+        assert_eq!(vault.debt_index_snapshot, protocol.debt_index);
+    }
+
+    #[test]
+    fn repayment_streak_rebates_interest() {
+        let mut protocol = protocol(DEBT);
+        let (mut feed, mut vault) = (feed(), vault(DEBT));
+        let mut stats: RevenueStats = zeroed();
+        // A 12-period streak with a repayment inside the last period earns 1%
+        vault.repayment_streak = 12;
+        vault.last_repayment_ts = YEAR - REPAYMENT_PERIOD_SECS;
+
+        let interest = accrue_vault_interest(&mut protocol, &mut feed, &mut vault, &mut stats, 0, YEAR).unwrap();
+        assert_eq!(interest, DEBT / 10 - DEBT / 1_000);
+
+        // A lapsed streak earns nothing
+        let (mut feed, mut vault) = (self::feed(), self::vault(DEBT));
+        let mut protocol = self::protocol(DEBT);
+        vault.repayment_streak = 12;
+        vault.last_repayment_ts = YEAR - REPAYMENT_PERIOD_SECS - 1;
+        let interest = accrue_vault_interest(&mut protocol, &mut feed, &mut vault, &mut stats, 0, YEAR).unwrap();
+        assert_eq!(interest, DEBT / 10);
+    }
+
+    #[test]
+    fn removed_debt_settles_interest_before_principal() {
+        let mut vault = vault(DEBT);
+        vault.unpaid_interest = 30;
+        assert_eq!(settle_unpaid_interest(&mut vault, 20), 0);
+        assert_eq!(vault.unpaid_interest, 10);
+        assert_eq!(settle_unpaid_interest(&mut vault, 50), 40);
+        assert_eq!(vault.unpaid_interest, 0);
+
+        // A liquidation's principal release leaves the type's counter at the principal left
+        let mut config: CollateralConfig = zeroed();
+        config.total_debt = DEBT;
+        let mut vault = vault_with_interest(DEBT, 100);
+        let principal = settle_unpaid_interest(&mut vault, 300);
+        release_collateral_debt(&mut config, principal);
+        assert_eq!(config.total_debt, DEBT - 200);
+    }
+
+    fn vault_with_interest(principal: u64, interest: u64) -> Vault {
+        let mut vault = vault(principal + interest);
+        vault.unpaid_interest = interest;
+        vault
+    }
+
+    #[test]
+    fn staker_discount_is_read_from_the_recorded_stake() {
+        let mut registry: FeeTierRegistry = zeroed();
+        registry.tiers[0] = FeeTier { min_stake: 100, discount_bps: 1_000 };
+        registry.tier_count = 1;
+        let stake = GovernanceStake { owner: Pubkey::new_unique(), amount: 100, warm_at: 0, bump: 0 };
+        let stake_key = Pubkey::new_unique();
+        let mut vault = vault(DEBT);
+
+        // Nothing recorded: no discount, even with a qualifying stake
+        assert_eq!(vault_stake_discount_bps(&vault, Some(&registry), Some((stake_key, &stake)), YEAR), 0);
+
+        vault.discount_stake = stake_key;
+        assert_eq!(vault_stake_discount_bps(&vault, Some(&registry), Some((stake_key, &stake)), YEAR), 1_000);
+        // Another stake, a missing account, a cold stake or an unstaked one earn nothing
+        let other = Pubkey::new_unique();
+        assert_eq!(vault_stake_discount_bps(&vault, Some(&registry), Some((other, &stake)), YEAR), 0);
+        assert_eq!(vault_stake_discount_bps(&vault, None, Some((stake_key, &stake)), YEAR), 0);
+        assert_eq!(vault_stake_discount_bps(&vault, Some(&registry), None, YEAR), 0);
+        let cold = GovernanceStake { warm_at: YEAR + 1, ..stake.clone() };
+        assert_eq!(vault_stake_discount_bps(&vault, Some(&registry), Some((stake_key, &cold)), YEAR), 0);
+        let unstaked = GovernanceStake { amount: 99, ..stake.clone() };
+        assert_eq!(vault_stake_discount_bps(&vault, Some(&registry), Some((stake_key, &unstaked)), YEAR), 0);
+
+        // The discount read at accrual comes off the interest
+        let mut protocol = protocol(DEBT);
+        let mut feed = feed();
+        let mut stats: RevenueStats = zeroed();
+        let interest = accrue_vault_interest(&mut protocol, &mut feed, &mut vault, &mut stats, 1_000, YEAR).unwrap();
+        assert_eq!(interest, DEBT / 10 - DEBT / 100);
+        assert_eq!(vault.stake_discount_bps, 1_000);
+    }
+}
+
+#[cfg(test)]
+mod settlement_tests {
+    use super::*;
+
+    const GOR_DECIMALS: u8 = 9;
+    const GOR: u64 = 1_000_000_000;
+
+    /// Settlement marked at $1 per GOR at t = 0, with 100 GUSD queued and `collateral` pooled
+    /// for 100 GUSD of settled GOR vault debt
+    fn settlement(collateral: u64) -> Settlement {
+        Settlement {
+            shutdown_at: 0,
+            price: 1_000_000,
+            marked_at: 0,
+            requested: 100_000_000,
+            collateral,
+            claimed: 0,
+            pool_bump: 0,
+            bump: 0,
+            marked_debt: 100_000_000,
+            settled_debt: 100_000_000,
+            debt: 100_000_000,
+        }
+    }
+
+    #[test]
+    fn claims_open_after_every_vault_settles_or_the_window_ends() {
+        let mut unmarked = settlement(0);
+        unmarked.price = 0;
+        assert!(!redemption_claims_open(&unmarked, 0, SETTLEMENT_WINDOW_SECS));
+
+        let marked = settlement(0);
+        assert!(redemption_claims_open(&marked, 0, 1));
+        // Debt left over, e.g. uncovered bad debt, only delays claims to the window's end
+        assert!(!redemption_claims_open(&marked, 5_000_000, SETTLEMENT_WINDOW_SECS - 1));
+        assert!(redemption_claims_open(&marked, 5_000_000, SETTLEMENT_WINDOW_SECS));
+    }
+
+    #[test]
+    fn entitlement_is_pro_rata_up_to_face_value() {
+        // 40 of 100 queued GUSD gets 40% of a 50 GOR pool
+        let (entitled, face_value) = redemption_entitlement(&settlement(50 * GOR), 40_000_000, GOR_DECIMALS).unwrap();
+        assert_eq!(face_value, 40 * GOR);
+        assert_eq!(entitled, 20 * GOR);
+
+        // Late settlements grow the share, but never past face value
+        let (entitled, _) = redemption_entitlement(&settlement(80 * GOR), 40_000_000, GOR_DECIMALS).unwrap();
+        assert_eq!(entitled, 32 * GOR);
+        let (entitled, _) = redemption_entitlement(&settlement(120 * GOR), 40_000_000, GOR_DECIMALS).unwrap();
+        assert_eq!(entitled, 40 * GOR);
+    }
+
+    #[test]
+    fn entitlements_never_exceed_the_pool() {
+        let pool = settlement(10 * GOR + 1);
+        let claims: u64 = [33_333_333, 33_333_333, 33_333_334]
+            .into_iter()
+            .map(|amount| redemption_entitlement(&pool, amount, GOR_DECIMALS).unwrap().0)
+            .sum();
+        assert!(claims <= pool.collateral);
+    }
+
+    #[test]
+    fn caps_across_gor_and_token_pools_add_up_to_face_value() {
+        // 100 GUSD outstanding at the mark: 60 settles into GOR at $1, 40 into a
+        // 6-decimal token at $2, both pools fully covered
+        let mut settlement = settlement(0);
+        settlement.settled_debt = 0;
+        settlement.debt = credit_settled_debt(&mut settlement, 60_000_000);
+        settlement.collateral = 60 * GOR;
+        let mut pool = SettlementTokenPool {
+            collateral_mint: Pubkey::new_unique(),
+            decimals: 6,
+            price: 2_000_000,
+            marked_at: 0,
+            debt: 0,
+            collateral: 20_000_000,
+            claimed: 0,
+            bump: 0,
+        };
+        pool.debt = credit_settled_debt(&mut settlement, 40_000_000);
+
+        // 50 of 100 queued GUSD is owed 30 GUSD in GOR and 20 GUSD in tokens
+        let (gor, face_value) = redemption_entitlement(&settlement, 50_000_000, GOR_DECIMALS).unwrap();
+        assert_eq!(gor, 30 * GOR);
+        assert_eq!(face_value, 50 * GOR);
+        let (tokens, _) = token_redemption_entitlement(&settlement, &pool, 50_000_000).unwrap();
+        assert_eq!(tokens, 10_000_000);
+
+        // Interest settled after the mark is not credited
+        assert_eq!(credit_settled_debt(&mut settlement, 5_000_000), 0);
+        assert_eq!(settlement.settled_debt, settlement.marked_debt);
+    }
+
+    #[test]
+    fn unsettled_vaults_hold_back_their_share() {
+        // Only 60 of the 100 GUSD outstanding has settled so far
+        let mut settlement = settlement(120 * GOR);
+        settlement.settled_debt = 60_000_000;
+        settlement.debt = 60_000_000;
+        let (entitled, face_value) = redemption_entitlement(&settlement, 40_000_000, GOR_DECIMALS).unwrap();
+        assert_eq!(entitled, 24 * GOR);
+        assert_eq!(face_value, 40 * GOR);
+    }
+}
+
+#[cfg(test)]
+mod priority_tests {
+    use super::*;
+
+    const RIGHTS_UNTIL: i64 = 1_000;
+
+    /// Protocol with a 100-slot priority window and `holder` owning the rights
+    fn protocol(holder: Pubkey) -> ProtocolState {
+        let mut protocol: ProtocolState = zeroed();
+        protocol.priority_window_slots = 100;
+        protocol.priority_liquidator = holder;
+        protocol.priority_rights_until = RIGHTS_UNTIL;
+        protocol
+    }
+
+    fn vault(flagged_slot: u64) -> Vault {
+        let mut vault: Vault = zeroed();
+        vault.flagged_slot = flagged_slot;
+        vault
+    }
+
+    fn clock(slot: u64, unix_timestamp: i64) -> Clock {
+        Clock {
+            slot,
+            unix_timestamp,
+            ..Clock::default()
+        }
+    }
+
+    #[test]
+    fn only_the_holder_liquidates_inside_the_window() {
+        let holder = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let protocol = protocol(holder);
+        let vault = vault(50);
+
+        assert!(require_priority_rights(&protocol, &vault, holder, &clock(60, 0)).is_ok());
+        assert_eq!(
+            require_priority_rights(&protocol, &vault, other, &clock(60, 0)).unwrap_err(),
+            error!(GusdError::PriorityWindowActive)
+        );
+        // Open to everyone once the window after flagging ends
+        assert!(require_priority_rights(&protocol, &vault, other, &clock(150, 0)).is_ok());
+    }
+
+    #[test]
+    fn window_needs_a_flag_and_live_rights() {
+        let holder = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        // Unflagged vault
+        assert!(require_priority_rights(&protocol(holder), &vault(0), other, &clock(60, 0)).is_ok());
+        // Expired rights
+        let expired = clock(60, RIGHTS_UNTIL);
+        assert!(require_priority_rights(&protocol(holder), &vault(50), other, &expired).is_ok());
+        // Nobody won the round
+        assert!(require_priority_rights(&protocol(Pubkey::default()), &vault(50), other, &clock(60, 0)).is_ok());
+        // Window disabled
+        let mut disabled = protocol(holder);
+        disabled.priority_window_slots = 0;
+        assert!(require_priority_rights(&disabled, &vault(50), other, &clock(60, 0)).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod bootstrap_tests {
+    use super::*;
+
+    const STARTS_AT: i64 = 1_000;
+    const ENDS_AT: i64 = 2_000;
+
+    /// 100 GUSD auction falling from 3 to 1 GOR per GUSD
+    fn auction(sold: u64) -> BootstrapAuction {
+        BootstrapAuction {
+            tranche: 100_000_000,
+            sold,
+            start_price: 3_000_000_000,
+            end_price: 1_000_000_000,
+            starts_at: STARTS_AT,
+            ends_at: ENDS_AT,
+            proceeds: 0,
+            finalized: false,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn price_falls_linearly_and_clamps_to_the_ends() {
+        let auction = auction(0);
+        assert_eq!(bootstrap_price(&auction, STARTS_AT - 10).unwrap(), 3_000_000_000);
+        assert_eq!(bootstrap_price(&auction, STARTS_AT).unwrap(), 3_000_000_000);
+        assert_eq!(bootstrap_price(&auction, 1_250).unwrap(), 2_500_000_000);
+        assert_eq!(bootstrap_price(&auction, 1_500).unwrap(), 2_000_000_000);
+        assert_eq!(bootstrap_price(&auction, ENDS_AT).unwrap(), 1_000_000_000);
+        assert_eq!(bootstrap_price(&auction, ENDS_AT + 10).unwrap(), 1_000_000_000);
+
+        let mut last = u64::MAX;
+        for now in (STARTS_AT..=ENDS_AT).step_by(37) {
+            let price = bootstrap_price(&auction, now).unwrap();
+            assert!(price <= last);
+            last = price;
+        }
+    }
+
+    #[test]
+    fn purchases_round_the_cost_up() {
+        // 0.5 GUSD + 1 unit at 2 GOR per GUSD
+        let price = bootstrap_price(&auction(0), 1_500).unwrap();
+        let cost = GusdUnits(500_001).cost_in_lamports_ceil(price).unwrap();
+        assert_eq!(cost, GorLamports(1_000_002_000));
+    }
+
+    #[test]
+    fn finalizes_once_sold_out_or_ended() {
+        assert!(!bootstrap_finalizable(&auction(40_000_000), 1_500));
+        assert!(bootstrap_finalizable(&auction(40_000_000), ENDS_AT));
+        // Selling out closes the auction early
+        assert!(bootstrap_finalizable(&auction(100_000_000), 1_500));
+    }
+
+    #[test]
+    fn issuance_counts_unminted_revenue_against_max_supply() {
+        let mut protocol: ProtocolState = zeroed();
+        assert!(require_within_max_supply(&protocol, u64::MAX - 1, 1).is_ok());
+
+        protocol.max_supply = 1_000;
+        protocol.accrued_fees = 100;
+        protocol.savings_accrued = 50;
+        protocol.insurance_accrued = 50;
+        assert!(require_within_max_supply(&protocol, 700, 100).is_ok());
+        assert!(require_within_max_supply(&protocol, 700, 101).is_err());
+        assert!(require_within_max_supply(&protocol, u64::MAX, 1).is_err());
+    }
+}
+
+#[cfg(test)]
+mod fee_revenue_tests {
+    use super::*;
+
+    /// 20% savings and 10% insurance shares, no surplus buffer cap
+    fn protocol() -> ProtocolState {
+        let mut protocol: ProtocolState = zeroed();
+        protocol.savings_fee_share_bps = 2_000;
+        protocol.insurance_fee_share_bps = 1_000;
+        protocol
+    }
+
+    #[test]
+    fn reversal_undoes_the_booked_split() {
+        let mut protocol = protocol();
+        protocol.surplus_buffer_cap = 500;
+        book_fee_revenue(&mut protocol, 1_000).unwrap();
+        assert_eq!(
+            (protocol.savings_accrued, protocol.insurance_accrued),
+            (200, 100)
+        );
+        assert_eq!((protocol.surplus_buffer, protocol.accrued_fees), (500, 200));
+
+        let reversed = reverse_fee_revenue(&mut protocol, 1_000).unwrap();
+        assert_eq!(reversed.total(), 1_000);
+        assert_eq!((reversed.savings, reversed.insurance), (200, 100));
+        assert_eq!(
+            (
+                protocol.savings_accrued,
+                protocol.insurance_accrued,
+                protocol.surplus_buffer,
+                protocol.accrued_fees
+            ),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn minted_shares_come_out_of_protocol_revenue() {
+        let mut protocol = protocol();
+        book_fee_revenue(&mut protocol, 1_000).unwrap();
+        // The savers' share was already dripped into the savings pool
+        protocol.savings_accrued = 0;
+
+        let reversed = reverse_fee_revenue(&mut protocol, 1_000).unwrap();
+        assert_eq!(reversed.savings, 0);
+        assert_eq!(reversed.insurance, 100);
+        assert_eq!(reversed.accrued_fees, 700);
+        assert_eq!(reversed.total(), 800);
+        assert_eq!(protocol.accrued_fees, 0);
+
+        // Other fees' unminted revenue covers the savers' share
+        let mut protocol = self::protocol();
+        book_fee_revenue(&mut protocol, 2_000).unwrap();
+        protocol.savings_accrued = 0;
+        let reversed = reverse_fee_revenue(&mut protocol, 1_000).unwrap();
+        assert_eq!(reversed.total(), 1_000);
+        assert_eq!(reversed.accrued_fees, 900);
+    }
+
+    #[test]
+    fn nothing_left_to_reverse() {
+        let mut protocol = protocol();
+        assert_eq!(reverse_fee_revenue(&mut protocol, 1_000).unwrap().total(), 0);
+    }
+}
+
+#[cfg(test)]
+mod collateral_balance_tests {
+    use super::*;
+
+    /// 150% minimum ratio, 125% liquidation threshold
+    fn config(debt_ceiling: u64, total_debt: u64) -> CollateralConfig {
+        let mut config: CollateralConfig = zeroed();
+        config.min_collateral_ratio_bps = 15_000;
+        config.liquidation_threshold_bps = 12_500;
+        config.debt_ceiling = debt_ceiling;
+        config.total_debt = total_debt;
+        config
+    }
+
+    #[test]
+    fn balances_back_no_more_than_their_type_ceiling_allows() {
+        // $1,500 backs $1,000 at the minimum ratio with no ceiling
+        assert_eq!(balance_debt_capacity(&config(0, 5_000), 1_500, false).unwrap(), 1_000);
+        // Only $400 of room is left under the type's ceiling
+        assert_eq!(balance_debt_capacity(&config(5_000, 4_600), 1_500, false).unwrap(), 400);
+        assert_eq!(balance_debt_capacity(&config(5_000, 6_000), 1_500, false).unwrap(), 0);
+    }
+
+    #[test]
+    fn liquidations_ignore_the_ceiling() {
+        // A full ceiling can't make an otherwise healthy vault liquidatable
+        assert_eq!(balance_debt_capacity(&config(5_000, 6_000), 1_500, true).unwrap(), 1_200);
+    }
 }
@@ -0,0 +1,954 @@
+//! Price sources and valuation helpers
+//!
+//! Each oracle backend is an `OracleAdapter`; `PriceSource` dispatches to the one a feed is
+//! configured for. Instructions only call `get_price`, `get_spot_price`, or
+//! `get_price_with_fallback`, which apply age, confidence, EMA, and spread rules the same
+//! way whatever the source.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    DeploymentConfig, GusdError, OracleFallbackUsed, OracleKind, OracleSource, PriceAccumulator,
+    PriceFeed, PriceMode, PriceObservation, ProtocolState, BPS_DENOMINATOR, EMA_PERIOD_SECS, EXCHANGE_RATE_ONE,
+    GUSD_DECIMALS, MAX_EXCHANGE_RATE_AGE_EPOCHS, NATIVE_COLLATERAL_MINT, PRICE_OBSERVATION_COUNT, PYTH_PRICE_UPDATE_DISCRIMINATOR, SLOT_DURATION_MS,
+    SWITCHBOARD_PRECISION, SWITCHBOARD_PULL_FEED_DISCRIMINATOR,
+};
+
+/// Pyth `PriceUpdateV2` verification level
+#[derive(AnchorDeserialize)]
+enum PythVerificationLevel {
+    Partial { _num_signatures: u8 },
+    Full,
+}
+
+/// Pyth `PriceFeedMessage`
+#[derive(AnchorDeserialize)]
+struct PythPriceFeedMessage {
+    feed_id: [u8; 32],
+    price: i64,
+    conf: u64,
+    exponent: i32,
+    publish_time: i64,
+    _prev_publish_time: i64,
+    _ema_price: i64,
+    _ema_conf: u64,
+}
+
+/// Pyth pull-oracle `PriceUpdateV2` account (layout of pyth-solana-receiver-sdk)
+#[derive(AnchorDeserialize)]
+struct PythPriceUpdateV2 {
+    _write_authority: Pubkey,
+    verification_level: PythVerificationLevel,
+    price_message: PythPriceFeedMessage,
+    _posted_slot: u64,
+}
+
+/// Oracle price (USD, 6 decimals) with its confidence interval and age
+pub(crate) struct OracleReading {
+    pub(crate) price: u64,
+    /// Confidence interval relative to price, rounded up (0 when the backend has none)
+    pub(crate) conf_bps: u64,
+    pub(crate) age_secs: u64,
+}
+
+/// Which edge of the oracle confidence interval to value at
+#[derive(Clone, Copy)]
+pub(crate) enum PriceBound {
+    /// Reported price (views, TWAP samples)
+    Mid,
+    /// `price - conf`: collateral value for minting and withdrawals
+    Lower,
+    /// `price + conf`: liquidation eligibility
+    Upper,
+}
+
+/// Operation a price is read for; selects the freshness requirement and price spread
+/// Repayments and deposits never read the price.
+#[derive(Clone, Copy)]
+pub(crate) enum PriceOperation {
+    Mint,
+    Withdraw,
+    Liquidation,
+    /// Views, cranks, and admin flows: `max_price_age_secs`
+    Other,
+}
+
+/// Maximum oracle price age for `operation` (seconds)
+/// A per-operation limit of 0 falls back to `max_price_age_secs`.
+fn max_price_age_for(protocol: &ProtocolState, operation: PriceOperation) -> u64 {
+    let limit = match operation {
+        PriceOperation::Mint => protocol.mint_max_price_age_secs,
+        PriceOperation::Withdraw => protocol.withdraw_max_price_age_secs,
+        PriceOperation::Liquidation => protocol.liquidation_max_price_age_secs,
+        PriceOperation::Other => 0,
+    };
+    if limit > 0 { limit } else { protocol.max_price_age_secs }
+}
+
+/// Reject readings with too wide a confidence interval and move the price to `bound`
+pub(crate) fn apply_confidence(protocol: &ProtocolState, reading: &OracleReading, bound: PriceBound) -> Result<u64> {
+    require!(
+        reading.conf_bps <= protocol.max_confidence_bps,
+        GusdError::PriceConfidenceTooWide
+    );
+
+    let factor_bps = match bound {
+        PriceBound::Mid => return Ok(reading.price),
+        PriceBound::Lower => BPS_DENOMINATOR.saturating_sub(reading.conf_bps),
+        PriceBound::Upper => BPS_DENOMINATOR.saturating_add(reading.conf_bps),
+    };
+    let adjusted = (reading.price as u128)
+        .checked_mul(factor_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(adjusted > 0, GusdError::InvalidPrice);
+    require!(adjusted <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(adjusted as u64)
+}
+
+/// Price (USD, 6 decimals) used to value collateral
+/// All valuation paths go through here: the spot price combined with the EMA per
+/// `price_mode`, converted into the peg currency, then shaded by the price spread for
+/// `operation`. LST feeds scale the
+/// result by their exchange rate.
+pub(crate) fn get_price(
+    protocol: &ProtocolState,
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+    bound: PriceBound,
+    operation: PriceOperation,
+) -> Result<u64> {
+    let spot = get_spot_price(protocol, feed, deployment, price_update, bound, operation)?;
+    let price = apply_fx_rate(protocol, apply_price_mode(protocol, feed, spot))?;
+    let price = apply_price_spread(protocol, price, operation)?;
+    apply_exchange_rate(feed, price)
+}
+
+/// Spot price (USD, 6 decimals)
+/// Uses the feed's ramped admin price while its fallback flag is set, otherwise the first oracle
+/// source in failover order whose price is within the age limit for `operation`. Oracle
+/// prices are moved to `bound` of their confidence interval.
+/// Oracle program ids come from the `DeploymentConfig` PDA so one binary serves every
+/// cluster.
+pub(crate) fn get_spot_price(
+    protocol: &ProtocolState,
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+    bound: PriceBound,
+    operation: PriceOperation,
+) -> Result<u64> {
+    if feed.use_admin_price {
+        return Ok(current_admin_price(feed, Clock::get()?.unix_timestamp));
+    }
+
+    let (price, source_index) = read_failover_price(protocol, feed, deployment, price_update, bound, operation)?
+        .ok_or(GusdError::StaleOraclePrice)?;
+    note_price_source(feed, source_index, price);
+    Ok(price)
+}
+
+/// Like `get_price`, but when every oracle source is stale the price falls back to
+/// `last_good_price` minus `stale_price_haircut_bps` if a haircut is configured. Used by
+/// mints, withdrawals, and views; liquidations always require a fresh price.
+pub(crate) fn get_price_with_fallback(
+    protocol: &ProtocolState,
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+    bound: PriceBound,
+    operation: PriceOperation,
+) -> Result<u64> {
+    let spot = if feed.use_admin_price {
+        current_admin_price(feed, Clock::get()?.unix_timestamp)
+    } else {
+        let fresh = read_failover_price(protocol, feed, deployment, price_update, bound, operation)?;
+        if let Some((price, source_index)) = fresh {
+            note_price_source(feed, source_index, price);
+            price
+        } else {
+            require!(
+                protocol.stale_price_haircut_bps > 0 && feed.last_good_price > 0,
+                GusdError::StaleOraclePrice
+            );
+            let haircut_price = (feed.last_good_price as u128)
+                .checked_mul(BPS_DENOMINATOR.saturating_sub(protocol.stale_price_haircut_bps) as u128)
+                .ok_or(GusdError::MathOverflow)?
+                .checked_div(BPS_DENOMINATOR as u128)
+                .ok_or(GusdError::MathOverflow)?;
+            require!(haircut_price > 0, GusdError::InvalidPrice);
+            haircut_price as u64
+        }
+    };
+    let price = apply_fx_rate(protocol, apply_price_mode(protocol, feed, spot))?;
+    let price = apply_price_spread(protocol, price, operation)?;
+    apply_exchange_rate(feed, price)
+}
+
+/// Byte offsets into an SPL stake pool `StakePool` account
+const STAKE_POOL_ACCOUNT_TYPE: u8 = 1;
+const STAKE_POOL_MINT_OFFSET: usize = 162;
+const STAKE_POOL_TOTAL_LAMPORTS_OFFSET: usize = 258;
+const STAKE_POOL_TOKEN_SUPPLY_OFFSET: usize = 266;
+const STAKE_POOL_LAST_UPDATE_EPOCH_OFFSET: usize = 274;
+
+/// Exchange rate of a stake pool's token: GOR lamports per `EXCHANGE_RATE_ONE` base units
+/// The pool must be updated for `epoch`; until then its totals miss the epoch's rewards.
+pub(crate) fn read_stake_pool_rate(stake_pool: &AccountInfo, pool_mint: &Pubkey, epoch: u64) -> Result<u64> {
+    let data = stake_pool.try_borrow_data()?;
+    require!(
+        data.len() >= STAKE_POOL_LAST_UPDATE_EPOCH_OFFSET + 8
+            && data[0] == STAKE_POOL_ACCOUNT_TYPE
+            && data[STAKE_POOL_MINT_OFFSET..STAKE_POOL_MINT_OFFSET + 32] == pool_mint.to_bytes(),
+        GusdError::InvalidStakePool
+    );
+    let read_u64 = |offset: usize| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&data[offset..offset + 8]);
+        u64::from_le_bytes(bytes)
+    };
+    require!(
+        read_u64(STAKE_POOL_LAST_UPDATE_EPOCH_OFFSET) == epoch,
+        GusdError::StakePoolNotUpdated
+    );
+
+    let total_lamports = read_u64(STAKE_POOL_TOTAL_LAMPORTS_OFFSET);
+    let pool_token_supply = read_u64(STAKE_POOL_TOKEN_SUPPLY_OFFSET);
+    if pool_token_supply == 0 {
+        return Ok(EXCHANGE_RATE_ONE);
+    }
+    let rate = (total_lamports as u128)
+        .checked_mul(EXCHANGE_RATE_ONE as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / pool_token_supply as u128;
+    require!(rate > 0, GusdError::InvalidStakePool);
+    require!(rate <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(rate as u64)
+}
+
+/// Scale a GOR price by an LST feed's exchange rate; other feeds pass through
+fn apply_exchange_rate(feed: &PriceFeed, price: u64) -> Result<u64> {
+    if feed.stake_pool == Pubkey::default() {
+        return Ok(price);
+    }
+    require!(
+        Clock::get()?.epoch <= feed.exchange_rate_epoch.saturating_add(MAX_EXCHANGE_RATE_AGE_EPOCHS),
+        GusdError::StaleExchangeRate
+    );
+    let scaled = (price as u128)
+        .checked_mul(feed.exchange_rate as u128)
+        .ok_or(GusdError::MathOverflow)?
+        / EXCHANGE_RATE_ONE as u128;
+    require!(scaled > 0, GusdError::InvalidPrice);
+    require!(scaled <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(scaled as u64)
+}
+
+/// Admin price in effect at `now`: the ramp start price moved toward the target
+/// `admin_price` by `admin_ramp_rate` per second, never past the target
+pub(crate) fn current_admin_price(feed: &PriceFeed, now: i64) -> u64 {
+    let elapsed = now.saturating_sub(feed.admin_price_ts).max(0) as u64;
+    let step = feed.admin_ramp_rate.saturating_mul(elapsed);
+    if feed.admin_price >= feed.admin_ramp_price {
+        feed.admin_ramp_price.saturating_add(step).min(feed.admin_price)
+    } else {
+        feed.admin_ramp_price.saturating_sub(step).max(feed.admin_price)
+    }
+}
+
+/// Fresh price from the first oracle source, in failover order, that passes the age and
+/// confidence limits, with its index (0 = primary, 1.. = `fallback_oracles`)
+/// The primary is read live or from its cache; a fallback only when `price_update` is its
+/// account. When no source is fresh, returns the primary's error, or `None` if the primary
+/// was merely stale.
+fn read_failover_price(
+    protocol: &ProtocolState,
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+    bound: PriceBound,
+    operation: PriceOperation,
+) -> Result<Option<(u64, u8)>> {
+    let clock = Clock::get()?;
+    let max_age = max_price_age_for(protocol, operation);
+
+    // An account meant for a fallback leaves the primary on its cached price
+    let primary_update = price_update.filter(|account| {
+        !feed.fallback_oracles.iter().any(|source| source.account == account.key())
+    });
+    let primary = PriceSource::for_feed(feed, primary_update)
+        .fresh_price(protocol, feed, deployment, &clock, max_age, bound);
+    if let Ok(Some(price)) = primary {
+        return Ok(Some((price, 0)));
+    }
+
+    for (index, source) in feed.fallback_oracles.iter().enumerate() {
+        let Some(adapter) = PriceSource::for_fallback(*source, price_update) else {
+            continue;
+        };
+        if let Ok(Some(price)) = adapter.fresh_price(protocol, feed, deployment, &clock, max_age, bound) {
+            return Ok(Some((price, index as u8 + 1)));
+        }
+    }
+    primary.map(|_| None)
+}
+
+/// Log and emit the source a price came from when it was not the primary
+fn note_price_source(feed: &PriceFeed, source_index: u8, price: u64) {
+    if source_index == 0 {
+        return;
+    }
+    msg!(
+        "Primary oracle for {} unavailable; priced at {} from fallback {}",
+        feed.collateral_mint,
+        price,
+        source_index
+    );
+    emit!(OracleFallbackUsed {
+        collateral_mint: feed.collateral_mint,
+        source_index,
+        price,
+    });
+}
+
+/// Convert a USD price into the peg currency: collateral/USD ÷ peg currency/USD
+/// A no-op unless `fx_price_feed` is set. The cached rate must be within
+/// `max_price_age_secs`; crank `update_fx_rate` to refresh it.
+pub(crate) fn apply_fx_rate(protocol: &ProtocolState, price: u64) -> Result<u64> {
+    if protocol.fx_price_feed == Pubkey::default() {
+        return Ok(price);
+    }
+    let age = Clock::get()?.unix_timestamp.saturating_sub(protocol.fx_rate_updated_at);
+    require!(
+        protocol.fx_rate > 0 && age <= protocol.max_price_age_secs as i64,
+        GusdError::StaleOraclePrice
+    );
+    let converted = (price as u128)
+        .checked_mul(10u128.pow(GUSD_DECIMALS as u32))
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(protocol.fx_rate as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(converted > 0, GusdError::InvalidPrice);
+    require!(converted <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(converted as u64)
+}
+
+/// Shade a price by `price_spread_bps` for `operation`
+/// Mints and withdrawals value collateral a little lower, and liquidation eligibility a
+/// little higher, so vaults near either limit resolve in the protocol's favour.
+pub(crate) fn apply_price_spread(protocol: &ProtocolState, price: u64, operation: PriceOperation) -> Result<u64> {
+    let factor_bps = match operation {
+        PriceOperation::Mint | PriceOperation::Withdraw => {
+            BPS_DENOMINATOR.saturating_sub(protocol.price_spread_bps)
+        }
+        PriceOperation::Liquidation => BPS_DENOMINATOR.saturating_add(protocol.price_spread_bps),
+        PriceOperation::Other => return Ok(price),
+    };
+    let adjusted = (price as u128)
+        .checked_mul(factor_bps as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(adjusted > 0, GusdError::InvalidPrice);
+    require!(adjusted <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(adjusted as u64)
+}
+
+/// Combine a spot price with the protocol EMA according to `price_mode`
+/// The EMA tracks GOR, so only the native feed and LST feeds (priced off GOR's oracle)
+/// use it; every other feed is valued at spot.
+fn apply_price_mode(protocol: &ProtocolState, feed: &PriceFeed, spot: u64) -> u64 {
+    let tracks_gor = feed.collateral_mint == NATIVE_COLLATERAL_MINT || feed.stake_pool != Pubkey::default();
+    if !tracks_gor {
+        return spot;
+    }
+    match protocol.price_mode {
+        PriceMode::Spot => spot,
+        PriceMode::Ema => protocol.ema_price,
+        PriceMode::MinSpotEma => spot.min(protocol.ema_price),
+    }
+}
+
+/// Move the EMA toward `price`, weighted by the time since its last update
+/// The weight is `elapsed / EMA_PERIOD_SECS`, capped at 1 (full reset after a long gap).
+pub(crate) fn update_ema_price(protocol: &mut ProtocolState, price: u64, now: i64) -> Result<()> {
+    let elapsed = now.saturating_sub(protocol.ema_updated_at).clamp(0, EMA_PERIOD_SECS) as i128;
+    let ema = protocol.ema_price as i128;
+    let delta = (price as i128 - ema)
+        .checked_mul(elapsed)
+        .ok_or(GusdError::MathOverflow)?
+        / EMA_PERIOD_SECS as i128;
+
+    protocol.ema_price = (ema + delta) as u64;
+    protocol.ema_updated_at = now;
+    Ok(())
+}
+
+/// A price source that produces an `OracleReading` for a feed
+/// Readings are not yet checked against age or confidence limits; `get_price` and the
+/// cranks apply those the same way for every source.
+trait OracleAdapter {
+    fn read(
+        &self,
+        feed: &PriceFeed,
+        deployment: &DeploymentConfig,
+        clock: &Clock,
+    ) -> Result<OracleReading>;
+}
+
+/// Pyth pull-oracle `PriceUpdateV2` account
+struct PythAdapter<'a, 'info> {
+    source: OracleSource,
+    price_update: &'a AccountInfo<'info>,
+}
+
+/// Switchboard on-demand `PullFeedAccountData` account
+struct SwitchboardAdapter<'a, 'info> {
+    source: OracleSource,
+    price_update: &'a AccountInfo<'info>,
+}
+
+/// Price cached on the feed by `crank_price` or `update_price_chainlink`
+struct CachedAdapter;
+
+impl OracleAdapter for PythAdapter<'_, '_> {
+    fn read(
+        &self,
+        _feed: &PriceFeed,
+        deployment: &DeploymentConfig,
+        clock: &Clock,
+    ) -> Result<OracleReading> {
+        read_pyth_price(
+            &self.source,
+            &deployment.pyth_receiver_program,
+            self.price_update,
+            clock.unix_timestamp,
+        )
+    }
+}
+
+impl OracleAdapter for SwitchboardAdapter<'_, '_> {
+    fn read(
+        &self,
+        _feed: &PriceFeed,
+        deployment: &DeploymentConfig,
+        clock: &Clock,
+    ) -> Result<OracleReading> {
+        read_switchboard_price(
+            &self.source,
+            &deployment.switchboard_program,
+            self.price_update,
+            clock.slot,
+        )
+    }
+}
+
+impl OracleAdapter for CachedAdapter {
+    fn read(
+        &self,
+        feed: &PriceFeed,
+        _deployment: &DeploymentConfig,
+        clock: &Clock,
+    ) -> Result<OracleReading> {
+        require!(feed.cached_observed_at > 0, GusdError::OracleNotConfigured);
+        Ok(OracleReading {
+            price: feed.cached_price,
+            conf_bps: feed.cached_conf_bps,
+            age_secs: clock.unix_timestamp.saturating_sub(feed.cached_observed_at).max(0) as u64,
+        })
+    }
+}
+
+/// Enum dispatch over the adapters
+/// A new price source is a new `OracleKind`, an adapter, and a variant here; instructions
+/// only ever see `get_price` and friends.
+enum PriceSource<'a, 'info> {
+    Pyth(PythAdapter<'a, 'info>),
+    Switchboard(SwitchboardAdapter<'a, 'info>),
+    Cached(CachedAdapter),
+}
+
+impl<'a, 'info> PriceSource<'a, 'info> {
+    /// Adapter for a feed's configured backend and the account passed for it
+    fn for_feed(feed: &PriceFeed, price_update: Option<&'a AccountInfo<'info>>) -> Self {
+        let source = OracleSource {
+            kind: feed.oracle_kind,
+            account: feed.oracle_account,
+            feed_id: feed.oracle_feed_id,
+        };
+        match (feed.oracle_kind, price_update) {
+            (OracleKind::Pyth, Some(price_update)) => Self::Pyth(PythAdapter { source, price_update }),
+            (OracleKind::Switchboard, Some(price_update)) => {
+                Self::Switchboard(SwitchboardAdapter { source, price_update })
+            }
+            // Without a price account, use the price cached by `crank_price`; Chainlink
+            // reports are always verified and cached by `update_price_chainlink`
+            _ => Self::Cached(CachedAdapter),
+        }
+    }
+
+    /// Adapter for a fallback source, or `None` unless `price_update` is its account
+    /// Fallbacks have no cache, so they can only be read live.
+    fn for_fallback(source: OracleSource, price_update: Option<&'a AccountInfo<'info>>) -> Option<Self> {
+        let price_update = price_update.filter(|account| account.key() == source.account)?;
+        match source.kind {
+            OracleKind::Pyth => Some(Self::Pyth(PythAdapter { source, price_update })),
+            OracleKind::Switchboard => Some(Self::Switchboard(SwitchboardAdapter { source, price_update })),
+            OracleKind::ChainlinkStreams => None,
+        }
+    }
+
+    /// Read this source and move it to `bound`, or `None` when older than `max_age`
+    fn fresh_price(
+        &self,
+        protocol: &ProtocolState,
+        feed: &PriceFeed,
+        deployment: &DeploymentConfig,
+        clock: &Clock,
+        max_age: u64,
+        bound: PriceBound,
+    ) -> Result<Option<u64>> {
+        let reading = self.read(feed, deployment, clock)?;
+        if reading.age_secs > max_age {
+            return Ok(None);
+        }
+        apply_confidence(protocol, &reading, bound).map(Some)
+    }
+}
+
+impl OracleAdapter for PriceSource<'_, '_> {
+    fn read(
+        &self,
+        feed: &PriceFeed,
+        deployment: &DeploymentConfig,
+        clock: &Clock,
+    ) -> Result<OracleReading> {
+        match self {
+            Self::Pyth(adapter) => adapter.read(feed, deployment, clock),
+            Self::Switchboard(adapter) => adapter.read(feed, deployment, clock),
+            Self::Cached(adapter) => adapter.read(feed, deployment, clock),
+        }
+    }
+}
+
+/// Read the configured oracle backend without applying the staleness limit
+pub(crate) fn read_oracle_price(
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+) -> Result<OracleReading> {
+    PriceSource::for_feed(feed, price_update).read(feed, deployment, &Clock::get()?)
+}
+
+/// Publish time of the feed's primary oracle reading (unix seconds), or `None` while the
+/// admin price drives valuation or the primary can't be read
+pub(crate) fn oracle_observed_at(
+    feed: &PriceFeed,
+    deployment: &DeploymentConfig,
+    price_update: Option<&AccountInfo>,
+) -> Option<i64> {
+    if feed.use_admin_price {
+        return None;
+    }
+    let primary_update = price_update.filter(|account| {
+        !feed.fallback_oracles.iter().any(|source| source.account == account.key())
+    });
+    let clock = Clock::get().ok()?;
+    let reading = PriceSource::for_feed(feed, primary_update)
+        .read(feed, deployment, &clock)
+        .ok()?;
+    Some(clock.unix_timestamp.saturating_sub(reading.age_secs as i64))
+}
+
+/// Minimum seconds between observation slots for a TWAP window of `twap_window_secs`
+/// Spacing slots `window / (COUNT - 1)` apart keeps an observation at least a window old in
+/// the ring, however often prices are written.
+pub(crate) fn observation_spacing_secs(twap_window_secs: u64) -> i64 {
+    twap_window_secs.div_ceil(PRICE_OBSERVATION_COUNT as u64 - 1).min(i64::MAX as u64) as i64
+}
+
+/// Whether the newest observation is at least `min_spacing_secs` old at `now`
+pub(crate) fn observation_due(accumulator: &PriceAccumulator, min_spacing_secs: i64, now: i64) -> bool {
+    let newest = &accumulator.observations[accumulator.head as usize];
+    newest.timestamp == 0 || now.saturating_sub(newest.timestamp) >= min_spacing_secs
+}
+
+/// Accumulate `last_price × elapsed` and, once `min_spacing_secs` have passed since the
+/// newest observation, push a new one for `price` at `now`
+/// Returns whether a slot was written; the running sum is always brought up to date.
+pub(crate) fn record_price_observation(
+    accumulator: &mut PriceAccumulator,
+    price: u64,
+    now: i64,
+    min_spacing_secs: i64,
+) -> Result<bool> {
+    let elapsed = now.saturating_sub(accumulator.last_update_ts).max(0) as u128;
+    accumulator.cumulative_price = accumulator.cumulative_price
+        .checked_add(
+            (accumulator.last_price as u128)
+                .checked_mul(elapsed)
+                .ok_or(GusdError::MathOverflow)?,
+        )
+        .ok_or(GusdError::MathOverflow)?;
+    accumulator.last_price = price;
+    accumulator.last_update_ts = now;
+
+    if !observation_due(accumulator, min_spacing_secs, now) {
+        return Ok(false);
+    }
+    let head = (accumulator.head as usize + 1) % PRICE_OBSERVATION_COUNT;
+    accumulator.observations[head] = PriceObservation {
+        timestamp: now,
+        cumulative_price: accumulator.cumulative_price,
+        price,
+    };
+    accumulator.head = head as u8;
+    Ok(true)
+}
+
+/// Time-weighted average price (USD, 6 decimals) over the trailing `window_secs`
+/// Uses the newest observation at least `window_secs` old as the start point.
+pub(crate) fn compute_twap(accumulator: &PriceAccumulator, window_secs: u64, now: i64) -> Result<u64> {
+    let cumulative_now = accumulator.cumulative_price
+        .checked_add(
+            (accumulator.last_price as u128)
+                .checked_mul(now.saturating_sub(accumulator.last_update_ts).max(0) as u128)
+                .ok_or(GusdError::MathOverflow)?,
+        )
+        .ok_or(GusdError::MathOverflow)?;
+
+    let window_start = now.saturating_sub(window_secs as i64);
+    let start = accumulator.observations
+        .iter()
+        .filter(|obs| obs.timestamp != 0 && obs.timestamp <= window_start)
+        .max_by_key(|obs| obs.timestamp)
+        .ok_or(GusdError::InsufficientPriceHistory)?;
+
+    let elapsed = now.saturating_sub(start.timestamp) as u128;
+    require!(elapsed > 0, GusdError::InsufficientPriceHistory);
+
+    let twap = cumulative_now
+        .checked_sub(start.cumulative_price)
+        .ok_or(GusdError::MathOverflow)?
+        .checked_div(elapsed)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(twap <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(twap as u64)
+}
+
+/// Read, validate, and normalize a Pyth pull-oracle price to 6 decimals
+fn read_pyth_price(
+    source: &OracleSource,
+    receiver_program: &Pubkey,
+    price_update: &AccountInfo,
+    now: i64,
+) -> Result<OracleReading> {
+    require_keys_eq!(
+        price_update.key(),
+        source.account,
+        GusdError::InvalidOracleAccount
+    );
+    require_keys_eq!(
+        *price_update.owner,
+        *receiver_program,
+        GusdError::InvalidOracleAccount
+    );
+
+    let data = price_update.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == PYTH_PRICE_UPDATE_DISCRIMINATOR,
+        GusdError::InvalidOracleAccount
+    );
+    let update = PythPriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| GusdError::InvalidOracleAccount)?;
+
+    require!(
+        matches!(update.verification_level, PythVerificationLevel::Full),
+        GusdError::InvalidOracleAccount
+    );
+
+    let message = update.price_message;
+    require!(message.feed_id == source.feed_id, GusdError::InvalidOracleAccount);
+    require!(message.price > 0, GusdError::InvalidPrice);
+
+    // Confidence shares the price exponent, so the ratio needs no normalization
+    let conf_bps = (message.conf as u128)
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .div_ceil(message.price as u128)
+        .min(u64::MAX as u128) as u64;
+
+    Ok(OracleReading {
+        price: normalize_price(message.price as u64, message.exponent)?,
+        conf_bps,
+        age_secs: now.saturating_sub(message.publish_time).max(0) as u64,
+    })
+}
+
+/// Byte offsets into a Switchboard `PullFeedAccountData` account (after the discriminator)
+const SWITCHBOARD_FEED_HASH_OFFSET: usize = 8 + 2112;
+const SWITCHBOARD_RESULT_VALUE_OFFSET: usize = 8 + 2256;
+const SWITCHBOARD_RESULT_STD_DEV_OFFSET: usize = 8 + 2272;
+const SWITCHBOARD_RESULT_RANGE_OFFSET: usize = 8 + 2304;
+const SWITCHBOARD_RESULT_SLOT_OFFSET: usize = 8 + 2360;
+
+/// Read, validate, and normalize a Switchboard on-demand feed result to 6 decimals
+fn read_switchboard_price(
+    source: &OracleSource,
+    switchboard_program: &Pubkey,
+    feed: &AccountInfo,
+    current_slot: u64,
+) -> Result<OracleReading> {
+    require_keys_eq!(feed.key(), source.account, GusdError::InvalidOracleAccount);
+    require_keys_eq!(
+        *feed.owner,
+        *switchboard_program,
+        GusdError::InvalidOracleAccount
+    );
+
+    let data = feed.try_borrow_data()?;
+    decode_switchboard_result(&data, &source.feed_id, current_slot)
+}
+
+/// Decode the current result of a Switchboard `PullFeedAccountData` account for `feed_id`
+/// The confidence is the wider of the samples' standard deviation and half their range,
+/// so a feed whose oracles disagree reads as uncertain even when most cluster together.
+fn decode_switchboard_result(
+    data: &[u8],
+    feed_id: &[u8; 32],
+    current_slot: u64,
+) -> Result<OracleReading> {
+    require!(
+        data.len() >= SWITCHBOARD_RESULT_SLOT_OFFSET + 8
+            && data[..8] == SWITCHBOARD_PULL_FEED_DISCRIMINATOR,
+        GusdError::InvalidOracleAccount
+    );
+    require!(
+        data[SWITCHBOARD_FEED_HASH_OFFSET..SWITCHBOARD_FEED_HASH_OFFSET + 32] == *feed_id,
+        GusdError::InvalidOracleAccount
+    );
+
+    let i128_at = |offset: usize| {
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&data[offset..offset + 16]);
+        i128::from_le_bytes(bytes)
+    };
+    let value = i128_at(SWITCHBOARD_RESULT_VALUE_OFFSET);
+    let std_dev = i128_at(SWITCHBOARD_RESULT_STD_DEV_OFFSET);
+    let range = i128_at(SWITCHBOARD_RESULT_RANGE_OFFSET);
+
+    let mut slot_bytes = [0u8; 8];
+    slot_bytes.copy_from_slice(&data[SWITCHBOARD_RESULT_SLOT_OFFSET..SWITCHBOARD_RESULT_SLOT_OFFSET + 8]);
+    let result_slot = u64::from_le_bytes(slot_bytes);
+
+    require!(value > 0, GusdError::InvalidPrice);
+    require!(std_dev >= 0 && range >= 0, GusdError::InvalidOracleAccount);
+
+    let normalized = (value as u128)
+        .checked_div(10u128.pow(SWITCHBOARD_PRECISION - GUSD_DECIMALS as u32))
+        .ok_or(GusdError::MathOverflow)?;
+    require!(normalized > 0, GusdError::InvalidPrice);
+    require!(normalized <= u64::MAX as u128, GusdError::MathOverflow);
+
+    // Spread and value share Switchboard's precision, so the ratio needs no normalization
+    let conf = (std_dev as u128).max(range as u128 / 2);
+    let conf_bps = conf
+        .checked_mul(BPS_DENOMINATOR as u128)
+        .ok_or(GusdError::MathOverflow)?
+        .div_ceil(value as u128)
+        .min(u64::MAX as u128) as u64;
+
+    let age_slots = current_slot.saturating_sub(result_slot);
+    Ok(OracleReading {
+        price: normalized as u64,
+        conf_bps,
+        age_secs: age_slots.saturating_mul(SLOT_DURATION_MS) / 1000,
+    })
+}
+
+/// Fields of a verified Chainlink Data Streams v3 report
+pub(crate) struct StreamsReport {
+    pub(crate) feed_id: [u8; 32],
+    pub(crate) observations_timestamp: i64,
+    pub(crate) native_fee: u128,
+    pub(crate) link_fee: u128,
+    pub(crate) expires_at: i64,
+    pub(crate) price: u128,
+    pub(crate) bid: u128,
+    pub(crate) ask: u128,
+}
+
+/// Decode an ABI-encoded v3 report (nine 32-byte big-endian words)
+/// Prices are int192; negative values are rejected.
+pub(crate) fn decode_streams_report(data: &[u8]) -> Result<StreamsReport> {
+    require!(data.len() >= 9 * 32, GusdError::InvalidOracleAccount);
+    let word = |i: usize| &data[i * 32..(i + 1) * 32];
+    let uint = |i: usize| -> Result<u128> {
+        let w = word(i);
+        require!(w[..16].iter().all(|&b| b == 0), GusdError::InvalidPrice);
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&w[16..]);
+        Ok(u128::from_be_bytes(bytes))
+    };
+
+    let mut feed_id = [0u8; 32];
+    feed_id.copy_from_slice(word(0));
+    let price = uint(6)?;
+    require!(price > 0, GusdError::InvalidPrice);
+
+    Ok(StreamsReport {
+        feed_id,
+        observations_timestamp: uint(2)? as i64,
+        native_fee: uint(3)?,
+        link_fee: uint(4)?,
+        expires_at: uint(5)? as i64,
+        price,
+        bid: uint(7)?,
+        ask: uint(8)?,
+    })
+}
+
+/// Convert `price * 10^exponent` into USD with 6 decimals (GUSD precision)
+fn normalize_price(price: u64, exponent: i32) -> Result<u64> {
+    let shift = exponent
+        .checked_add(GUSD_DECIMALS as i32)
+        .ok_or(GusdError::MathOverflow)?;
+    require!(shift.unsigned_abs() <= 18, GusdError::InvalidPrice);
+
+    let scale = 10u128.pow(shift.unsigned_abs());
+    let normalized = if shift >= 0 {
+        (price as u128).checked_mul(scale).ok_or(GusdError::MathOverflow)?
+    } else {
+        (price as u128).checked_div(scale).ok_or(GusdError::MathOverflow)?
+    };
+
+    require!(normalized > 0, GusdError::InvalidPrice);
+    require!(normalized <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(normalized as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn protocol(mode: PriceMode) -> ProtocolState {
+        let mut protocol: ProtocolState = crate::zeroed();
+        protocol.price_mode = mode;
+        protocol.ema_price = 4_000;
+        protocol
+    }
+
+    fn feed(collateral_mint: Pubkey, stake_pool: Pubkey) -> PriceFeed {
+        let mut feed: PriceFeed = crate::zeroed();
+        feed.collateral_mint = collateral_mint;
+        feed.stake_pool = stake_pool;
+        feed
+    }
+
+    #[test]
+    fn price_mode_applies_the_gor_ema_only_to_gor_priced_feeds() {
+        let native = feed(NATIVE_COLLATERAL_MINT, Pubkey::default());
+        let lst = feed(Pubkey::new_unique(), Pubkey::new_unique());
+        let token = feed(Pubkey::new_unique(), Pubkey::default());
+
+        let ema = protocol(PriceMode::Ema);
+        assert_eq!(apply_price_mode(&ema, &native, 5_000), 4_000);
+        assert_eq!(apply_price_mode(&ema, &lst, 5_000), 4_000);
+        assert_eq!(apply_price_mode(&ema, &token, 990_000), 990_000);
+
+        let min = protocol(PriceMode::MinSpotEma);
+        assert_eq!(apply_price_mode(&min, &native, 3_000), 3_000);
+        assert_eq!(apply_price_mode(&min, &native, 5_000), 4_000);
+        assert_eq!(apply_price_mode(&min, &token, 990_000), 990_000);
+
+        assert_eq!(apply_price_mode(&protocol(PriceMode::Spot), &native, 5_000), 5_000);
+    }
+
+    /// A `PullFeedAccountData` account laid out field by field as in switchboard-on-demand:
+    /// 32 submissions of 64 bytes, authority, queue, feed hash at 2112, then the current
+    /// result at 2256 (value, std_dev, mean, range, ..., slot at +104)
+    fn switchboard_fixture(feed_id: [u8; 32], value: i128, std_dev: i128, range: i128) -> Vec<u8> {
+        let mut data = vec![0u8; 8 + 3200];
+        data[..8].copy_from_slice(&SWITCHBOARD_PULL_FEED_DISCRIMINATOR);
+        data[8 + 2112..8 + 2144].copy_from_slice(&feed_id);
+        data[8 + 2256..8 + 2272].copy_from_slice(&value.to_le_bytes());
+        data[8 + 2272..8 + 2288].copy_from_slice(&std_dev.to_le_bytes());
+        data[8 + 2288..8 + 2304].copy_from_slice(&value.to_le_bytes());
+        data[8 + 2304..8 + 2320].copy_from_slice(&range.to_le_bytes());
+        data[8 + 2360..8 + 2368].copy_from_slice(&1_000u64.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn switchboard_result_is_read_at_the_sdk_offsets() {
+        let feed_id = [7u8; 32];
+        // $0.004776 at 18 decimals, std-dev 0.5%, range 0.4%
+        let value = 4_776 * 10i128.pow(12);
+        let data = switchboard_fixture(feed_id, value, value / 200, value / 250);
+
+        let reading = decode_switchboard_result(&data, &feed_id, 1_025).unwrap();
+        assert_eq!(reading.price, 4_776);
+        assert_eq!(reading.conf_bps, 50);
+        assert_eq!(reading.age_secs, 10);
+
+        assert!(decode_switchboard_result(&data, &[8u8; 32], 1_025).is_err());
+        assert!(decode_switchboard_result(&data[..8 + 2360], &feed_id, 1_025).is_err());
+    }
+
+    #[test]
+    fn switchboard_confidence_widens_to_half_the_range() {
+        let feed_id = [7u8; 32];
+        let value = 4_776 * 10i128.pow(12);
+        // std-dev 0.1%, but one oracle 6% off the rest
+        let data = switchboard_fixture(feed_id, value, value / 1_000, value * 6 / 100);
+        let reading = decode_switchboard_result(&data, &feed_id, 1_000).unwrap();
+        assert_eq!(reading.conf_bps, 300);
+
+        let data = switchboard_fixture(feed_id, value, -1, 0);
+        assert!(decode_switchboard_result(&data, &feed_id, 1_000).is_err());
+    }
+
+    /// An ABI-encoded v3 report: nine big-endian 32-byte words
+    fn streams_report(price: i128) -> Vec<u8> {
+        let mut data = vec![0u8; 9 * 32];
+        let mut put = |i: usize, value: i128| {
+            // int192/uint192 words, sign-extended to 32 bytes
+            let fill = if value < 0 { 0xff } else { 0 };
+            data[i * 32..i * 32 + 16].fill(fill);
+            data[i * 32 + 16..(i + 1) * 32].copy_from_slice(&value.to_be_bytes());
+        };
+        put(2, 1_700_000_000);
+        put(3, 12);
+        put(4, 34);
+        put(5, 1_700_000_300);
+        put(6, price);
+        put(7, price - 1_000);
+        put(8, price + 1_000);
+        data[..32].copy_from_slice(&[9u8; 32]);
+        data
+    }
+
+    #[test]
+    fn streams_report_decodes_each_word() {
+        let report = decode_streams_report(&streams_report(4_776 * 10i128.pow(12))).unwrap();
+        assert_eq!(report.feed_id, [9u8; 32]);
+        assert_eq!(report.observations_timestamp, 1_700_000_000);
+        assert_eq!(report.native_fee, 12);
+        assert_eq!(report.link_fee, 34);
+        assert_eq!(report.expires_at, 1_700_000_300);
+        assert_eq!(report.price, 4_776 * 10u128.pow(12));
+        assert_eq!(report.bid, 4_776 * 10u128.pow(12) - 1_000);
+        assert_eq!(report.ask, 4_776 * 10u128.pow(12) + 1_000);
+
+        assert!(decode_streams_report(&streams_report(4_776)[..8 * 32]).is_err());
+    }
+
+    #[test]
+    fn streams_report_rejects_non_positive_prices() {
+        assert!(decode_streams_report(&streams_report(-4_776 * 10i128.pow(12))).is_err());
+        assert!(decode_streams_report(&streams_report(-1)).is_err());
+        assert!(decode_streams_report(&streams_report(0)).is_err());
+    }
+}
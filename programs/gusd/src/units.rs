@@ -0,0 +1,178 @@
+//! Typed amounts for price, collateral, and debt math
+//!
+//! Shared by the on-chain program and Rust clients (depend on `gusd` with the
+//! `no-entrypoint` feature) so both sides convert units the same way. TypeScript
+//! clients use the line-for-line mirror in `app/units.ts`; change both together.
+
+use anchor_lang::prelude::*;
+
+use crate::{GusdError, BPS_DENOMINATOR, GUSD_DECIMALS, HEALTH_FACTOR_ONE};
+
+/// Native collateral in base units (lamports for GOR, `native_decimals` decimals)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GorLamports(pub u64);
+
+/// GUSD in mint base units (6 decimals)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GusdUnits(pub u64);
+
+/// USD amount with 6 decimals; also used for prices (USD per whole native token)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UsdValue6(pub u64);
+
+/// Narrow a u128 result to u64, failing instead of truncating
+fn to_u64(value: u128) -> Result<u64> {
+    require!(value <= u64::MAX as u128, GusdError::MathOverflow);
+    Ok(value as u64)
+}
+
+impl GorLamports {
+    /// USD value at `price` (rounds down)
+    pub fn to_usd(self, price: UsdValue6, native_decimals: u8) -> Result<UsdValue6> {
+        let value = (self.0 as u128)
+            .checked_mul(price.0 as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(10u128.pow(native_decimals as u32))
+            .ok_or(GusdError::MathOverflow)?;
+        Ok(UsdValue6(to_u64(value)?))
+    }
+}
+
+impl GusdUnits {
+    /// USD value of GUSD debt (1 GUSD = $1, same decimals)
+    pub fn to_usd(self) -> UsdValue6 {
+        const _: () = assert!(GUSD_DECIMALS == 6);
+        UsdValue6(self.0)
+    }
+
+    /// Lamports paid for this much GUSD at `lamports_per_gusd` per whole GUSD (rounds up)
+    pub fn cost_in_lamports_ceil(self, lamports_per_gusd: u64) -> Result<GorLamports> {
+        let cost = (self.0 as u128)
+            .checked_mul(lamports_per_gusd as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(10u128.pow(GUSD_DECIMALS as u32));
+        Ok(GorLamports(to_u64(cost)?))
+    }
+}
+
+impl UsdValue6 {
+    /// Native amount worth this much at `price` (rounds down)
+    pub fn to_lamports(self, price: UsdValue6, native_decimals: u8) -> Result<GorLamports> {
+        let amount = (self.0 as u128)
+            .checked_mul(10u128.pow(native_decimals as u32))
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(price.0 as u128)
+            .ok_or(GusdError::MathOverflow)?;
+        Ok(GorLamports(to_u64(amount)?))
+    }
+
+    /// Native amount worth at least this much at `price` (rounds up)
+    pub fn to_lamports_ceil(self, price: UsdValue6, native_decimals: u8) -> Result<GorLamports> {
+        require!(price.0 > 0, GusdError::MathOverflow);
+        let amount = (self.0 as u128)
+            .checked_mul(10u128.pow(native_decimals as u32))
+            .ok_or(GusdError::MathOverflow)?
+            .div_ceil(price.0 as u128);
+        Ok(GorLamports(to_u64(amount)?))
+    }
+
+    /// This value as a ratio of `debt` in bps, or `u64::MAX` when there is no debt
+    pub fn ratio_bps(self, debt: GusdUnits) -> Result<u64> {
+        if debt.0 == 0 {
+            return Ok(u64::MAX);
+        }
+        let ratio = (self.0 as u128)
+            .checked_mul(BPS_DENOMINATOR as u128)
+            .ok_or(GusdError::MathOverflow)?
+            .checked_div(debt.to_usd().0 as u128)
+            .ok_or(GusdError::MathOverflow)?;
+        Ok(ratio.min(u64::MAX as u128) as u64)
+    }
+}
+
+/// Normalize a collateral ratio to a health factor (Aave convention): 1.0
+/// (`HEALTH_FACTOR_ONE`) at `liquidation_threshold_bps`, the position's collateral type
+/// threshold, and liquidatable below it. Debt-free positions (`u64::MAX` ratio) stay
+/// `u64::MAX`.
+pub fn health_factor(collateral_ratio_bps: u64, liquidation_threshold_bps: u64) -> u64 {
+    if collateral_ratio_bps == u64::MAX || liquidation_threshold_bps == 0 {
+        return u64::MAX;
+    }
+    let factor = (collateral_ratio_bps as u128)
+        .saturating_mul(HEALTH_FACTOR_ONE as u128)
+        / liquidation_threshold_bps as u128;
+    factor.min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LIQUIDATION_THRESHOLD_BPS;
+
+    const GOR_DECIMALS: u8 = 9;
+    /// $0.004776 per GOR
+    const PRICE: UsdValue6 = UsdValue6(4776);
+
+    #[test]
+    fn lamports_to_usd_rounds_down() {
+        // 50,000 GOR is worth $238.80
+        let value = GorLamports(50_000_000_000_000).to_usd(PRICE, GOR_DECIMALS).unwrap();
+        assert_eq!(value, UsdValue6(238_800_000));
+        // One lamport is worth less than a micro-dollar
+        assert_eq!(GorLamports(1).to_usd(PRICE, GOR_DECIMALS).unwrap(), UsdValue6(0));
+    }
+
+    #[test]
+    fn usd_to_lamports_rounds_each_way() {
+        let floor = UsdValue6(1).to_lamports(PRICE, GOR_DECIMALS).unwrap();
+        let ceil = UsdValue6(1).to_lamports_ceil(PRICE, GOR_DECIMALS).unwrap();
+        assert_eq!(floor, GorLamports(209_380));
+        assert_eq!(ceil, GorLamports(209_381));
+        // Exact conversions agree
+        let exact = UsdValue6(4776).to_lamports_ceil(PRICE, GOR_DECIMALS).unwrap();
+        assert_eq!(exact, GorLamports(1_000_000_000));
+        assert_eq!(exact, UsdValue6(4776).to_lamports(PRICE, GOR_DECIMALS).unwrap());
+    }
+
+    #[test]
+    fn round_trip_never_creates_value() {
+        for usd in [1, 999, 1_000_000, 123_456_789] {
+            let lamports = UsdValue6(usd).to_lamports(PRICE, GOR_DECIMALS).unwrap();
+            assert!(lamports.to_usd(PRICE, GOR_DECIMALS).unwrap().0 <= usd);
+            let lamports = UsdValue6(usd).to_lamports_ceil(PRICE, GOR_DECIMALS).unwrap();
+            assert!(lamports.to_usd(PRICE, GOR_DECIMALS).unwrap().0 >= usd.saturating_sub(1));
+        }
+    }
+
+    #[test]
+    fn conversions_fail_instead_of_truncating() {
+        assert!(GorLamports(u64::MAX).to_usd(UsdValue6(u64::MAX), 0).is_err());
+        assert!(UsdValue6(u64::MAX).to_lamports(UsdValue6(1), GOR_DECIMALS).is_err());
+        assert!(UsdValue6(u64::MAX).to_lamports_ceil(UsdValue6(1), GOR_DECIMALS).is_err());
+        assert!(UsdValue6(1).to_lamports(UsdValue6(0), GOR_DECIMALS).is_err());
+        assert!(UsdValue6(1).to_lamports_ceil(UsdValue6(0), GOR_DECIMALS).is_err());
+    }
+
+    #[test]
+    fn gusd_cost_rounds_up() {
+        // 1.5 GUSD at 3 lamports per GUSD costs 4.5, so 5 lamports
+        assert_eq!(GusdUnits(1_500_000).cost_in_lamports_ceil(3).unwrap(), GorLamports(5));
+        assert_eq!(GusdUnits(2_000_000).cost_in_lamports_ceil(3).unwrap(), GorLamports(6));
+        assert_eq!(GusdUnits(0).cost_in_lamports_ceil(3).unwrap(), GorLamports(0));
+    }
+
+    #[test]
+    fn ratio_and_health_factor() {
+        assert_eq!(UsdValue6(150).ratio_bps(GusdUnits(100)).unwrap(), 15_000);
+        assert_eq!(UsdValue6(150).ratio_bps(GusdUnits(0)).unwrap(), u64::MAX);
+        assert_eq!(health_factor(LIQUIDATION_THRESHOLD_BPS, LIQUIDATION_THRESHOLD_BPS), HEALTH_FACTOR_ONE);
+        assert_eq!(
+            health_factor(LIQUIDATION_THRESHOLD_BPS / 2, LIQUIDATION_THRESHOLD_BPS),
+            HEALTH_FACTOR_ONE / 2
+        );
+        assert_eq!(health_factor(u64::MAX, LIQUIDATION_THRESHOLD_BPS), u64::MAX);
+        // Each type's own threshold is 1.0
+        assert_eq!(health_factor(13_000, 13_000), HEALTH_FACTOR_ONE);
+        assert_eq!(health_factor(12_000, 15_000), HEALTH_FACTOR_ONE * 4 / 5);
+    }
+}
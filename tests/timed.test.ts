@@ -0,0 +1,3527 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { BankrunProvider } from "anchor-bankrun";
+import { Clock, ProgramTestContext, startAnchor } from "solana-bankrun";
+import {
+  ComputeBudgetProgram,
+  Ed25519Program,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  AccountLayout,
+  AccountState,
+  AuthorityType,
+  MINT_SIZE,
+  TOKEN_PROGRAM_ID,
+  createAssociatedTokenAccountIdempotentInstruction,
+  createBurnInstruction,
+  createInitializeMint2Instruction,
+  createMintToInstruction,
+  createSetAuthorityInstruction,
+  createTransferInstruction,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { assert } from "chai";
+import { Gusd } from "../target/types/gusd";
+import IDL from "../target/idl/gusd.json";
+
+// Flows gated on elapsed time, run in-process under bankrun so the clock can be moved.
+// Each describe block starts its own bank and initializes the protocol in it.
+
+// Program constants the flows wait on
+const VAULT_INACTIVITY_PERIOD_SECS = 2 * 365 * 24 * 60 * 60;
+const VAULT_RECLAIM_WINDOW_SECS = 90 * 24 * 60 * 60;
+
+// $0.004776 per GOR, as in the main suite
+const INITIAL_GOR_PRICE = 4776;
+const GOR_DECIMALS = 9;
+
+interface Bank {
+  context: ProgramTestContext;
+  provider: BankrunProvider;
+  program: Program<Gusd>;
+  admin: Keypair;
+  pda: (...seeds: Buffer[]) => PublicKey;
+  protocolState: PublicKey;
+  deploymentConfig: PublicKey;
+  priceFeed: PublicKey;
+  collateralConfig: PublicKey;
+  treasury: PublicKey;
+  // Owner of the Pyth price accounts the deployment accepts
+  pythReceiver: PublicKey;
+}
+
+interface TestVault {
+  owner: Keypair;
+  positionMint: PublicKey;
+  positionTokenAccount: PublicKey;
+  vault: PublicKey;
+  vaultCollateral: PublicKey;
+  // Set for vaults of a token collateral type
+  tokenType?: TokenType;
+  vaultTokenAccount?: PublicKey;
+}
+
+interface TokenType {
+  mint: PublicKey;
+  priceFeed: PublicKey;
+  collateralConfig: PublicKey;
+}
+
+// Start a bank with the program deployed and the protocol initialized at the GOR price
+async function startBank(): Promise<Bank> {
+  const context = await startAnchor(".", [], []);
+  const provider = new BankrunProvider(context);
+  anchor.setProvider(provider);
+  const program = new Program<Gusd>(IDL as Gusd, provider);
+  const pda = (...seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+  const bank: Bank = {
+    context,
+    provider,
+    program,
+    admin: context.payer,
+    pda,
+    protocolState: pda(Buffer.from("protocol")),
+    deploymentConfig: pda(Buffer.from("deployment")),
+    priceFeed: pda(Buffer.from("price_feed"), PublicKey.default.toBuffer()),
+    collateralConfig: pda(Buffer.from("collateral_config"), PublicKey.default.toBuffer()),
+    treasury: pda(Buffer.from("treasury")),
+    pythReceiver: Keypair.generate().publicKey,
+  };
+
+  await program.methods
+    .initialize(
+      new anchor.BN(INITIAL_GOR_PRICE),
+      GOR_DECIMALS,
+      bank.pythReceiver,
+      Keypair.generate().publicKey,
+      Keypair.generate().publicKey
+    )
+    .accounts({
+      admin: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      gusdMint: pda(Buffer.from("gusd_mint")),
+      deploymentConfig: bank.deploymentConfig,
+      priceAccumulator: pda(Buffer.from("price_accumulator")),
+      priceFeed: bank.priceFeed,
+      collateralConfig: bank.collateralConfig,
+      revenueStats: pda(Buffer.from("revenue_stats")),
+      supplyStats: pda(Buffer.from("supply_stats")),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  return bank;
+}
+
+// Move the bank's clock forward by `seconds`, and to the next slot so a repeated
+// transaction isn't taken for the one already processed
+async function warp(bank: Bank, seconds: number) {
+  const clock = await bank.context.banksClient.getClock();
+  bank.context.warpToSlot(clock.slot + BigInt(1));
+  bank.context.setClock(
+    new Clock(
+      clock.slot + BigInt(1),
+      clock.epochStartTimestamp,
+      clock.epoch,
+      clock.leaderScheduleEpoch,
+      clock.unixTimestamp + BigInt(seconds)
+    )
+  );
+}
+
+// Move the bank's clock into the next epoch, one second and one slot on
+async function nextEpoch(bank: Bank) {
+  const clock = await bank.context.banksClient.getClock();
+  bank.context.warpToSlot(clock.slot + BigInt(1));
+  bank.context.setClock(
+    new Clock(
+      clock.slot + BigInt(1),
+      clock.unixTimestamp,
+      clock.epoch + BigInt(1),
+      clock.leaderScheduleEpoch + BigInt(1),
+      clock.unixTimestamp + BigInt(1)
+    )
+  );
+}
+
+// A new keypair holding `sol` SOL
+async function fundedKeypair(bank: Bank, sol = 100): Promise<Keypair> {
+  const keypair = Keypair.generate();
+  await bank.provider.sendAndConfirm(
+    new Transaction().add(
+      SystemProgram.transfer({
+        fromPubkey: bank.admin.publicKey,
+        toPubkey: keypair.publicKey,
+        lamports: sol * LAMPORTS_PER_SOL,
+      })
+    )
+  );
+  return keypair;
+}
+
+// A new SPL mint with the admin as mint authority
+async function createTokenMint(bank: Bank, decimals: number): Promise<PublicKey> {
+  const mint = Keypair.generate();
+  const rent = await bank.context.banksClient.getRent();
+  await bank.provider.sendAndConfirm(
+    new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: bank.admin.publicKey,
+        newAccountPubkey: mint.publicKey,
+        lamports: Number(rent.minimumBalance(BigInt(MINT_SIZE))),
+        space: MINT_SIZE,
+        programId: TOKEN_PROGRAM_ID,
+      }),
+      createInitializeMint2Instruction(mint.publicKey, decimals, bank.admin.publicKey, null)
+    ),
+    [mint]
+  );
+  return mint.publicKey;
+}
+
+// Mint `amount` of `mint` to `owner`'s associated account, creating it if needed
+async function fundTokens(
+  bank: Bank,
+  mint: PublicKey,
+  owner: PublicKey,
+  amount: number
+): Promise<PublicKey> {
+  const account = getAssociatedTokenAddressSync(mint, owner);
+  const transaction = new Transaction().add(
+    createAssociatedTokenAccountIdempotentInstruction(bank.admin.publicKey, account, owner, mint)
+  );
+  if (amount > 0) {
+    transaction.add(createMintToInstruction(mint, account, bank.admin.publicKey, amount));
+  }
+  await bank.provider.sendAndConfirm(transaction);
+  return account;
+}
+
+// Token balance of an SPL token account
+async function tokenBalance(bank: Bank, account: PublicKey): Promise<bigint> {
+  return AccountLayout.decode((await bank.context.banksClient.getAccount(account)).data).amount;
+}
+
+// Add an admin-priced SPL collateral type
+async function addTokenType(
+  bank: Bank,
+  decimals: number,
+  priceUsd: number,
+  minCollateralRatioBps: number,
+  liquidationThresholdBps: number
+): Promise<TokenType> {
+  const mint = await createTokenMint(bank, decimals);
+  const tokenType: TokenType = {
+    mint,
+    priceFeed: bank.pda(Buffer.from("price_feed"), mint.toBuffer()),
+    collateralConfig: bank.pda(Buffer.from("collateral_config"), mint.toBuffer()),
+  };
+  await bank.program.methods
+    .initPriceFeed(mint, decimals, new anchor.BN(priceUsd))
+    .accounts({
+      admin: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      priceFeed: tokenType.priceFeed,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  await bank.program.methods
+    .addCollateralType(
+      mint,
+      new anchor.BN(minCollateralRatioBps),
+      new anchor.BN(liquidationThresholdBps)
+    )
+    .accounts({
+      admin: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      priceFeed: tokenType.priceFeed,
+      collateralConfig: tokenType.collateralConfig,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  return tokenType;
+}
+
+// Create a vault of `tokenType` for a new funded owner and deposit `amount` tokens
+async function createTokenVault(
+  bank: Bank,
+  tokenType: TokenType,
+  amount: number
+): Promise<TestVault> {
+  const { program, pda } = bank;
+  const owner = await fundedKeypair(bank);
+  const positionMint = Keypair.generate();
+  const testVault: TestVault = {
+    owner,
+    positionMint: positionMint.publicKey,
+    positionTokenAccount: getAssociatedTokenAddressSync(positionMint.publicKey, owner.publicKey),
+    vault: pda(Buffer.from("vault"), positionMint.publicKey.toBuffer()),
+    vaultCollateral: pda(Buffer.from("vault_collateral"), positionMint.publicKey.toBuffer()),
+    tokenType,
+    vaultTokenAccount: pda(Buffer.from("vault_token"), positionMint.publicKey.toBuffer()),
+  };
+  await program.methods
+    .createVault()
+    .accounts({
+      owner: owner.publicKey,
+      vault: testVault.vault,
+      vaultCollateral: testVault.vaultCollateral,
+      deploymentConfig: bank.deploymentConfig,
+      collateralConfig: tokenType.collateralConfig,
+      kycAttestation: null,
+      protocolState: bank.protocolState,
+      positionMint: positionMint.publicKey,
+      ownerPositionAccount: testVault.positionTokenAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([owner, positionMint])
+    .rpc();
+  await program.methods
+    .initVaultTokenAccount()
+    .accounts({
+      owner: owner.publicKey,
+      delegate: null,
+      payer: owner.publicKey,
+      positionTokenAccount: testVault.positionTokenAccount,
+      vault: testVault.vault,
+      vaultCollateral: testVault.vaultCollateral,
+      collateralMint: tokenType.mint,
+      vaultTokenAccount: testVault.vaultTokenAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([owner])
+    .rpc();
+  const ownerTokenAccount = await fundTokens(bank, tokenType.mint, owner.publicKey, amount);
+  await program.methods
+    .depositCollateral(new anchor.BN(amount))
+    .accounts({
+      owner: owner.publicKey,
+      delegate: null,
+      positionTokenAccount: testVault.positionTokenAccount,
+      vault: testVault.vault,
+      vaultCollateral: testVault.vaultCollateral,
+      protocolState: bank.protocolState,
+      instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      collateralConfig: tokenType.collateralConfig,
+      collateralMint: tokenType.mint,
+      vaultTokenAccount: testVault.vaultTokenAccount,
+      ownerTokenAccount,
+      collateralTokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([owner])
+    .rpc();
+  return testVault;
+}
+
+// Create a GOR vault for `owner` (a new funded keypair by default) and deposit `lamports`
+async function createGorVault(
+  bank: Bank,
+  lamports: number,
+  owner?: Keypair
+): Promise<TestVault> {
+  const { program, pda } = bank;
+  owner = owner ?? (await fundedKeypair(bank, lamports / LAMPORTS_PER_SOL + 100));
+  const positionMint = Keypair.generate();
+  const testVault: TestVault = {
+    owner,
+    positionMint: positionMint.publicKey,
+    positionTokenAccount: getAssociatedTokenAddressSync(positionMint.publicKey, owner.publicKey),
+    vault: pda(Buffer.from("vault"), positionMint.publicKey.toBuffer()),
+    vaultCollateral: pda(Buffer.from("vault_collateral"), positionMint.publicKey.toBuffer()),
+  };
+  await program.methods
+    .createVault()
+    .accounts({
+      owner: owner.publicKey,
+      vault: testVault.vault,
+      vaultCollateral: testVault.vaultCollateral,
+      deploymentConfig: bank.deploymentConfig,
+      collateralConfig: bank.collateralConfig,
+      kycAttestation: null,
+      protocolState: bank.protocolState,
+      positionMint: positionMint.publicKey,
+      ownerPositionAccount: testVault.positionTokenAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([owner, positionMint])
+    .rpc();
+  if (lamports > 0) {
+    await program.methods
+      .depositCollateral(new anchor.BN(lamports))
+      .accounts({
+        owner: owner.publicKey,
+        delegate: null,
+        positionTokenAccount: testVault.positionTokenAccount,
+        vault: testVault.vault,
+        vaultCollateral: testVault.vaultCollateral,
+        protocolState: bank.protocolState,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        collateralConfig: bank.collateralConfig,
+        collateralMint: null,
+        vaultTokenAccount: null,
+        ownerTokenAccount: null,
+        collateralTokenProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+  }
+  return testVault;
+}
+
+// Fee-discount accounts a mint may pass
+interface MintFeeAccounts {
+  feeExemption?: PublicKey;
+  feeTierRegistry?: PublicKey;
+  governanceStake?: PublicKey;
+}
+
+// Mint `amount` GUSD against a vault; margin members go in `remainingAccounts`
+async function mintGusd(
+  bank: Bank,
+  testVault: TestVault,
+  amount: number,
+  marginAccount: PublicKey | null = null,
+  members: PublicKey[] = [],
+  fees: MintFeeAccounts = {}
+) {
+  const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+  await bank.program.methods
+    .mintGusd(new anchor.BN(amount))
+    .accountsPartial({
+      owner: testVault.owner.publicKey,
+      delegate: null,
+      positionTokenAccount: testVault.positionTokenAccount,
+      vault: testVault.vault,
+      protocolState: bank.protocolState,
+      gusdMint,
+      userGusdAccount: getAssociatedTokenAddressSync(gusdMint, testVault.owner.publicKey),
+      deploymentConfig: bank.deploymentConfig,
+      priceFeed: testVault.tokenType?.priceFeed ?? bank.priceFeed,
+      collateralConfig: testVault.tokenType?.collateralConfig ?? bank.collateralConfig,
+      priceUpdate: null,
+      marginAccount,
+      feeExemption: fees.feeExemption ?? null,
+      feeTierRegistry: fees.feeTierRegistry ?? null,
+      governanceStake: fees.governanceStake ?? null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .remainingAccounts(
+      members.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+    )
+    .signers([testVault.owner])
+    .rpc();
+}
+
+// Liquidate a GOR vault as `liquidator`, repaying from their GUSD account, with no tip
+function liquidateVault(bank: Bank, liquidator: Keypair, testVault: TestVault) {
+  const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+  return bank.program.methods
+    .liquidate(new anchor.BN(0))
+    .accountsPartial({
+      liquidator: liquidator.publicKey,
+      vaultOwner: testVault.owner.publicKey,
+      positionTokenAccount: null,
+      vault: testVault.vault,
+      vaultCollateral: testVault.vaultCollateral,
+      protocolState: bank.protocolState,
+      revenueStats: bank.pda(Buffer.from("revenue_stats")),
+      supplyStats: bank.pda(Buffer.from("supply_stats")),
+      gusdMint,
+      liquidatorGusdAccount: getAssociatedTokenAddressSync(gusdMint, liquidator.publicKey),
+      priceAccumulator: bank.pda(Buffer.from("price_accumulator")),
+      deploymentConfig: bank.deploymentConfig,
+      priceFeed: bank.priceFeed,
+      collateralConfig: bank.collateralConfig,
+      priceUpdate: null,
+      marginAccount: null,
+      collateralMint: null,
+      vaultTokenAccount: null,
+      liquidatorCollateralAccount: null,
+      collateralTokenProgram: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      keeperAccount: null,
+      instructions: null,
+      feeTierRegistry: null,
+      governanceStake: null,
+    })
+    .signers([liquidator])
+    .rpc();
+}
+
+// Current and next parameter changelog pages, which risk setters log to
+async function parameterLogPages(bank: Bank) {
+  const { parameterLogEntries } = await bank.program.account.protocolState.fetch(
+    bank.protocolState
+  );
+  const page = parameterLogEntries.divn(32);
+  const [parameterLog, nextParameterLog] = [page, page.addn(1)].map((index) =>
+    bank.pda(Buffer.from("parameter_log"), index.toArrayLike(Buffer, "le", 8))
+  );
+  return { parameterLog, nextParameterLog, systemProgram: SystemProgram.programId };
+}
+
+// Set a risk parameter as the risk manager (the admin by default)
+async function setRiskParameter(bank: Bank, parameter: object, value: number) {
+  const { parameterLog, systemProgram } = await parameterLogPages(bank);
+  await bank.program.methods
+    .setRiskParameter(parameter as any, new anchor.BN(value))
+    .accounts({
+      riskManager: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      parameterLog,
+      systemProgram,
+    })
+    .rpc();
+}
+
+// Create the treasury's GUSD account, which fee and premium payments go to
+async function initTreasury(bank: Bank): Promise<PublicKey> {
+  const treasuryGusdAccount = bank.pda(Buffer.from("treasury_gusd"));
+  await bank.program.methods
+    .initTreasury()
+    .accounts({
+      treasurer: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      treasury: bank.treasury,
+      gusdMint: bank.pda(Buffer.from("gusd_mint")),
+      treasuryGusdAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  return treasuryGusdAccount;
+}
+
+// Accrue a vault's stability fee up to now
+function accrueInterest(bank: Bank, testVault: TestVault) {
+  return bank.program.methods
+    .accrueInterest()
+    .accounts({
+      vault: testVault.vault,
+      protocolState: bank.protocolState,
+      revenueStats: bank.pda(Buffer.from("revenue_stats")),
+      priceFeed: testVault.tokenType?.priceFeed ?? bank.priceFeed,
+      keeper: null,
+      keeperAccount: null,
+      instructions: null,
+      feeTierRegistry: null,
+      governanceStake: null,
+    })
+    .rpc();
+}
+
+// Set a feed's admin price, GOR's by default; it ramps from the current one
+async function setPrice(bank: Bank, price: number, priceFeed = bank.priceFeed) {
+  await bank.program.methods
+    .updatePrice(new anchor.BN(price))
+    .accounts({
+      admin: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      priceAccumulator: bank.pda(Buffer.from("price_accumulator")),
+      priceFeed,
+    })
+    .rpc();
+}
+
+// Write a fully verified Pyth `PriceUpdateV2` account publishing `price` (8 decimals) now
+async function setPythPrice(
+  bank: Bank,
+  account: PublicKey,
+  feedId: number[],
+  price: number,
+  conf = 0
+) {
+  const { unixTimestamp } = await bank.context.banksClient.getClock();
+  const data = Buffer.alloc(133);
+  Buffer.from([34, 241, 35, 99, 157, 126, 244, 205]).copy(data, 0);
+  data.writeUInt8(1, 40); // VerificationLevel::Full
+  Buffer.from(feedId).copy(data, 41);
+  data.writeBigInt64LE(BigInt(price), 73);
+  data.writeBigUInt64LE(BigInt(conf), 81);
+  data.writeInt32LE(-8, 89);
+  data.writeBigInt64LE(unixTimestamp, 93);
+  data.writeBigInt64LE(unixTimestamp, 101);
+  bank.context.setAccount(account, {
+    lamports: LAMPORTS_PER_SOL,
+    data,
+    owner: bank.pythReceiver,
+    executable: false,
+  });
+}
+
+// Point GOR's feed at a Pyth price account, switching valuation to the oracle
+async function setPythOracle(bank: Bank, account: PublicKey, feedId: number[]) {
+  await bank.program.methods
+    .setOracle({ pyth: {} }, account, feedId)
+    .accounts({
+      admin: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      priceFeed: bank.priceFeed,
+      parameterLog: (await parameterLogPages(bank)).parameterLog,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+}
+
+// Sample GOR's oracle price into the TWAP with `priceUpdate` as its price account
+function recordPriceSample(bank: Bank, priceUpdate: PublicKey) {
+  return bank.program.methods
+    .recordPriceSample()
+    .accounts({
+      protocolState: bank.protocolState,
+      priceAccumulator: bank.pda(Buffer.from("price_accumulator")),
+      deploymentConfig: bank.deploymentConfig,
+      priceFeed: bank.priceFeed,
+      priceUpdate,
+      keeper: null,
+      keeperAccount: null,
+      instructions: null,
+    })
+    .rpc();
+}
+
+// Global settlement accounts shared by the shutdown, mark, settle, and claim steps
+function settlementAccounts(bank: Bank) {
+  return {
+    protocolState: bank.protocolState,
+    settlement: bank.pda(Buffer.from("settlement")),
+    settlementPool: bank.pda(Buffer.from("settlement_pool")),
+    systemProgram: SystemProgram.programId,
+  };
+}
+
+// A token type's settlement pool and the token account it holds its tokens in
+function settlementTokenPool(bank: Bank, tokenType: TokenType) {
+  return {
+    settlementTokenPool: bank.pda(Buffer.from("settlement_pool"), tokenType.mint.toBuffer()),
+    settlementPoolTokenAccount: bank.pda(
+      Buffer.from("settlement_pool_tokens"),
+      tokenType.mint.toBuffer()
+    ),
+  };
+}
+
+// Shut the protocol down and open the redemption window
+async function triggerShutdown(bank: Bank) {
+  await bank.program.methods
+    .triggerShutdown()
+    .accounts({ admin: bank.admin.publicKey, ...settlementAccounts(bank) })
+    .rpc();
+}
+
+// Burn `amount` of `owner`'s GUSD into a redemption request
+async function requestRedemption(bank: Bank, owner: Keypair, amount: number) {
+  const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+  const { settlement, protocolState, systemProgram } = settlementAccounts(bank);
+  await bank.program.methods
+    .requestRedemption(new anchor.BN(amount))
+    .accounts({
+      owner: owner.publicKey,
+      settlement,
+      redemptionRequest: bank.pda(Buffer.from("redemption"), owner.publicKey.toBuffer()),
+      protocolState,
+      supplyStats: bank.pda(Buffer.from("supply_stats")),
+      gusdMint,
+      userGusdAccount: getAssociatedTokenAddressSync(gusdMint, owner.publicKey),
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram,
+    })
+    .signers([owner])
+    .rpc();
+}
+
+// Mark the GOR settlement price once the redemption window has passed, then each token
+// type's
+async function markSettlement(bank: Bank, tokenTypes: TokenType[] = []) {
+  const { settlement, protocolState, systemProgram } = settlementAccounts(bank);
+  await bank.program.methods
+    .markSettlement()
+    .accounts({
+      admin: bank.admin.publicKey,
+      protocolState,
+      settlement,
+      deploymentConfig: bank.deploymentConfig,
+      priceFeed: bank.priceFeed,
+      priceUpdate: null,
+    })
+    .rpc();
+  for (const tokenType of tokenTypes) {
+    await bank.program.methods
+      .markTokenSettlement()
+      .accounts({
+        admin: bank.admin.publicKey,
+        protocolState,
+        settlement,
+        deploymentConfig: bank.deploymentConfig,
+        collateralMint: tokenType.mint,
+        priceFeed: tokenType.priceFeed,
+        priceUpdate: null,
+        ...settlementTokenPool(bank, tokenType),
+        collateralTokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram,
+      })
+      .rpc();
+  }
+}
+
+// Settle a vault at the marked price as its holder, returning the rest of its collateral;
+// a vault with extra collateral balances passes them in `balances`
+function settleVault(bank: Bank, testVault: TestVault, balances: PublicKey[] = []) {
+  const tokenType = testVault.tokenType;
+  return bank.program.methods
+    .settleVault()
+    .accounts({
+      vaultOwner: testVault.owner.publicKey,
+      positionTokenAccount: testVault.positionTokenAccount,
+      vault: testVault.vault,
+      vaultCollateral: testVault.vaultCollateral,
+      ...settlementAccounts(bank),
+      collateralConfig: tokenType?.collateralConfig ?? bank.collateralConfig,
+      ...(tokenType
+        ? settlementTokenPool(bank, tokenType)
+        : { settlementTokenPool: null, settlementPoolTokenAccount: null }),
+      collateralMint: tokenType?.mint ?? null,
+      vaultTokenAccount: testVault.vaultTokenAccount ?? null,
+      ownerTokenAccount: tokenType
+        ? getAssociatedTokenAddressSync(tokenType.mint, testVault.owner.publicKey)
+        : null,
+      collateralTokenProgram: tokenType ? TOKEN_PROGRAM_ID : null,
+    })
+    .remainingAccounts(
+      balances.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+    )
+    .rpc();
+}
+
+// Assert `action` fails with the program error `name`. Bankrun errors carry the error's
+// code rather than its logs, so the name, message, or hex code may appear.
+async function expectError(bank: Bank, action: Promise<unknown>, name: string) {
+  const idlError = bank.program.idl.errors.find(
+    (error) => error.name.toLowerCase() === name.toLowerCase()
+  );
+  assert.ok(idlError, `no program error named ${name}`);
+  try {
+    await action;
+  } catch (error) {
+    const text = error.toString();
+    assert.isTrue(
+      text.includes(name) ||
+        text.includes(idlError.msg) ||
+        text.includes(`0x${idlError.code.toString(16)}`),
+      text
+    );
+    return;
+  }
+  assert.fail(`Expected ${name}`);
+}
+
+describe("GUSD timed flows", () => {
+  describe("Abandoned vaults", () => {
+    let bank: Bank;
+
+    before(async () => {
+      bank = await startBank();
+    });
+
+    const flag = (testVault: TestVault) =>
+      bank.program.methods
+        .flagAbandonedVault()
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          vaultOwner: testVault.owner.publicKey,
+          vault: testVault.vault,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+        })
+        .rpc();
+
+    it("Rejects third-party top-ups of a vault flagged as abandoned", async () => {
+      // 10 GOR is about $0.05, under the $1 dust limit
+      const dust = await createGorVault(bank, 10 * LAMPORTS_PER_SOL);
+      await warp(bank, VAULT_INACTIVITY_PERIOD_SECS);
+      await flag(dust);
+
+      const rescuer = await fundedKeypair(bank);
+      await expectError(
+        bank,
+        bank.program.methods
+          .depositCollateralFor(new anchor.BN(LAMPORTS_PER_SOL))
+          .accounts({
+            depositor: rescuer.publicKey,
+            vault: dust.vault,
+            vaultCollateral: dust.vaultCollateral,
+            protocolState: bank.protocolState,
+            collateralConfig: bank.collateralConfig,
+            collateralMint: null,
+            vaultTokenAccount: null,
+            depositorTokenAccount: null,
+            collateralTokenProgram: null,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([rescuer])
+          .rpc(),
+        "VaultFlaggedAbandoned"
+      );
+      const vault = await bank.program.account.vault.fetch(dust.vault);
+      assert.equal(vault.collateralAmount.toNumber(), 10 * LAMPORTS_PER_SOL);
+    });
+
+    it("Flags only inactive dust vaults, and the holder can reclaim", async () => {
+      const dust = await createGorVault(bank, 10 * LAMPORTS_PER_SOL);
+      // 1,000 GOR is about $4.78
+      const funded = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+
+      // Not inactive long enough
+      await warp(bank, VAULT_INACTIVITY_PERIOD_SECS - 60);
+      await expectError(bank, flag(dust), "VaultNotAbandoned");
+      await warp(bank, 60);
+
+      // Worth more than dust
+      await expectError(bank, flag(funded), "VaultNotAbandoned");
+
+      await flag(dust);
+      let vault = await bank.program.account.vault.fetch(dust.vault);
+      assert.isAbove(vault.abandonedFlaggedAt.toNumber(), 0);
+      await warp(bank, 1);
+      await expectError(bank, flag(dust), "VaultNotAbandoned");
+
+      await bank.program.methods
+        .reclaimVault()
+        .accounts({
+          owner: dust.owner.publicKey,
+          delegate: null,
+          positionTokenAccount: dust.positionTokenAccount,
+          vault: dust.vault,
+        })
+        .signers([dust.owner])
+        .rpc();
+      vault = await bank.program.account.vault.fetch(dust.vault);
+      assert.equal(vault.abandonedFlaggedAt.toNumber(), 0);
+
+      // Reclaiming counts as activity, so the inactivity period starts over
+      await warp(bank, 1);
+      await expectError(bank, flag(dust), "VaultNotAbandoned");
+    });
+
+    const sweep = (testVault: TestVault) =>
+      bank.program.methods
+        .sweepAbandonedVault()
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          vaultOwner: testVault.owner.publicKey,
+          vault: testVault.vault,
+          vaultCollateral: testVault.vaultCollateral,
+          treasury: bank.treasury,
+          systemProgram: SystemProgram.programId,
+          positionTokenAccount: testVault.positionTokenAccount,
+          positionMint: testVault.positionMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+        })
+        .rpc();
+
+    it("Sweeps a flagged vault to the treasury once the reclaim window ends", async () => {
+      const { banksClient } = bank.context;
+      const dust = await createGorVault(bank, 10 * LAMPORTS_PER_SOL);
+      await warp(bank, VAULT_INACTIVITY_PERIOD_SECS);
+      await flag(dust);
+
+      await expectError(bank, sweep(dust), "ReclaimWindowActive");
+      await warp(bank, VAULT_RECLAIM_WINDOW_SECS);
+
+      const protocolBefore = await bank.program.account.protocolState.fetch(bank.protocolState);
+      const collateralLamports = await banksClient.getBalance(dust.vaultCollateral);
+      const treasuryBefore = await banksClient.getBalance(bank.treasury);
+      await sweep(dust);
+
+      assert.isNull(await banksClient.getAccount(dust.vault));
+      assert.equal(await banksClient.getBalance(dust.vaultCollateral), BigInt(0));
+      assert.equal(
+        await banksClient.getBalance(bank.treasury),
+        treasuryBefore + collateralLamports
+      );
+      const protocol = await bank.program.account.protocolState.fetch(bank.protocolState);
+      assert.equal(
+        protocolBefore.totalCollateral.sub(protocol.totalCollateral).toNumber(),
+        10 * LAMPORTS_PER_SOL
+      );
+
+      // The holder wasn't there to burn the position NFT, so it's frozen
+      const position = AccountLayout.decode(
+        (await banksClient.getAccount(dust.positionTokenAccount)).data
+      );
+      assert.equal(position.state, AccountState.Frozen);
+    });
+
+    it("Re-checks the dust limit at sweep time", async () => {
+      // 200 GOR is about $0.96 when flagged
+      const dust = await createGorVault(bank, 200 * LAMPORTS_PER_SOL);
+      await warp(bank, VAULT_INACTIVITY_PERIOD_SECS);
+      await flag(dust);
+
+      // A 10x price makes it worth about $9.55 by the end of the window
+      await setPrice(bank, INITIAL_GOR_PRICE * 10);
+      await warp(bank, VAULT_RECLAIM_WINDOW_SECS);
+      await expectError(bank, sweep(dust), "VaultNotAbandoned");
+
+      await setPrice(bank, INITIAL_GOR_PRICE);
+      await warp(bank, VAULT_RECLAIM_WINDOW_SECS);
+      await sweep(dust);
+      assert.isNull(await bank.context.banksClient.getAccount(dust.vault));
+    });
+  });
+
+  describe("Margin accounts", () => {
+    let bank: Bank;
+    let user: Keypair;
+    let lender: TestVault;
+    let borrower: TestVault;
+    let marginAccount: PublicKey;
+
+    // GOR ratios tighter than the protocol's 150%/120%, so margin checks must read them
+    const MIN_CR_BPS = 16000;
+    const THRESHOLD_BPS = 13000;
+    const MARGIN_BUFFER_BPS = 2500;
+
+    before(async () => {
+      bank = await startBank();
+      await bank.program.methods
+        .setCollateralType(
+          new anchor.BN(MIN_CR_BPS),
+          new anchor.BN(THRESHOLD_BPS),
+          new anchor.BN(0),
+          true
+        )
+        .accounts({
+          riskManager: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          collateralConfig: bank.collateralConfig,
+          ...(await parameterLogPages(bank)),
+        })
+        .rpc();
+
+      // Two vaults of one user: one holds collateral, the other borrows against both
+      user = await fundedKeypair(bank, 160_000);
+      lender = await createGorVault(bank, 100_000 * LAMPORTS_PER_SOL, user);
+      borrower = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL, user);
+      marginAccount = bank.pda(Buffer.from("margin_account"), user.publicKey.toBuffer());
+    });
+
+    const members = () => [lender.vault, borrower.vault];
+    const asRemaining = (keys: PublicKey[]) =>
+      keys.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+
+    const marginHealth = () =>
+      bank.program.methods
+        .getMarginHealth()
+        .accounts({
+          marginAccount,
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+          collateralConfig: bank.collateralConfig,
+        })
+        .remainingAccounts(asRemaining(members()))
+        .view();
+
+    const leave = (testVault: TestVault, remaining: PublicKey[]) =>
+      bank.program.methods
+        .leaveMarginAccount()
+        .accounts({
+          authority: user.publicKey,
+          owner: user.publicKey,
+          delegate: null,
+          positionTokenAccount: testVault.positionTokenAccount,
+          vault: testVault.vault,
+          marginAccount,
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+          revenueStats: bank.pda(Buffer.from("revenue_stats")),
+          feeTierRegistry: null,
+          governanceStake: null,
+          collateralConfig: bank.collateralConfig,
+        })
+        .remainingAccounts(asRemaining(remaining))
+        .signers([user])
+        .rpc();
+
+    it("Creates a margin account and joins one user's two vaults to it", async () => {
+      await bank.program.methods
+        .createMarginAccount()
+        .accounts({
+          authority: user.publicKey,
+          marginAccount,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user])
+        .rpc();
+      for (const member of [lender, borrower]) {
+        await bank.program.methods
+          .joinMarginAccount()
+          .accounts({
+            authority: user.publicKey,
+            owner: user.publicKey,
+            delegate: null,
+            positionTokenAccount: member.positionTokenAccount,
+            vault: member.vault,
+            marginAccount,
+          })
+          .signers([user])
+          .rpc();
+      }
+
+      const account = await bank.program.account.marginAccount.fetch(marginAccount);
+      assert.deepEqual(
+        account.vaults.map((vault) => vault.toBase58()),
+        members().map((vault) => vault.toBase58())
+      );
+      const vault = await bank.program.account.vault.fetch(borrower.vault);
+      assert.ok(vault.marginAccount.equals(marginAccount));
+    });
+
+    it("Lets the borrower mint against both vaults, up to the GOR ratio plus the buffer", async () => {
+      // $238.80 of collateral alone supports $149.25 at 160%; the account's $716.40 supports
+      // $387.24 at 185%
+      await expectError(
+        bank,
+        mintGusd(bank, borrower, 390_000_000, marginAccount, members()),
+        "InsufficientCollateral"
+      );
+      await mintGusd(bank, borrower, 300_000_000, marginAccount, members());
+
+      const health = await marginHealth();
+      const { debtAmount } = await bank.program.account.vault.fetch(borrower.vault);
+      assert.equal(health.vaultCount, 2);
+      assert.equal(health.collateralAmount.toString(), (150_000 * LAMPORTS_PER_SOL).toString());
+      assert.equal(health.debtAmount.toString(), debtAmount.toString());
+      assert.equal(health.minCollateralRatioBps.toNumber(), MIN_CR_BPS + MARGIN_BUFFER_BPS);
+      assert.isFalse(health.isLiquidatable);
+    });
+
+    it("Keeps vaults in the account unless each side stays healthy on leaving", async () => {
+      // The borrower alone is far under 160%
+      await expectError(bank, leave(borrower, [lender.vault]), "WouldUndercollateralize");
+      // The lender can't leave the borrower's debt behind either
+      await expectError(bank, leave(lender, [borrower.vault]), "WouldUndercollateralize");
+    });
+
+    it("Liquidates the borrower's debt with the lender's collateral below the GOR threshold", async () => {
+      const liquidator = await fundedKeypair(bank, 130_000);
+      const liquidatorVault = await createGorVault(bank, 120_000 * LAMPORTS_PER_SOL, liquidator);
+      await mintGusd(bank, liquidatorVault, 300_000_000);
+
+      // Move the price so the account sits at 125%: under GOR's 130% threshold, above the
+      // protocol's 120%
+      const { debtAmount } = await bank.program.account.vault.fetch(borrower.vault);
+      const price = debtAmount
+        .muln(12_500)
+        .mul(new anchor.BN(LAMPORTS_PER_SOL))
+        .div(new anchor.BN(10_000).mul(new anchor.BN(150_000 * LAMPORTS_PER_SOL)));
+      await warp(bank, 60);
+      await setPrice(bank, price.toNumber());
+      await warp(bank, 60 * 60);
+
+      const before = await marginHealth();
+      assert.isTrue(before.isLiquidatable);
+      assert.isAbove(before.collateralRatioBps.toNumber(), 12_000);
+      assert.isBelow(before.collateralRatioBps.toNumber(), THRESHOLD_BPS);
+
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      await bank.program.methods
+        .liquidateMargin()
+        .accountsPartial({
+          liquidator: liquidator.publicKey,
+          marginAccount,
+          debtVaultOwner: user.publicKey,
+          debtVault: borrower.vault,
+          collateralVaultOwner: user.publicKey,
+          collateralVault: lender.vault,
+          vaultCollateral: lender.vaultCollateral,
+          protocolState: bank.protocolState,
+          gusdMint,
+          liquidatorGusdAccount: getAssociatedTokenAddressSync(gusdMint, liquidator.publicKey),
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          collateralConfig: bank.collateralConfig,
+          priceUpdate: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          keeperAccount: null,
+          instructions: null,
+          feeTierRegistry: null,
+          governanceStake: null,
+        })
+        .remainingAccounts(asRemaining(members()))
+        .signers([liquidator])
+        .rpc();
+
+      const borrowerAfter = await bank.program.account.vault.fetch(borrower.vault);
+      const lenderAfter = await bank.program.account.vault.fetch(lender.vault);
+      assert.isTrue(borrowerAfter.debtAmount.lt(debtAmount));
+      assert.isBelow(lenderAfter.collateralAmount.toNumber(), 100_000 * LAMPORTS_PER_SOL);
+      // The borrower's own collateral is untouched
+      assert.equal(borrowerAfter.collateralAmount.toNumber(), 50_000 * LAMPORTS_PER_SOL);
+    });
+  });
+
+  describe("Global settlement", () => {
+    let bank: Bank;
+    let token: TokenType;
+    let gorVault: TestVault;
+    let tokenVault: TestVault;
+
+    // A $1 token at a 200% minimum ratio
+    const TOKEN_PRICE = 1_000_000;
+
+    before(async () => {
+      bank = await startBank();
+      token = await addTokenType(bank, 6, TOKEN_PRICE, 20000, 15000);
+
+      // $477.60 of GOR and $1,000 of the token, each backing 100 GUSD
+      gorVault = await createGorVault(bank, 100_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, gorVault, 100_000_000);
+      tokenVault = await createTokenVault(bank, token, 1_000_000_000);
+      await mintGusd(bank, tokenVault, 100_000_000);
+    });
+
+    it("Settles GOR and token vaults into their pools and pays redemptions from both", async () => {
+      const { program } = bank;
+      const { settlement: settlementPda, settlementPool } = settlementAccounts(bank);
+      const { settlementTokenPool: tokenPoolPda, settlementPoolTokenAccount } = settlementTokenPool(
+        bank,
+        token
+      );
+      const gorDebt = (await program.account.vault.fetch(gorVault.vault)).debtAmount;
+      const tokenDebt = (await program.account.vault.fetch(tokenVault.vault)).debtAmount;
+
+      await triggerShutdown(bank);
+      await requestRedemption(bank, gorVault.owner, 50_000_000);
+      await requestRedemption(bank, tokenVault.owner, 50_000_000);
+
+      // Vaults can't settle before the price is marked
+      await expectError(bank, settleVault(bank, gorVault), "SettlementNotMarked");
+      await warp(bank, 24 * 60 * 60);
+      await markSettlement(bank, [token]);
+
+      // Claims wait until every vault has settled
+      const claim = (owner: Keypair) =>
+        program.methods
+          .claimRedemption()
+          .accounts({
+            owner: owner.publicKey,
+            redemptionRequest: bank.pda(Buffer.from("redemption"), owner.publicKey.toBuffer()),
+            ...settlementAccounts(bank),
+          })
+          .signers([owner])
+          .rpc();
+      await expectError(bank, claim(gorVault.owner), "SettlementIncomplete");
+
+      // Each vault pools the collateral worth its debt and returns the rest to its holder
+      await warp(bank, 1);
+      await settleVault(bank, gorVault);
+      await settleVault(bank, tokenVault);
+      for (const testVault of [gorVault, tokenVault]) {
+        const vault = await program.account.vault.fetch(testVault.vault);
+        assert.equal(vault.debtAmount.toNumber(), 0);
+        assert.equal(vault.collateralAmount.toNumber(), 0);
+      }
+      const settlement = await program.account.settlement.fetch(settlementPda);
+      const tokenPool = await program.account.settlementTokenPool.fetch(tokenPoolPda);
+      const price = settlement.price;
+      assert.equal(price.toNumber(), INITIAL_GOR_PRICE);
+      assert.equal(tokenPool.price.toNumber(), TOKEN_PRICE);
+      const lamportsPerGusd = new anchor.BN(10).pow(new anchor.BN(GOR_DECIMALS));
+      assert.equal(
+        settlement.collateral.toString(),
+        gorDebt.mul(lamportsPerGusd).add(price.subn(1)).div(price).toString()
+      );
+      assert.equal(settlement.debt.toString(), gorDebt.toString());
+      assert.equal(tokenPool.collateral.toString(), tokenDebt.toString());
+      assert.equal(tokenPool.debt.toString(), tokenDebt.toString());
+      assert.equal(
+        (await tokenBalance(bank, settlementPoolTokenAccount)).toString(),
+        tokenDebt.toString()
+      );
+      assert.equal(
+        (await program.account.protocolState.fetch(bank.protocolState)).totalDebt.toNumber(),
+        0
+      );
+
+      // Each pool pays a request its pro-rata share, capped at the face value of the pool's
+      // part of the burned GUSD
+      const amount = new anchor.BN(50_000_000);
+      const entitled = (collateral: anchor.BN, debt: anchor.BN, poolPrice: anchor.BN, unit: anchor.BN) =>
+        anchor.BN.min(
+          collateral.mul(amount).div(settlement.requested),
+          amount.mul(debt).div(settlement.markedDebt).mul(unit).div(poolPrice)
+        );
+      const gorOwed = entitled(settlement.collateral, settlement.debt, price, lamportsPerGusd);
+      const tokenOwed = entitled(
+        tokenPool.collateral,
+        tokenPool.debt,
+        tokenPool.price,
+        new anchor.BN(1_000_000)
+      );
+
+      const owner = gorVault.owner;
+      const lamportsBefore = (await bank.context.banksClient.getAccount(owner.publicKey)).lamports;
+      const poolBefore = (await bank.context.banksClient.getAccount(settlementPool)).lamports;
+      await claim(owner);
+      const poolAfter = (await bank.context.banksClient.getAccount(settlementPool)).lamports;
+      assert.equal(poolBefore - poolAfter, gorOwed.toNumber());
+      assert.isAbove(
+        (await bank.context.banksClient.getAccount(owner.publicKey)).lamports,
+        lamportsBefore
+      );
+      // Nothing more is owed until another vault grows the pool
+      await warp(bank, 1);
+      await expectError(bank, claim(owner), "InvalidAmount");
+
+      const ownerTokenAccount = await fundTokens(bank, token.mint, owner.publicKey, 0);
+      await program.methods
+        .claimTokenRedemption()
+        .accounts({
+          owner: owner.publicKey,
+          redemptionRequest: bank.pda(Buffer.from("redemption"), owner.publicKey.toBuffer()),
+          tokenClaim: bank.pda(
+            Buffer.from("redemption_claim"),
+            token.mint.toBuffer(),
+            owner.publicKey.toBuffer()
+          ),
+          protocolState: bank.protocolState,
+          settlement: settlementPda,
+          collateralMint: token.mint,
+          settlementTokenPool: tokenPoolPda,
+          settlementPoolTokenAccount,
+          ownerTokenAccount,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      assert.equal((await tokenBalance(bank, ownerTokenAccount)).toString(), tokenOwed.toString());
+      const request = await program.account.redemptionRequest.fetch(
+        bank.pda(Buffer.from("redemption"), owner.publicKey.toBuffer())
+      );
+      assert.equal(request.claimed.toString(), gorOwed.toString());
+    });
+  });
+
+  describe("Collateral balances", () => {
+    let bank: Bank;
+    let token: TokenType;
+
+    // A $1 token at a 200% minimum ratio and a 150% liquidation threshold
+    before(async () => {
+      bank = await startBank();
+      token = await addTokenType(bank, 6, 1_000_000, 20000, 15000);
+    });
+
+    interface TestBalance {
+      collateralBalance: PublicKey;
+      balanceTokenAccount: PublicKey;
+      ownerTokenAccount: PublicKey;
+      // [collateral_balance, collateral_config, price_feed, price_update]; the admin-priced
+      // feed ignores the price update
+      remainingAccounts: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[];
+    }
+
+    // Open a balance of the token in `testVault` and deposit `amount` into it
+    async function openBalance(testVault: TestVault, amount: number): Promise<TestBalance> {
+      const seeds = [testVault.vault.toBuffer(), token.mint.toBuffer()];
+      const collateralBalance = bank.pda(Buffer.from("collateral_balance"), ...seeds);
+      const balanceTokenAccount = bank.pda(Buffer.from("balance_token"), ...seeds);
+      const owner = testVault.owner;
+      await bank.program.methods
+        .openCollateralBalance()
+        .accounts({
+          owner: owner.publicKey,
+          delegate: null,
+          payer: owner.publicKey,
+          positionTokenAccount: testVault.positionTokenAccount,
+          vault: testVault.vault,
+          vaultCollateral: testVault.vaultCollateral,
+          collateralConfig: token.collateralConfig,
+          collateralMint: token.mint,
+          collateralBalance,
+          balanceTokenAccount,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      const ownerTokenAccount = await fundTokens(bank, token.mint, owner.publicKey, amount);
+      if (amount > 0) {
+        await bank.program.methods
+          .depositCollateralBalance(new anchor.BN(amount))
+          .accounts({
+            owner: owner.publicKey,
+            delegate: null,
+            positionTokenAccount: testVault.positionTokenAccount,
+            vault: testVault.vault,
+            collateralBalance,
+            collateralConfig: token.collateralConfig,
+            collateralMint: token.mint,
+            balanceTokenAccount,
+            ownerTokenAccount,
+            collateralTokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([owner])
+          .rpc();
+      }
+      const remainingAccounts = [
+        collateralBalance,
+        token.collateralConfig,
+        token.priceFeed,
+        token.priceFeed,
+      ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+      return { collateralBalance, balanceTokenAccount, ownerTokenAccount, remainingAccounts };
+    }
+
+    // A GOR vault holding 1 GOR, with `amount` of the token in a balance backing `debt`
+    async function balanceBackedVault(amount: number, debt: number) {
+      const testVault = await createGorVault(bank, LAMPORTS_PER_SOL);
+      const balance = await openBalance(testVault, amount);
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      await bank.program.methods
+        .mintGusd(new anchor.BN(debt))
+        .accountsPartial({
+          owner: testVault.owner.publicKey,
+          delegate: null,
+          positionTokenAccount: testVault.positionTokenAccount,
+          vault: testVault.vault,
+          protocolState: bank.protocolState,
+          gusdMint,
+          userGusdAccount: getAssociatedTokenAddressSync(gusdMint, testVault.owner.publicKey),
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          collateralConfig: bank.collateralConfig,
+          priceUpdate: null,
+          marginAccount: null,
+          feeExemption: null,
+          feeTierRegistry: null,
+          governanceStake: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(balance.remainingAccounts)
+        .signers([testVault.owner])
+        .rpc();
+      return { testVault, balance };
+    }
+
+    it("Closes a balance only once it's empty, returning tokens sent to it directly", async () => {
+      const testVault = await createGorVault(bank, LAMPORTS_PER_SOL);
+      const balance = await openBalance(testVault, 5_000_000);
+      const owner = testVault.owner;
+      const close = () =>
+        bank.program.methods
+          .closeCollateralBalance()
+          .accounts({
+            owner: owner.publicKey,
+            delegate: null,
+            positionTokenAccount: testVault.positionTokenAccount,
+            vault: testVault.vault,
+            vaultCollateral: testVault.vaultCollateral,
+            collateralBalance: balance.collateralBalance,
+            collateralMint: token.mint,
+            balanceTokenAccount: balance.balanceTokenAccount,
+            ownerTokenAccount: balance.ownerTokenAccount,
+            collateralTokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([owner])
+          .rpc();
+      await expectError(bank, close(), "VaultNotEmpty");
+
+      await bank.program.methods
+        .withdrawCollateralBalance(new anchor.BN(5_000_000))
+        .accounts({
+          owner: owner.publicKey,
+          delegate: null,
+          positionTokenAccount: testVault.positionTokenAccount,
+          vault: testVault.vault,
+          vaultCollateral: testVault.vaultCollateral,
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          collateralConfig: bank.collateralConfig,
+          priceUpdate: null,
+          collateralBalance: balance.collateralBalance,
+          balanceCollateralConfig: token.collateralConfig,
+          collateralMint: token.mint,
+          balanceTokenAccount: balance.balanceTokenAccount,
+          ownerTokenAccount: balance.ownerTokenAccount,
+          treasury: bank.treasury,
+          treasuryCollateralAccount: null,
+          feeExemption: null,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+          revenueStats: bank.pda(Buffer.from("revenue_stats")),
+          feeTierRegistry: null,
+          governanceStake: null,
+        })
+        .remainingAccounts(balance.remainingAccounts)
+        .signers([owner])
+        .rpc();
+      // Tokens the balance never credited
+      await bank.provider.sendAndConfirm(
+        new Transaction().add(
+          createMintToInstruction(
+            token.mint,
+            balance.balanceTokenAccount,
+            bank.admin.publicKey,
+            2_000_000
+          )
+        )
+      );
+
+      await warp(bank, 1);
+      await close();
+      assert.isNull(await bank.context.banksClient.getAccount(balance.collateralBalance));
+      assert.isNull(await bank.context.banksClient.getAccount(balance.balanceTokenAccount));
+      assert.equal(await tokenBalance(bank, balance.ownerTokenAccount), BigInt(7_000_000));
+      const vault = await bank.program.account.vault.fetch(testVault.vault);
+      assert.isEmpty(vault.collateralBalances);
+    });
+
+    it("Liquidates a balance that no longer covers the debt the vault's GOR doesn't", async () => {
+      // $200 of the token backs 60 GUSD; the vault's GOR is worth under a cent
+      const { testVault, balance } = await balanceBackedVault(200_000_000, 60_000_000);
+      const liquidator = await fundedKeypair(bank, 50_100);
+      const liquidatorVault = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL, liquidator);
+      await mintGusd(bank, liquidatorVault, 100_000_000);
+      const liquidatorCollateralAccount = await fundTokens(bank, token.mint, liquidator.publicKey, 0);
+      const treasuryCollateralAccount = getAssociatedTokenAddressSync(
+        token.mint,
+        bank.treasury,
+        true
+      );
+      await bank.provider.sendAndConfirm(
+        new Transaction().add(
+          createAssociatedTokenAccountIdempotentInstruction(
+            bank.admin.publicKey,
+            treasuryCollateralAccount,
+            bank.treasury,
+            token.mint
+          )
+        )
+      );
+
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      const liquidate = () =>
+        bank.program.methods
+          .liquidateCollateralBalance()
+          .accounts({
+            liquidator: liquidator.publicKey,
+            vault: testVault.vault,
+            vaultCollateral: testVault.vaultCollateral,
+            protocolState: bank.protocolState,
+            revenueStats: bank.pda(Buffer.from("revenue_stats")),
+            supplyStats: bank.pda(Buffer.from("supply_stats")),
+            gusdMint,
+            liquidatorGusdAccount: getAssociatedTokenAddressSync(gusdMint, liquidator.publicKey),
+            priceAccumulator: bank.pda(Buffer.from("price_accumulator")),
+            deploymentConfig: bank.deploymentConfig,
+            priceFeed: bank.priceFeed,
+            collateralConfig: bank.collateralConfig,
+            priceUpdate: null,
+            collateralBalance: balance.collateralBalance,
+            balancePriceFeed: token.priceFeed,
+            balanceCollateralConfig: token.collateralConfig,
+            balancePriceUpdate: null,
+            collateralMint: token.mint,
+            balanceTokenAccount: balance.balanceTokenAccount,
+            liquidatorCollateralAccount,
+            treasury: bank.treasury,
+            treasuryCollateralAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            collateralTokenProgram: TOKEN_PROGRAM_ID,
+            feeTierRegistry: null,
+            governanceStake: null,
+          })
+          .remainingAccounts(balance.remainingAccounts)
+          .signers([liquidator])
+          .rpc();
+      await expectError(bank, liquidate(), "VaultNotLiquidatable");
+
+      // At $0.40 the balance is worth $80, 133% of the debt against a 150% threshold
+      await warp(bank, 60);
+      await setPrice(bank, 400_000, token.priceFeed);
+      await warp(bank, 2 * 60 * 60);
+      const debtBefore = (await bank.program.account.vault.fetch(testVault.vault)).debtAmount;
+      await liquidate();
+
+      const vault = await bank.program.account.vault.fetch(testVault.vault);
+      const { amount } = await bank.program.account.collateralBalance.fetch(
+        balance.collateralBalance
+      );
+      const seized = 200_000_000 - amount.toNumber();
+      assert.isTrue(vault.debtAmount.lt(debtBefore));
+      assert.isAbove(seized, 0);
+      assert.equal(
+        (await tokenBalance(bank, liquidatorCollateralAccount)) +
+          (await tokenBalance(bank, treasuryCollateralAccount)),
+        BigInt(seized)
+      );
+      assert.equal(
+        (await tokenBalance(bank, balance.balanceTokenAccount)).toString(),
+        amount.toString()
+      );
+    });
+
+    it("Settles a balance's part of the debt before its vault settles", async () => {
+      // $160 of the token at $0.40 backs 60 GUSD
+      const { testVault, balance } = await balanceBackedVault(400_000_000, 60_000_000);
+      const debt = (await bank.program.account.vault.fetch(testVault.vault)).debtAmount;
+      const balanceAccounts = [balance.collateralBalance];
+
+      await triggerShutdown(bank);
+      await warp(bank, 24 * 60 * 60);
+      await markSettlement(bank, [token]);
+
+      // The vault can't settle while its balance holds its part of the debt
+      await expectError(
+        bank,
+        settleVault(bank, testVault, balanceAccounts),
+        "CollateralBalanceUnsettled"
+      );
+
+      const { settlementTokenPool: tokenPoolPda, settlementPoolTokenAccount } =
+        settlementTokenPool(bank, token);
+      await bank.program.methods
+        .settleCollateralBalance()
+        .accounts({
+          vault: testVault.vault,
+          vaultCollateral: testVault.vaultCollateral,
+          protocolState: bank.protocolState,
+          settlement: bank.pda(Buffer.from("settlement")),
+          collateralConfig: bank.collateralConfig,
+          collateralBalance: balance.collateralBalance,
+          balanceCollateralConfig: token.collateralConfig,
+          settlementTokenPool: tokenPoolPda,
+          settlementPoolTokenAccount,
+          collateralMint: token.mint,
+          balanceTokenAccount: balance.balanceTokenAccount,
+          collateralTokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      // $160 at 200% backs 80 GUSD, so the balance settles all 60 with $60 of tokens
+      const toPool = debt.muln(1_000_000).addn(400_000 - 1).divn(400_000);
+      const settledBalance = await bank.program.account.collateralBalance.fetch(
+        balance.collateralBalance
+      );
+      assert.isTrue(settledBalance.settled);
+      assert.equal(settledBalance.amount.toString(), new anchor.BN(400_000_000).sub(toPool).toString());
+      const pool = await bank.program.account.settlementTokenPool.fetch(tokenPoolPda);
+      assert.equal(pool.collateral.toString(), toPool.toString());
+      assert.equal(pool.debt.toString(), debt.toString());
+      assert.equal((await tokenBalance(bank, settlementPoolTokenAccount)).toString(), toPool.toString());
+      assert.equal((await bank.program.account.vault.fetch(testVault.vault)).debtAmount.toNumber(), 0);
+
+      // With no debt left, settling returns the vault's GOR to its holder
+      await warp(bank, 1);
+      await settleVault(bank, testVault, balanceAccounts);
+      const vault = await bank.program.account.vault.fetch(testVault.vault);
+      assert.equal(vault.collateralAmount.toNumber(), 0);
+    });
+  });
+
+  describe("Oracle migration", () => {
+    let bank: Bank;
+    const pythAccount = Keypair.generate().publicKey;
+    const feedId = Array.from({ length: 32 }, (_, i) => i + 1);
+
+    // Program constants the migration waits on
+    const MIN_ORACLE_SHADOW_SECS = 24 * 60 * 60;
+    const ORACLE_SWITCH_TIMELOCK_SECS = 48 * 60 * 60;
+    // GOR prices at Pyth's 8 decimals
+    const pythPrice = (price: number) => price * 100;
+
+    before(async () => {
+      bank = await startBank();
+    });
+
+    const configure = () => ({
+      admin: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      priceFeed: bank.priceFeed,
+    });
+    const beginShadow = () =>
+      bank.program.methods
+        .beginOracleShadow(
+          { pyth: {} },
+          pythAccount,
+          feedId,
+          new anchor.BN(MIN_ORACLE_SHADOW_SECS),
+          new anchor.BN(100)
+        )
+        .accounts(configure())
+        .rpc();
+    const recordShadowPrice = () =>
+      bank.program.methods
+        .recordShadowPrice()
+        .accounts({
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: pythAccount,
+          keeper: null,
+          keeperAccount: null,
+          instructions: null,
+        })
+        .rpc();
+    const scheduleSwitch = () =>
+      bank.program.methods.scheduleOracleSwitch().accounts(configure()).rpc();
+    const activate = () => bank.program.methods.activateOracle().accounts(configure()).rpc();
+    const cancel = () => bank.program.methods.cancelOracleMigration().accounts(configure()).rpc();
+
+    it("Records shadow samples against the admin price without switching to the oracle", async () => {
+      await expectError(bank, recordShadowPrice(), "NoOracleShadow");
+      await beginShadow();
+
+      // $0.0048 is 0.5% above the admin price, within the 1% alert threshold
+      await warp(bank, 60);
+      await setPythPrice(bank, pythAccount, feedId, pythPrice(4_800));
+      await recordShadowPrice();
+      let feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(feed.shadowSamples, 1);
+      assert.equal(feed.shadowMaxDivergenceBps.toNumber(), 50);
+      assert.isTrue(feed.useAdminPrice);
+      assert.ok(feed.oracleAccount.equals(pythAccount));
+
+      // A 3% gap is recorded too, and blocks the switch once the period ends
+      await warp(bank, 60);
+      await setPythPrice(bank, pythAccount, feedId, pythPrice(4_920));
+      await recordShadowPrice();
+      feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(feed.shadowSamples, 2);
+      assert.equal(feed.shadowMaxDivergenceBps.toNumber(), 301);
+      await warp(bank, MIN_ORACLE_SHADOW_SECS);
+      await expectError(bank, scheduleSwitch(), "OracleShadowDiverged");
+
+      // Cancelling clears the run and leaves the admin price in charge
+      await cancel();
+      feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(feed.shadowStartedAt.toNumber(), 0);
+      assert.equal(feed.shadowSamples, 0);
+      assert.isTrue(feed.useAdminPrice);
+      await warp(bank, 1);
+      await expectError(bank, scheduleSwitch(), "NoOracleShadow");
+    });
+
+    it("Holds a clean shadow run's switch behind the timelock, and lets it be cancelled", async () => {
+      await warp(bank, 1);
+      await beginShadow();
+      await expectError(bank, scheduleSwitch(), "OracleShadowIncomplete");
+
+      await warp(bank, 60);
+      await setPythPrice(bank, pythAccount, feedId, pythPrice(INITIAL_GOR_PRICE));
+      await recordShadowPrice();
+      await warp(bank, MIN_ORACLE_SHADOW_SECS);
+      await scheduleSwitch();
+      const { unixTimestamp } = await bank.context.banksClient.getClock();
+      let feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(
+        feed.oracleActivationAt.toString(),
+        (unixTimestamp + BigInt(ORACLE_SWITCH_TIMELOCK_SECS)).toString()
+      );
+      await expectError(bank, activate(), "OracleSwitchTimelocked");
+      await expectError(bank, scheduleSwitch(), "OracleSwitchAlreadyScheduled");
+
+      // A scheduled switch can still be called off
+      await cancel();
+      await warp(bank, ORACLE_SWITCH_TIMELOCK_SECS);
+      await expectError(bank, activate(), "NoOracleShadow");
+      feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.isTrue(feed.useAdminPrice);
+      assert.equal(feed.oracleActivationAt.toNumber(), 0);
+
+      // Run again, and let the timelock pass this time
+      await beginShadow();
+      await setPythPrice(bank, pythAccount, feedId, pythPrice(INITIAL_GOR_PRICE));
+      await recordShadowPrice();
+      await warp(bank, MIN_ORACLE_SHADOW_SECS);
+      await scheduleSwitch();
+      await warp(bank, ORACLE_SWITCH_TIMELOCK_SECS);
+      await activate();
+      feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.isFalse(feed.useAdminPrice);
+      assert.deepEqual(feed.oracleKind, { pyth: {} });
+      assert.equal(feed.shadowStartedAt.toNumber(), 0);
+      await expectError(bank, beginShadow(), "OracleAlreadyActive");
+    });
+  });
+
+  describe("Epoch outflow caps", () => {
+    let bank: Bank;
+    let liquidator: Keypair;
+    let idle: TestVault;
+    let borrowers: TestVault[];
+
+    before(async () => {
+      bank = await startBank();
+
+      // Three vaults at 159% and a liquidator at 212%; a debt-free vault holds GOR's
+      // aggregate ratio up once the price falls
+      borrowers = [];
+      for (let i = 0; i < 3; i++) {
+        const borrower = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL);
+        await mintGusd(bank, borrower, 150_000_000);
+        borrowers.push(borrower);
+      }
+      liquidator = await fundedKeypair(bank, 210_000);
+      const liquidatorVault = await createGorVault(bank, 200_000 * LAMPORTS_PER_SOL, liquidator);
+      await mintGusd(bank, liquidatorVault, 450_000_000);
+      idle = await createGorVault(bank, 200_000 * LAMPORTS_PER_SOL);
+
+      // At $0.00345 the borrowers sit at 115%, under the 120% threshold; GOR as a whole
+      // is at 211%
+      await warp(bank, 60);
+      await setPrice(bank, 3_450);
+      await warp(bank, 60 * 60);
+    });
+
+    const setEpochCaps = async (liquidationCap: anchor.BN, redemptionCap: anchor.BN) =>
+      bank.program.methods
+        .setEpochCaps(liquidationCap, redemptionCap)
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          ...(await parameterLogPages(bank)),
+        })
+        .rpc();
+    const liquidatedThisEpoch = async () =>
+      (await bank.program.account.protocolState.fetch(bank.protocolState))
+        .epochLiquidatedCollateral;
+
+    it("Caps the GOR seized by liquidations each epoch, and resets the count each epoch", async () => {
+      // Uncapped by default; the counter still tracks what left
+      await liquidateVault(bank, liquidator, borrowers[0]);
+      const seized = await liquidatedThisEpoch();
+      assert.isTrue(seized.gtn(0));
+
+      // Room for one and a half of these liquidations per epoch
+      const cap = seized.muln(3).divn(2);
+      await setEpochCaps(cap, new anchor.BN(0));
+      let protocol = await bank.program.account.protocolState.fetch(bank.protocolState);
+      assert.equal(protocol.epochLiquidationCap.toString(), cap.toString());
+      assert.equal(protocol.epochRedemptionCap.toNumber(), 0);
+
+      await expectError(
+        bank,
+        liquidateVault(bank, liquidator, borrowers[1]),
+        "EpochOutflowCapExceeded"
+      );
+
+      await nextEpoch(bank);
+      await liquidateVault(bank, liquidator, borrowers[1]);
+      protocol = await bank.program.account.protocolState.fetch(bank.protocolState);
+      assert.equal(protocol.epochLiquidatedCollateral.toString(), seized.toString());
+    });
+
+    it("Lifts the liquidation cap while GOR as a whole is below its minimum ratio", async () => {
+      await warp(bank, 1);
+      await expectError(
+        bank,
+        liquidateVault(bank, liquidator, borrowers[2]),
+        "EpochOutflowCapExceeded"
+      );
+
+      // Without the idle vault's GOR the system falls to 146%, under GOR's 150%
+      const { collateralAmount } = await bank.program.account.vault.fetch(idle.vault);
+      await bank.program.methods
+        .withdrawCollateral(collateralAmount)
+        .accounts({
+          owner: idle.owner.publicKey,
+          delegate: null,
+          positionTokenAccount: idle.positionTokenAccount,
+          vault: idle.vault,
+          vaultCollateral: idle.vaultCollateral,
+          protocolState: bank.protocolState,
+          priceFeed: bank.priceFeed,
+          collateralConfig: bank.collateralConfig,
+          priceUpdate: null,
+          marginAccount: null,
+          feeExemption: null,
+          collateralMint: null,
+          vaultTokenAccount: null,
+          ownerTokenAccount: null,
+          treasuryCollateralAccount: null,
+          collateralTokenProgram: null,
+          systemProgram: SystemProgram.programId,
+          feeTierRegistry: null,
+          governanceStake: null,
+        })
+        .signers([idle.owner])
+        .rpc();
+
+      await liquidateVault(bank, liquidator, borrowers[2]);
+      const { epochLiquidationCap } = await bank.program.account.protocolState.fetch(
+        bank.protocolState
+      );
+      assert.isTrue((await liquidatedThisEpoch()).gt(epochLiquidationCap));
+      const vault = await bank.program.account.vault.fetch(borrowers[2].vault);
+      assert.equal(vault.debtAmount.toNumber(), 0);
+    });
+  });
+
+  describe("Launch ramp", () => {
+    let bank: Bank;
+    let borrower: TestVault;
+
+    const LAUNCH_RAMP_INTERVAL_SECS = 7 * 24 * 60 * 60;
+    const gusd = (amount: number) => new anchor.BN(amount * 1_000_000);
+
+    before(async () => {
+      bank = await startBank();
+      // $955.20 of GOR, good for $636.80 at 150%
+      borrower = await createGorVault(bank, 200_000 * LAMPORTS_PER_SOL);
+    });
+
+    const setLaunchRamp = async (ceiling: anchor.BN, stepBps: number, target: anchor.BN) =>
+      bank.program.methods
+        .setLaunchRamp(ceiling, new anchor.BN(stepBps), target)
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          ...(await parameterLogPages(bank)),
+        })
+        .rpc();
+    const haltLaunchRamp = () =>
+      bank.program.methods
+        .haltLaunchRamp()
+        .accounts({ guardian: bank.admin.publicKey, protocolState: bank.protocolState })
+        .rpc();
+    const protocolState = () => bank.program.account.protocolState.fetch(bank.protocolState);
+
+    it("Raises the debt ceiling each week until it reaches the target", async () => {
+      await expectError(bank, setLaunchRamp(gusd(200), 5_000, gusd(100)), "InvalidParameter");
+      await setLaunchRamp(gusd(100), 5_000, gusd(200));
+      let protocol = await protocolState();
+      assert.deepEqual(protocol.lifecycle, { guardedLaunch: {} });
+      assert.equal(protocol.debtCeiling.toString(), gusd(100).toString());
+
+      await mintGusd(bank, borrower, 60_000_000);
+      await expectError(bank, mintGusd(bank, borrower, 50_000_000), "DebtCeilingExceeded");
+
+      // A week in, minting applies the first 50% step
+      await warp(bank, LAUNCH_RAMP_INTERVAL_SECS);
+      await mintGusd(bank, borrower, 50_000_000);
+      protocol = await protocolState();
+      assert.equal(protocol.debtCeiling.toString(), gusd(150).toString());
+      assert.equal(protocol.totalDebt.toString(), gusd(110).toString());
+
+      // The next step stops at the target, which ends the ramp and the guarded launch
+      await warp(bank, LAUNCH_RAMP_INTERVAL_SECS);
+      await mintGusd(bank, borrower, 60_000_000);
+      protocol = await protocolState();
+      assert.equal(protocol.debtCeiling.toString(), gusd(200).toString());
+      assert.equal(protocol.launchRampStepBps.toNumber(), 0);
+      assert.equal(protocol.launchRampTarget.toNumber(), 0);
+      assert.deepEqual(protocol.lifecycle, { active: {} });
+      await expectError(bank, haltLaunchRamp(), "LaunchRampInactive");
+    });
+
+    it("Freezes the ceiling where the guardian halts the ramp, counting steps already due", async () => {
+      await setLaunchRamp(gusd(200), 5_000, gusd(400));
+      await warp(bank, LAUNCH_RAMP_INTERVAL_SECS);
+      await haltLaunchRamp();
+      let protocol = await protocolState();
+      assert.equal(protocol.debtCeiling.toString(), gusd(300).toString());
+      assert.equal(protocol.launchRampStepBps.toNumber(), 0);
+      assert.deepEqual(protocol.lifecycle, { active: {} });
+
+      // No more steps arrive after the halt
+      await warp(bank, LAUNCH_RAMP_INTERVAL_SECS);
+      await mintGusd(bank, borrower, 100_000_000);
+      await expectError(bank, mintGusd(bank, borrower, 50_000_000), "DebtCeilingExceeded");
+      protocol = await protocolState();
+      assert.equal(protocol.debtCeiling.toString(), gusd(300).toString());
+    });
+  });
+
+  describe("Liquidation insurance", () => {
+    let bank: Bank;
+    let liquidator: Keypair;
+    let insured: TestVault;
+    let uninsured: TestVault;
+
+    const INSURANCE_PERIOD_SECS = 30 * 24 * 60 * 60;
+    const insuranceFund = () => bank.pda(Buffer.from("insurance_fund"));
+
+    before(async () => {
+      bank = await startBank();
+      // Two vaults at 159%, and a liquidator with GUSD to repay both
+      insured = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL);
+      uninsured = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL);
+      for (const testVault of [insured, uninsured]) {
+        await mintGusd(bank, testVault, 150_000_000);
+      }
+      liquidator = await fundedKeypair(bank, 130_000);
+      await mintGusd(
+        bank,
+        await createGorVault(bank, 120_000 * LAMPORTS_PER_SOL, liquidator),
+        300_000_000
+      );
+    });
+
+    const buyInsurance = (testVault: TestVault, periods: number) => {
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      return bank.program.methods
+        .buyLiquidationInsurance(periods)
+        .accounts({
+          owner: testVault.owner.publicKey,
+          delegate: null,
+          positionTokenAccount: testVault.positionTokenAccount,
+          vault: testVault.vault,
+          protocolState: bank.protocolState,
+          revenueStats: bank.pda(Buffer.from("revenue_stats")),
+          gusdMint,
+          userGusdAccount: getAssociatedTokenAddressSync(gusdMint, testVault.owner.publicKey),
+          insuranceFund: insuranceFund(),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([testVault.owner])
+        .rpc();
+    };
+
+    it("Sells 30-day periods of cover for a share of the vault's debt", async () => {
+      await expectError(bank, buyInsurance(insured, 2), "InsuranceUnavailable");
+
+      // 1% of debt per period, for half the liquidation penalty
+      await setRiskParameter(bank, { insurancePremiumBps: {} }, 100);
+      await setRiskParameter(bank, { insuranceDiscountBps: {} }, 5_000);
+      await expectError(bank, buyInsurance(insured, 0), "InvalidAmount");
+
+      await warp(bank, 1);
+      await buyInsurance(insured, 2);
+      const { unixTimestamp } = await bank.context.banksClient.getClock();
+      let vault = await bank.program.account.vault.fetch(insured.vault);
+      assert.equal(
+        vault.insuredUntil.toNumber(),
+        Number(unixTimestamp) + 2 * INSURANCE_PERIOD_SECS
+      );
+      assert.equal(await tokenBalance(bank, insuranceFund()), BigInt(3_000_000));
+
+      // More periods extend the cover from where it ends
+      await warp(bank, 24 * 60 * 60);
+      await buyInsurance(insured, 1);
+      vault = await bank.program.account.vault.fetch(insured.vault);
+      assert.equal(
+        vault.insuredUntil.toNumber(),
+        Number(unixTimestamp) + 3 * INSURANCE_PERIOD_SECS
+      );
+      assert.equal(await tokenBalance(bank, insuranceFund()), BigInt(4_500_000));
+      const revenue = await bank.program.account.revenueStats.fetch(
+        bank.pda(Buffer.from("revenue_stats"))
+      );
+      assert.equal(revenue.insurancePremiums.toNumber(), 4_500_000);
+    });
+
+    it("Liquidates a covered vault at the discounted penalty", async () => {
+      // At $0.00345 both vaults sit near 115%, under the 120% threshold
+      await warp(bank, 60);
+      await setPrice(bank, 3_450);
+      await warp(bank, 60 * 60);
+
+      const seized = [];
+      for (const testVault of [insured, uninsured]) {
+        await liquidateVault(bank, liquidator, testVault);
+        const vault = await bank.program.account.vault.fetch(testVault.vault);
+        assert.equal(vault.debtAmount.toNumber(), 0);
+        seized.push(new anchor.BN(50_000 * LAMPORTS_PER_SOL).sub(vault.collateralAmount));
+      }
+
+      // Both repaid the same debt: the covered vault lost 105% of it, the other 110%
+      const [insuredSeized, uninsuredSeized] = seized;
+      assert.isTrue(insuredSeized.lt(uninsuredSeized));
+      assert.isAtMost(
+        insuredSeized.muln(110).sub(uninsuredSeized.muln(105)).abs().toNumber(),
+        110
+      );
+    });
+  });
+
+  describe("Stability fee locks", () => {
+    let bank: Bank;
+    let treasuryGusd: PublicKey;
+    let locked: TestVault;
+    let floating: TestVault;
+
+    const SECONDS_PER_YEAR = 365 * 24 * 60 * 60;
+    const DAY_SECS = 24 * 60 * 60;
+
+    before(async () => {
+      bank = await startBank();
+      treasuryGusd = await initTreasury(bank);
+      await setRiskParameter(bank, { stabilityFeeBps: {} }, 500);
+      locked = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL);
+      floating = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL);
+      for (const testVault of [locked, floating]) {
+        await mintGusd(bank, testVault, 100_000_000);
+      }
+    });
+
+    const lockStabilityFee = (testVault: TestVault, days: number) => {
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      return bank.program.methods
+        .lockStabilityFee(days)
+        .accounts({
+          owner: testVault.owner.publicKey,
+          delegate: null,
+          positionTokenAccount: testVault.positionTokenAccount,
+          vault: testVault.vault,
+          protocolState: bank.protocolState,
+          revenueStats: bank.pda(Buffer.from("revenue_stats")),
+          priceFeed: bank.priceFeed,
+          gusdMint,
+          userGusdAccount: getAssociatedTokenAddressSync(gusdMint, testVault.owner.publicKey),
+          treasuryGusdAccount: treasuryGusd,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          feeTierRegistry: null,
+          governanceStake: null,
+        })
+        .signers([testVault.owner])
+        .rpc();
+    };
+
+    it("Locks the current rate for an upfront premium paid to the treasury", async () => {
+      await expectError(bank, lockStabilityFee(locked, 365), "RateLockUnavailable");
+
+      // 1% of the debt per year locked
+      await setRiskParameter(bank, { rateLockPremiumBps: {} }, 100);
+      await expectError(bank, lockStabilityFee(locked, 366), "InvalidAmount");
+      await warp(bank, 1);
+      await lockStabilityFee(locked, 365);
+
+      const { unixTimestamp } = await bank.context.banksClient.getClock();
+      const vault = await bank.program.account.vault.fetch(locked.vault);
+      assert.equal(vault.lockedRateBps.toNumber(), 500);
+      assert.equal(vault.rateLockedUntil.toNumber(), Number(unixTimestamp) + 365 * DAY_SECS);
+      assert.equal(vault.rateLockPrincipal.toString(), vault.debtAmount.toString());
+      assert.equal(await tokenBalance(bank, treasuryGusd), BigInt(1_000_000));
+
+      await expectError(bank, lockStabilityFee(locked, 30), "RateLockActive");
+    });
+
+    it("Accrues the locked rate through a fee rise while the floating vault pays the new one", async () => {
+      const before = await Promise.all(
+        [locked, floating].map((testVault) => bank.program.account.vault.fetch(testVault.vault))
+      );
+      await setRiskParameter(bank, { stabilityFeeBps: {} }, 2_000);
+      await warp(bank, SECONDS_PER_YEAR / 2);
+      for (const testVault of [locked, floating]) {
+        await accrueInterest(bank, testVault);
+      }
+
+      const [lockedAfter, floatingAfter] = await Promise.all(
+        [locked, floating].map((testVault) => bank.program.account.vault.fetch(testVault.vault))
+      );
+      // Half a year at the locked 5% on the locked $100, against 20% floating
+      assert.equal(lockedAfter.debtAmount.sub(before[0].debtAmount).toNumber(), 2_500_000);
+      assert.approximately(
+        floatingAfter.debtAmount.sub(before[1].debtAmount).toNumber(),
+        10_000_000,
+        1
+      );
+    });
+
+    it("Lets the vault lock again, at the new rate, once the lock ends", async () => {
+      await warp(bank, SECONDS_PER_YEAR / 2);
+      await lockStabilityFee(locked, 30);
+      const vault = await bank.program.account.vault.fetch(locked.vault);
+      assert.equal(vault.lockedRateBps.toNumber(), 2_000);
+      assert.equal(vault.rateLockPrincipal.toString(), vault.debtAmount.toString());
+    });
+  });
+
+  describe("Bad debt", () => {
+    let bank: Bank;
+    let liquidator: Keypair;
+    let liquidatorVault: TestVault;
+    let underwater: TestVault;
+
+    before(async () => {
+      bank = await startBank();
+      // One price for every operation keeps the liquidation math exact
+      await setRiskParameter(bank, { priceSpreadBps: {} }, 0);
+      underwater = await createGorVault(bank, 22_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, underwater, 50_000_000);
+      liquidator = await fundedKeypair(bank, 110_000);
+      liquidatorVault = await createGorVault(bank, 100_000 * LAMPORTS_PER_SOL, liquidator);
+      await mintGusd(bank, liquidatorVault, 100_000_000);
+
+      // At $0.002 the vault's $44 of GOR backs $50 of debt: with the 10% bonus it covers
+      // $40 of it, and $10 is left with nothing behind it
+      await warp(bank, 60);
+      await setPrice(bank, 2_000);
+      await warp(bank, 60 * 60);
+      await liquidateVault(bank, liquidator, underwater);
+    });
+
+    const recordBadDebt = (testVault: TestVault) =>
+      bank.program.methods
+        .recordBadDebt()
+        .accounts({
+          keeper: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          vaultOwner: testVault.owner.publicKey,
+          vault: testVault.vault,
+          collateralConfig: bank.collateralConfig,
+        })
+        .rpc();
+    const coverBadDebt = (amount: number) =>
+      bank.program.methods
+        .coverBadDebt(new anchor.BN(amount))
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          supplyStats: bank.pda(Buffer.from("supply_stats")),
+          gusdMint: bank.pda(Buffer.from("gusd_mint")),
+          insuranceFund: bank.pda(Buffer.from("insurance_fund")),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    it("Writes off the debt of a vault liquidated down to no collateral", async () => {
+      const vault = await bank.program.account.vault.fetch(underwater.vault);
+      assert.equal(vault.collateralAmount.toNumber(), 0);
+      assert.equal(vault.debtAmount.toNumber(), 10_000_000);
+      await expectError(bank, recordBadDebt(liquidatorVault), "NoBadDebt");
+
+      const { totalDebt } = await bank.program.account.protocolState.fetch(bank.protocolState);
+      await recordBadDebt(underwater);
+      const protocol = await bank.program.account.protocolState.fetch(bank.protocolState);
+      assert.equal(protocol.badDebt.toNumber(), 10_000_000);
+      // The GUSD is still out there, so it still counts as debt
+      assert.equal(protocol.totalDebt.toString(), totalDebt.toString());
+      const after = await bank.program.account.vault.fetch(underwater.vault);
+      assert.equal(after.debtAmount.toNumber(), 0);
+      await warp(bank, 1);
+      await expectError(bank, recordBadDebt(underwater), "NoBadDebt");
+    });
+
+    it("Burns insurance fund GUSD against recorded bad debt", async () => {
+      // A 5% premium on the liquidator's $100 opens the fund with $5
+      await setRiskParameter(bank, { insurancePremiumBps: {} }, 500);
+      await setRiskParameter(bank, { insuranceDiscountBps: {} }, 1);
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      const insuranceFund = bank.pda(Buffer.from("insurance_fund"));
+      await bank.program.methods
+        .buyLiquidationInsurance(1)
+        .accounts({
+          owner: liquidator.publicKey,
+          delegate: null,
+          positionTokenAccount: liquidatorVault.positionTokenAccount,
+          vault: liquidatorVault.vault,
+          protocolState: bank.protocolState,
+          revenueStats: bank.pda(Buffer.from("revenue_stats")),
+          gusdMint,
+          userGusdAccount: getAssociatedTokenAddressSync(gusdMint, liquidator.publicKey),
+          insuranceFund,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([liquidator])
+        .rpc();
+
+      await expectError(bank, coverBadDebt(0), "InvalidAmount");
+      await expectError(bank, coverBadDebt(11_000_000), "NoBadDebt");
+      await expectError(bank, coverBadDebt(10_000_000), "InsufficientInsuranceFund");
+
+      const { totalDebt } = await bank.program.account.protocolState.fetch(bank.protocolState);
+      await coverBadDebt(4_000_000);
+      const protocol = await bank.program.account.protocolState.fetch(bank.protocolState);
+      assert.equal(protocol.badDebt.toNumber(), 6_000_000);
+      assert.equal(protocol.totalDebt.toString(), totalDebt.subn(4_000_000).toString());
+      assert.equal(await tokenBalance(bank, insuranceFund), BigInt(1_000_000));
+      const supply = await bank.program.account.supplyStats.fetch(
+        bank.pda(Buffer.from("supply_stats"))
+      );
+      assert.equal(supply.burnedBadDebt.toNumber(), 4_000_000);
+    });
+  });
+
+  describe("Peg tracking", () => {
+    let bank: Bank;
+    let gusdFeed: PublicKey;
+    const pegState = () => bank.pda(Buffer.from("peg_state"));
+
+    before(async () => {
+      bank = await startBank();
+      // An admin-priced feed for GUSD itself, at the peg
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      gusdFeed = bank.pda(Buffer.from("price_feed"), gusdMint.toBuffer());
+      await bank.program.methods
+        .initPriceFeed(gusdMint, 6, new anchor.BN(1_000_000))
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          priceFeed: gusdFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bank.program.methods
+        .initPegState()
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          pegState: pegState(),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    const updatePegPrice = () =>
+      bank.program.methods
+        .updatePegPrice()
+        .accounts({
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          pegState: pegState(),
+          priceFeed: gusdFeed,
+          priceUpdate: null,
+        })
+        .rpc();
+    const pegStatus = () =>
+      bank.program.methods
+        .getPegStatus()
+        .accounts({ protocolState: bank.protocolState, pegState: pegState() })
+        .view();
+    // Move the GUSD market price to `price`, letting the ramp finish
+    const setGusdPrice = async (price: number) => {
+      await warp(bank, 60);
+      await setPrice(bank, price, gusdFeed);
+      await warp(bank, 10 * 60);
+    };
+
+    it("Flags a de-peg past the threshold and keeps its start while it lasts", async () => {
+      await updatePegPrice();
+      let status = await pegStatus();
+      assert.equal(status.price.toNumber(), 1_000_000);
+      assert.isFalse(status.isDepegged);
+
+      // $0.985 is 150 bps off, inside the default 200
+      await setGusdPrice(985_000);
+      await updatePegPrice();
+      status = await pegStatus();
+      assert.equal(status.deviationBps.toNumber(), 150);
+      assert.isFalse(status.isDepegged);
+
+      await setGusdPrice(970_000);
+      await updatePegPrice();
+      const { unixTimestamp } = await bank.context.banksClient.getClock();
+      status = await pegStatus();
+      assert.equal(status.deviationBps.toNumber(), 300);
+      assert.isTrue(status.isDepegged);
+      assert.equal(status.depeggedSince.toNumber(), Number(unixTimestamp));
+
+      await warp(bank, 60 * 60);
+      await updatePegPrice();
+      status = await pegStatus();
+      assert.equal(status.depeggedSince.toNumber(), Number(unixTimestamp));
+      assert.isAbove(status.updatedAt.toNumber(), Number(unixTimestamp));
+    });
+
+    it("Clears the de-peg once the price is back within the threshold", async () => {
+      await setGusdPrice(1_010_000);
+      await updatePegPrice();
+      const status = await pegStatus();
+      assert.equal(status.price.toNumber(), 1_010_000);
+      assert.equal(status.deviationBps.toNumber(), 100);
+      assert.isFalse(status.isDepegged);
+      assert.equal(status.depeggedSince.toNumber(), 0);
+    });
+  });
+
+  describe("Fallback oracles", () => {
+    let bank: Bank;
+    // GOR's primary Pyth account and two fallbacks, each on its own feed id
+    const [primary, firstFallback, secondFallback] = [1, 2, 3].map((seed) => ({
+      account: Keypair.generate().publicKey,
+      feedId: Array.from({ length: 32 }, () => seed),
+    }));
+
+    // Pyth prices older than this are stale
+    const MAX_PRICE_AGE_SECS = 60;
+
+    before(async () => {
+      bank = await startBank();
+      await setPythOracle(bank, primary.account, primary.feedId);
+    });
+
+    const pyth = (source: typeof primary) => ({
+      kind: { pyth: {} },
+      account: source.account,
+      feedId: source.feedId,
+    });
+    const setFallbackOracles = (fallbacks: ReturnType<typeof pyth>[]) =>
+      bank.program.methods
+        .setFallbackOracles(fallbacks)
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          priceFeed: bank.priceFeed,
+        })
+        .rpc();
+    const lastGoodPrice = async () =>
+      (await bank.program.account.priceFeed.fetch(bank.priceFeed)).lastGoodPrice.toNumber();
+
+    it("Accepts up to two distinct Pyth or Switchboard fallbacks", async () => {
+      await expectError(
+        bank,
+        setFallbackOracles([{ ...pyth(firstFallback), kind: { chainlinkStreams: {} } }]),
+        "InvalidFallbackOracle"
+      );
+      await expectError(bank, setFallbackOracles([pyth(primary)]), "InvalidFallbackOracle");
+      await expectError(
+        bank,
+        setFallbackOracles([pyth(firstFallback), pyth(firstFallback)]),
+        "InvalidFallbackOracle"
+      );
+      await expectError(
+        bank,
+        setFallbackOracles([pyth(firstFallback), pyth(secondFallback), pyth(primary)]),
+        "TooManyFallbackOracles"
+      );
+
+      await setFallbackOracles([pyth(firstFallback), pyth(secondFallback)]);
+      const feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.ok(feed.fallbackOracles[0].account.equals(firstFallback.account));
+      assert.ok(feed.fallbackOracles[1].account.equals(secondFallback.account));
+    });
+
+    it("Prices from each fallback in order once the sources before it are stale", async () => {
+      await warp(bank, 1);
+      await setPythPrice(bank, primary.account, primary.feedId, 500_000);
+      await recordPriceSample(bank, primary.account);
+      assert.equal(await lastGoodPrice(), 5_000);
+
+      // The primary goes stale; only a passed fallback can stand in for it
+      await warp(bank, MAX_PRICE_AGE_SECS + 1);
+      await setPythPrice(bank, firstFallback.account, firstFallback.feedId, 490_000);
+      await expectError(bank, recordPriceSample(bank, primary.account), "StaleOraclePrice");
+      await recordPriceSample(bank, firstFallback.account);
+      assert.equal(await lastGoodPrice(), 4_900);
+
+      await warp(bank, MAX_PRICE_AGE_SECS + 1);
+      await setPythPrice(bank, secondFallback.account, secondFallback.feedId, 480_000);
+      // Without a fresh source the primary's error is reported: nothing is cached for it
+      await expectError(
+        bank,
+        recordPriceSample(bank, firstFallback.account),
+        "OracleNotConfigured"
+      );
+      await recordPriceSample(bank, secondFallback.account);
+      assert.equal(await lastGoodPrice(), 4_800);
+
+      // A fresh primary takes over again
+      await warp(bank, 1);
+      await setPythPrice(bank, primary.account, primary.feedId, 495_000);
+      await recordPriceSample(bank, primary.account);
+      assert.equal(await lastGoodPrice(), 4_950);
+    });
+  });
+
+  describe("Oracle price cranking", () => {
+    let bank: Bank;
+    let borrower: TestVault;
+    const pythAccount = Keypair.generate().publicKey;
+    const feedId = Array.from({ length: 32 }, (_, i) => 32 - i);
+
+    // Pyth prices older than this are stale
+    const MAX_PRICE_AGE_SECS = 60;
+
+    before(async () => {
+      bank = await startBank();
+      borrower = await createGorVault(bank, 50_000 * LAMPORTS_PER_SOL);
+      await setPythOracle(bank, pythAccount, feedId);
+    });
+
+    const crankPrice = () =>
+      bank.program.methods
+        .crankPrice()
+        .accounts({
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: pythAccount,
+          keeper: null,
+          keeperAccount: null,
+          instructions: null,
+        })
+        .rpc();
+
+    it("Caches a fresh, confident Pyth price in the feed", async () => {
+      // $0.005 ± 3% is wider than the 2% confidence limit
+      await setPythPrice(bank, pythAccount, feedId, 500_000, 15_000);
+      await expectError(bank, crankPrice(), "PriceConfidenceTooWide");
+
+      await warp(bank, 1);
+      await setPythPrice(bank, pythAccount, feedId, 500_000);
+      await crankPrice();
+      const { unixTimestamp } = await bank.context.banksClient.getClock();
+      const feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(feed.cachedPrice.toNumber(), 5_000);
+      assert.equal(feed.cachedConfBps.toNumber(), 0);
+      assert.equal(feed.cachedObservedAt.toNumber(), Number(unixTimestamp));
+
+      // The same publication can't be cached twice
+      await warp(bank, 1);
+      await expectError(bank, crankPrice(), "StaleOraclePrice");
+    });
+
+    it("Values vaults against the cached price until it goes stale", async () => {
+      // $250 of GOR at the cached price, without passing the Pyth account
+      await mintGusd(bank, borrower, 100_000_000);
+
+      await warp(bank, MAX_PRICE_AGE_SECS + 1);
+      await expectError(bank, mintGusd(bank, borrower, 10_000_000), "StaleOraclePrice");
+      await expectError(bank, crankPrice(), "StaleOraclePrice");
+
+      await warp(bank, 1);
+      await setPythPrice(bank, pythAccount, feedId, 510_000);
+      await crankPrice();
+      const feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(feed.cachedPrice.toNumber(), 5_100);
+      await mintGusd(bank, borrower, 10_000_000);
+      const vault = await bank.program.account.vault.fetch(borrower.vault);
+      assert.equal(vault.debtAmount.toNumber(), 110_000_000);
+    });
+  });
+
+  describe("Signed price batches", () => {
+    let bank: Bank;
+    const feeders = [Keypair.generate(), Keypair.generate()];
+    const feederRegistry = () => bank.pda(Buffer.from("feeders"));
+
+    before(async () => {
+      bank = await startBank();
+      await bank.program.methods
+        .setPriceFeeders(feeders.map((feeder) => feeder.publicKey), 2)
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          feederRegistry: feederRegistry(),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      // Observations must come after the accumulator's checkpoint from initialization
+      await warp(bank, 2 * 60);
+    });
+
+    type Observation = { price: number; timestamp: number };
+    // Submit `observations` of GOR's price with a signature from each of `signers`
+    const submitBatch = (observations: Observation[], signers: Keypair[]) => {
+      const points = observations.map(({ price, timestamp }) => ({
+        price: new anchor.BN(price),
+        timestamp: new anchor.BN(timestamp),
+      }));
+      const message = Buffer.concat([
+        Buffer.from("GUSD_PRICE_BATCH_V2"),
+        bank.program.programId.toBuffer(),
+        PublicKey.default.toBuffer(),
+        ...points.flatMap(({ price, timestamp }) => [
+          price.toArrayLike(Buffer, "le", 8),
+          timestamp.toArrayLike(Buffer, "le", 8),
+        ]),
+      ]);
+      return bank.program.methods
+        .updatePriceBatchSigned(points)
+        .accounts({
+          protocolState: bank.protocolState,
+          priceAccumulator: bank.pda(Buffer.from("price_accumulator")),
+          priceFeed: bank.priceFeed,
+          feederRegistry: feederRegistry(),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .preInstructions(
+          signers.map((signer) =>
+            Ed25519Program.createInstructionWithPrivateKey({
+              privateKey: signer.secretKey,
+              message,
+            })
+          )
+        )
+        .rpc();
+    };
+    const now = async () => Number((await bank.context.banksClient.getClock()).unixTimestamp);
+
+    it("Rejects batches short of the feeder threshold, out of order, or stale", async () => {
+      const t = await now();
+      const observations = [
+        { price: 4_800, timestamp: t - 30 },
+        { price: 4_850, timestamp: t - 20 },
+      ];
+      await expectError(bank, submitBatch(observations, [feeders[0]]), "InvalidPriceSignature");
+      await expectError(
+        bank,
+        submitBatch([...observations].reverse(), feeders),
+        "InvalidParameter"
+      );
+      await expectError(
+        bank,
+        submitBatch([{ price: 4_800, timestamp: t - 61 }], feeders),
+        "StaleOraclePrice"
+      );
+    });
+
+    it("Applies each observation at its own timestamp, checkpointing the TWAP", async () => {
+      await warp(bank, 1);
+      const t = await now();
+      const observations = [
+        { price: 4_800, timestamp: t - 30 },
+        { price: 4_850, timestamp: t - 20 },
+        { price: 4_900, timestamp: t - 10 },
+      ];
+      await submitBatch(observations, feeders);
+
+      const feed = await bank.program.account.priceFeed.fetch(bank.priceFeed);
+      assert.equal(feed.adminPrice.toNumber(), 4_900);
+      assert.equal(feed.adminPriceTs.toNumber(), t - 10);
+      const registry = await bank.program.account.feederRegistry.fetch(feederRegistry());
+      assert.equal(registry.lastSignedTs.toNumber(), t - 10);
+
+      // Each observation checkpoints the price in effect as the ramp, at 1 per second,
+      // moves toward the one before
+      const history = await bank.program.methods
+        .getPriceHistory()
+        .accounts({ priceAccumulator: bank.pda(Buffer.from("price_accumulator")) })
+        .view();
+      assert.deepEqual(
+        history.slice(-3).map(({ timestamp, price }) => [timestamp.toNumber(), price.toNumber()]),
+        [
+          [t - 30, 4_776],
+          [t - 20, 4_786],
+          [t - 10, 4_796],
+        ]
+      );
+
+      // The same signatures can't be replayed
+      await warp(bank, 1);
+      await expectError(bank, submitBatch(observations, feeders), "InvalidPriceSignature");
+    });
+  });
+
+  describe("Circuit breaker", () => {
+    let bank: Bank;
+    const pythAccount = Keypair.generate().publicKey;
+    const feedId = Array.from({ length: 32 }, (_, i) => i * 2);
+
+    const CIRCUIT_BREAKER_WINDOW_SECS = 5 * 60;
+
+    before(async () => {
+      bank = await startBank();
+      await setPythOracle(bank, pythAccount, feedId);
+      await warp(bank, 1);
+      await setPythPrice(bank, pythAccount, feedId, 500_000);
+      await recordPriceSample(bank, pythAccount);
+    });
+
+    const sample = async (price: number) => {
+      await setPythPrice(bank, pythAccount, feedId, price);
+      await recordPriceSample(bank, pythAccount);
+    };
+    const feed = () => bank.program.account.priceFeed.fetch(bank.priceFeed);
+    const protocolState = () => bank.program.account.protocolState.fetch(bank.protocolState);
+
+    it("Accepts samples within the deviation limit of the last good price", async () => {
+      // 18% up, inside the default 20%
+      await warp(bank, 10);
+      await sample(590_000);
+      assert.equal((await feed()).lastGoodPrice.toNumber(), 5_900);
+      assert.deepEqual((await protocolState()).lifecycle, { active: {} });
+    });
+
+    it("Pauses the protocol on a larger jump and discards the sample", async () => {
+      // 22% up from $0.0059
+      await warp(bank, 10);
+      await sample(720_000);
+      const { unixTimestamp } = await bank.context.banksClient.getClock();
+      const protocol = await protocolState();
+      assert.deepEqual(protocol.lifecycle, { paused: {} });
+      assert.equal(protocol.pausedAt.toNumber(), Number(unixTimestamp));
+      assert.equal((await feed()).lastGoodPrice.toNumber(), 5_900);
+
+      await bank.program.methods
+        .unpauseProtocol()
+        .accounts({ admin: bank.admin.publicKey, protocolState: bank.protocolState })
+        .rpc();
+      assert.deepEqual((await protocolState()).lifecycle, { active: {} });
+    });
+
+    it("Accepts any move once the last good price is older than the window", async () => {
+      await warp(bank, CIRCUIT_BREAKER_WINDOW_SECS + 1);
+      await sample(720_000);
+      assert.equal((await feed()).lastGoodPrice.toNumber(), 7_200);
+      assert.deepEqual((await protocolState()).lifecycle, { active: {} });
+    });
+  });
+
+  describe("Governance notices", () => {
+    let bank: Bank;
+    let funded: TestVault;
+    let empty: TestVault;
+
+    const DAY_SECS = 24 * 60 * 60;
+    const GOVERNANCE_NOTICE_MIN_SECS = 7 * DAY_SECS;
+
+    before(async () => {
+      bank = await startBank();
+      funded = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+      empty = await createGorVault(bank, 0);
+    });
+
+    const now = async () => Number((await bank.context.banksClient.getClock()).unixTimestamp);
+    const admin = () => ({ admin: bank.admin.publicKey, protocolState: bank.protocolState });
+    const postNotice = (effectiveAt: number) =>
+      bank.program.methods
+        .postGovernanceNotice({ riskParameterChange: {} }, new anchor.BN(effectiveAt))
+        .accounts(admin())
+        .rpc();
+    const clearNotice = () =>
+      bank.program.methods.clearGovernanceNotice().accounts(admin()).rpc();
+    const vaultNotice = (testVault: TestVault) =>
+      bank.program.methods
+        .getVaultNotice()
+        .accounts({
+          vault: testVault.vault,
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+          collateralConfig: bank.collateralConfig,
+        })
+        .view();
+
+    it("Posts a notice at least a week out and counts each vault down to it", async () => {
+      let notice = await vaultNotice(funded);
+      assert.isNull(notice.action);
+      assert.isFalse(notice.vaultAffected);
+
+      const postedAt = await now();
+      await expectError(
+        bank,
+        postNotice(postedAt + GOVERNANCE_NOTICE_MIN_SECS - 1),
+        "NoticePeriodTooShort"
+      );
+      const effectiveAt = postedAt + 8 * DAY_SECS;
+      await postNotice(effectiveAt);
+      const { pendingNotice } = await bank.program.account.protocolState.fetch(
+        bank.protocolState
+      );
+      assert.equal(pendingNotice.postedAt.toNumber(), postedAt);
+
+      notice = await vaultNotice(funded);
+      assert.deepEqual(notice.action, { riskParameterChange: {} });
+      assert.equal(notice.effectiveAt.toNumber(), effectiveAt);
+      assert.equal(notice.secondsRemaining.toNumber(), 8 * DAY_SECS);
+      assert.isTrue(notice.vaultAffected);
+      // A vault holding nothing has nothing to act on
+      assert.isFalse((await vaultNotice(empty)).vaultAffected);
+
+      await warp(bank, 3 * DAY_SECS);
+      notice = await vaultNotice(funded);
+      assert.equal(notice.secondsRemaining.toNumber(), 5 * DAY_SECS);
+      await warp(bank, 6 * DAY_SECS);
+      notice = await vaultNotice(funded);
+      assert.equal(notice.secondsRemaining.toNumber(), 0);
+    });
+
+    it("Clears the pending notice", async () => {
+      await clearNotice();
+      const notice = await vaultNotice(funded);
+      assert.isNull(notice.action);
+      assert.equal(notice.effectiveAt.toNumber(), 0);
+      await warp(bank, 1);
+      await expectError(bank, clearNotice(), "NoPendingNotice");
+    });
+  });
+
+  describe("Parameter change previews", () => {
+    let bank: Bank;
+    let healthy: TestVault;
+    let stretched: TestVault;
+    let underwater: TestVault;
+    let debtFree: TestVault;
+
+    before(async () => {
+      bank = await startBank();
+      healthy = await createGorVault(bank, 2_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, healthy, 2_000_000);
+      stretched = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, stretched, 2_000_000);
+      underwater = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, underwater, 3_000_000);
+      debtFree = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+
+      // At $0.0035 the vaults stand at 350%, 175%, and 116% (already liquidatable)
+      await warp(bank, 60);
+      await setPrice(bank, 3_500);
+      await warp(bank, 60 * 60);
+    });
+
+    const preview = (minCollateralRatioBps: number, liquidationThresholdBps: number) =>
+      bank.program.methods
+        .previewParameterChange(
+          new anchor.BN(minCollateralRatioBps),
+          new anchor.BN(liquidationThresholdBps)
+        )
+        .accounts({
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+        })
+        .remainingAccounts(
+          [healthy, stretched, underwater, debtFree].map(({ vault }) => ({
+            pubkey: vault,
+            isSigner: false,
+            isWritable: false,
+          }))
+        )
+        .view();
+
+    it("Counts the vaults and debt a proposed min CR and threshold would catch", async () => {
+      const result = await preview(20_000, 18_000);
+      assert.equal(result.vaultsScanned, 4);
+      assert.equal(result.vaultsBelowMinCr, 2);
+      assert.equal(result.debtBelowMinCr.toNumber(), 5_000_000);
+      assert.equal(result.vaultsLiquidatable, 2);
+      assert.equal(result.debtLiquidatable.toNumber(), 5_000_000);
+      // The underwater vault is liquidatable under today's threshold already
+      assert.equal(result.newlyLiquidatableVaults, 1);
+      assert.equal(result.newlyLiquidatableDebt.toNumber(), 2_000_000);
+    });
+
+    it("Counts nothing new when the proposal matches today's parameters", async () => {
+      const result = await preview(15_000, 12_000);
+      assert.equal(result.vaultsBelowMinCr, 1);
+      assert.equal(result.vaultsLiquidatable, 1);
+      assert.equal(result.debtLiquidatable.toNumber(), 3_000_000);
+      assert.equal(result.newlyLiquidatableVaults, 0);
+    });
+
+    it("Rejects a threshold below 100% or above the min CR", async () => {
+      await expectError(bank, preview(15_000, 9_999), "InvalidParameter");
+      await expectError(bank, preview(15_000, 15_001), "InvalidParameter");
+    });
+  });
+
+  describe("Vault scan pages", () => {
+    let bank: Bank;
+    let underwater: TestVault;
+    // Every vault in ascending key order, as pages must list them
+    let sorted: PublicKey[];
+
+    before(async () => {
+      bank = await startBank();
+      underwater = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, underwater, 3_000_000);
+      const healthy = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+      await mintGusd(bank, healthy, 2_000_000);
+      const debtFree = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL);
+      sorted = [underwater, healthy, debtFree]
+        .map(({ vault }) => vault)
+        .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()));
+
+      // At $0.0035 the indebted vaults stand at 116% and 175%
+      await warp(bank, 60);
+      await setPrice(bank, 3_500);
+      await warp(bank, 60 * 60);
+    });
+
+    const scanCursor = () => bank.pda(Buffer.from("scan_cursor"));
+    const checkPage = (vaults: PublicKey[], restart: boolean) =>
+      bank.program.methods
+        .checkAndFlagPage(restart)
+        .accounts({
+          keeper: bank.admin.publicKey,
+          scanCursor: scanCursor(),
+          protocolState: bank.protocolState,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+          collateralConfig: bank.collateralConfig,
+        })
+        .remainingAccounts(
+          vaults.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        )
+        .rpc();
+    const flaggedSlots = async () =>
+      Promise.all(
+        sorted.map(async (vault) =>
+          (await bank.program.account.vault.fetch(vault)).flaggedSlot.toNumber()
+        )
+      );
+    const currentSlot = async () => Number((await bank.context.banksClient.getClock()).slot);
+
+    it("Flags the unhealthy vault across pages and advances the cursor", async () => {
+      const slot = await currentSlot();
+      await checkPage(sorted.slice(0, 1), true);
+      let cursor = await bank.program.account.scanCursor.fetch(scanCursor());
+      assert.equal(cursor.sweep.toNumber(), 1);
+      assert.isTrue(cursor.lastVault.equals(sorted[0]));
+
+      await checkPage(sorted.slice(1), false);
+      cursor = await bank.program.account.scanCursor.fetch(scanCursor());
+      assert.equal(cursor.sweep.toNumber(), 1);
+      assert.isTrue(cursor.lastVault.equals(sorted[2]));
+      assert.equal(cursor.lastSlot.toNumber(), slot);
+
+      const expected = sorted.map((vault) => (vault.equals(underwater.vault) ? slot : 0));
+      assert.deepEqual(await flaggedSlots(), expected);
+    });
+
+    it("Rejects vaults at or before the cursor", async () => {
+      await expectError(bank, checkPage(sorted.slice(2), false), "ScanOutOfOrder");
+      await expectError(bank, checkPage(sorted.slice(0, 1), false), "ScanOutOfOrder");
+    });
+
+    it("Keeps the first flag through later sweeps and clears it once healthy", async () => {
+      const firstFlags = await flaggedSlots();
+      await warp(bank, 1);
+      await checkPage(sorted, true);
+      assert.equal(
+        (await bank.program.account.scanCursor.fetch(scanCursor())).sweep.toNumber(),
+        2
+      );
+      assert.deepEqual(await flaggedSlots(), firstFlags);
+
+      await warp(bank, 60);
+      await setPrice(bank, INITIAL_GOR_PRICE);
+      await warp(bank, 60 * 60);
+      await checkPage(sorted, true);
+      assert.deepEqual(await flaggedSlots(), [0, 0, 0]);
+    });
+  });
+
+  describe("Treasury spends", () => {
+    let bank: Bank;
+    let treasuryGusd: PublicKey;
+    let adminGusd: PublicKey;
+
+    const TREASURY_SPEND_DELAY_SECS = 48 * 60 * 60;
+
+    before(async () => {
+      bank = await startBank();
+      treasuryGusd = await initTreasury(bank);
+      const adminVault = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL, bank.admin);
+      await mintGusd(bank, adminVault, 3_000_000);
+      adminGusd = getAssociatedTokenAddressSync(
+        bank.pda(Buffer.from("gusd_mint")),
+        bank.admin.publicKey
+      );
+      await bank.provider.sendAndConfirm(
+        new Transaction().add(
+          createTransferInstruction(adminGusd, treasuryGusd, bank.admin.publicKey, 2_000_000),
+          SystemProgram.transfer({
+            fromPubkey: bank.admin.publicKey,
+            toPubkey: bank.treasury,
+            lamports: 5 * LAMPORTS_PER_SOL,
+          })
+        )
+      );
+    });
+
+    const spendAccount = (id: number) =>
+      bank.pda(Buffer.from("treasury_spend"), new anchor.BN(id).toArrayLike(Buffer, "le", 8));
+    const now = async () => Number((await bank.context.banksClient.getClock()).unixTimestamp);
+    const lamports = async (account: PublicKey) =>
+      (await bank.context.banksClient.getAccount(account)).lamports;
+    const propose = (id: number, gusdAmount: number, spendLamports: number) =>
+      bank.program.methods
+        .proposeTreasurySpend(new anchor.BN(gusdAmount), new anchor.BN(spendLamports))
+        .accounts({
+          treasurer: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          treasurySpend: spendAccount(id),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    const execute = (id: number) =>
+      bank.program.methods
+        .executeTreasurySpend()
+        .accounts({
+          protocolState: bank.protocolState,
+          treasurySpend: spendAccount(id),
+          proposer: bank.admin.publicKey,
+          treasury: bank.treasury,
+          gusdMint: bank.pda(Buffer.from("gusd_mint")),
+          treasuryGusdAccount: treasuryGusd,
+          recipient: bank.admin.publicKey,
+          recipientGusdAccount: adminGusd,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    it("Pays a spend to the fee destination once its delay has passed", async () => {
+      const proposedAt = await now();
+      await propose(0, 1_500_000, 2 * LAMPORTS_PER_SOL);
+      const spend = await bank.program.account.treasurySpend.fetch(spendAccount(0));
+      assert.isTrue(spend.recipient.equals(bank.admin.publicKey));
+      assert.equal(spend.executableAt.toNumber(), proposedAt + TREASURY_SPEND_DELAY_SECS);
+      await expectError(bank, execute(0), "TreasurySpendTimelocked");
+
+      await warp(bank, TREASURY_SPEND_DELAY_SECS);
+      const treasuryLamports = await lamports(bank.treasury);
+      const adminGusdBefore = await tokenBalance(bank, adminGusd);
+      await execute(0);
+      assert.equal(await tokenBalance(bank, treasuryGusd), BigInt(500_000));
+      assert.equal(await tokenBalance(bank, adminGusd), adminGusdBefore + BigInt(1_500_000));
+      assert.equal(
+        Number(treasuryLamports) - Number(await lamports(bank.treasury)),
+        2 * LAMPORTS_PER_SOL
+      );
+      assert.isNull(await bank.context.banksClient.getAccount(spendAccount(0)));
+    });
+
+    it("Holds a spend the treasury can't cover until the guardian cancels it", async () => {
+      await propose(1, 1_000_000, 0);
+      await warp(bank, TREASURY_SPEND_DELAY_SECS);
+      await expectError(bank, execute(1), "InsufficientTreasuryBalance");
+
+      await bank.program.methods
+        .cancelTreasurySpend()
+        .accounts({
+          guardian: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          treasurySpend: spendAccount(1),
+          proposer: bank.admin.publicKey,
+        })
+        .rpc();
+      assert.isNull(await bank.context.banksClient.getAccount(spendAccount(1)));
+    });
+
+    it("Times later proposals by the configured delay", async () => {
+      const setDelay = (delaySecs: number) =>
+        bank.program.methods
+          .setTreasurySpendDelay(new anchor.BN(delaySecs))
+          .accounts({ admin: bank.admin.publicKey, protocolState: bank.protocolState })
+          .rpc();
+      await expectError(bank, setDelay(60 * 60), "InvalidParameter");
+      await setDelay(24 * 60 * 60);
+
+      const proposedAt = await now();
+      await propose(2, 100_000, 0);
+      const spend = await bank.program.account.treasurySpend.fetch(spendAccount(2));
+      assert.equal(spend.executableAt.toNumber(), proposedAt + 24 * 60 * 60);
+    });
+  });
+
+  describe("Fee collection", () => {
+    let bank: Bank;
+    let treasuryGusd: PublicKey;
+    let borrower: TestVault;
+
+    const SECONDS_PER_YEAR = 365 * 24 * 60 * 60;
+
+    before(async () => {
+      bank = await startBank();
+      treasuryGusd = await initTreasury(bank);
+      await setRiskParameter(bank, { mintFeeBps: {} }, 100);
+      borrower = await createGorVault(bank, 2_000 * LAMPORTS_PER_SOL);
+    });
+
+    const protocolState = () => bank.program.account.protocolState.fetch(bank.protocolState);
+    const collectFees = (treasurer = bank.admin) =>
+      bank.program.methods
+        .collectFees()
+        .accounts({
+          treasurer: treasurer.publicKey,
+          protocolState: bank.protocolState,
+          supplyStats: bank.pda(Buffer.from("supply_stats")),
+          gusdMint: bank.pda(Buffer.from("gusd_mint")),
+          treasuryGusdAccount: treasuryGusd,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([treasurer])
+        .rpc();
+
+    it("Mints the accrued mint fee into the treasury", async () => {
+      await mintGusd(bank, borrower, 2_000_000);
+      const vault = await bank.program.account.vault.fetch(borrower.vault);
+      assert.equal(vault.debtAmount.toNumber(), 2_020_000);
+      assert.equal((await protocolState()).accruedFees.toNumber(), 20_000);
+
+      await collectFees();
+      assert.equal(await tokenBalance(bank, treasuryGusd), BigInt(20_000));
+      assert.equal((await protocolState()).accruedFees.toNumber(), 0);
+      const supplyStats = await bank.program.account.supplyStats.fetch(
+        bank.pda(Buffer.from("supply_stats"))
+      );
+      assert.equal(supplyStats.mintedTreasuryFees.toNumber(), 20_000);
+
+      // Nothing accrued since, so a second collection realizes nothing
+      await warp(bank, 1);
+      await collectFees();
+      assert.equal(await tokenBalance(bank, treasuryGusd), BigInt(20_000));
+    });
+
+    it("Mints accrued stability fees into the treasury", async () => {
+      await setRiskParameter(bank, { stabilityFeeBps: {} }, 1_000);
+      await accrueInterest(bank, borrower);
+      const debtBefore = (await bank.program.account.vault.fetch(borrower.vault)).debtAmount;
+      await warp(bank, SECONDS_PER_YEAR);
+      await accrueInterest(bank, borrower);
+      const debtAfter = (await bank.program.account.vault.fetch(borrower.vault)).debtAmount;
+      const interest = debtAfter.sub(debtBefore).toNumber();
+      assert.isAbove(interest, 0);
+      assert.equal((await protocolState()).accruedFees.toNumber(), interest);
+
+      await collectFees();
+      assert.equal(await tokenBalance(bank, treasuryGusd), BigInt(20_000 + interest));
+    });
+
+    it("Only lets the treasurer collect", async () => {
+      const outsider = await fundedKeypair(bank);
+      await expectError(bank, collectFees(outsider), "Unauthorized");
+    });
+  });
+
+  describe("Governance staking", () => {
+    let bank: Bank;
+    let governanceMint: PublicKey;
+    let staker: Keypair;
+    let stakerTokens: PublicKey;
+    let stakerVault: TestVault;
+
+    const STAKE_WARMUP_SECS = 7 * 24 * 60 * 60;
+
+    before(async () => {
+      bank = await startBank();
+      governanceMint = await createTokenMint(bank, 6);
+      await bank.program.methods
+        .initFeeTiers()
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          feeTierRegistry: feeTierRegistry(),
+          governanceMint,
+          stakeEscrow: stakeEscrow(),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await bank.program.methods
+        .setFeeTiers([
+          { minStake: new anchor.BN(1_000), discountBps: new anchor.BN(2_000) },
+          { minStake: new anchor.BN(5_000), discountBps: new anchor.BN(5_000) },
+        ])
+        .accounts({
+          riskManager: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          feeTierRegistry: feeTierRegistry(),
+          ...(await parameterLogPages(bank)),
+        })
+        .rpc();
+      await setRiskParameter(bank, { mintFeeBps: {} }, 100);
+
+      staker = await fundedKeypair(bank, 2_100);
+      stakerTokens = await fundTokens(bank, governanceMint, staker.publicKey, 10_000);
+      stakerVault = await createGorVault(bank, 2_000 * LAMPORTS_PER_SOL, staker);
+    });
+
+    const feeTierRegistry = () => bank.pda(Buffer.from("fee_tiers"));
+    const stakeEscrow = () => bank.pda(Buffer.from("gov_stake_escrow"));
+    const governanceStake = () =>
+      bank.pda(Buffer.from("gov_stake"), staker.publicKey.toBuffer());
+    const stakeAccounts = () => ({
+      owner: staker.publicKey,
+      feeTierRegistry: feeTierRegistry(),
+      governanceMint,
+      ownerTokenAccount: stakerTokens,
+      stakeEscrow: stakeEscrow(),
+      governanceStake: governanceStake(),
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+    const stake = (amount: number) =>
+      bank.program.methods
+        .stakeGovernance(new anchor.BN(amount))
+        .accounts({ ...stakeAccounts(), systemProgram: SystemProgram.programId })
+        .signers([staker])
+        .rpc();
+    const unstake = (amount: number) =>
+      bank.program.methods
+        .unstakeGovernance(new anchor.BN(amount))
+        .accounts(stakeAccounts())
+        .signers([staker])
+        .rpc();
+    const now = async () => Number((await bank.context.banksClient.getClock()).unixTimestamp);
+    // Mint fee charged on a 1 GUSD mint passing the stake, read off the vault's new debt
+    const mintFee = async () => {
+      const debt = async () =>
+        (await bank.program.account.vault.fetch(stakerVault.vault)).debtAmount.toNumber();
+      const debtBefore = await debt();
+      // Each mint is the same transaction, so move past the slot of the last one
+      await warp(bank, 1);
+      await mintGusd(bank, stakerVault, 1_000_000, null, [], {
+        feeTierRegistry: feeTierRegistry(),
+        governanceStake: governanceStake(),
+      });
+      return (await debt()) - debtBefore - 1_000_000;
+    };
+
+    it("Escrows a stake and withholds its discount until warm", async () => {
+      const stakedAt = await now();
+      await stake(5_000);
+      const position = await bank.program.account.governanceStake.fetch(governanceStake());
+      assert.equal(position.amount.toNumber(), 5_000);
+      assert.equal(position.warmAt.toNumber(), stakedAt + STAKE_WARMUP_SECS);
+      const registry = await bank.program.account.feeTierRegistry.fetch(feeTierRegistry());
+      assert.equal(registry.totalStaked.toNumber(), 5_000);
+      assert.equal(await tokenBalance(bank, stakeEscrow()), BigInt(5_000));
+      assert.equal(await tokenBalance(bank, stakerTokens), BigInt(5_000));
+
+      assert.equal(await mintFee(), 10_000);
+      await warp(bank, STAKE_WARMUP_SECS);
+      // The top tier halves the 1% fee
+      assert.equal(await mintFee(), 5_000);
+    });
+
+    it("Drops to the tier the remaining stake reaches after unstaking", async () => {
+      await unstake(4_000);
+      const position = await bank.program.account.governanceStake.fetch(governanceStake());
+      assert.equal(position.amount.toNumber(), 1_000);
+      const registry = await bank.program.account.feeTierRegistry.fetch(feeTierRegistry());
+      assert.equal(registry.totalStaked.toNumber(), 1_000);
+      assert.equal(await tokenBalance(bank, stakerTokens), BigInt(9_000));
+      assert.equal(await mintFee(), 8_000);
+
+      await expectError(bank, unstake(1_001), "InvalidAmount");
+      await expectError(bank, stake(0), "InvalidAmount");
+    });
+
+    it("Restarts the warm-up when more is staked", async () => {
+      const stakedAt = await now();
+      await stake(4_000);
+      const position = await bank.program.account.governanceStake.fetch(governanceStake());
+      assert.equal(position.amount.toNumber(), 5_000);
+      assert.equal(position.warmAt.toNumber(), stakedAt + STAKE_WARMUP_SECS);
+      assert.equal(await mintFee(), 10_000);
+    });
+  });
+
+  describe("Protocol-owned liquidity", () => {
+    let bank: Bank;
+    let treasuryGusd: PublicKey;
+    let poolGusd: PublicKey;
+    let lpMint: PublicKey;
+    let treasuryLp: PublicKey;
+    let polPosition: PublicKey;
+
+    // 210 GOR is $1.00296 at the initial price, within 0.3% of 1 GUSD
+    const BALANCED_LAMPORTS = 210 * LAMPORTS_PER_SOL;
+
+    before(async () => {
+      bank = await startBank();
+      treasuryGusd = await initTreasury(bank);
+      const adminVault = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL, bank.admin);
+      await mintGusd(bank, adminVault, 3_000_000);
+      poolGusd = getAssociatedTokenAddressSync(
+        bank.pda(Buffer.from("gusd_mint")),
+        bank.admin.publicKey
+      );
+
+      // The token program stands in for the AMM: the treasury PDA mints and burns the LP
+      // token itself, and a GUSD transfer out of the treasury plays the deposit
+      lpMint = await createTokenMint(bank, 6);
+      treasuryLp = getAssociatedTokenAddressSync(lpMint, bank.treasury, true);
+      await bank.provider.sendAndConfirm(
+        new Transaction().add(
+          createTransferInstruction(poolGusd, treasuryGusd, bank.admin.publicKey, 2_000_000),
+          createSetAuthorityInstruction(
+            lpMint,
+            bank.admin.publicKey,
+            AuthorityType.MintTokens,
+            bank.treasury
+          ),
+          createAssociatedTokenAccountIdempotentInstruction(
+            bank.admin.publicKey,
+            treasuryLp,
+            bank.treasury,
+            lpMint
+          )
+        )
+      );
+
+      const pool = Keypair.generate().publicKey;
+      polPosition = bank.pda(Buffer.from("pol_position"), pool.toBuffer());
+      await bank.program.methods
+        .registerPolPool(pool)
+        .accounts({
+          admin: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          polPosition,
+          lpMint,
+          ammProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    const polAccounts = () => ({
+      treasurer: bank.admin.publicKey,
+      protocolState: bank.protocolState,
+      treasury: bank.treasury,
+      treasuryGusdAccount: treasuryGusd,
+      polPosition,
+      lpMint,
+      treasuryLpAccount: treasuryLp,
+      ammProgram: TOKEN_PROGRAM_ID,
+    });
+    // Forward a token program instruction the treasury signs as the AMM route
+    const route = (instruction: anchor.web3.TransactionInstruction) => ({
+      accounts: instruction.keys.map(({ pubkey, isWritable }) => ({
+        pubkey,
+        isWritable,
+        isSigner: false,
+      })),
+      data: instruction.data,
+    });
+    const mintLp = (amount: number) =>
+      route(createMintToInstruction(lpMint, treasuryLp, bank.treasury, amount));
+    const depositGusd = (amount: number) =>
+      route(createTransferInstruction(treasuryGusd, poolGusd, bank.treasury, amount));
+    const burnLp = (amount: number) =>
+      route(createBurnInstruction(treasuryLp, lpMint, bank.treasury, amount));
+    const deploy = (
+      gusdAmount: number,
+      lamports: number,
+      minLpOut: number,
+      { accounts, data }: ReturnType<typeof route>
+    ) =>
+      bank.program.methods
+        .deployPol(
+          new anchor.BN(gusdAmount),
+          new anchor.BN(lamports),
+          new anchor.BN(minLpOut),
+          data
+        )
+        .accounts({
+          ...polAccounts(),
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+        })
+        .remainingAccounts(accounts)
+        .rpc();
+    const withdraw = (
+      lpAmount: number,
+      minGusdOut: number,
+      { accounts, data }: ReturnType<typeof route>
+    ) =>
+      bank.program.methods
+        .withdrawPol(new anchor.BN(lpAmount), new anchor.BN(minGusdOut), new anchor.BN(0), data)
+        .accounts(polAccounts())
+        .remainingAccounts(accounts)
+        .rpc();
+    const position = () => bank.program.account.polPosition.fetch(polPosition);
+
+    it("Records the LP tokens and GUSD a deployment moves", async () => {
+      await deploy(1_000_000, BALANCED_LAMPORTS, 500_000, mintLp(500_000));
+      assert.equal((await position()).lpAmount.toNumber(), 500_000);
+      assert.equal(await tokenBalance(bank, treasuryLp), BigInt(500_000));
+
+      await deploy(1_000_000, BALANCED_LAMPORTS, 0, depositGusd(1_000_000));
+      const { gusdDeployed, gorDeployed } = await position();
+      assert.equal(gusdDeployed.toNumber(), 1_000_000);
+      assert.equal(gorDeployed.toNumber(), 0);
+      assert.equal(await tokenBalance(bank, treasuryGusd), BigInt(1_000_000));
+    });
+
+    it("Rejects skewed legs and routes that overspend or underdeliver", async () => {
+      await expectError(
+        bank,
+        deploy(1_000_000, BALANCED_LAMPORTS / 2, 0, mintLp(1)),
+        "PolImbalanced"
+      );
+      await expectError(
+        bank,
+        deploy(500_000, BALANCED_LAMPORTS / 2, 0, depositGusd(500_001)),
+        "SlippageExceeded"
+      );
+      await expectError(
+        bank,
+        deploy(1_000_000, BALANCED_LAMPORTS, 500_001, mintLp(500_000)),
+        "SlippageExceeded"
+      );
+    });
+
+    it("Redeems LP tokens within the requested amount and minimums", async () => {
+      await expectError(bank, withdraw(500_001, 0, burnLp(1)), "InvalidAmount");
+      await expectError(bank, withdraw(199_999, 0, burnLp(200_000)), "SlippageExceeded");
+      await expectError(bank, withdraw(200_000, 1, burnLp(200_000)), "SlippageExceeded");
+
+      await withdraw(200_000, 0, burnLp(200_000));
+      assert.equal((await position()).lpAmount.toNumber(), 300_000);
+      assert.equal(await tokenBalance(bank, treasuryLp), BigInt(300_000));
+    });
+  });
+
+  describe("Keeper rebates", () => {
+    let bank: Bank;
+    let treasuryGusd: PublicKey;
+    let keeper: Keypair;
+    let keeperGusd: PublicKey;
+    let vaults: TestVault[];
+
+    before(async () => {
+      bank = await startBank();
+      treasuryGusd = await initTreasury(bank);
+      const adminVault = await createGorVault(bank, 1_000 * LAMPORTS_PER_SOL, bank.admin);
+      await mintGusd(bank, adminVault, 1_000_000);
+      const gusdMint = bank.pda(Buffer.from("gusd_mint"));
+      await bank.provider.sendAndConfirm(
+        new Transaction().add(
+          createTransferInstruction(
+            getAssociatedTokenAddressSync(gusdMint, bank.admin.publicKey),
+            treasuryGusd,
+            bank.admin.publicKey,
+            1_000_000
+          )
+        )
+      );
+      vaults = [
+        await createGorVault(bank, 10 * LAMPORTS_PER_SOL),
+        await createGorVault(bank, 10 * LAMPORTS_PER_SOL),
+      ];
+
+      keeper = await fundedKeypair(bank);
+      keeperGusd = await fundTokens(bank, gusdMint, keeper.publicKey, 0);
+      await bank.program.methods
+        .registerKeeper(keeper.publicKey)
+        .accounts({
+          treasurer: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          keeperAccount: keeperAccount(),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // At $5 per GOR, 100,000 lamports of fees are worth 500 GUSD base units
+      await setRiskParameter(bank, { priceSlewBpsPerSec: {} }, 10_000);
+      await warp(bank, 1);
+      await setPrice(bank, 5_000_000);
+      await warp(bank, 60 * 60);
+    });
+
+    const keeperAccount = () => bank.pda(Buffer.from("keeper"), keeper.publicKey.toBuffer());
+    const credited = () => bank.program.account.keeperAccount.fetch(keeperAccount());
+    // Accrue a vault as the keeper, optionally paying a priority fee
+    const crank = (testVault: TestVault, microLamports = 0) =>
+      bank.program.methods
+        .accrueInterest()
+        .accounts({
+          vault: testVault.vault,
+          protocolState: bank.protocolState,
+          revenueStats: bank.pda(Buffer.from("revenue_stats")),
+          priceFeed: bank.priceFeed,
+          keeper: keeper.publicKey,
+          keeperAccount: keeperAccount(),
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          feeTierRegistry: null,
+          governanceStake: null,
+        })
+        .preInstructions(
+          microLamports > 0
+            ? [
+                ComputeBudgetProgram.setComputeUnitLimit({ units: 200_000 }),
+                ComputeBudgetProgram.setComputeUnitPrice({ microLamports }),
+              ]
+            : []
+        )
+        .signers([keeper])
+        .rpc();
+    const claim = () =>
+      bank.program.methods
+        .claimKeeperRebate()
+        .accounts({
+          keeper: keeper.publicKey,
+          keeperAccount: keeperAccount(),
+          protocolState: bank.protocolState,
+          treasury: bank.treasury,
+          gusdMint: bank.pda(Buffer.from("gusd_mint")),
+          treasuryGusdAccount: treasuryGusd,
+          keeperGusdAccount: keeperGusd,
+          deploymentConfig: bank.deploymentConfig,
+          priceFeed: bank.priceFeed,
+          priceUpdate: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([keeper])
+        .rpc();
+
+    it("Credits each crank's fee once per slot, capped", async () => {
+      await crank(vaults[0]);
+      let account = await credited();
+      assert.equal(account.cranks.toNumber(), 1);
+      assert.equal(account.unclaimedFeeLamports.toNumber(), 5_000);
+      // A second crank in the same slot earns nothing more
+      await crank(vaults[1]);
+      assert.equal((await credited()).unclaimedFeeLamports.toNumber(), 5_000);
+
+      // 5,000 base plus 200,000 of priority fee, capped at 100,000
+      await warp(bank, 1);
+      await crank(vaults[0], 1_000_000);
+      account = await credited();
+      assert.equal(account.cranks.toNumber(), 2);
+      assert.equal(account.unclaimedFeeLamports.toNumber(), 105_000);
+      assert.equal(account.totalFeeLamports.toNumber(), 105_000);
+
+      // Someone else can't crank on the keeper's account
+      const impostor = await fundedKeypair(bank);
+      await expectError(
+        bank,
+        bank.program.methods
+          .accrueInterest()
+          .accounts({
+            vault: vaults[1].vault,
+            protocolState: bank.protocolState,
+            revenueStats: bank.pda(Buffer.from("revenue_stats")),
+            priceFeed: bank.priceFeed,
+            keeper: impostor.publicKey,
+            keeperAccount: keeperAccount(),
+            instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+            feeTierRegistry: null,
+            governanceStake: null,
+          })
+          .signers([impostor])
+          .rpc(),
+        "InvalidKeeperCredit"
+      );
+    });
+
+    it("Rebates credited fees within the epoch cap", async () => {
+      // Rebates are off until a cap is set
+      await expectError(bank, claim(), "KeeperRebateUnavailable");
+      await setRiskParameter(bank, { keeperRebateEpochCap: {} }, 300);
+
+      // 105,000 lamports are owed 525; the 300 paid settles 60,000 of them
+      await claim();
+      let account = await credited();
+      assert.equal(await tokenBalance(bank, keeperGusd), BigInt(300));
+      assert.equal(account.unclaimedFeeLamports.toNumber(), 45_000);
+      assert.equal(account.totalRebated.toNumber(), 300);
+      await warp(bank, 1);
+      await expectError(bank, claim(), "KeeperRebateUnavailable");
+
+      await nextEpoch(bank);
+      await claim();
+      account = await credited();
+      assert.equal(await tokenBalance(bank, keeperGusd), BigInt(525));
+      assert.equal(account.unclaimedFeeLamports.toNumber(), 0);
+      assert.equal(account.totalRebated.toNumber(), 525);
+      await warp(bank, 1);
+      await expectError(bank, claim(), "InvalidAmount");
+    });
+  });
+
+  describe("Fee exemptions", () => {
+    let bank: Bank;
+    let holder: TestVault;
+    let feeExemption: PublicKey;
+
+    const DAY_SECS = 24 * 60 * 60;
+
+    before(async () => {
+      bank = await startBank();
+      await setRiskParameter(bank, { mintFeeBps: {} }, 100);
+      holder = await createGorVault(bank, 2_000 * LAMPORTS_PER_SOL);
+      feeExemption = bank.pda(Buffer.from("fee_exempt"), holder.owner.publicKey.toBuffer());
+    });
+
+    const now = async () => Number((await bank.context.banksClient.getClock()).unixTimestamp);
+    const addExemption = (expiresAt: number, riskManager = bank.admin) =>
+      bank.program.methods
+        .addFeeExemption(holder.owner.publicKey, new anchor.BN(expiresAt))
+        .accounts({
+          riskManager: riskManager.publicKey,
+          protocolState: bank.protocolState,
+          feeExemption,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([riskManager])
+        .rpc();
+    // Mint fee charged on a 1 GUSD mint, read off the vault's new debt
+    const mintFee = async (exempt: boolean) => {
+      const debt = async () =>
+        (await bank.program.account.vault.fetch(holder.vault)).debtAmount.toNumber();
+      const debtBefore = await debt();
+      // Each mint is the same transaction, so move past the slot of the last one
+      await warp(bank, 1);
+      await mintGusd(bank, holder, 1_000_000, null, [], exempt ? { feeExemption } : {});
+      return (await debt()) - debtBefore - 1_000_000;
+    };
+
+    it("Waives the mint fee until the exemption expires", async () => {
+      const addedAt = await now();
+      await expectError(bank, addExemption(addedAt), "InvalidExpiry");
+      await addExemption(addedAt + DAY_SECS);
+      const exemption = await bank.program.account.feeExemption.fetch(feeExemption);
+      assert.isTrue(exemption.address.equals(holder.owner.publicKey));
+      assert.isTrue(exemption.addedBy.equals(bank.admin.publicKey));
+      assert.equal(exemption.addedAt.toNumber(), addedAt);
+      assert.equal(exemption.expiresAt.toNumber(), addedAt + DAY_SECS);
+
+      assert.equal(await mintFee(true), 0);
+      await warp(bank, DAY_SECS);
+      // The lapsed entry can still be passed, but no longer waives anything
+      assert.equal(await mintFee(true), 10_000);
+    });
+
+    it("Renews an entry, here without an expiry", async () => {
+      await addExemption(0);
+      assert.equal(
+        (await bank.program.account.feeExemption.fetch(feeExemption)).expiresAt.toNumber(),
+        0
+      );
+      await warp(bank, 365 * DAY_SECS);
+      assert.equal(await mintFee(true), 0);
+
+      const outsider = await fundedKeypair(bank);
+      await expectError(bank, addExemption(0, outsider), "Unauthorized");
+    });
+
+    it("Charges the fee again once the exemption is removed", async () => {
+      await bank.program.methods
+        .removeFeeExemption()
+        .accounts({
+          riskManager: bank.admin.publicKey,
+          protocolState: bank.protocolState,
+          feeExemption,
+        })
+        .rpc();
+      assert.isNull(await bank.context.banksClient.getAccount(feeExemption));
+      assert.equal(await mintFee(false), 10_000);
+    });
+  });
+});